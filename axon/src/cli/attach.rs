@@ -6,20 +6,22 @@
 //! It handles resolving pod identity, waiting for pod readiness, and delegating
 //! the shell session management to `PodConsole`.
 
-use std::time::Duration;
-
 use clap::Args;
 use k8s_openapi::api::core::v1::Pod;
-use kube::Api;
+use kube::{Api, api::ListParams};
+use snafu::ResultExt;
 
 use crate::{
+    PROJECT_NAME,
     cli::{
-        Error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
+        Error, error,
+        internal::{ApiPodExt, ResolvedResources, ResourceResolver, ShellWords, to_argv},
     },
     config::Config,
+    consts::{DEFAULT_POD_READY_TIMEOUT, k8s::labels},
     ext::PodExt,
     pod_console::PodConsole,
+    ui::fuzzy_finder::PodListExt as _,
 };
 
 /// Represents the command to attach to an interactive shell within a Kubernetes
@@ -41,47 +43,106 @@ pub struct AttachCommand {
     )]
     pub namespace: Option<String>,
 
-    /// Name of the temporary pod to attach to.
+    /// Name of the pod to attach to.
     ///
-    /// If not specified, Axon's default pod name will be used.
+    /// Any pod in the namespace can be targeted by its exact name, including
+    /// pods not created by Axon. If not specified, Axon's default pod name will
+    /// be used.
     #[arg(
         short = 'p',
         long = "pod-name",
-        help = "Name of the temporary pod to attach to. If not specified, Axon's default pod name \
-                will be used."
+        help = "Name of the pod to attach to. Any pod in the namespace can be targeted by its \
+                exact name, including pods not created by Axon. If not specified, Axon's default \
+                pod name will be used."
     )]
     pub pod_name: Option<String>,
 
+    /// Select the pod to attach to with the fuzzy finder across all
+    /// Kubernetes namespaces.
+    ///
+    /// Only pods managed by Axon are listed.
+    #[arg(
+        short = 'A',
+        long = "all-namespaces",
+        conflicts_with_all = ["namespace", "pod_name"],
+        help = "Select the pod to attach to with the fuzzy finder across all Kubernetes \
+                namespaces. Only pods managed by Axon are listed."
+    )]
+    pub all_namespaces: bool,
+
     /// Command and arguments for the interactive shell to use.
     ///
-    /// For example: `/bin/bash` or `bash -c 'sh'`. If not specified, Axon will
-    /// attempt to detect the shell automatically based on the pod's image.
+    /// For example: `/bin/bash` or `bash -c 'sh'`. The value is split into
+    /// arguments like a shell would. If not specified, Axon will attempt to
+    /// detect the shell automatically based on the pod's image.
     #[arg(
         short = 's',
         long = "shell",
+        allow_hyphen_values = true,
         help = "Command and arguments for the interactive shell to use (e.g., `/bin/bash`, `bash \
-                -c 'sh'`). If not specified, Axon will attempt to detect the shell."
+                -c 'sh'`), split into arguments like a shell would. If not specified, Axon will \
+                attempt to detect the shell."
     )]
-    pub interactive_shell: Vec<String>,
+    pub interactive_shell: Vec<ShellWords>,
+
+    /// Name of the container to attach to.
+    ///
+    /// If not specified, the pod's default container is used, falling back to
+    /// its first container.
+    #[arg(
+        short = 'c',
+        long = "container",
+        help = "Name of the container to attach to. If not specified, the pod's default container \
+                is used, falling back to its first container."
+    )]
+    pub container: Option<String>,
 
     /// The maximum time in seconds to wait for the pod to be running before
     /// timing out.
     ///
-    /// Defaults to 15 seconds.
+    /// Defaults to the global `--pod-ready-timeout`, or to 15 seconds.
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "15",
-        help = "The maximum time in seconds to wait for the pod to be running before timing out."
+        help = "The maximum time in seconds to wait for the pod to be running before timing out. \
+                Defaults to the global --pod-ready-timeout, or 15 seconds."
     )]
-    pub timeout_secs: u64,
+    pub timeout_secs: Option<u64>,
+
+    /// Reattach when the connection to the pod is lost while the shell is
+    /// still running, up to the given number of times.
+    ///
+    /// Exiting the shell always ends the session.
+    #[arg(
+        long = "reattach",
+        value_name = "ATTEMPTS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "5",
+        help = "Reattach when the connection to the pod is lost while the shell is still \
+                running, up to ATTEMPTS times (5 if not given). Exiting the shell always ends \
+                the session."
+    )]
+    pub reattach: Option<u32>,
+
+    /// End the remote shell when the session is ended locally, instead of
+    /// leaving it running in the container.
+    #[arg(
+        long = "cleanup-on-detach",
+        help = "End the remote shell when the session is ended locally, e.g. by closing stdin, by \
+                sending it Ctrl-C and Ctrl-D and closing its stdin. Without this flag the shell \
+                and the processes it started are left running in the container."
+    )]
+    pub cleanup_on_detach: bool,
 }
 
 impl AttachCommand {
     /// Executes the `attach` command, connecting to an interactive shell in a
     /// specified Kubernetes pod.
     ///
-    /// This asynchronous function resolves the target pod's identity, waits for
+    /// This asynchronous function resolves the target pod's identity, either
+    /// from the arguments or, with `--all-namespaces`, by letting the user
+    /// select a pod with the fuzzy finder. It then waits for
     /// the pod to reach a running state, determines the interactive shell
     /// to use, and then delegates the actual shell session management to
     /// `PodConsole`.
@@ -105,23 +166,110 @@ impl AttachCommand {
     /// * An error occurs during the establishment or operation of the
     ///   interactive console session.
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, pod_name, interactive_shell, timeout_secs } = self;
+        let Self {
+            namespace,
+            pod_name,
+            all_namespaces,
+            container,
+            interactive_shell,
+            timeout_secs,
+            reattach,
+            cleanup_on_detach,
+        } = self;
 
         // Resolve Identity
-        let ResolvedResources { namespace, pod_name } =
-            ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name);
+        let ResolvedResources { namespace, pod_name } = if all_namespaces {
+            let list_params = ListParams {
+                label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+                ..ListParams::default()
+            };
+            let selected = Api::<Pod>::all(kube_client.clone())
+                .list(&list_params)
+                .await
+                .context(error::ListPodsSnafu)?
+                .find_pods()
+                .await;
+            let Some((namespace, pod_name)) = selected.into_iter().next() else {
+                return Ok(());
+            };
+            ResolvedResources { namespace, pod_name }
+        } else {
+            ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name)
+        };
 
         // Resolve Pod API & Status
         let api = Api::<Pod>::namespaced(kube_client, &namespace);
         let pod = api
-            .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
+            .await_running_status(
+                &pod_name,
+                &namespace,
+                config.pod_ready_timeout(timeout_secs, DEFAULT_POD_READY_TIMEOUT),
+            )
             .await?;
 
         // Resolve Shell
-        let shell =
-            if interactive_shell.is_empty() { pod.interactive_shell() } else { interactive_shell };
+        let shell = if interactive_shell.is_empty() {
+            pod.interactive_shell()
+        } else {
+            to_argv(interactive_shell)
+        };
+
+        // Resolve Container
+        let container = container.or_else(|| pod.default_container());
 
         // Delegate behavior
-        PodConsole::new(api, pod_name, namespace, shell).run().await.map_err(Error::from)
+        PodConsole::new(api, pod_name, namespace, shell)
+            .container(container)
+            .reattach(reattach.unwrap_or_default())
+            .cleanup_on_detach(cleanup_on_detach)
+            .run()
+            .await
+            .map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        attach: AttachCommand,
+    }
+
+    #[test]
+    fn test_interactive_shell() {
+        let argv = ["bash", "-lc", "exec zsh"].map(String::from).to_vec();
+        for args in [
+            &["axon", "-p", "my-pod", "-s", "bash -lc 'exec zsh'"][..],
+            &["axon", "-p", "my-pod", "-s", "bash", "-s", "-lc", "-s", "'exec zsh'"],
+        ] {
+            let Cli { attach } = Cli::try_parse_from(args).unwrap();
+            assert_eq!(to_argv(attach.interactive_shell), argv, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_reattach() {
+        for (args, expected) in [
+            (&["axon", "-p", "my-pod"][..], None),
+            (&["axon", "--reattach", "-p", "my-pod"], Some(5)),
+            (&["axon", "--reattach=2", "-p", "my-pod"], Some(2)),
+        ] {
+            let Cli { attach } = Cli::try_parse_from(args).unwrap();
+            assert_eq!(attach.reattach, expected, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_cleanup_on_detach() {
+        let Cli { attach } = Cli::try_parse_from(["axon", "-p", "my-pod"]).unwrap();
+        assert!(!attach.cleanup_on_detach);
+        let Cli { attach } =
+            Cli::try_parse_from(["axon", "--cleanup-on-detach", "-p", "my-pod"]).unwrap();
+        assert!(attach.cleanup_on_detach);
     }
 }
@@ -0,0 +1,313 @@
+//! Construction of the Kubernetes client shared by all commands.
+//!
+//! The client configuration is inferred like `kube::Client::try_default` does,
+//! from the kubeconfig file or the in-cluster environment, and then adjusted
+//! with the proxy environment variables and the global client options of the
+//! CLI. Idempotent requests that fail transiently are retried.
+
+use std::time::Duration;
+
+use http::{Method, Request, Response, StatusCode};
+use kube::client::{Body, ClientBuilder};
+use snafu::ResultExt;
+use tower::{
+    BoxError, ServiceBuilder,
+    buffer::BufferLayer,
+    retry::{Policy, RetryLayer},
+};
+
+use crate::cli::{Error, error};
+
+/// The default number of times a failed idempotent request is retried.
+pub const DEFAULT_API_RETRIES: usize = 3;
+
+/// The delay before the first retry of a failed request, doubled for each
+/// subsequent retry.
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// The number of requests that can be queued in front of the retrying
+/// service.
+const REQUEST_BUFFER_CAPACITY: usize = 1024;
+
+/// Options adjusting how the Kubernetes client connects to the API server.
+#[derive(Clone, Debug)]
+pub struct ClientOptions {
+    /// Whether the certificate of the API server is accepted without being
+    /// verified.
+    pub insecure_skip_tls_verify: bool,
+
+    /// The time to wait for a response of the API server, or `None` to keep
+    /// the default of the client.
+    pub request_timeout: Option<Duration>,
+
+    /// The number of times a failed idempotent request is retried.
+    pub api_retries: usize,
+
+    /// The user to impersonate, if any.
+    pub impersonate_user: Option<String>,
+
+    /// The groups to impersonate. Only honored along with `impersonate_user`.
+    pub impersonate_groups: Vec<String>,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            insecure_skip_tls_verify: false,
+            request_timeout: None,
+            api_retries: DEFAULT_API_RETRIES,
+            impersonate_user: None,
+            impersonate_groups: Vec::new(),
+        }
+    }
+}
+
+/// Builds a Kubernetes client.
+///
+/// Unless the kubeconfig sets a `proxy-url`, requests are sent through the
+/// proxy given by the `HTTPS_PROXY` (or, for plain HTTP API servers,
+/// `HTTP_PROXY`) environment variable, except for hosts listed in `NO_PROXY`.
+///
+/// When `options.impersonate_user` is set, every request is made as that user
+/// and the given groups, replacing any impersonation set in the kubeconfig.
+/// The authenticated identity must be allowed to `impersonate` them by RBAC,
+/// otherwise the API server rejects the requests.
+///
+/// `GET` requests, which covers gets, lists and watches, are retried up to
+/// `options.api_retries` times when the connection fails or the API server
+/// answers with a transient error status. Mutating requests are never retried,
+/// to avoid applying their side effects twice.
+///
+/// # Arguments
+///
+/// * `options` - The options adjusting the inferred client configuration.
+///
+/// # Errors
+///
+/// Returns an `Error` if no client configuration can be inferred, or if the
+/// client cannot be built from it.
+pub async fn build_client(options: &ClientOptions) -> Result<kube::Client, Error> {
+    let ClientOptions {
+        insecure_skip_tls_verify,
+        request_timeout,
+        api_retries,
+        impersonate_user,
+        impersonate_groups,
+    } = options;
+
+    let mut config = kube::Config::infer().await.context(error::InferKubeConfigSnafu)?;
+
+    if config.proxy_url.is_none() {
+        let host = config.cluster_url.host().unwrap_or_default();
+        let is_https = config.cluster_url.scheme_str() != Some("http");
+        if let Some(proxy) = select_proxy(host, is_https, |name| std::env::var(name).ok()) {
+            match proxy.parse() {
+                Ok(proxy_url) => config.proxy_url = Some(proxy_url),
+                Err(err) => tracing::warn!("Ignoring invalid proxy URL '{proxy}': {err}"),
+            }
+        }
+    }
+    if *insecure_skip_tls_verify {
+        config.accept_invalid_certs = true;
+    }
+    if let Some(request_timeout) = request_timeout {
+        config.read_timeout = Some(*request_timeout);
+    }
+    if let Some(impersonate_user) = impersonate_user {
+        config.auth_info.impersonate = Some(impersonate_user.clone());
+        config.auth_info.impersonate_groups =
+            Some(impersonate_groups.clone()).filter(|groups| !groups.is_empty());
+    }
+
+    // The retrying service has to be cloneable, which the buffer provides
+    let layers = ServiceBuilder::new()
+        .layer(RetryLayer::new(RetryIdempotent::new(*api_retries)))
+        .layer(BufferLayer::new(REQUEST_BUFFER_CAPACITY));
+    Ok(ClientBuilder::try_from(config).context(error::KubeConfigSnafu)?.with_layer(&layers).build())
+}
+
+/// A retry policy for idempotent requests failing transiently.
+#[derive(Clone, Debug)]
+struct RetryIdempotent {
+    /// The number of retries left for the request.
+    remaining: usize,
+    /// The delay before the next retry.
+    backoff: Duration,
+}
+
+impl RetryIdempotent {
+    const fn new(retries: usize) -> Self {
+        Self { remaining: retries, backoff: INITIAL_RETRY_BACKOFF }
+    }
+}
+
+impl<B> Policy<Request<Body>, Response<B>, BoxError> for RetryIdempotent {
+    type Future = tokio::time::Sleep;
+
+    fn retry(
+        &mut self,
+        req: &mut Request<Body>,
+        result: &mut Result<Response<B>, BoxError>,
+    ) -> Option<Self::Future> {
+        let transient = result.as_ref().map_or(true, |response| is_transient(response.status()));
+        if self.remaining == 0 || !transient || !is_idempotent(req.method()) {
+            return None;
+        }
+
+        self.remaining -= 1;
+        let backoff = self.backoff;
+        self.backoff = backoff.saturating_mul(2);
+        tracing::debug!("Retrying {} {} in {backoff:?}", req.method(), req.uri());
+        Some(tokio::time::sleep(backoff))
+    }
+
+    fn clone_request(&mut self, req: &Request<Body>) -> Option<Request<Body>> {
+        if self.remaining == 0 || !is_idempotent(req.method()) {
+            return None;
+        }
+
+        let mut clone = Request::new(req.body().try_clone()?);
+        *clone.method_mut() = req.method().clone();
+        *clone.uri_mut() = req.uri().clone();
+        *clone.version_mut() = req.version();
+        *clone.headers_mut() = req.headers().clone();
+        Some(clone)
+    }
+}
+
+/// Returns whether requests with `method` can be retried without duplicating
+/// side effects.
+fn is_idempotent(method: &Method) -> bool { method == Method::GET }
+
+/// Returns whether a response status indicates a failure that may go away when
+/// the request is retried.
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Selects the proxy to reach `host` through, from the proxy environment
+/// variables.
+///
+/// Both the upper and lower case variants of the variables are honored, the
+/// upper case one taking precedence. A proxy given without a scheme is assumed
+/// to be an HTTP proxy.
+///
+/// # Arguments
+///
+/// * `host` - The host of the API server.
+/// * `is_https` - Whether the API server is reached over HTTPS.
+/// * `env` - Looks up an environment variable by name.
+///
+/// # Returns
+///
+/// The URL of the proxy, or `None` if no proxy is configured or `host` is
+/// excluded by `NO_PROXY`.
+fn select_proxy(
+    host: &str,
+    is_https: bool,
+    env: impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+    let var = |name: &str| {
+        env(name)
+            .or_else(|| env(&name.to_lowercase()))
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+    };
+
+    let proxy = if is_https { var("HTTPS_PROXY") } else { var("HTTP_PROXY") }?;
+    if var("NO_PROXY").is_some_and(|no_proxy| is_excluded(host, &no_proxy)) {
+        return None;
+    }
+
+    if proxy.contains("://") { Some(proxy) } else { Some(format!("http://{proxy}")) }
+}
+
+/// Returns whether `host` matches an entry of a `NO_PROXY` list.
+///
+/// An entry matches the host itself and all of its subdomains, with or without
+/// a leading dot. A `*` entry matches every host. Ports are ignored.
+fn is_excluded(host: &str, no_proxy: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+
+    no_proxy.split(',').map(str::trim).filter(|entry| !entry.is_empty()).any(|entry| {
+        if entry == "*" {
+            return true;
+        }
+        let entry = entry.trim_start_matches('.').to_ascii_lowercase();
+        // Strip a port, unless the entry is a bare IPv6 address
+        let entry = match entry.rsplit_once(':') {
+            Some((name, port))
+                if !name.contains(':') && port.chars().all(|c| c.is_ascii_digit()) =>
+            {
+                name.to_string()
+            }
+            _ => entry,
+        };
+        let entry = entry.trim_start_matches('[').trim_end_matches(']');
+        host == entry || host.strip_suffix(entry).is_some_and(|prefix| prefix.ends_with('.'))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use http::{Method, StatusCode};
+
+    use super::{is_excluded, is_idempotent, is_transient, select_proxy};
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars = vars
+            .iter()
+            .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+            .collect::<HashMap<_, _>>();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_select_proxy() {
+        let vars = env(&[("HTTPS_PROXY", "proxy.corp:3128"), ("http_proxy", "http://plain:80")]);
+        assert_eq!(select_proxy("k8s.corp", true, &vars), Some("http://proxy.corp:3128".into()));
+        assert_eq!(select_proxy("k8s.corp", false, &vars), Some("http://plain:80".into()));
+        assert_eq!(select_proxy("k8s.corp", true, env(&[])), None);
+    }
+
+    #[test]
+    fn test_select_proxy_honors_no_proxy() {
+        let vars =
+            env(&[("https_proxy", "http://proxy:3128"), ("no_proxy", "localhost, .internal")]);
+        assert_eq!(select_proxy("localhost", true, &vars), None);
+        assert_eq!(select_proxy("api.internal", true, &vars), None);
+        assert_eq!(select_proxy("api.example.com", true, &vars), Some("http://proxy:3128".into()));
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        assert!(is_excluded("anything", "*"));
+        assert!(is_excluded("k8s.corp.example", "example"));
+        assert!(is_excluded("k8s.corp.example", "corp.example:6443"));
+        assert!(is_excluded("10.0.0.1", "10.0.0.1"));
+        assert!(is_excluded("[::1]", "::1"));
+        assert!(!is_excluded("notexample", "example"));
+        assert!(!is_excluded("k8s.corp", ""));
+    }
+
+    #[test]
+    fn test_only_reads_are_retried() {
+        assert!(is_idempotent(&Method::GET));
+        assert!(!is_idempotent(&Method::POST));
+        assert!(!is_idempotent(&Method::DELETE));
+        assert!(!is_idempotent(&Method::PATCH));
+
+        assert!(is_transient(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_transient(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient(StatusCode::NOT_FOUND));
+        assert!(!is_transient(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+}
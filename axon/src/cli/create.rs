@@ -3,36 +3,61 @@
 //!
 //! It handles the parsing of command-line arguments related to pod creation,
 //! resolves pod identity, constructs the Kubernetes Pod manifest based on
-//! user-defined specifications (default, preset, or manual), and interacts
-//! with the Kubernetes API to create the pod. Optionally, it can automatically
-//! attach to the pod's console upon successful creation.
+//! user-defined specifications (default, preset, or manual), or adopts a full
+//! Pod manifest read from a file, and interacts with the Kubernetes API to
+//! create the pod. Optionally, it can create a
+//! companion Service exposing the spec's service ports, create the target
+//! namespace if it is missing, and automatically attach to the pod's console
+//! upon successful creation.
 
-use std::{collections::BTreeMap, time::Duration};
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::IsTerminal,
+    path::{Path, PathBuf},
+};
 
 use clap::{ArgAction, Args, Parser};
-use k8s_openapi::api::core::v1::{Container, ContainerPort, Pod, PodSpec};
+use k8s_openapi::api::core::v1::{
+    Container, ContainerPort, Namespace, Pod, PodSpec, Service, ServiceSpec,
+};
 use kube::{
     Api,
     api::{ObjectMeta, PostParams},
 };
 use snafu::{OptionExt, ResultExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::{
     PROJECT_NAME, PROJECT_VERSION,
     cli::{
         Error, error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
+        internal::{
+            ApiPodExt, ResolvedResources, ResourceResolver, ShellWords, output, random_hex, to_argv,
+        },
+    },
+    config::{
+        Config, ImagePullPolicy, ImageVariables, PortMapping, Probe, ServicePorts, Spec, Spread,
     },
-    config::{Config, ImagePullPolicy, PortMapping, ServicePorts, Spec},
     consts::{
-        DEFAULT_INTERACTIVE_SHELL,
+        DEFAULT_CREATE_POD_READY_TIMEOUT, DEFAULT_INTERACTIVE_SHELL,
         k8s::{annotations, labels},
     },
+    ext::PodExt,
     pod_console::PodConsole,
+    ui::fuzzy_finder::SpecListExt,
 };
 
 const DEFAULT_CONTAINER_NAME: &str = "axon-container";
 
+/// The maximum length of a pod name that is a DNS-1123 label.
+const MAX_POD_NAME_LENGTH: usize = 63;
+
+/// The number of log lines shown when the pod cannot be attached to.
+const FAILURE_LOG_LINES: i64 = 20;
+
+/// The maximum length of a resource name that is a DNS-1123 subdomain.
+const MAX_RESOURCE_NAME_LENGTH: usize = 253;
+
 /// Represents the `create` command in the CLI, used for provisioning new
 /// temporary Kubernetes pods.
 ///
@@ -40,6 +65,7 @@ const DEFAULT_CONTAINER_NAME: &str = "axon-container";
 /// the new pod, such as its namespace, name, automatic attachment behavior,
 /// and timeout settings.
 #[derive(Args, Clone)]
+#[expect(clippy::struct_excessive_bools, reason = "Every bool is an independent command-line flag")]
 pub struct CreateCommand {
     /// Kubernetes namespace to create the pod in. Defaults to the current
     /// Kubernetes context's namespace.
@@ -72,16 +98,167 @@ pub struct CreateCommand {
     )]
     pub auto_attach: bool,
 
+    /// Do not attach with `auto_attach` if the pod already existed, e.g. when
+    /// it is shared with other users.
+    #[arg(
+        long = "no-attach-on-exist",
+        help = "With --auto-attach, only attach if the pod has just been created, not if it \
+                already existed."
+    )]
+    pub no_attach_on_exist: bool,
+
+    /// Print the last lines of the pod's logs if `auto_attach` fails, e.g.
+    /// because the pod keeps crashing.
+    #[arg(
+        long = "show-logs-on-failure",
+        value_name = "BOOL",
+        action = ArgAction::Set,
+        num_args = 0..=1,
+        require_equals = true,
+        default_value_t = true,
+        default_missing_value = "true",
+        help = "With --auto-attach, print the last lines of the pod's logs if it does not become \
+                running or cannot be attached to, e.g. because it keeps crashing. Enabled by \
+                default, disable with --show-logs-on-failure=false."
+    )]
+    pub show_logs_on_failure: bool,
+
     /// The maximum time in seconds to wait for the pod to be created and
     /// running before timing out.
+    ///
+    /// Defaults to the global `--pod-ready-timeout`, or to 90 seconds.
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "90",
         help = "The maximum time in seconds to wait for the pod to be created and running before \
-                timing out."
+                timing out. Defaults to the global --pod-ready-timeout, or 90 seconds."
+    )]
+    pub timeout_secs: Option<u64>,
+
+    /// Also create a `ClusterIP` Service named after the pod, exposing the
+    /// service ports (ssh, http, https) of the spec, and probe the SSH port
+    /// for readiness unless the container already has a readiness probe.
+    #[arg(
+        long = "with-service",
+        help = "Also create a ClusterIP Service named after the pod, exposing the service ports \
+                (ssh, http, https) of the spec. Implies a TCP readiness probe on the SSH port, \
+                unless the container has a readiness probe, so that the pod is only ready once \
+                sshd accepts connections."
+    )]
+    pub with_service: bool,
+
+    /// Create the namespace, labeled as managed by Axon, if it does not exist.
+    #[arg(
+        long = "create-namespace",
+        help = "Create the namespace, labeled as managed by Axon, if it does not exist. Without \
+                this flag, a missing namespace is an error, so that a typo never creates one."
+    )]
+    pub create_namespace: bool,
+
+    /// Create the pod from a full Pod manifest in YAML or JSON, read from a
+    /// file or from stdin with `-`, instead of from a spec.
+    ///
+    /// Its name and namespace are used unless given on the command line, and
+    /// the labels and annotations Axon relies on are merged into it.
+    #[arg(
+        short = 'f',
+        long = "filename",
+        value_name = "PATH",
+        help = "Create the pod from a full Pod manifest in YAML or JSON, read from PATH or from \
+                stdin with '-', instead of from a spec. The labels and annotations Axon relies on \
+                are merged into it."
+    )]
+    pub filename: Option<PathBuf>,
+
+    /// The container of the manifest to attach to, required if it has more
+    /// than one container and does not name a default container.
+    #[arg(
+        short = 'c',
+        long = "container",
+        requires = "filename",
+        help = "The container of the manifest given with --filename to attach to. Required if the \
+                manifest has more than one container and does not name a default container."
+    )]
+    pub container: Option<String>,
+
+    /// The CPU architecture substituted for `{arch}` in the images of the
+    /// spec, instead of the one of the local machine.
+    #[arg(
+        long = "arch",
+        value_name = "ARCH",
+        help = "The CPU architecture substituted for {arch} in the images of the spec, e.g. \
+                'amd64' or 'arm64'. Defaults to the architecture of the local machine."
+    )]
+    pub arch: Option<String>,
+
+    /// Append a random suffix to the pod name, so that several pods can be
+    /// created from the same name in parallel. The final name is printed to
+    /// stdout, even with `--quiet`.
+    #[arg(
+        long = "random-suffix",
+        help = "Append a random suffix to the pod name, e.g. 'axon-3f9a0c2e', so that several \
+                pods can be created from the same name in parallel. The final name is printed to \
+                stdout, even with --quiet. Fails rather than attaching if a pod of that name \
+                exists already."
+    )]
+    pub random_suffix: bool,
+
+    /// Spread the Axon pods across nodes with a pod anti-affinity, preferred
+    /// by default, overriding the `spread` of the spec.
+    #[arg(
+        long = "spread",
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "preferred",
+        help = "Spread the Axon pods across nodes with a pod anti-affinity on the managed-by \
+                label. 'preferred' (the default) lets the scheduler fall back to a node with \
+                another Axon pod, 'required' does not. Overrides the spread of the spec."
+    )]
+    pub spread: Option<Spread>,
+
+    /// The service account the pod runs as, overriding the `serviceAccount`
+    /// of the spec.
+    #[arg(
+        long = "service-account",
+        value_name = "NAME",
+        help = "The service account the pod runs as, e.g. to call the Kubernetes API with its \
+                permissions from inside the pod. Overrides the serviceAccount of the spec."
     )]
-    pub timeout_secs: u64,
+    pub service_account: Option<String>,
+
+    /// The name of the container of the pod, overriding the `containerName`
+    /// of the spec.
+    #[arg(
+        long = "container-name",
+        value_name = "NAME",
+        conflicts_with = "filename",
+        help = "The name of the container of the pod, which is also the container attached to by \
+                default. Overrides the containerName of the spec, defaults to 'axon-container'."
+    )]
+    pub container_name: Option<String>,
+
+    /// Pick the spec with a fuzzy finder over the specs of the configuration,
+    /// instead of using the default spec.
+    #[arg(
+        short = 'i',
+        long = "interactive",
+        conflicts_with = "filename",
+        help = "Pick the spec with a fuzzy finder over the specs of the configuration, showing \
+                their name, image and pull policy. Falls back to the default spec if the \
+                selection is aborted or stdin is not a terminal."
+    )]
+    pub interactive: bool,
+
+    /// Print the manifest of the pod as YAML instead of creating it, without
+    /// any interaction with the cluster.
+    #[arg(
+        long = "dump-manifest",
+        help = "Print the manifest of the pod as YAML to stdout instead of creating it. Needs no \
+                reachable cluster, so the namespace is only set if given by -n, the manifest or \
+                the spec."
+    )]
+    pub dump_manifest: bool,
 
     /// Defines the mode for pod creation, specifying how the pod's image and
     /// configuration are determined.
@@ -97,7 +274,10 @@ impl CreateCommand {
     /// the pod specification based on the chosen `Mode` (default, preset, or
     /// manual), constructs the Kubernetes Pod manifest, creates the pod in
     /// the cluster, and if `auto_attach` is true, waits for the pod to be
-    /// running and then initiates an interactive console session.
+    /// running and then initiates an interactive console session. A pod that
+    /// already exists is left as is, and only attached to unless
+    /// `no_attach_on_exist` is set, except with `random_suffix`, where it is
+    /// never adopted and the final pod name is printed to stdout.
     ///
     /// # Arguments
     ///
@@ -111,85 +291,694 @@ impl CreateCommand {
     ///
     /// Returns an `Error` if:
     /// - A specified preset `spec_name` is not found in the configuration.
+    /// - The pod name with the suffix of `random_suffix` is not a valid
+    ///   DNS-1123 label.
+    /// - The service account name is not a valid resource name.
+    /// - The container name is not a valid DNS-1123 label.
+    /// - An image of the spec contains an unknown placeholder or is not a valid
+    ///   image reference.
+    /// - The manifest given with `filename` cannot be read, is not a valid Pod,
+    ///   or has no obvious container to attach to.
     /// - Serialization of the interactive shell command to JSON fails.
-    /// - Creation of the pod in Kubernetes fails.
+    /// - Creation of the pod in Kubernetes fails, including because a pod of
+    ///   the suffixed name exists already with `random_suffix`.
+    /// - The suffixed pod name cannot be written to stdout.
+    /// - The namespace cannot be read or created, if `create_namespace` is set.
+    /// - Creation of the companion Service fails, if `with_service` is set.
     /// - Waiting for the pod to reach a running state times out or fails.
-    /// - Attaching to the pod's console fails.
+    /// - Attaching to the pod's console fails, after printing the last lines of
+    ///   its logs if `show_logs_on_failure` is set.
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, pod_name, auto_attach, timeout_secs, mode } = self;
+        let pod = self
+            .build_pod(&config, |namespace, pod_name, spec_namespace| {
+                ResourceResolver::from((&kube_client, &config))
+                    .spec_namespace(spec_namespace)
+                    .resolve(namespace, pod_name)
+            })
+            .await?;
+        let Self {
+            auto_attach,
+            no_attach_on_exist,
+            show_logs_on_failure,
+            timeout_secs,
+            with_service,
+            create_namespace,
+            random_suffix,
+            ..
+        } = self;
+        let namespace = pod.metadata.namespace.clone().unwrap_or_default();
+        let pod_name = pod.metadata.name.clone().unwrap_or_default();
+        let interactive_shell = pod.interactive_shell();
+        let service_ports = pod.service_ports();
+        let default_container = pod.default_container();
 
-        // Resolve Identity
+        // Apply to Cluster
+        if create_namespace {
+            ensure_namespace(kube_client.clone(), &namespace).await?;
+        }
+        let api = Api::<Pod>::namespaced(kube_client.clone(), &namespace);
+
+        // A suffixed name is meant to be new, so an existing pod is someone else's
+        let pod_existed = ensure_pod(&api, &pod, &pod_name, &namespace, !random_suffix).await?;
+        if random_suffix {
+            // Scripts need the generated name, so it is not subject to `--quiet`
+            let mut stdout = tokio::io::stdout();
+            stdout
+                .write_all(format!("{pod_name}\n").as_bytes())
+                .await
+                .context(error::WriteStdoutSnafu)?;
+            stdout.flush().await.context(error::WriteStdoutSnafu)?;
+        }
+        if with_service {
+            ensure_service(kube_client, &pod_name, &namespace, &service_ports).await?;
+        }
+
+        if !auto_attach || (pod_existed && no_attach_on_exist) {
+            return Ok(());
+        }
+        let timeout = config.pod_ready_timeout(timeout_secs, DEFAULT_CREATE_POD_READY_TIMEOUT);
+        let attached = match api.await_running_status(&pod_name, &namespace, timeout).await {
+            Ok(_pod) => {
+                PodConsole::new(api.clone(), pod_name.clone(), namespace, interactive_shell)
+                    .container(default_container.clone())
+                    .run()
+                    .await
+                    .map_err(Error::from)
+            }
+            Err(err) => Err(err),
+        };
+        if attached.is_err() && show_logs_on_failure {
+            print_failure_logs(&api, &pod_name, default_container.as_deref()).await;
+        }
+        attached
+    }
+
+    /// Prints the manifest of the pod `run` would create to stdout as YAML,
+    /// without any interaction with the cluster.
+    ///
+    /// The namespace is the one given on the command line, in the manifest or
+    /// in the spec, and is left out if there is none, so that the current
+    /// Kubernetes context decides when the manifest is applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the manifest cannot be built, for the reasons
+    /// listed by `run`, serialized or written to stdout.
+    pub async fn dump_manifest(self, config: Config) -> Result<(), Error> {
+        let mut pod = self
+            .build_pod(&config, |namespace, pod_name, spec_namespace| ResolvedResources {
+                namespace: namespace
+                    .filter(|namespace| !namespace.is_empty())
+                    .or(spec_namespace)
+                    .unwrap_or_default(),
+                pod_name: pod_name
+                    .filter(|pod_name| !pod_name.is_empty())
+                    .unwrap_or_else(|| config.default_pod_name.clone()),
+            })
+            .await?;
+        if pod.metadata.namespace.as_deref() == Some("") {
+            pod.metadata.namespace = None;
+        }
+        let manifest = serde_yaml::to_string(&pod).context(error::SerializePodManifestSnafu)?;
+        tokio::io::stdout().write_all(manifest.as_bytes()).await.context(error::WriteStdoutSnafu)
+    }
+
+    /// Builds the manifest of the pod from the spec or the manifest given on
+    /// the command line, and the options overriding them.
+    ///
+    /// `resolve` determines the namespace and the name of the pod from those
+    /// given on the command line or in the manifest, and the namespace of the
+    /// spec.
+    async fn build_pod(
+        &self,
+        config: &Config,
+        resolve: impl FnOnce(Option<String>, Option<String>, Option<String>) -> ResolvedResources,
+    ) -> Result<Pod, Error> {
+        let source =
+            Source::load(config, self.filename.clone(), self.mode.clone(), self.interactive)
+                .await?;
+
+        // Resolve Identity, preferring the command line over the manifest or
+        // the spec
+        let namespace = self.namespace.clone();
+        let pod_name = self.pod_name.clone();
+        let (namespace, pod_name, spec_namespace) = match &source {
+            Source::Manifest { pod, .. } => (
+                namespace
+                    .filter(|namespace| !namespace.is_empty())
+                    .or_else(|| pod.metadata.namespace.clone()),
+                pod_name.or_else(|| pod.metadata.name.clone()),
+                None,
+            ),
+            Source::Spec(spec) => (namespace, pod_name, spec.namespace.clone()),
+        };
         let ResolvedResources { namespace, pod_name } =
-            ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name);
-
-        let target = match mode {
-            None | Some(Mode::Default) => config.find_default_spec(),
-            Some(Mode::Preset { spec_name }) => config
-                .find_spec_by_name(&spec_name)
-                .with_context(|| error::SpecNotFoundSnafu { spec_name: spec_name.clone() })?,
-            Some(Mode::Manual {
-                image,
-                image_pull_policy,
-                command,
-                args,
-                interactive_shell,
-                port_mappings,
-            }) => Spec {
-                name: pod_name.clone(),
-                image,
-                image_pull_policy,
-                port_mappings,
-                service_ports: ServicePorts::default(),
-                command,
-                args,
-                interactive_shell,
-            },
+            resolve(namespace, pod_name, spec_namespace);
+        let pod_name =
+            if self.random_suffix { append_suffix(&pod_name, &random_hex())? } else { pod_name };
+
+        // Construct the Pod Manifest
+        let mut pod = match source {
+            Source::Manifest { pod, filename } => {
+                adopt_pod_manifest(*pod, &pod_name, &namespace, self.container.clone(), &filename)?
+            }
+            Source::Spec(mut spec) => {
+                if let Some(container_name) = self.container_name.clone() {
+                    spec.container_name = Some(container_name);
+                }
+                let variables = ImageVariables::local(self.arch.clone());
+                pod_from_spec(*spec, &variables, &pod_name, &namespace)?
+            }
         };
+        if let Some(spec) = pod.spec.as_mut() {
+            apply_scheduling_overrides(spec, self.spread, self.service_account.clone())?;
+        }
+        if self.with_service {
+            add_ssh_readiness_probe(&mut pod);
+        }
+        Ok(pod)
+    }
+}
 
-        let interactive_shell = if target.interactive_shell.is_empty() {
-            DEFAULT_INTERACTIVE_SHELL.clone()
-        } else {
-            target.interactive_shell.clone()
+/// Prints the last lines of the logs of the pod `pod_name` to stderr, so that
+/// the user sees why it could not be attached to.
+async fn print_failure_logs(api: &Api<Pod>, pod_name: &str, container: Option<&str>) {
+    if let Some(logs) = api.recent_logs(pod_name, container, FAILURE_LOG_LINES).await {
+        eprint!("{}", render_failure_logs(pod_name, &logs));
+    } else {
+        eprintln!("No logs are available for pod/{pod_name}");
+    }
+}
+
+/// Renders the logs printed by `print_failure_logs` under a header naming the
+/// pod.
+fn render_failure_logs(pod_name: &str, logs: &str) -> String {
+    let newline = if logs.ends_with('\n') { "" } else { "\n" };
+    format!("Last {FAILURE_LOG_LINES} lines of the logs of pod/{pod_name}:\n{logs}{newline}")
+}
+
+/// What a pod is created from.
+enum Source {
+    /// A full Pod manifest, read from `filename`.
+    Manifest { pod: Box<Pod>, filename: PathBuf },
+    /// A spec of the configuration or given on the command line.
+    Spec(Box<Spec>),
+}
+
+impl Source {
+    /// Reads the manifest of `filename` if given, or selects the spec of
+    /// `mode`, picking it with the fuzzy finder if `interactive` is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if `filename` or `interactive` is combined with a
+    /// mode, if the manifest cannot be read, or if the preset of `mode` is
+    /// not found in the configuration.
+    async fn load(
+        config: &Config,
+        filename: Option<PathBuf>,
+        mode: Option<Mode>,
+        interactive: bool,
+    ) -> Result<Self, Error> {
+        let conflicting_flag = match (&filename, interactive) {
+            (Some(_), _) => Some("--filename"),
+            (None, true) => Some("--interactive"),
+            (None, false) => None,
         };
+        if let (Some(flag), Some(_)) = (conflicting_flag, &mode) {
+            return error::GenericSnafu {
+                message: format!("{flag} cannot be combined with a creation mode"),
+            }
+            .fail();
+        }
 
-        // Apply to Cluster
-        let api = Api::<Pod>::namespaced(kube_client, &namespace);
-
-        let pod_exists = api.get(&pod_name).await.is_ok();
-        if pod_exists {
-            println!("pod/{pod_name} has been created in namespace {namespace}");
-        } else {
-            // Construct the Pod Manifest
-            let pod = build_pod_manifest(&pod_name, &namespace, target, &interactive_shell)?;
-            let _resource =
-                api.create(&PostParams::default(), &pod).await.context(error::CreatePodSnafu {
-                    pod_name: pod_name.clone(),
-                    namespace: namespace.clone(),
-                })?;
-
-            println!("pod/{pod_name} created in namespace {namespace}");
+        if let Some(filename) = filename {
+            return Ok(Self::Manifest {
+                pod: Box::new(read_pod_manifest(&filename).await?),
+                filename,
+            });
         }
+        let mode = if interactive { pick_spec(config).await } else { mode };
+        select_spec(config, mode).map(|spec| Self::Spec(Box::new(spec)))
+    }
+}
 
-        if auto_attach {
-            let _pod = api
-                .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
-                .await?;
-            PodConsole::new(api, pod_name, namespace, interactive_shell)
-                .run()
+/// Lets the user pick a spec of the configuration with the fuzzy finder, if
+/// stdin is a terminal.
+///
+/// Returns the preset mode of the selected spec, or `None`, i.e. the default
+/// spec, if the selection is aborted or stdin is not a terminal.
+async fn pick_spec(config: &Config) -> Option<Mode> {
+    if !std::io::stdin().is_terminal() {
+        return None;
+    }
+    config.specs.find_spec().await.map(|spec_name| Mode::Preset { spec_name })
+}
+
+/// Applies the scheduling options given on the command line to `spec`,
+/// overriding those of the spec or manifest it was built from.
+///
+/// # Errors
+///
+/// Returns `InvalidServiceAccountName` if the resulting service account name
+/// is not a valid resource name.
+fn apply_scheduling_overrides(
+    spec: &mut PodSpec,
+    spread: Option<Spread>,
+    service_account: Option<String>,
+) -> Result<(), Error> {
+    if let Some(spread) = spread {
+        spread.apply(spec);
+    }
+    if service_account.is_some() {
+        spec.service_account_name = service_account;
+    }
+    if let Some(name) = &spec.service_account_name {
+        validate_dns1123_subdomain(name).map_err(|reason| {
+            error::InvalidServiceAccountNameSnafu { name: name.clone(), reason }.build()
+        })?;
+    }
+    Ok(())
+}
+
+/// Adds a TCP readiness probe on the SSH service port to the default
+/// container of `pod`, so that the pod is only ready once `sshd` accepts
+/// connections.
+///
+/// Nothing is added if the pod has no SSH service port, or if the container
+/// already has a readiness probe.
+fn add_ssh_readiness_probe(pod: &mut Pod) {
+    let (Some(ssh_port), Some(container_name)) = (pod.service_ports().ssh, pod.default_container())
+    else {
+        return;
+    };
+    let container = pod
+        .spec
+        .iter_mut()
+        .flat_map(|spec| spec.containers.iter_mut())
+        .find(|container| container.name == container_name);
+    if let Some(container) = container {
+        let _probe = container
+            .readiness_probe
+            .get_or_insert_with(|| Probe::tcp_socket(ssh_port).to_kubernetes_probe());
+    }
+}
+
+/// Appends `suffix` to `pod_name`, truncating `pod_name` so that the result
+/// fits in a DNS-1123 label.
+///
+/// # Errors
+///
+/// Returns `InvalidPodName` if the result is not a valid DNS-1123 label, e.g.
+/// because `pod_name` contains uppercase letters.
+fn append_suffix(pod_name: &str, suffix: &str) -> Result<String, Error> {
+    let max_length = MAX_POD_NAME_LENGTH.saturating_sub(suffix.len() + 1);
+    let base = pod_name.get(..max_length).unwrap_or(pod_name).trim_end_matches('-');
+    let pod_name = format!("{base}-{suffix}");
+    validate_dns1123_label(&pod_name).map_err(|reason| {
+        error::InvalidPodNameSnafu { pod_name: pod_name.clone(), reason }.build()
+    })?;
+    Ok(pod_name)
+}
+
+/// Checks that `name` is a DNS-1123 label: at most 63 lowercase alphanumeric
+/// characters or `-`, starting and ending with an alphanumeric character.
+fn validate_dns1123_label(name: &str) -> Result<(), String> {
+    if name.len() > MAX_POD_NAME_LENGTH {
+        return Err(format!("it is longer than {MAX_POD_NAME_LENGTH} characters"));
+    }
+    validate_dns1123_characters(name)
+}
+
+/// Checks that `name` is a DNS-1123 subdomain, as required for the names of
+/// most resources: at most 253 characters, made of DNS-1123 labels separated
+/// by `.`.
+fn validate_dns1123_subdomain(name: &str) -> Result<(), String> {
+    if name.len() > MAX_RESOURCE_NAME_LENGTH {
+        return Err(format!("it is longer than {MAX_RESOURCE_NAME_LENGTH} characters"));
+    }
+    name.split('.').try_for_each(validate_dns1123_characters)
+}
+
+/// Checks that `label` consists of lowercase alphanumeric characters or `-`,
+/// and starts and ends with an alphanumeric character.
+fn validate_dns1123_characters(label: &str) -> Result<(), String> {
+    let is_alphanumeric = |c: char| c.is_ascii_lowercase() || c.is_ascii_digit();
+    if !label.chars().all(|c| is_alphanumeric(c) || c == '-') {
+        return Err("it must consist of lowercase alphanumeric characters or '-'".to_string());
+    }
+    if !label.starts_with(is_alphanumeric) || !label.ends_with(is_alphanumeric) {
+        return Err("it must start and end with an alphanumeric character".to_string());
+    }
+    Ok(())
+}
+
+/// Checks that `image` is a plausible image reference,
+/// `[REGISTRY[:PORT]/]PATH[:TAG][@DIGEST]`, e.g. `docker.io/alpine:3.20`.
+///
+/// The check is lenient and only rejects references the container runtime
+/// could never pull, such as empty path components, empty tags or tags with
+/// invalid characters.
+fn validate_image_reference(image: &str) -> Result<(), String> {
+    let is_alphanumeric = |c: char| c.is_ascii_alphanumeric();
+    if image.is_empty() {
+        return Err("it is empty".to_string());
+    }
+    let (name, digest) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest)),
+        None => (image, None),
+    };
+    if let Some(digest) = digest {
+        let valid = digest.split_once(':').is_some_and(|(algorithm, encoded)| {
+            !algorithm.is_empty()
+                && algorithm.chars().all(|c| is_alphanumeric(c) || "+._-".contains(c))
+                && !encoded.is_empty()
+                && encoded.chars().all(is_alphanumeric)
+        });
+        if !valid {
+            return Err(format!("'{digest}' is not a valid digest"));
+        }
+    }
+
+    // A `:` after the last `/` separates the tag, one before is a registry port
+    let (name, tag) = match name.rsplit_once(':') {
+        Some((path, tag)) if !tag.contains('/') => (path, Some(tag)),
+        _ => (name, None),
+    };
+    if let Some(tag) = tag {
+        let valid = tag.len() <= 128
+            && tag.starts_with(|c: char| is_alphanumeric(c) || c == '_')
+            && tag.chars().all(|c| is_alphanumeric(c) || "_.-".contains(c));
+        if !valid {
+            return Err(format!("'{tag}' is not a valid tag"));
+        }
+    }
+
+    let mut components = name.split('/').peekable();
+    let registry = components.next_if(|component| {
+        name.contains('/') && (component.contains(['.', ':']) || *component == "localhost")
+    });
+    if let Some(registry) = registry {
+        let (host, port) = registry.split_once(':').unwrap_or((registry, "0"));
+        if host.is_empty()
+            || !host.chars().all(|c| is_alphanumeric(c) || ".-".contains(c))
+            || port.parse::<u16>().is_err()
+        {
+            return Err(format!("'{registry}' is not a valid registry"));
+        }
+    }
+    for component in components {
+        let valid = component.starts_with(is_alphanumeric)
+            && component.ends_with(is_alphanumeric)
+            && component
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "._-".contains(c));
+        if !valid {
+            return Err(format!(
+                "'{component}' is not a valid path component, it must consist of lowercase \
+                 alphanumeric characters separated by '.', '_' or '-'"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Creates the pod `pod_name` from `pod`, unless it already exists and
+/// `adopt_existing` is set, and reports which was the case on stdout.
+///
+/// Returns whether the pod already existed.
+///
+/// # Errors
+///
+/// Returns an `Error` if the pod cannot be created, e.g. because it already
+/// exists and `adopt_existing` is not set.
+async fn ensure_pod(
+    api: &Api<Pod>,
+    pod: &Pod,
+    pod_name: &str,
+    namespace: &str,
+    adopt_existing: bool,
+) -> Result<bool, Error> {
+    let existed = adopt_existing && api.get(pod_name).await.is_ok();
+    if !existed {
+        let _resource = api
+            .create(&PostParams::default(), pod)
+            .await
+            .context(error::CreatePodSnafu { namespace, pod_name })?;
+    }
+    output::status(pod_status_line(pod_name, namespace, existed));
+    Ok(existed)
+}
+
+/// Returns the line reporting that the pod `pod_name` has been created, or
+/// that it `existed` already.
+fn pod_status_line(pod_name: &str, namespace: &str, existed: bool) -> String {
+    if existed {
+        format!("pod/{pod_name} already exists in namespace {namespace}")
+    } else {
+        format!("pod/{pod_name} created in namespace {namespace}")
+    }
+}
+
+/// Creates the companion Service of the pod `pod_name`, exposing its service
+/// ports, unless it already exists or there are no service ports, and reports
+/// which was the case on stdout.
+///
+/// # Errors
+///
+/// Returns an `Error` if the Service cannot be created.
+async fn ensure_service(
+    kube_client: kube::Client,
+    pod_name: &str,
+    namespace: &str,
+    service_ports: &ServicePorts,
+) -> Result<(), Error> {
+    let api = Api::<Service>::namespaced(kube_client, namespace);
+    match build_service_manifest(pod_name, namespace, service_ports) {
+        None => {
+            output::status(format_args!(
+                "service/{pod_name} skipped, no service ports are defined"
+            ));
+        }
+        Some(_) if api.get(pod_name).await.is_ok() => {
+            output::status(format_args!(
+                "service/{pod_name} already exists in namespace {namespace}"
+            ));
+        }
+        Some(service) => {
+            let _resource = api
+                .create(&PostParams::default(), &service)
                 .await
-                .map_err(Error::from)
-        } else {
-            Ok(())
+                .context(error::CreateServiceSnafu { service_name: pod_name, namespace })?;
+            output::status(format_args!("service/{pod_name} created in namespace {namespace}"));
+        }
+    }
+    Ok(())
+}
+
+/// Creates the namespace `namespace`, labeled as managed by Axon, unless it
+/// already exists, and reports which was the case on stdout.
+///
+/// # Errors
+///
+/// Returns an `Error` if the namespace cannot be read or created.
+async fn ensure_namespace(kube_client: kube::Client, namespace: &str) -> Result<(), Error> {
+    let api = Api::<Namespace>::all(kube_client);
+    if api.get_opt(namespace).await.context(error::GetNamespaceSnafu { namespace })?.is_some() {
+        output::status(format_args!("namespace/{namespace} already exists"));
+        return Ok(());
+    }
+
+    let manifest = Namespace {
+        metadata: ObjectMeta {
+            name: Some(namespace.to_string()),
+            labels: Some(BTreeMap::from([(
+                labels::MANAGED_BY.to_string(),
+                PROJECT_NAME.to_string(),
+            )])),
+            ..ObjectMeta::default()
+        },
+        ..Namespace::default()
+    };
+    match api.create(&PostParams::default(), &manifest).await {
+        Ok(_) => output::status(format_args!("namespace/{namespace} created")),
+        // Created concurrently, e.g. by another job of the same pipeline
+        Err(kube::Error::Api(status)) if status.is_already_exists() => {
+            output::status(format_args!("namespace/{namespace} already exists"));
+        }
+        Err(source) => return Err(source).context(error::CreateNamespaceSnafu { namespace }),
+    }
+    Ok(())
+}
+
+/// Selects the spec of `mode`: the default spec, a preset of the
+/// configuration, or a spec given on the command line.
+///
+/// # Errors
+///
+/// Returns an `Error` if the preset is not found in the configuration.
+fn select_spec(config: &Config, mode: Option<Mode>) -> Result<Spec, Error> {
+    let spec = match mode {
+        None | Some(Mode::Default) => config.find_default_spec(),
+        Some(Mode::Preset { spec_name }) => config
+            .find_spec_by_name(&spec_name)
+            .with_context(|| error::SpecNotFoundSnafu { spec_name: spec_name.clone() })?,
+        Some(Mode::Manual {
+            image,
+            image_pull_policy,
+            command,
+            args,
+            interactive_shell,
+            port_mappings,
+        }) => Spec {
+            name: PROJECT_NAME.to_string(),
+            image,
+            image_pull_policy,
+            port_mappings,
+            service_ports: ServicePorts::default(),
+            command,
+            args,
+            interactive_shell: to_argv(interactive_shell),
+            init_containers: Vec::new(),
+            sidecars: Vec::new(),
+            spread: None,
+            service_account: None,
+            namespace: None,
+            container_name: None,
+            readiness_probe: None,
+            liveness_probe: None,
+        },
+    };
+    Ok(spec)
+}
+
+/// Builds the Pod manifest of `spec`, whose image placeholders are substituted
+/// with `variables`.
+///
+/// # Errors
+///
+/// Returns an `Error` if an image has an unknown placeholder, or if the
+/// interactive shell cannot be serialized.
+fn pod_from_spec(
+    spec: Spec,
+    variables: &ImageVariables,
+    pod_name: &str,
+    namespace: &str,
+) -> Result<Pod, Error> {
+    let target = spec.render_images(variables)?;
+
+    let interactive_shell = if target.interactive_shell.is_empty() {
+        DEFAULT_INTERACTIVE_SHELL.clone()
+    } else {
+        target.interactive_shell.clone()
+    };
+    build_pod_manifest(pod_name, namespace, target, &interactive_shell)
+}
+
+/// Reads a Pod manifest in YAML or JSON from `filename`, or from stdin if it
+/// is `-`.
+///
+/// # Errors
+///
+/// Returns an `Error` if the manifest cannot be read or is not a valid `Pod`.
+async fn read_pod_manifest(filename: &Path) -> Result<Pod, Error> {
+    let data = if filename == Path::new("-") {
+        let mut data = Vec::new();
+        let _size = tokio::io::stdin()
+            .read_to_end(&mut data)
+            .await
+            .context(error::ReadPodManifestSnafu { filename })?;
+        data
+    } else {
+        tokio::fs::read(filename).await.context(error::ReadPodManifestSnafu { filename })?
+    };
+    serde_yaml::from_slice(&data).context(error::ParsePodManifestSnafu { filename })
+}
+
+/// Adopts a user-provided Pod manifest, so that it is managed by Axon like the
+/// pods built from a spec.
+///
+/// The name and namespace of the pod are set to `pod_name` and `namespace`,
+/// and the labels and annotations Axon relies on are merged into those of the
+/// manifest. Fields set by the API server, such as the resource version or
+/// the status, are cleared so that the output of `kubectl get -o yaml` can be
+/// used as is.
+///
+/// # Errors
+///
+/// Returns an `Error` if the manifest has no containers, if `container` is
+/// not one of them, or if it has several containers, none of which is named
+/// the default by the manifest or by `container`.
+fn adopt_pod_manifest(
+    mut pod: Pod,
+    pod_name: &str,
+    namespace: &str,
+    container: Option<String>,
+    filename: &Path,
+) -> Result<Pod, Error> {
+    let invalid = |reason: String| error::InvalidPodManifestSnafu { filename, reason }.build();
+    let containers = pod.spec.as_ref().map_or_else(Vec::new, |spec| {
+        spec.containers.iter().map(|container| container.name.clone()).collect()
+    });
+    let metadata = &mut pod.metadata;
+    let labels = metadata.labels.get_or_insert_default();
+    let annotations = metadata.annotations.get_or_insert_default();
+
+    let default_container = match (container, containers.as_slice()) {
+        (_, []) => return Err(invalid("it has no containers".to_string())),
+        (Some(container), _) if !containers.contains(&container) => {
+            return Err(invalid(format!("it has no container named '{container}'")));
+        }
+        (Some(container), _) => Some(container),
+        (None, [container]) => Some(container.clone()),
+        (None, _)
+            if labels.contains_key(labels::DEFAULT_CONTAINER)
+                || annotations.contains_key(labels::DEFAULT_CONTAINER) =>
+        {
+            None
+        }
+        (None, _) => {
+            return Err(invalid(format!(
+                "it has {} containers, use --container to choose the one to attach to",
+                containers.len()
+            )));
+        }
+    };
+    if let Some(default_container) = default_container {
+        if let Some(annotation) = annotations.get_mut(labels::DEFAULT_CONTAINER) {
+            annotation.clone_from(&default_container);
         }
+        let _unused = labels.insert(labels::DEFAULT_CONTAINER.to_string(), default_container);
+    }
+
+    labels.extend([
+        (labels::MANAGED_BY.to_string(), PROJECT_NAME.to_string()),
+        (labels::INSTANCE.to_string(), pod_name.to_string()),
+    ]);
+    if !annotations.contains_key(annotations::SHELL_INTERACTIVE.as_str()) {
+        let shell_json = serde_json::to_string(&*DEFAULT_INTERACTIVE_SHELL)
+            .context(error::SerializeInteractiveShellSnafu)?;
+        let _unused = annotations.insert(annotations::SHELL_INTERACTIVE.to_string(), shell_json);
     }
+    let _unused = annotations.insert(annotations::VERSION.to_string(), PROJECT_VERSION.to_string());
+
+    metadata.name = Some(pod_name.to_string());
+    metadata.namespace = Some(namespace.to_string());
+    metadata.generate_name = None;
+    metadata.resource_version = None;
+    metadata.uid = None;
+    metadata.creation_timestamp = None;
+    metadata.managed_fields = None;
+    pod.status = None;
+    Ok(pod)
 }
 
 /// Builds a Kubernetes `Pod` manifest based on the provided specifications.
 ///
 /// This function constructs a `Pod` object, populating its metadata (name,
-/// namespace, labels, annotations) and spec (containers, image, command,
-/// arguments, ports) according to the `pod_name`, `namespace`, `target`
-/// specification, and the interactive shell command.
+/// namespace, labels, annotations) and spec (containers, init containers,
+/// image, command, arguments, ports) according to the `pod_name`, `namespace`,
+/// `target` specification, and the interactive shell command.
 ///
 /// # Arguments
 ///
@@ -209,13 +998,17 @@ impl CreateCommand {
 /// # Errors
 ///
 /// Returns an `Error` if the `interactive_shell` cannot be serialized into a
-/// JSON string for the Kubernetes annotation.
+/// JSON string for the Kubernetes annotation, or if `validate_containers`
+/// rejects the containers of the spec.
 fn build_pod_manifest(
     pod_name: impl Into<String>,
     namespace: impl Into<String>,
     target: Spec,
     interactive_shell: &[String],
 ) -> Result<Pod, Error> {
+    let container_name =
+        target.container_name.clone().unwrap_or_else(|| DEFAULT_CONTAINER_NAME.to_string());
+    validate_containers(&target, &container_name)?;
     let image = Some(target.image);
     let command = (!target.command.is_empty()).then_some(target.command);
     let args = (!target.args.is_empty()).then_some(target.args);
@@ -231,9 +1024,44 @@ fn build_pod_manifest(
             .collect::<Vec<_>>()
     });
 
+    let pod_name = pod_name.into();
+    let init_containers = (!target.init_containers.is_empty()).then(|| {
+        target
+            .init_containers
+            .into_iter()
+            .map(|init_container| Container {
+                name: init_container.name,
+                image: Some(init_container.image),
+                image_pull_policy: Some(init_container.image_pull_policy.to_string()),
+                command: (!init_container.command.is_empty()).then_some(init_container.command),
+                args: (!init_container.args.is_empty()).then_some(init_container.args),
+                ..Container::default()
+            })
+            .collect::<Vec<_>>()
+    });
+    let sidecars = target.sidecars.into_iter().map(|sidecar| Container {
+        name: sidecar.name,
+        image: Some(sidecar.image),
+        image_pull_policy: Some(sidecar.image_pull_policy.to_string()),
+        command: (!sidecar.command.is_empty()).then_some(sidecar.command),
+        args: (!sidecar.args.is_empty()).then_some(sidecar.args),
+        ports: (!sidecar.ports.is_empty()).then(|| {
+            sidecar
+                .ports
+                .into_iter()
+                .map(|port| ContainerPort {
+                    container_port: i32::from(port),
+                    ..ContainerPort::default()
+                })
+                .collect()
+        }),
+        ..Container::default()
+    });
+
     let labels = BTreeMap::from_iter([
         (labels::MANAGED_BY.to_string(), PROJECT_NAME.to_string()),
-        (labels::DEFAULT_CONTAINER.to_string(), DEFAULT_CONTAINER_NAME.to_string()),
+        (labels::INSTANCE.to_string(), pod_name.clone()),
+        (labels::DEFAULT_CONTAINER.to_string(), container_name.clone()),
     ]);
 
     let annotations = {
@@ -249,30 +1077,127 @@ fn build_pod_manifest(
         .collect::<BTreeMap<_, _>>()
     };
 
+    let mut spec = PodSpec {
+        containers: std::iter::once(Container {
+            name: container_name,
+            image,
+            image_pull_policy,
+            command,
+            args,
+            ports: container_ports,
+            readiness_probe: target.readiness_probe.as_ref().map(Probe::to_kubernetes_probe),
+            liveness_probe: target.liveness_probe.as_ref().map(Probe::to_kubernetes_probe),
+            ..Container::default()
+        })
+        .chain(sidecars)
+        .collect(),
+        init_containers,
+        service_account_name: target.service_account,
+        ..PodSpec::default()
+    };
+    if let Some(spread) = target.spread {
+        spread.apply(&mut spec);
+    }
+
     Ok(Pod {
         metadata: ObjectMeta {
-            name: Some(pod_name.into()),
+            name: Some(pod_name),
             namespace: Some(namespace.into()),
             labels: Some(labels),
             annotations: Some(annotations),
             ..ObjectMeta::default()
         },
-        spec: Some(PodSpec {
-            containers: vec![Container {
-                name: DEFAULT_CONTAINER_NAME.to_string(),
-                image,
-                image_pull_policy,
-                command,
-                args,
-                ports: container_ports,
-                ..Container::default()
-            }],
-            ..PodSpec::default()
-        }),
+        spec: Some(spec),
         ..Pod::default()
     })
 }
 
+/// Checks the containers of `target`, whose main container is named
+/// `container_name`, before a Pod manifest is built from it.
+///
+/// # Errors
+///
+/// Returns `InvalidImageReference` if the image of the spec, of an init
+/// container or of a sidecar is malformed, or `InvalidContainerName` if the
+/// name of the container or of a sidecar is not a DNS-1123 label or is used
+/// by several containers.
+fn validate_containers(target: &Spec, container_name: &str) -> Result<(), Error> {
+    let images = std::iter::once(&target.image)
+        .chain(target.init_containers.iter().map(|init_container| &init_container.image))
+        .chain(target.sidecars.iter().map(|sidecar| &sidecar.image));
+    for image in images {
+        validate_image_reference(image).map_err(|reason| {
+            error::InvalidImageReferenceSnafu { image: image.clone(), reason }.build()
+        })?;
+    }
+
+    let mut names = HashSet::new();
+    let all_names = std::iter::once(container_name)
+        .chain(target.init_containers.iter().map(|init_container| init_container.name.as_str()))
+        .chain(target.sidecars.iter().map(|sidecar| sidecar.name.as_str()));
+    for name in all_names {
+        validate_dns1123_label(name)
+            .and_then(|()| {
+                names
+                    .insert(name)
+                    .then_some(())
+                    .ok_or_else(|| "it is used by several containers".to_string())
+            })
+            .map_err(|reason| {
+                error::InvalidContainerNameSnafu { name: name.to_string(), reason }.build()
+            })?;
+    }
+    Ok(())
+}
+
+/// Builds a `ClusterIP` Kubernetes `Service` manifest exposing the service
+/// ports of a pod created by Axon.
+///
+/// The Service is named after the pod and selects it through its
+/// `app.kubernetes.io/instance` label. It is labeled as managed by Axon, so
+/// that `delete --cascade` cleans it up along with the pod.
+///
+/// # Arguments
+///
+/// * `pod_name` - The name of the pod, also used as the name of the Service.
+/// * `namespace` - The Kubernetes namespace where the Service will reside.
+/// * `service_ports` - The ports to expose.
+///
+/// # Returns
+///
+/// The constructed `Service`, or `None` if no service port is defined.
+fn build_service_manifest(
+    pod_name: &str,
+    namespace: &str,
+    service_ports: &ServicePorts,
+) -> Option<Service> {
+    let ports = service_ports.to_kubernetes_service_ports();
+    if ports.is_empty() {
+        return None;
+    }
+
+    let labels = BTreeMap::from_iter([
+        (labels::MANAGED_BY.to_string(), PROJECT_NAME.to_string()),
+        (labels::INSTANCE.to_string(), pod_name.to_string()),
+    ]);
+
+    Some(Service {
+        metadata: ObjectMeta {
+            name: Some(pod_name.to_string()),
+            namespace: Some(namespace.to_string()),
+            labels: Some(labels.clone()),
+            ..ObjectMeta::default()
+        },
+        spec: Some(ServiceSpec {
+            type_: Some("ClusterIP".to_string()),
+            selector: Some(labels),
+            ports: Some(ports),
+            ..ServiceSpec::default()
+        }),
+        ..Service::default()
+    })
+}
+
 /// Defines the different modes for creating a Kubernetes pod.
 ///
 /// Users can choose between a default configuration, a predefined preset
@@ -336,14 +1261,16 @@ pub enum Mode {
         args: Vec<String>,
 
         /// Interactive shell command and arguments to use when attaching to the
-        /// container (e.g., `/bin/bash`, `bash -c 'sh'`).
+        /// container (e.g., `/bin/bash`, `bash -c 'sh'`), split into arguments
+        /// like a shell would.
         #[arg(
             long = "shell",
             action = ArgAction::Append,
+            allow_hyphen_values = true,
             default_value = "/bin/sh",
-            help = "Interactive shell command and arguments to use when attaching to the container (e.g., `/bin/bash`, `bash -c 'sh'`)."
+            help = "Interactive shell command and arguments to use when attaching to the container (e.g., `/bin/bash`, `bash -c 'sh'`), split into arguments like a shell would."
         )]
-        interactive_shell: Vec<String>,
+        interactive_shell: Vec<ShellWords>,
 
         /// Port mappings to forward from the local machine to the container
         /// (e.g., `8080:80/tcp`). Can be specified multiple times.
@@ -355,3 +1282,328 @@ pub enum Mode {
         port_mappings: Vec<PortMapping>,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Sidecar;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        create: CreateCommand,
+    }
+
+    #[test]
+    fn test_spread() {
+        let spec = Spec { spread: Some(Spread::Required), ..Spec::default() };
+        let pod = build_pod_manifest("axon", "dev", spec, &[]).unwrap();
+        let affinity = pod.spec.unwrap().affinity.unwrap();
+        assert!(
+            affinity
+                .pod_anti_affinity
+                .unwrap()
+                .required_during_scheduling_ignored_during_execution
+                .is_some()
+        );
+
+        let pod = build_pod_manifest("axon", "dev", Spec::default(), &[]).unwrap();
+        assert_eq!(pod.spec.unwrap().affinity, None);
+
+        let spread = |args: &[&str]| {
+            Cli::try_parse_from([&["create"], args].concat()).map(|cli| cli.create.spread)
+        };
+        assert_eq!(spread(&[]).unwrap(), None);
+        assert_eq!(spread(&["--spread"]).unwrap(), Some(Spread::Preferred));
+        assert_eq!(spread(&["--spread=required"]).unwrap(), Some(Spread::Required));
+        assert!(spread(&["--spread=always"]).is_err());
+    }
+
+    #[test]
+    fn test_show_logs_on_failure() {
+        let show_logs = |args: &[&str]| {
+            Cli::try_parse_from([&["create"], args].concat())
+                .map(|cli| cli.create.show_logs_on_failure)
+        };
+        assert!(show_logs(&[]).unwrap());
+        assert!(show_logs(&["--show-logs-on-failure"]).unwrap());
+        assert!(!show_logs(&["--show-logs-on-failure=false"]).unwrap());
+
+        assert_eq!(
+            render_failure_logs("axon", "panic: boom"),
+            "Last 20 lines of the logs of pod/axon:\npanic: boom\n"
+        );
+        assert_eq!(
+            render_failure_logs("axon", "panic: boom\n"),
+            "Last 20 lines of the logs of pod/axon:\npanic: boom\n"
+        );
+    }
+
+    #[test]
+    fn test_pod_status_line() {
+        assert_eq!(pod_status_line("axon", "dev", false), "pod/axon created in namespace dev");
+        assert_eq!(
+            pod_status_line("axon", "dev", true),
+            "pod/axon already exists in namespace dev"
+        );
+    }
+
+    #[test]
+    fn test_service_account() {
+        let spec = Spec { service_account: Some("debugger".to_string()), ..Spec::default() };
+        let pod = build_pod_manifest("axon", "dev", spec, &[]).unwrap();
+        assert_eq!(pod.spec.unwrap().service_account_name.as_deref(), Some("debugger"));
+
+        for name in ["debugger", "ci.kube-system", "a1"] {
+            assert_eq!(validate_dns1123_subdomain(name), Ok(()), "{name}");
+        }
+        for name in ["Debugger", "ci..system", "-ci", "ci_bot", ""] {
+            assert!(validate_dns1123_subdomain(name).is_err(), "{name}");
+        }
+        assert!(validate_dns1123_subdomain(&"a".repeat(254)).is_err());
+
+        let mut spec = PodSpec { service_account_name: Some("spec".into()), ..PodSpec::default() };
+        apply_scheduling_overrides(&mut spec, None, None).unwrap();
+        assert_eq!(spec.service_account_name.as_deref(), Some("spec"));
+        apply_scheduling_overrides(&mut spec, None, Some("cli".into())).unwrap();
+        assert_eq!(spec.service_account_name.as_deref(), Some("cli"));
+        assert!(matches!(
+            apply_scheduling_overrides(&mut spec, None, Some("CLI".into())),
+            Err(Error::InvalidServiceAccountName { .. })
+        ));
+    }
+
+    #[test]
+    fn test_probes() {
+        let probe = |port| Some(Probe::tcp_socket(port));
+        let spec = Spec { liveness_probe: probe(8080), ..Spec::default() };
+        let pod = build_pod_manifest("axon", "dev", spec, &[]).unwrap();
+        let container = &pod.spec.as_ref().unwrap().containers[0];
+        assert_eq!(container.readiness_probe, None);
+        assert_eq!(container.liveness_probe, probe(8080).map(|probe| probe.to_kubernetes_probe()));
+
+        let ssh = ServicePorts { ssh: Some(2222), ..ServicePorts::default() };
+        let readiness_probe = |spec| {
+            let mut pod = build_pod_manifest("axon", "dev", spec, &[]).unwrap();
+            add_ssh_readiness_probe(&mut pod);
+            pod.spec.unwrap().containers.swap_remove(0).readiness_probe
+        };
+        assert_eq!(
+            readiness_probe(Spec { service_ports: ssh.clone(), ..Spec::default() }),
+            probe(2222).map(|probe| probe.to_kubernetes_probe())
+        );
+        assert_eq!(
+            readiness_probe(Spec {
+                service_ports: ssh,
+                readiness_probe: probe(22),
+                ..Spec::default()
+            }),
+            probe(22).map(|probe| probe.to_kubernetes_probe())
+        );
+        assert_eq!(readiness_probe(Spec::default()), None);
+    }
+
+    #[test]
+    fn test_sidecars() {
+        let sidecar = |name: &str| Sidecar {
+            name: name.to_string(),
+            image: "docker.io/envoyproxy/envoy:v1.31-latest".to_string(),
+            image_pull_policy: ImagePullPolicy::IfNotPresent,
+            command: Vec::new(),
+            args: vec!["--log-level".to_string(), "debug".to_string()],
+            ports: vec![10_000],
+        };
+        let spec = Spec { sidecars: vec![sidecar("proxy")], ..Spec::default() };
+        let pod = build_pod_manifest("axon", "dev", spec, &[]).unwrap();
+        assert_eq!(pod.default_container().as_deref(), Some(DEFAULT_CONTAINER_NAME));
+        let containers = pod.spec.unwrap().containers;
+        assert_eq!(
+            containers.iter().map(|container| container.name.as_str()).collect::<Vec<_>>(),
+            [DEFAULT_CONTAINER_NAME, "proxy"]
+        );
+        assert_eq!(containers[1].command, None);
+        assert_eq!(containers[1].args.as_ref().unwrap().len(), 2);
+        assert_eq!(containers[1].ports.as_ref().unwrap()[0].container_port, 10_000);
+        assert_eq!(containers[1].image_pull_policy.as_deref(), Some("IfNotPresent"));
+
+        for sidecars in [
+            vec![sidecar("proxy"), sidecar("proxy")],
+            vec![sidecar(DEFAULT_CONTAINER_NAME)],
+            vec![sidecar("Proxy")],
+        ] {
+            let spec = Spec { sidecars, ..Spec::default() };
+            assert!(matches!(
+                build_pod_manifest("axon", "dev", spec, &[]),
+                Err(Error::InvalidContainerName { .. })
+            ));
+        }
+        let spec = Spec {
+            sidecars: vec![Sidecar { image: "envoy:".to_string(), ..sidecar("proxy") }],
+            ..Spec::default()
+        };
+        assert!(matches!(
+            build_pod_manifest("axon", "dev", spec, &[]),
+            Err(Error::InvalidImageReference { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_container_name() {
+        let pod = build_pod_manifest("axon", "dev", Spec::default(), &[]).unwrap();
+        assert_eq!(pod.default_container().as_deref(), Some(DEFAULT_CONTAINER_NAME));
+
+        let spec = Spec { container_name: Some("shell".to_string()), ..Spec::default() };
+        let pod = build_pod_manifest("axon", "dev", spec, &[]).unwrap();
+        assert_eq!(pod.metadata.labels.as_ref().unwrap()[labels::DEFAULT_CONTAINER], "shell");
+        assert_eq!(pod.default_container().as_deref(), Some("shell"));
+        assert_eq!(pod.spec.unwrap().containers[0].name, "shell");
+
+        let cli = Cli::try_parse_from(["create", "--container-name", "debug"]).unwrap();
+        let pod = cli
+            .create
+            .build_pod(&Config::default(), |_, _, _| ResolvedResources {
+                namespace: "dev".to_string(),
+                pod_name: "axon".to_string(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(pod.default_container().as_deref(), Some("debug"));
+        assert_eq!(pod.spec.unwrap().containers[0].name, "debug");
+
+        let spec = Spec { container_name: Some("Shell".to_string()), ..Spec::default() };
+        assert!(matches!(
+            build_pod_manifest("axon", "dev", spec, &[]),
+            Err(Error::InvalidContainerName { .. })
+        ));
+        assert!(
+            Cli::try_parse_from(["create", "-f", "pod.yaml", "--container-name", "a"]).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_pod() {
+        let cli =
+            Cli::try_parse_from(["create", "--dump-manifest", "--service-account", "ci"]).unwrap();
+        assert!(cli.create.dump_manifest);
+        let pod = cli
+            .create
+            .build_pod(&Config::default(), |_namespace, pod_name, spec_namespace| {
+                assert_eq!((pod_name, spec_namespace), (None, None));
+                ResolvedResources { namespace: "dev".to_string(), pod_name: "axon".to_string() }
+            })
+            .await
+            .unwrap();
+        assert_eq!(pod.metadata.name.as_deref(), Some("axon"));
+        assert_eq!(pod.metadata.namespace.as_deref(), Some("dev"));
+        assert_eq!(pod.spec.unwrap().service_account_name.as_deref(), Some("ci"));
+    }
+
+    #[test]
+    fn test_validate_image_reference() {
+        for image in [
+            "alpine",
+            "alpine:3.20",
+            "docker.io/library/alpine:latest",
+            "my-repo/my-image:latest",
+            "localhost/tools",
+            "localhost:5000/tools:v1.0-rc_1",
+            "registry.example.com:443/team/sub_team/image.name",
+            "ghcr.io/org/image@sha256:0123456789abcdef",
+            "alpine:3@sha256:0123456789abcdef",
+            "UPPER.example.com/image",
+        ] {
+            assert_eq!(validate_image_reference(image), Ok(()), "{image}");
+        }
+        for image in [
+            "",
+            "docker.io//alpine:latest",
+            "docker.io/alpine:",
+            "alpine:la$test",
+            "alpine:-latest",
+            "docker.io/Alpine",
+            "/alpine",
+            "alpine/",
+            "alpine@sha256",
+            "alpine@:0123",
+            "registry.example.com:http/alpine",
+            "my_registry.io/alpine",
+        ] {
+            assert!(validate_image_reference(image).is_err(), "{image}");
+        }
+
+        let spec = Spec { image: "docker.io//alpine:".to_string(), ..Spec::default() };
+        assert!(matches!(
+            build_pod_manifest("axon", "dev", spec, &[]),
+            Err(Error::InvalidImageReference { .. })
+        ));
+    }
+
+    #[test]
+    fn test_append_suffix() {
+        assert_eq!(append_suffix("axon", "3f9a").unwrap(), "axon-3f9a");
+
+        let long_name = format!("{}-debug", "a".repeat(60));
+        let pod_name = append_suffix(&long_name, "3f9a").unwrap();
+        assert_eq!(pod_name, format!("{}-3f9a", "a".repeat(58)));
+        assert_eq!(pod_name.len(), MAX_POD_NAME_LENGTH);
+
+        let dash_at_cut = format!("{}-{}", "a".repeat(57), "b".repeat(10));
+        assert_eq!(
+            append_suffix(&dash_at_cut, "3f9a").unwrap(),
+            format!("{}-3f9a", "a".repeat(57))
+        );
+
+        for pod_name in ["Axon", "axon_debug", "-axon", ""] {
+            assert!(
+                matches!(append_suffix(pod_name, "3f9a"), Err(Error::InvalidPodName { .. })),
+                "{pod_name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_adopt_pod_manifest() {
+        let manifest = |containers: &str| -> Pod {
+            serde_yaml::from_str(&format!(
+                "metadata:\n  name: debug\n  resourceVersion: '42'\n  labels:\n    team: \
+                 infra\nspec:\n  containers: {containers}\nstatus:\n  phase: Running\n"
+            ))
+            .unwrap()
+        };
+        let adopt = |pod: Pod, container: Option<&str>| {
+            adopt_pod_manifest(pod, "axon", "dev", container.map(String::from), Path::new("-"))
+        };
+
+        let pod = adopt(manifest("[{name: main, image: alpine}]"), None).unwrap();
+        assert_eq!(pod.metadata.name.as_deref(), Some("axon"));
+        assert_eq!(pod.metadata.namespace.as_deref(), Some("dev"));
+        assert_eq!(pod.metadata.resource_version, None);
+        assert_eq!(pod.status, None);
+        let labels = pod.metadata.labels.as_ref().unwrap();
+        assert_eq!(labels["team"], "infra");
+        assert_eq!(labels[labels::MANAGED_BY], PROJECT_NAME);
+        assert_eq!(labels[labels::INSTANCE], "axon");
+        assert_eq!(pod.default_container().as_deref(), Some("main"));
+        assert_eq!(pod.interactive_shell(), *DEFAULT_INTERACTIVE_SHELL);
+
+        let two_containers = "[{name: main, image: alpine}, {name: tools, image: busybox}]";
+        let pod = adopt(manifest(two_containers), Some("tools")).unwrap();
+        assert_eq!(pod.default_container().as_deref(), Some("tools"));
+
+        for (containers, container, reason) in [
+            ("[]", None, "it has no containers"),
+            (two_containers, Some("debug"), "it has no container named 'debug'"),
+            (
+                two_containers,
+                None,
+                "it has 2 containers, use --container to choose the one to attach to",
+            ),
+        ] {
+            let err = adopt(manifest(containers), container).unwrap_err();
+            assert!(
+                matches!(&err, Error::InvalidPodManifest { reason: actual, .. } if actual == reason),
+                "{err}"
+            );
+        }
+    }
+}
@@ -3,26 +3,37 @@
 //! This module provides the `DeleteCommand` struct, which defines the
 //! command-line arguments and logic for deleting one or more temporary pods. It
 //! supports specifying pod names directly or using a fuzzy finder for
-//! interactive selection if no names are provided.
+//! interactive selection if no names are provided. With `--selector`, every
+//! pod matching a label selector is deleted instead, and `--dry-run` only
+//! prints the pods the selector matches. With `--cascade`, the services and
+//! config maps managed by Axon that share a pod's name are deleted along with
+//! it.
+
+use std::io::IsTerminal;
 
 use clap::{ArgAction, Args};
 use futures::{StreamExt, TryStreamExt};
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::{
+    NamespaceResourceScope,
+    api::core::v1::{ConfigMap, Pod, Service},
+};
 use kube::{
-    Api,
-    api::{DeleteParams, ListParams},
+    Api, Resource, ResourceExt,
+    api::{DeleteParams, ListParams, ObjectList},
 };
+use serde::de::DeserializeOwned;
 use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
 
 use crate::{
     PROJECT_NAME,
     cli::{
         error::{self, Error},
-        internal::{ResolvedResources, ResourceResolver},
+        internal::{ResolvedResources, ResourceResolver, output},
     },
     config::Config,
     consts::k8s::labels,
-    ui::fuzzy_finder::PodListExt as _,
+    ui::{fuzzy_finder::PodListExt as _, table::PodListExt as _, terminal},
 };
 
 /// Represents the command-line arguments for deleting temporary Kubernetes
@@ -57,6 +68,47 @@ pub struct DeleteCommand {
         help = "Names of the temporary pods to delete. If no names are provided, a fuzzy finder will be used to select pods managed by Axon."
     )]
     pub pod_names: Vec<String>,
+
+    /// Select the pods to delete with the fuzzy finder across all Kubernetes
+    /// namespaces.
+    #[arg(
+        short = 'A',
+        long = "all-namespaces",
+        conflicts_with_all = ["namespace", "pod_names"],
+        help = "Select the pods to delete with the fuzzy finder across all Kubernetes namespaces."
+    )]
+    pub all_namespaces: bool,
+
+    /// Delete every pod managed by Axon that matches this label selector
+    /// instead of selecting them with the fuzzy finder.
+    #[arg(
+        short = 'l',
+        long = "selector",
+        conflicts_with = "pod_names",
+        value_parser = parse_selector,
+        help = "Delete every pod managed by Axon that matches this label selector (e.g. \
+                'team=infra,tier!=db') instead of selecting them with the fuzzy finder. The \
+                selector must not be empty."
+    )]
+    pub selector: Option<String>,
+
+    /// Print the pods matched by `--selector` instead of deleting them.
+    #[arg(
+        long = "dry-run",
+        requires = "selector",
+        help = "Print the pods that --selector matches as a table and exit without deleting \
+                anything."
+    )]
+    pub dry_run: bool,
+
+    /// Also delete the services and config maps managed by Axon that share the
+    /// name of a deleted pod.
+    #[arg(
+        long = "cascade",
+        help = "Also delete the Services and ConfigMaps managed by Axon that share the name of a \
+                deleted pod."
+    )]
+    pub cascade: bool,
 }
 
 impl DeleteCommand {
@@ -65,9 +117,12 @@ impl DeleteCommand {
     ///
     /// This function first resolves the target Kubernetes namespace. If no pod
     /// names are provided in the command, it lists all pods labeled as
-    /// managed by Axon and uses an interactive fuzzy finder to allow the
-    /// user to select which ones to delete. It then proceeds to delete the
-    /// selected or specified pods.
+    /// managed by Axon, in the namespace or across all namespaces with
+    /// `--all-namespaces`, and uses an interactive fuzzy finder to allow the
+    /// user to select which ones to delete, or takes every listed pod that
+    /// matches `--selector`. It then proceeds to delete the selected or
+    /// specified pods. With `--dry-run`, the pods matching the selector are
+    /// printed as a table and nothing is deleted.
     ///
     /// # Arguments
     ///
@@ -86,7 +141,10 @@ impl DeleteCommand {
     ///   permissions).
     /// * If the fuzzy finder encounters an error during interactive pod
     ///   selection.
+    /// * If writing the table of `--dry-run` to `stdout` fails.
     /// * If deleting a specific pod fails.
+    /// * If `--cascade` is set and listing or deleting the associated services
+    ///   or config maps fails.
     ///
     /// # Panics
     ///
@@ -94,33 +152,53 @@ impl DeleteCommand {
     /// `futures` operations might panic in extreme cases of unrecoverable
     /// errors (e.g., OOM).
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, pod_names } = self;
+        let Self { namespace, pod_names, all_namespaces, selector, dry_run, cascade } = self;
 
         // Resolve Identity
         let ResolvedResources { namespace, .. } =
             ResourceResolver::from((&kube_client, &config)).resolve(namespace, None);
 
-        let api = Api::<Pod>::namespaced(kube_client, &namespace);
-        let pod_names = if pod_names.is_empty() {
+        let targets = if pod_names.is_empty() {
             let list_params = ListParams {
-                label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+                label_selector: Some(label_selector(selector.as_deref())),
                 ..ListParams::default()
             };
-
-            api.list(&list_params)
-                .await
-                .with_context(|_| error::ListPodsWithNamespaceSnafu {
-                    namespace: namespace.clone(),
-                })?
-                .find_pod_names()
-                .await
+            let pods = if all_namespaces {
+                Api::<Pod>::all(kube_client.clone())
+                    .list(&list_params)
+                    .await
+                    .context(error::ListPodsSnafu)?
+            } else {
+                Api::<Pod>::namespaced(kube_client.clone(), &namespace)
+                    .list(&list_params)
+                    .await
+                    .with_context(|_| error::ListPodsWithNamespaceSnafu {
+                        namespace: namespace.clone(),
+                    })?
+            };
+            if dry_run {
+                return print_plan(&pods).await;
+            }
+            if selector.is_some() {
+                if pods.items.is_empty() {
+                    output::status(format_args!("No pods managed by Axon match the selector"));
+                }
+                pods.iter()
+                    .map(|pod| {
+                        (pod.namespace().unwrap_or_else(|| namespace.clone()), pod.name_any())
+                    })
+                    .collect()
+            } else {
+                pods.find_pods().await
+            }
         } else {
-            pod_names
+            pod_names.into_iter().map(|pod_name| (namespace.clone(), pod_name)).collect()
         };
 
-        let futs = pod_names.into_iter().map(|pod_name| {
-            let api = api.clone();
-            let namespace = namespace.clone();
+        let futs = targets.into_iter().map(|(namespace, pod_name)| {
+            let api = Api::<Pod>::namespaced(kube_client.clone(), &namespace);
+            let service_api = Api::<Service>::namespaced(kube_client.clone(), &namespace);
+            let config_map_api = Api::<ConfigMap>::namespaced(kube_client.clone(), &namespace);
             async move {
                 let pod_exists = api.get(&pod_name).await.is_ok();
                 if pod_exists {
@@ -130,9 +208,18 @@ impl DeleteCommand {
                             namespace: namespace.clone(),
                         },
                     )?;
-                    println!("pod/{pod_name} deleted in namespace {namespace}");
+                    output::status(format_args!("pod/{pod_name} deleted in namespace {namespace}"));
                 } else {
-                    println!("pod/{pod_name} does not exist in namespace {namespace}");
+                    output::status(format_args!(
+                        "pod/{pod_name} does not exist in namespace {namespace}"
+                    ));
+                }
+
+                if cascade {
+                    delete_managed_resources(&service_api, "service", &namespace, &pod_name)
+                        .await?;
+                    delete_managed_resources(&config_map_api, "configmap", &namespace, &pod_name)
+                        .await?;
                 }
 
                 Ok::<(), Error>(())
@@ -144,3 +231,136 @@ impl DeleteCommand {
         Ok(())
     }
 }
+
+/// Returns the label selector of the pods managed by Axon, narrowed by the
+/// user-supplied `selector` if any.
+fn label_selector(selector: Option<&str>) -> String {
+    let managed_by = format!("{}={PROJECT_NAME}", labels::MANAGED_BY);
+    match selector {
+        Some(selector) => format!("{managed_by},{selector}"),
+        None => managed_by,
+    }
+}
+
+/// Parses the `--selector` argument, refusing a blank selector, e.g. from an
+/// unset variable, which would match and delete every pod managed by Axon.
+fn parse_selector(selector: &str) -> Result<String, String> {
+    let selector = selector.trim();
+    if selector.is_empty() {
+        return Err("the selector must not be empty, as it would match every pod managed by Axon"
+            .to_string());
+    }
+    Ok(selector.to_string())
+}
+
+/// Prints `pods`, the pods that would be deleted, as a table.
+async fn print_plan(pods: &ObjectList<Pod>) -> Result<(), Error> {
+    if pods.items.is_empty() {
+        output::status(format_args!("No pods managed by Axon match the selector"));
+        return Ok(());
+    }
+    let is_terminal = std::io::stdout().is_terminal();
+    let table = pods.render_table(
+        terminal::use_color(false),
+        is_terminal.then(|| usize::from(terminal::size().0)),
+    );
+
+    let mut stdout = tokio::io::stdout();
+    stdout.write_all(table.as_bytes()).await.context(error::WriteStdoutSnafu)?;
+    stdout.write_u8(b'\n').await.context(error::WriteStdoutSnafu)
+}
+
+/// Deletes the resources managed by Axon that are named `name`.
+///
+/// The resources are looked up with the same `managed-by` label selector used
+/// for pods, restricted to the given name, so resources not created by Axon
+/// are never touched. Each deleted resource is reported on stdout.
+///
+/// # Arguments
+///
+/// * `api` - The namespaced API of the resource kind.
+/// * `kind` - The kind of the resources, used in messages (e.g. `service`).
+/// * `namespace` - The namespace of the resources.
+/// * `name` - The name shared with the deleted pod.
+///
+/// # Errors
+///
+/// Returns an error if listing or deleting the resources fails.
+async fn delete_managed_resources<K>(
+    api: &Api<K>,
+    kind: &'static str,
+    namespace: &str,
+    name: &str,
+) -> Result<(), Error>
+where
+    K: Resource<Scope = NamespaceResourceScope> + Clone + DeserializeOwned + std::fmt::Debug,
+{
+    let list_params = ListParams {
+        label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+        field_selector: Some(format!("metadata.name={name}")),
+        ..ListParams::default()
+    };
+    let resources = api
+        .list(&list_params)
+        .await
+        .with_context(|_| error::ListResourcesSnafu { kind, namespace: namespace.to_string() })?;
+
+    for resource in resources {
+        let Some(name) = resource.meta().name.clone() else {
+            continue;
+        };
+        let _resource = api.delete(&name, &DeleteParams::default()).await.with_context(|_| {
+            error::DeleteResourceSnafu {
+                kind,
+                namespace: namespace.to_string(),
+                name: name.clone(),
+            }
+        })?;
+        output::status(format_args!("{kind}/{name} deleted in namespace {namespace}"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        delete: DeleteCommand,
+    }
+
+    #[test]
+    fn test_label_selector() {
+        let managed_by = format!("{}={PROJECT_NAME}", labels::MANAGED_BY);
+        assert_eq!(label_selector(None), managed_by);
+        assert_eq!(
+            label_selector(Some("team=infra,tier!=db")),
+            format!("{managed_by},team=infra,tier!=db")
+        );
+    }
+
+    #[test]
+    fn test_selector_flags() {
+        let Cli { delete } =
+            Cli::try_parse_from(["axon", "-l", "team=infra", "--dry-run"]).unwrap();
+        assert_eq!(delete.selector.as_deref(), Some("team=infra"));
+        assert!(delete.dry_run);
+
+        let Cli { delete } = Cli::try_parse_from(["axon", "-l", " team=infra "]).unwrap();
+        assert_eq!(delete.selector.as_deref(), Some("team=infra"));
+
+        for args in [
+            &["axon", "--dry-run"][..],
+            &["axon", "-l", "team=infra", "-p", "my-pod"],
+            &["axon", "-l", ""],
+            &["axon", "-l", " "],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
+        }
+    }
+}
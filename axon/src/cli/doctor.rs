@@ -0,0 +1,277 @@
+//! Defines the `doctor` subcommand, which diagnoses the environment Axon runs
+//! in.
+//!
+//! New users often hit failures whose cause is far from the command they ran,
+//! such as a missing kubeconfig or SSH key. `doctor` runs a series of checks,
+//! built from the same pieces the other commands use, and prints a checklist
+//! with a hint for every failed check.
+
+use std::{
+    fmt::{self, Write as _},
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use clap::Args;
+use kube::{Api, api::ListParams};
+use snafu::ResultExt;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use crate::{
+    cli::{Error, error},
+    config::Config,
+    metrics::PodMetrics,
+    ssh,
+};
+
+/// Represents the command to diagnose the environment of Axon.
+#[derive(Args, Clone)]
+pub struct DoctorCommand {}
+
+/// The outcome of a single check.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Outcome {
+    /// The check passed, with what was found.
+    Pass(String),
+    /// The check failed, with why and how to fix it.
+    Fail {
+        /// What went wrong.
+        detail: String,
+        /// What the user can do about it.
+        hint: &'static str,
+    },
+    /// The check could not run because an earlier one failed.
+    Skipped(&'static str),
+}
+
+/// A check of the checklist.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Check {
+    /// What is checked.
+    name: &'static str,
+    /// Whether a failure makes the command fail, rather than only degrading
+    /// some commands.
+    critical: bool,
+    /// The outcome of the check.
+    outcome: Outcome,
+}
+
+impl Check {
+    /// Returns whether the check makes the command fail.
+    const fn is_critical_failure(&self) -> bool {
+        self.critical && matches!(self.outcome, Outcome::Fail { .. })
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { name, critical, outcome } = self;
+        match outcome {
+            Outcome::Pass(detail) => write!(f, "[PASS] {name}: {detail}"),
+            Outcome::Fail { detail, hint } => {
+                let label = if *critical { "FAIL" } else { "WARN" };
+                write!(f, "[{label}] {name}: {detail}\n       Hint: {hint}")
+            }
+            Outcome::Skipped(reason) => write!(f, "[SKIP] {name}: {reason}"),
+        }
+    }
+}
+
+impl DoctorCommand {
+    /// Runs every check and prints the checklist to stdout.
+    ///
+    /// The configuration and the Kubernetes client are given as results, as
+    /// their failures are what the command diagnoses.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_path` - The path the configuration is loaded from.
+    /// * `config` - The result of loading the configuration.
+    /// * `kube_client` - The result of building the Kubernetes client.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::DoctorChecksFailed` if the configuration cannot be
+    /// loaded or the Kubernetes API server cannot be reached, or an `Error` if
+    /// writing the checklist to `stdout` fails. Failures of the other checks
+    /// only degrade some commands and are reported as warnings.
+    pub async fn run(
+        self,
+        config_path: PathBuf,
+        config: Result<Config, Error>,
+        kube_client: Result<kube::Client, Error>,
+    ) -> Result<(), Error> {
+        let ssh_private_key_file_path =
+            config.as_ref().ok().and_then(|config| config.ssh_private_key_file_path.clone());
+        let mut checks = vec![check_config(&config_path, &config)];
+        checks.extend(check_cluster(kube_client).await);
+        checks.push(check_ssh_key(ssh_private_key_file_path).await);
+        checks.push(check_tar().await);
+
+        let mut stdout = tokio::io::stdout();
+        stdout.write_all(render(&checks).as_bytes()).await.context(error::WriteStdoutSnafu)?;
+
+        let failed = checks.iter().filter(|check| check.is_critical_failure()).count();
+        snafu::ensure!(failed == 0, error::DoctorChecksFailedSnafu { failed });
+        Ok(())
+    }
+}
+
+/// Checks that the configuration loads.
+fn check_config(config_path: &Path, config: &Result<Config, Error>) -> Check {
+    let outcome = match config {
+        Ok(_) if config_path.exists() => Outcome::Pass(format!("loaded {}", config_path.display())),
+        Ok(_) => {
+            Outcome::Pass(format!("no file at {}, the defaults are used", config_path.display()))
+        }
+        Err(err) => Outcome::Fail {
+            detail: err.to_string(),
+            hint: "Fix the configuration file, or write a fresh one with `axon init --force`",
+        },
+    };
+    Check { name: "Configuration", critical: true, outcome }
+}
+
+/// Checks that the Kubernetes API server is reachable, and then whether the
+/// resource metrics API is served.
+async fn check_cluster(kube_client: Result<kube::Client, Error>) -> [Check; 2] {
+    const API_SERVER: &str = "Kubernetes API server";
+    const METRICS_API: &str = "Resource metrics API";
+    const KUBECONFIG_HINT: &str = "Check the kubeconfig (KUBECONFIG or ~/.kube/config) and its \
+                                   current context, e.g. with `kubectl cluster-info`";
+
+    let kube_client = match kube_client {
+        Ok(kube_client) => kube_client,
+        Err(err) => {
+            return [
+                Check {
+                    name: API_SERVER,
+                    critical: true,
+                    outcome: Outcome::Fail { detail: err.to_string(), hint: KUBECONFIG_HINT },
+                },
+                Check {
+                    name: METRICS_API,
+                    critical: false,
+                    outcome: Outcome::Skipped("the Kubernetes client could not be built"),
+                },
+            ];
+        }
+    };
+
+    let api_server = match kube_client.apiserver_version().await {
+        Ok(info) => Outcome::Pass(format!("reachable, server version {}", info.git_version)),
+        Err(err) => {
+            let metrics_api = Outcome::Skipped("the Kubernetes API server is not reachable");
+            return [
+                Check {
+                    name: API_SERVER,
+                    critical: true,
+                    outcome: Outcome::Fail { detail: err.to_string(), hint: KUBECONFIG_HINT },
+                },
+                Check { name: METRICS_API, critical: false, outcome: metrics_api },
+            ];
+        }
+    };
+
+    let namespace = kube_client.default_namespace().to_string();
+    let metrics = Api::<PodMetrics>::namespaced(kube_client, &namespace)
+        .list(&ListParams::default().limit(1))
+        .await;
+    let metrics_api = match metrics {
+        Ok(_) => Outcome::Pass("available".to_string()),
+        Err(kube::Error::Api(status)) if status.is_not_found() || status.code == 503 => {
+            Outcome::Fail {
+                detail: "not available".to_string(),
+                hint: "Install metrics-server in the cluster to use `axon top`",
+            }
+        }
+        Err(err) => Outcome::Fail {
+            detail: err.to_string(),
+            hint: "Check that you may list pod metrics in the namespace to use `axon top`",
+        },
+    };
+
+    [
+        Check { name: API_SERVER, critical: true, outcome: api_server },
+        Check { name: METRICS_API, critical: false, outcome: metrics_api },
+    ]
+}
+
+/// Checks that an SSH private key can be loaded from the configured path or
+/// the default ones.
+async fn check_ssh_key(ssh_private_key_file_path: Option<PathBuf>) -> Check {
+    let outcome = match ssh::resolve_ssh_key_pair(ssh_private_key_file_path).await {
+        Ok((private_key, _)) => Outcome::Pass(format!("{} key found", private_key.algorithm())),
+        Err(err) => Outcome::Fail {
+            detail: err.to_string(),
+            hint: "Generate a key pair with `axon ssh keygen`, or set `sshPrivateKeyFilePath` in \
+                   the configuration, to use the `axon ssh` commands",
+        },
+    };
+    Check { name: "SSH private key", critical: false, outcome }
+}
+
+/// Checks that `tar` can be run on the local machine.
+async fn check_tar() -> Check {
+    let status = Command::new("tar")
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await;
+    let outcome = match status {
+        Ok(status) if status.success() => Outcome::Pass("found".to_string()),
+        Ok(status) => Outcome::Fail {
+            detail: format!("`tar --version` {status}"),
+            hint: "Install tar to use `--tar` with `axon ssh get` and `axon ssh put`",
+        },
+        Err(err) => Outcome::Fail {
+            detail: err.to_string(),
+            hint: "Install tar to use `--tar` with `axon ssh get` and `axon ssh put`",
+        },
+    };
+    Check { name: "Local tar", critical: false, outcome }
+}
+
+/// Renders `checks` as a checklist, one check per line.
+fn render(checks: &[Check]) -> String {
+    checks.iter().fold(String::new(), |mut checklist, check| {
+        let _unused = writeln!(checklist, "{check}");
+        checklist
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let checks = [
+            Check { name: "Configuration", critical: true, outcome: Outcome::Pass("ok".into()) },
+            Check {
+                name: "Kubernetes API server",
+                critical: true,
+                outcome: Outcome::Fail { detail: "refused".into(), hint: "check it" },
+            },
+            Check {
+                name: "Resource metrics API",
+                critical: false,
+                outcome: Outcome::Skipped("not reachable"),
+            },
+            Check {
+                name: "Local tar",
+                critical: false,
+                outcome: Outcome::Fail { detail: "not found".into(), hint: "install it" },
+            },
+        ];
+        assert_eq!(
+            render(&checks),
+            "[PASS] Configuration: ok\n[FAIL] Kubernetes API server: refused\n       Hint: check \
+             it\n[SKIP] Resource metrics API: not reachable\n[WARN] Local tar: not found\n       \
+             Hint: install it\n"
+        );
+        assert_eq!(checks.iter().filter(|check| check.is_critical_failure()).count(), 1);
+    }
+}
@@ -6,8 +6,34 @@
 //! such as configuration issues, Kubernetes API failures, SSH problems, and UI
 //! interaction errors.
 
+use clap::ValueEnum;
 use snafu::Snafu;
 
+/// The process exit codes reported for errors, see [`Error::exit_code`].
+pub mod exit_code {
+    /// Any failure not covered by a more specific code.
+    pub const GENERIC: i32 = 1;
+    /// A configuration error.
+    pub const CONFIGURATION: i32 = 3;
+    /// A failure of the Kubernetes API.
+    pub const KUBERNETES_API: i32 = 4;
+    /// An SSH failure.
+    pub const SSH: i32 = 5;
+    /// A timeout.
+    pub const TIMEOUT: i32 = 6;
+}
+
+/// The format in which an error is reported on stderr.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ErrorFormat {
+    /// A human-readable message.
+    #[default]
+    Text,
+    /// A JSON object with a stable `code`, the message and the namespace and
+    /// pod name involved, if any.
+    Json,
+}
+
 /// Represents all possible errors that can occur within the `cli` module.
 ///
 /// This enum consolidates error types from various sub-modules and external
@@ -50,10 +76,187 @@ pub enum Error {
         spec_name: String,
     },
 
+    /// An error indicating that a file transfer did not complete within the
+    /// allotted time.
+    #[snafu(display(
+        "File transfer of {} timed out after {}",
+        path.display(),
+        humantime::format_duration(*elapsed)
+    ))]
+    TransferTimeout {
+        /// The remote path of the file being transferred.
+        path: std::path::PathBuf,
+        /// The time spent on the transfer before it was aborted.
+        elapsed: std::time::Duration,
+    },
+
+    /// An error indicating that the SSH session to a pod was not established
+    /// within the allotted time.
+    #[snafu(display(
+        "Timed out after {} establishing the SSH session to the pod. The port forwarding may \
+         accept connections that sshd never answers, e.g. when it is not running",
+        humantime::format_duration(*timeout)
+    ))]
+    SshConnectTimeout {
+        /// The time allotted to the SSH handshake and authentication.
+        timeout: std::time::Duration,
+    },
+
+    /// An error indicating that the destination of a file transfer exists and
+    /// must not be overwritten.
+    #[snafu(display(
+        "Destination {} already exists, remove --no-clobber to overwrite it",
+        path.display()
+    ))]
+    DestinationExists {
+        /// The local or remote path of the destination.
+        path: std::path::PathBuf,
+    },
+
+    /// An error indicating that the local destination of a download cannot be
+    /// written to.
+    #[snafu(display("Invalid destination {}: {reason}", path.display()))]
+    InvalidDestination {
+        /// The local path the file would be written to.
+        path: std::path::PathBuf,
+        /// Why the file cannot be written to the path.
+        reason: String,
+    },
+
+    /// An error indicating that `tar`, required to transfer a directory with
+    /// `--tar`, is not installed in the pod.
+    #[snafu(display("tar is not installed in the pod, it is required by --tar"))]
+    TarNotFound,
+
+    /// An error that occurs when failing to run `tar` on the local machine.
+    #[snafu(display("Failed to run tar on the local machine, error: {source}"))]
+    RunLocalTar {
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
+
+    /// An error indicating that `tar` failed to create or extract the archive
+    /// of a directory transfer.
+    #[snafu(display("tar failed on the {location}: {reason}"))]
+    TarFailed {
+        /// Where `tar` ran, the local machine or the remote pod.
+        location: &'static str,
+        /// How `tar` failed, such as its error output.
+        reason: String,
+    },
+
+    /// An error indicating that the fuzzy finder was closed without selecting
+    /// a pod.
+    #[snafu(display("No pod was selected"))]
+    NoPodSelected,
+
+    /// An error indicating that critical checks of `doctor` failed.
+    #[snafu(display("{failed} critical check(s) failed"))]
+    DoctorChecksFailed {
+        /// The number of failed critical checks.
+        failed: usize,
+    },
+
+    /// An error indicating that a command run with `execute --all` failed on
+    /// some of the pods.
+    #[snafu(display(
+        "The command failed on {} of {total} pods: {}",
+        failures.len(),
+        failures.join(", ")
+    ))]
+    CommandFailedOnPods {
+        /// The pods the command failed on, each with how it failed.
+        failures: Vec<String>,
+        /// The number of pods the command ran on.
+        total: usize,
+    },
+
+    /// An error that occurs when failing to read a pod manifest.
+    #[snafu(display("Failed to read pod manifest {}, error: {source}", filename.display()))]
+    ReadPodManifest {
+        /// The file the manifest was read from, `-` for stdin.
+        filename: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
+    /// An error that occurs when a pod manifest is not a valid `Pod`.
+    #[snafu(display("Failed to parse pod manifest {}, error: {source}", filename.display()))]
+    ParsePodManifest {
+        /// The file the manifest was read from, `-` for stdin.
+        filename: std::path::PathBuf,
+        source: serde_yaml::Error,
+    },
+
+    /// An error indicating that a pod manifest cannot be used by Axon.
+    #[snafu(display("Invalid pod manifest {}: {reason}", filename.display()))]
+    InvalidPodManifest {
+        /// The file the manifest was read from, `-` for stdin.
+        filename: std::path::PathBuf,
+        /// Why the manifest cannot be used.
+        reason: String,
+    },
+
+    /// An error indicating that an image of a spec is not a valid image
+    /// reference.
+    #[snafu(display("Invalid image reference '{image}': {reason}"))]
+    InvalidImageReference {
+        /// The invalid image reference.
+        image: String,
+        /// Why the reference is invalid.
+        reason: String,
+    },
+
+    /// An error indicating that a pod name is not a valid DNS-1123 label.
+    #[snafu(display("Invalid pod name '{pod_name}': {reason}"))]
+    InvalidPodName {
+        /// The invalid pod name.
+        pod_name: String,
+        /// Why the name is not a valid DNS-1123 label.
+        reason: String,
+    },
+
+    /// An error indicating that a container name is not a valid DNS-1123
+    /// label.
+    #[snafu(display("Invalid container name '{name}': {reason}"))]
+    InvalidContainerName {
+        /// The invalid container name.
+        name: String,
+        /// Why the name is not a valid DNS-1123 label.
+        reason: String,
+    },
+
+    /// An error indicating that a service account name is not a valid
+    /// resource name.
+    #[snafu(display("Invalid service account name '{name}': {reason}"))]
+    InvalidServiceAccountName {
+        /// The invalid service account name.
+        name: String,
+        /// Why the name is not a valid DNS-1123 subdomain.
+        reason: String,
+    },
+
     /// An error that occurs when failing to write to stdout.
     #[snafu(display("Failed to write to stdout, error: {source}"))]
     WriteStdout { source: std::io::Error },
 
+    /// An error that occurs when failing to write to stderr.
+    #[snafu(display("Failed to write to stderr, error: {source}"))]
+    WriteStderr { source: std::io::Error },
+
+    /// An error that occurs when failing to write the environment file of
+    /// `port-forward --env-file`.
+    #[snafu(display("Failed to write environment file {}, error: {source}", filename.display()))]
+    WriteEnvFile {
+        /// The file that could not be written.
+        filename: std::path::PathBuf,
+        source: std::io::Error,
+    },
+
+    /// An error indicating that no Kubernetes client configuration could be
+    /// inferred from the kubeconfig file or the in-cluster environment.
+    #[snafu(display("Failed to infer Kubernetes client configuration, error: {source}"))]
+    InferKubeConfig { source: kube::config::InferConfigError },
+
     /// An error indicating a failure to initialize the Kubernetes client
     /// configuration.
     #[snafu(display("Failed to initialize Kubernetes client configuration, error: {source}"))]
@@ -71,6 +274,40 @@ pub enum Error {
         source: Box<kube::Error>,
     },
 
+    /// An error that occurs when failing to create a Kubernetes service.
+    #[snafu(display(
+        "Failed to create service {service_name} in namespace {namespace}, error: {source}"
+    ))]
+    CreateService {
+        /// The namespace where the service creation failed.
+        namespace: String,
+        /// The name of the service that failed to be created.
+        service_name: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error that occurs when failing to read a Kubernetes namespace.
+    #[snafu(display("Failed to get namespace {namespace}, error: {source}"))]
+    GetNamespace {
+        /// The name of the namespace.
+        namespace: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error that occurs when failing to create a Kubernetes namespace.
+    #[snafu(display("Failed to create namespace {namespace}, error: {source}"))]
+    CreateNamespace {
+        /// The name of the namespace that failed to be created.
+        namespace: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
     /// An error that occurs when failing to delete a Kubernetes pod.
     #[snafu(display("Failed to delete pod {pod_name} in namespace {namespace}, error: {source}"))]
     DeletePod {
@@ -83,6 +320,33 @@ pub enum Error {
         source: Box<kube::Error>,
     },
 
+    /// An error that occurs when failing to list Kubernetes resources of a
+    /// given kind within a namespace.
+    #[snafu(display("Failed to list {kind} resources in namespace {namespace}, error: {source}"))]
+    ListResources {
+        /// The kind of the resources, e.g. `service`.
+        kind: &'static str,
+        /// The namespace where the listing failed.
+        namespace: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error that occurs when failing to delete a Kubernetes resource.
+    #[snafu(display("Failed to delete {kind}/{name} in namespace {namespace}, error: {source}"))]
+    DeleteResource {
+        /// The kind of the resource, e.g. `service`.
+        kind: &'static str,
+        /// The namespace where the resource deletion failed.
+        namespace: String,
+        /// The name of the resource that failed to be deleted.
+        name: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
     /// An error that occurs when failing to list Kubernetes pods.
     #[snafu(display("Failed to list pods, error: {source}"))]
     ListPods {
@@ -117,6 +381,20 @@ pub enum Error {
         pod_name: String,
     },
 
+    /// An error indicating a timeout occurred while waiting for a pod to meet a
+    /// condition.
+    #[snafu(display(
+        "Timed out waiting for pod '{pod_name}' in namespace '{namespace}' to be {condition}"
+    ))]
+    WaitForPodCondition {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+        /// The condition that was waited for, e.g. `ready`.
+        condition: String,
+    },
+
     /// An error that occurs when failing to wait for a Kubernetes pod's status.
     #[snafu(display(
         "Failed to wait for pod {pod_name} status in namespace {namespace}, error: {source}"
@@ -142,6 +420,147 @@ pub enum Error {
         source: Box<kube::Error>,
     },
 
+    /// An error that occurs when failing to list the events of a pod.
+    #[snafu(display(
+        "Failed to list events of pod '{pod_name}' in namespace '{namespace}', error: {source}"
+    ))]
+    ListEvents {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error indicating that the user is not allowed to watch events.
+    #[snafu(display(
+        "Not allowed to watch events in namespace '{namespace}'. Ask a cluster administrator for \
+         the 'list' and 'watch' permissions on events, or run without --follow"
+    ))]
+    WatchEventsForbidden {
+        /// The namespace in which events were watched.
+        namespace: String,
+    },
+
+    /// An error indicating that the resource metrics API is not served by the
+    /// cluster.
+    #[snafu(display(
+        "The resource metrics API (metrics.k8s.io) is not available. Make sure metrics-server is \
+         installed and running in the cluster"
+    ))]
+    MetricsApiUnavailable,
+
+    /// An error that occurs when failing to list the metrics of pods.
+    #[snafu(display("Failed to list pod metrics, error: {source}"))]
+    ListPodMetrics {
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error indicating that the `proxy` command has no forwarding rule to
+    /// start.
+    #[snafu(display(
+        "No proxy rules are given. Pass them with --rule or list them under proxyRules in the \
+         configuration file"
+    ))]
+    NoProxyRules,
+
+    /// An error that occurs when failing to get a Kubernetes service.
+    #[snafu(display(
+        "Failed to get service {service_name} in namespace {namespace}, error: {source}"
+    ))]
+    GetService {
+        /// The namespace of the service.
+        namespace: String,
+        /// The name of the service.
+        service_name: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error that occurs when failing to list the endpoints of a service.
+    #[snafu(display(
+        "Failed to list the endpoints of service {service_name} in namespace {namespace}, error: \
+         {source}"
+    ))]
+    ListEndpointSlices {
+        /// The namespace of the service.
+        namespace: String,
+        /// The name of the service.
+        service_name: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error indicating that no pod backing a service is ready.
+    #[snafu(display("Service {service_name} in namespace {namespace} has no ready endpoint"))]
+    ServiceHasNoReadyEndpoint {
+        /// The namespace of the service.
+        namespace: String,
+        /// The name of the service.
+        service_name: String,
+    },
+
+    /// An error indicating that a service does not expose the requested port.
+    #[snafu(display(
+        "Service {service_name} in namespace {namespace} does not expose port {port}"
+    ))]
+    ServicePortNotFound {
+        /// The namespace of the service.
+        namespace: String,
+        /// The name of the service.
+        service_name: String,
+        /// The requested service port.
+        port: u16,
+    },
+
+    /// An error indicating that a pod has no IP address to reach it at.
+    #[snafu(display("Pod {pod_name} in namespace {namespace} has no IP address"))]
+    PodHasNoIp {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+    },
+
+    /// An error indicating that a pod is not running.
+    #[snafu(display(
+        "Pod {pod_name} in namespace {namespace} is not running, its phase is {phase}"
+    ))]
+    PodNotRunning {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+        /// The phase of the pod.
+        phase: String,
+    },
+
+    /// An error indicating that a pod has no container port with the name
+    /// given as a port to forward to, or used as the target port of a service.
+    #[snafu(display(
+        "Pod {pod_name} in namespace {namespace} has no container port named '{port_name}', {}",
+        if available.is_empty() {
+            "it declares no named ports".to_string()
+        } else {
+            format!("available: {}", available.join(", "))
+        }
+    ))]
+    ContainerPortNotFound {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+        /// The name of the container port.
+        port_name: String,
+        /// The names of the container ports the pod declares.
+        available: Vec<String>,
+    },
+
     /// An error that occurs when failing to initialize a Tokio runtime.
     #[snafu(display("Failed to create tokio runtime, error: {source}"))]
     InitializeTokioRuntime { source: std::io::Error },
@@ -159,12 +578,334 @@ pub enum Error {
         source: Box<kube::Error>,
     },
 
+    /// An error indicating that the commands run in a pod to set up SSH exited
+    /// unsuccessfully.
+    #[snafu(display(
+        "SSH setup commands failed in pod '{pod_name}' (namespace '{namespace}'): {reason}"
+    ))]
+    SshSetupCommandFailed {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+        /// Why the commands failed, as reported by the exec API.
+        reason: String,
+    },
+
+    /// An error indicating that the home directory of the user whose SSH key
+    /// is authorized could not be determined in a pod.
+    #[snafu(display(
+        "Failed to determine the home directory of {} in pod '{pod_name}' (namespace \
+         '{namespace}')",
+        user.as_ref().map_or_else(|| "the container user".to_string(), |user| format!("user '{user}'"))
+    ))]
+    DetermineHomeDirectory {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+        /// The user whose home directory was looked up, or `None` for the
+        /// container user.
+        user: Option<String>,
+    },
+
+    /// An error that occurs when failing to check for or generate the sshd host
+    /// keys of a pod.
+    #[snafu(display("Failed to generate SSH host keys in pod '{pod_name}', error: {source}"))]
+    GenerateHostKeys {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+
+        #[snafu(source(from(kube::Error, Box::new)))]
+        source: Box<kube::Error>,
+    },
+
+    /// An error indicating that sshd host keys are missing in a pod and
+    /// `ssh-keygen` is not available to generate them.
+    #[snafu(display(
+        "Pod '{pod_name}' (namespace '{namespace}') has no SSH host keys and 'ssh-keygen' is not \
+         available in its image to generate them"
+    ))]
+    SshKeygenNotFound {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+    },
+
+    /// An error indicating that `ssh-keygen` failed to generate the sshd host
+    /// keys of a pod.
+    #[snafu(display(
+        "'ssh-keygen -A' failed to generate SSH host keys in pod '{pod_name}' (namespace \
+         '{namespace}'){}",
+        if output.is_empty() { String::new() } else { format!(": {output}") }
+    ))]
+    HostKeyGenerationFailed {
+        /// The namespace of the pod.
+        namespace: String,
+        /// The name of the pod.
+        pod_name: String,
+        /// The unexpected output of the generation script.
+        output: String,
+    },
+
+    /// An error that occurs when failing to serialize the manifest of a pod.
+    #[snafu(display("Failed to serialize pod manifest, error: {source}"))]
+    SerializePodManifest { source: serde_yaml::Error },
+
     /// An error that occurs when failing to serialize interactive shell
     /// configuration.
     #[snafu(display("Failed to serialize interactive shell configuration, error: {source}"))]
     SerializeInteractiveShell { source: serde_json::Error },
 }
 
+impl Error {
+    /// Returns a stable, machine-readable code identifying the kind of error.
+    ///
+    /// The code is the name of the variant and does not change with the
+    /// wording of the error message.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::Generic { .. } => "Generic",
+            Self::Configuration { .. } => "Configuration",
+            Self::Ssh { .. } => "Ssh",
+            Self::TerminalUi { .. } => "TerminalUi",
+            Self::PortForwarder { .. } => "PortForwarder",
+            Self::PodConsole { .. } => "PodConsole",
+            Self::SpecNotFound { .. } => "SpecNotFound",
+            Self::TransferTimeout { .. } => "TransferTimeout",
+            Self::SshConnectTimeout { .. } => "SshConnectTimeout",
+            Self::InvalidDestination { .. } => "InvalidDestination",
+            Self::DestinationExists { .. } => "DestinationExists",
+            Self::TarNotFound => "TarNotFound",
+            Self::CommandFailedOnPods { .. } => "CommandFailedOnPods",
+            Self::DoctorChecksFailed { .. } => "DoctorChecksFailed",
+            Self::NoPodSelected => "NoPodSelected",
+            Self::RunLocalTar { .. } => "RunLocalTar",
+            Self::TarFailed { .. } => "TarFailed",
+            Self::ReadPodManifest { .. } => "ReadPodManifest",
+            Self::ParsePodManifest { .. } => "ParsePodManifest",
+            Self::InvalidPodManifest { .. } => "InvalidPodManifest",
+            Self::InvalidImageReference { .. } => "InvalidImageReference",
+            Self::InvalidPodName { .. } => "InvalidPodName",
+            Self::InvalidContainerName { .. } => "InvalidContainerName",
+            Self::InvalidServiceAccountName { .. } => "InvalidServiceAccountName",
+            Self::WriteStdout { .. } => "WriteStdout",
+            Self::WriteStderr { .. } => "WriteStderr",
+            Self::WriteEnvFile { .. } => "WriteEnvFile",
+            Self::SerializePodManifest { .. } => "SerializePodManifest",
+            Self::InferKubeConfig { .. } => "InferKubeConfig",
+            Self::KubeConfig { .. } => "KubeConfig",
+            Self::CreatePod { .. } => "CreatePod",
+            Self::CreateService { .. } => "CreateService",
+            Self::GetNamespace { .. } => "GetNamespace",
+            Self::CreateNamespace { .. } => "CreateNamespace",
+            Self::DeletePod { .. } => "DeletePod",
+            Self::ListResources { .. } => "ListResources",
+            Self::DeleteResource { .. } => "DeleteResource",
+            Self::ListPods { .. } => "ListPods",
+            Self::GetPod { .. } => "GetPod",
+            Self::WaitForPodStatus { .. } => "WaitForPodStatus",
+            Self::WaitForPodCondition { .. } => "WaitForPodCondition",
+            Self::GetPodStatus { .. } => "GetPodStatus",
+            Self::ListPodsWithNamespace { .. } => "ListPodsWithNamespace",
+            Self::ListEvents { .. } => "ListEvents",
+            Self::WatchEventsForbidden { .. } => "WatchEventsForbidden",
+            Self::MetricsApiUnavailable => "MetricsApiUnavailable",
+            Self::ListPodMetrics { .. } => "ListPodMetrics",
+            Self::NoProxyRules => "NoProxyRules",
+            Self::GetService { .. } => "GetService",
+            Self::ListEndpointSlices { .. } => "ListEndpointSlices",
+            Self::ServiceHasNoReadyEndpoint { .. } => "ServiceHasNoReadyEndpoint",
+            Self::ServicePortNotFound { .. } => "ServicePortNotFound",
+            Self::ContainerPortNotFound { .. } => "ContainerPortNotFound",
+            Self::PodHasNoIp { .. } => "PodHasNoIp",
+            Self::PodNotRunning { .. } => "PodNotRunning",
+            Self::InitializeTokioRuntime { .. } => "InitializeTokioRuntime",
+            Self::UploadSshKey { .. } => "UploadSshKey",
+            Self::SshSetupCommandFailed { .. } => "SshSetupCommandFailed",
+            Self::DetermineHomeDirectory { .. } => "DetermineHomeDirectory",
+            Self::GenerateHostKeys { .. } => "GenerateHostKeys",
+            Self::SshKeygenNotFound { .. } => "SshKeygenNotFound",
+            Self::HostKeyGenerationFailed { .. } => "HostKeyGenerationFailed",
+            Self::SerializeInteractiveShell { .. } => "SerializeInteractiveShell",
+        }
+    }
+
+    /// Returns the process exit code reported for the error.
+    ///
+    /// Errors are grouped into categories, so that scripts can branch on the
+    /// kind of failure:
+    ///
+    /// | Code | Category                                                    |
+    /// |------|-------------------------------------------------------------|
+    /// | 1    | Any other failure, e.g. writing to stdout                   |
+    /// | 2    | Invalid command-line usage (reported by the argument parser) |
+    /// | 3    | Configuration, including the Kubernetes client configuration |
+    /// | 4    | Kubernetes API, including port forwarding and attaching      |
+    /// | 5    | SSH, including setting up SSH access in a pod                |
+    /// | 6    | Timeouts                                                    |
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            // Checked before the other port forwarder errors, which are API errors
+            Self::TransferTimeout { .. }
+            | Self::SshConnectTimeout { .. }
+            | Self::WaitForPodStatus { .. }
+            | Self::WaitForPodCondition { .. }
+            | Self::PortForwarder {
+                source: crate::port_forwarder::Error::PodPortUnreachable { .. },
+            } => exit_code::TIMEOUT,
+            Self::Configuration { .. }
+            | Self::SpecNotFound { .. }
+            | Self::NoProxyRules
+            | Self::ReadPodManifest { .. }
+            | Self::ParsePodManifest { .. }
+            | Self::InvalidPodManifest { .. }
+            | Self::InvalidImageReference { .. }
+            | Self::InferKubeConfig { .. }
+            | Self::KubeConfig { .. } => exit_code::CONFIGURATION,
+            Self::PortForwarder { .. }
+            | Self::PodConsole { .. }
+            | Self::CreatePod { .. }
+            | Self::CreateService { .. }
+            | Self::GetNamespace { .. }
+            | Self::CreateNamespace { .. }
+            | Self::DeletePod { .. }
+            | Self::ListResources { .. }
+            | Self::DeleteResource { .. }
+            | Self::ListPods { .. }
+            | Self::GetPod { .. }
+            | Self::GetPodStatus { .. }
+            | Self::ListPodsWithNamespace { .. }
+            | Self::ListEvents { .. }
+            | Self::WatchEventsForbidden { .. }
+            | Self::MetricsApiUnavailable
+            | Self::ListPodMetrics { .. }
+            | Self::GetService { .. }
+            | Self::ListEndpointSlices { .. }
+            | Self::ServiceHasNoReadyEndpoint { .. }
+            | Self::ServicePortNotFound { .. }
+            | Self::ContainerPortNotFound { .. }
+            | Self::PodHasNoIp { .. }
+            | Self::PodNotRunning { .. } => exit_code::KUBERNETES_API,
+            Self::Ssh { .. }
+            | Self::UploadSshKey { .. }
+            | Self::SshSetupCommandFailed { .. }
+            | Self::DetermineHomeDirectory { .. }
+            | Self::GenerateHostKeys { .. }
+            | Self::SshKeygenNotFound { .. }
+            | Self::HostKeyGenerationFailed { .. } => exit_code::SSH,
+            Self::Generic { .. }
+            | Self::InvalidDestination { .. }
+            | Self::DestinationExists { .. }
+            | Self::TarNotFound
+            | Self::CommandFailedOnPods { .. }
+            | Self::DoctorChecksFailed { .. }
+            | Self::NoPodSelected
+            | Self::RunLocalTar { .. }
+            | Self::TarFailed { .. }
+            | Self::InvalidPodName { .. }
+            | Self::InvalidContainerName { .. }
+            | Self::InvalidServiceAccountName { .. }
+            | Self::TerminalUi { .. }
+            | Self::WriteStdout { .. }
+            | Self::WriteStderr { .. }
+            | Self::WriteEnvFile { .. }
+            | Self::InitializeTokioRuntime { .. }
+            | Self::SerializePodManifest { .. }
+            | Self::SerializeInteractiveShell { .. } => exit_code::GENERIC,
+        }
+    }
+
+    /// Returns the namespace involved in the error, if any.
+    fn namespace(&self) -> Option<&str> {
+        match self {
+            Self::CreatePod { namespace, .. }
+            | Self::CreateService { namespace, .. }
+            | Self::GetNamespace { namespace, .. }
+            | Self::CreateNamespace { namespace, .. }
+            | Self::DeletePod { namespace, .. }
+            | Self::ListResources { namespace, .. }
+            | Self::DeleteResource { namespace, .. }
+            | Self::GetPod { namespace, .. }
+            | Self::WaitForPodStatus { namespace, .. }
+            | Self::WaitForPodCondition { namespace, .. }
+            | Self::GetPodStatus { namespace, .. }
+            | Self::ListPodsWithNamespace { namespace, .. }
+            | Self::ListEvents { namespace, .. }
+            | Self::WatchEventsForbidden { namespace }
+            | Self::GetService { namespace, .. }
+            | Self::ListEndpointSlices { namespace, .. }
+            | Self::ServiceHasNoReadyEndpoint { namespace, .. }
+            | Self::ServicePortNotFound { namespace, .. }
+            | Self::ContainerPortNotFound { namespace, .. }
+            | Self::PodHasNoIp { namespace, .. }
+            | Self::PodNotRunning { namespace, .. }
+            | Self::UploadSshKey { namespace, .. }
+            | Self::SshSetupCommandFailed { namespace, .. }
+            | Self::DetermineHomeDirectory { namespace, .. }
+            | Self::GenerateHostKeys { namespace, .. }
+            | Self::SshKeygenNotFound { namespace, .. }
+            | Self::HostKeyGenerationFailed { namespace, .. } => Some(namespace),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the pod involved in the error, if any.
+    fn pod_name(&self) -> Option<&str> {
+        match self {
+            Self::CreatePod { pod_name, .. }
+            | Self::DeletePod { pod_name, .. }
+            | Self::GetPod { pod_name, .. }
+            | Self::WaitForPodStatus { pod_name, .. }
+            | Self::WaitForPodCondition { pod_name, .. }
+            | Self::GetPodStatus { pod_name, .. }
+            | Self::ListEvents { pod_name, .. }
+            | Self::ContainerPortNotFound { pod_name, .. }
+            | Self::PodHasNoIp { pod_name, .. }
+            | Self::PodNotRunning { pod_name, .. }
+            | Self::UploadSshKey { pod_name, .. }
+            | Self::SshSetupCommandFailed { pod_name, .. }
+            | Self::DetermineHomeDirectory { pod_name, .. }
+            | Self::GenerateHostKeys { pod_name, .. }
+            | Self::SshKeygenNotFound { pod_name, .. }
+            | Self::HostKeyGenerationFailed { pod_name, .. }
+            | Self::InvalidPodName { pod_name, .. } => Some(pod_name),
+            _ => None,
+        }
+    }
+
+    /// Renders the error for reporting on stderr.
+    ///
+    /// In the JSON format, the error is rendered as a single-line object such
+    /// as `{"code":"CreatePod","message":"...","namespace":"...",
+    /// "pod_name":"..."}`, where `namespace` and `pod_name` are only present
+    /// when the error involves them.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - The format to render the error in.
+    pub fn render(&self, format: ErrorFormat) -> String {
+        match format {
+            ErrorFormat::Text => format!("Error: {self}"),
+            ErrorFormat::Json => {
+                let mut object = serde_json::Map::new();
+                let _unused = object.insert("code".to_string(), self.code().into());
+                let _unused = object.insert("message".to_string(), self.to_string().into());
+                if let Some(namespace) = self.namespace() {
+                    let _unused = object.insert("namespace".to_string(), namespace.into());
+                }
+                if let Some(pod_name) = self.pod_name() {
+                    let _unused = object.insert("pod_name".to_string(), pod_name.into());
+                }
+                serde_json::Value::Object(object).to_string()
+            }
+        }
+    }
+}
+
 /// Implements conversion from `crate::config::Error` to `Error::Configuration`.
 impl From<crate::config::Error> for Error {
     /// Converts a `crate::config::Error` into an `Error::Configuration`
@@ -241,3 +982,256 @@ impl From<crate::pod_console::Error> for Error {
     /// An `Error::PodConsole` containing the original error.
     fn from(source: crate::pod_console::Error) -> Self { Self::PodConsole { source } }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
+    use super::{Error, ErrorFormat, exit_code};
+
+    fn kube_error() -> kube::Error { kube::Error::LinesCodecMaxLineLengthExceeded }
+
+    fn io_error() -> std::io::Error { std::io::Error::other("boom") }
+
+    fn namespace() -> String { "dev".to_string() }
+
+    fn pod_name() -> String { "axon".to_string() }
+
+    fn assert_exit_code(errors: impl IntoIterator<Item = Error>, expected: i32) {
+        for err in errors {
+            assert_eq!(err.exit_code(), expected, "exit code of {}", err.code());
+        }
+    }
+
+    #[test]
+    fn test_exit_code_generic() {
+        let errors = [
+            Error::Generic { message: "boom".into() },
+            Error::InvalidDestination { path: PathBuf::from("out/file"), reason: "missing".into() },
+            Error::DestinationExists { path: PathBuf::from("out/file") },
+            Error::TarNotFound,
+            Error::CommandFailedOnPods { failures: vec!["axon (exit code 1)".into()], total: 2 },
+            Error::DoctorChecksFailed { failed: 1 },
+            Error::NoPodSelected,
+            Error::RunLocalTar { source: io_error() },
+            Error::TarFailed { location: "remote pod", reason: "exited with code 2".into() },
+            Error::InvalidPodName { pod_name: pod_name(), reason: "it is too long".into() },
+            Error::InvalidServiceAccountName { name: "CI".into(), reason: "uppercase".into() },
+            Error::InvalidContainerName { name: "Shell".into(), reason: "uppercase".into() },
+            Error::TerminalUi {
+                source: crate::ui::terminal::Error::EnableTerminalRawMode { source: io_error() },
+            },
+            Error::WriteStdout { source: io_error() },
+            Error::WriteStderr { source: io_error() },
+            Error::WriteEnvFile { filename: PathBuf::from(".env"), source: io_error() },
+            Error::InitializeTokioRuntime { source: io_error() },
+            Error::SerializeInteractiveShell {
+                source: serde_json::from_str::<u8>("").expect_err("invalid JSON"),
+            },
+            Error::SerializePodManifest {
+                source: serde_yaml::from_str::<u8>("-").expect_err("invalid YAML"),
+            },
+        ];
+        assert_exit_code(errors, exit_code::GENERIC);
+    }
+
+    #[test]
+    fn test_exit_code_configuration() {
+        // `InferKubeConfig` is left out as its source cannot be constructed
+        let errors = [
+            Error::Configuration {
+                source: crate::config::Error::OpenConfig {
+                    filename: PathBuf::from("config.yaml"),
+                    source: io_error(),
+                },
+            },
+            Error::SpecNotFound { spec_name: "default".into() },
+            Error::NoProxyRules,
+            Error::ReadPodManifest { filename: PathBuf::from("pod.yaml"), source: io_error() },
+            Error::ParsePodManifest {
+                filename: PathBuf::from("-"),
+                source: serde_yaml::from_str::<u8>("-").expect_err("invalid YAML"),
+            },
+            Error::InvalidPodManifest {
+                filename: PathBuf::from("pod.yaml"),
+                reason: "it has no containers".into(),
+            },
+            Error::InvalidImageReference { image: "alpine:".into(), reason: "empty tag".into() },
+            Error::KubeConfig { source: kube_error() },
+        ];
+        assert_exit_code(errors, exit_code::CONFIGURATION);
+    }
+
+    #[test]
+    fn test_exit_code_kubernetes_api() {
+        let errors = [
+            Error::PortForwarder {
+                source: crate::port_forwarder::Error::BindTcpSocket {
+                    socket_address: SocketAddr::from(([127, 0, 0, 1], 8080)),
+                    source: io_error(),
+                },
+            },
+            Error::PodConsole { source: crate::pod_console::Error::CopyIo { source: io_error() } },
+            Error::CreatePod {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::CreateService {
+                namespace: namespace(),
+                service_name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::GetNamespace { namespace: namespace(), source: Box::new(kube_error()) },
+            Error::CreateNamespace { namespace: namespace(), source: Box::new(kube_error()) },
+            Error::DeletePod {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::ListResources {
+                kind: "Service",
+                namespace: namespace(),
+                source: Box::new(kube_error()),
+            },
+            Error::DeleteResource {
+                kind: "Service",
+                namespace: namespace(),
+                name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::ListPods { source: Box::new(kube_error()) },
+            Error::GetPod {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::GetPodStatus {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                source: Box::new(kube::runtime::wait::Error::ProbeFailed(
+                    kube::runtime::watcher::Error::NoResourceVersion,
+                )),
+            },
+            Error::ListPodsWithNamespace { namespace: namespace(), source: Box::new(kube_error()) },
+            Error::ListEvents {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::WatchEventsForbidden { namespace: namespace() },
+            Error::MetricsApiUnavailable,
+            Error::ListPodMetrics { source: Box::new(kube_error()) },
+            Error::GetService {
+                namespace: namespace(),
+                service_name: "web".into(),
+                source: Box::new(kube_error()),
+            },
+            Error::ListEndpointSlices {
+                namespace: namespace(),
+                service_name: "web".into(),
+                source: Box::new(kube_error()),
+            },
+            Error::ServiceHasNoReadyEndpoint { namespace: namespace(), service_name: "web".into() },
+            Error::ServicePortNotFound {
+                namespace: namespace(),
+                service_name: "web".into(),
+                port: 80,
+            },
+            Error::ContainerPortNotFound {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                port_name: "http".into(),
+                available: vec!["grpc".into()],
+            },
+            Error::PodHasNoIp { namespace: namespace(), pod_name: pod_name() },
+            Error::PodNotRunning {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                phase: "Pending".into(),
+            },
+        ];
+        assert_exit_code(errors, exit_code::KUBERNETES_API);
+    }
+
+    #[test]
+    fn test_exit_code_ssh() {
+        let errors = [
+            Error::Ssh { source: crate::ssh::Error::Cancelled },
+            Error::UploadSshKey {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::SshSetupCommandFailed {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                reason: "exit code 1".to_string(),
+            },
+            Error::DetermineHomeDirectory {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                user: None,
+            },
+            Error::GenerateHostKeys {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                source: Box::new(kube_error()),
+            },
+            Error::SshKeygenNotFound { namespace: namespace(), pod_name: pod_name() },
+            Error::HostKeyGenerationFailed {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                output: String::new(),
+            },
+        ];
+        assert_exit_code(errors, exit_code::SSH);
+    }
+
+    #[test]
+    fn test_exit_code_timeout() {
+        let errors = [
+            Error::TransferTimeout { path: PathBuf::from("/tmp"), elapsed: Duration::ZERO },
+            Error::SshConnectTimeout { timeout: Duration::from_secs(10) },
+            Error::WaitForPodStatus { namespace: namespace(), pod_name: pod_name() },
+            Error::WaitForPodCondition {
+                namespace: namespace(),
+                pod_name: pod_name(),
+                condition: "ready".into(),
+            },
+            Error::PortForwarder {
+                source: crate::port_forwarder::Error::PodPortUnreachable {
+                    pod_name: pod_name(),
+                    remote_port: 80,
+                    timeout: Duration::ZERO,
+                    reason: String::new(),
+                },
+            },
+        ];
+        assert_exit_code(errors, exit_code::TIMEOUT);
+    }
+
+    #[test]
+    fn test_render_json() {
+        let err = Error::WaitForPodStatus { namespace: "dev".into(), pod_name: "axon".into() };
+        let json: serde_json::Value =
+            serde_json::from_str(&err.render(ErrorFormat::Json)).expect("valid JSON");
+        assert_eq!(json["code"], "WaitForPodStatus");
+        assert_eq!(json["message"], err.to_string());
+        assert_eq!(json["namespace"], "dev");
+        assert_eq!(json["pod_name"], "axon");
+
+        let err = Error::MetricsApiUnavailable;
+        let json: serde_json::Value =
+            serde_json::from_str(&err.render(ErrorFormat::Json)).expect("valid JSON");
+        assert_eq!(json["code"], "MetricsApiUnavailable");
+        assert!(json.get("namespace").is_none());
+        assert!(json.get("pod_name").is_none());
+    }
+
+    #[test]
+    fn test_render_text() {
+        let err = Error::Generic { message: "boom".into() };
+        assert_eq!(err.render(ErrorFormat::Text), "Error: boom");
+    }
+}
@@ -0,0 +1,215 @@
+//! Defines the `events` subcommand for showing the Kubernetes events of a pod.
+//!
+//! Events explain what happened to a pod, for example why it is stuck in the
+//! `Pending` phase, which its status alone does not tell.
+
+use std::{
+    pin::pin,
+    time::{Duration, SystemTime},
+};
+
+use clap::Args;
+use futures::StreamExt;
+use k8s_openapi::api::core::v1::{Event, Pod};
+use kube::{
+    Api,
+    api::ListParams,
+    runtime::{WatchStreamExt, watcher},
+};
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    PROJECT_NAME,
+    cli::{
+        Error, error,
+        internal::{ResolvedResources, ResourceResolver, signal},
+    },
+    config::Config,
+    consts::k8s::labels,
+    ui::{
+        fuzzy_finder::PodListExt as _,
+        table::{EventExt, EventListExt},
+    },
+};
+
+/// Represents the command to show the Kubernetes events of a pod.
+#[derive(Args, Clone)]
+pub struct EventsCommand {
+    /// Kubernetes namespace of the target pod.
+    ///
+    /// If not specified, the default namespace will be used.
+    #[arg(
+        short,
+        long,
+        help = "Kubernetes namespace of the target pod. If not specified, the default namespace \
+                will be used."
+    )]
+    pub namespace: Option<String>,
+
+    /// Name of the pod to show events for.
+    ///
+    /// If not specified, a fuzzy finder will be used to select a pod managed by
+    /// Axon.
+    #[arg(help = "Name of the pod to show events for. If not specified, a fuzzy finder will be \
+                  used to select a pod managed by Axon.")]
+    pub pod_name: Option<String>,
+
+    /// Keep streaming new events of the pod until interrupted.
+    #[arg(
+        short,
+        long,
+        help = "Keep streaming new events of the pod until interrupted with Ctrl-C. Requires \
+                permission to watch events in the namespace."
+    )]
+    pub follow: bool,
+
+    /// Only show events last observed within this duration.
+    #[arg(
+        long,
+        value_parser = humantime::parse_duration,
+        help = "Only show events last observed within this duration (e.g. '10m', '1h')."
+    )]
+    pub since: Option<Duration>,
+}
+
+impl EventsCommand {
+    /// Executes the `events` command, listing the events whose involved
+    /// object is the target pod, or streaming them with `--follow`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `EventsCommand` instance containing the parsed
+    ///   command-line arguments.
+    /// * `kube_client` - A Kubernetes client used to interact with the API
+    ///   server.
+    /// * `config` - The application's configuration, used for resolving
+    ///   resources.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    ///
+    /// * Listing the pods for the fuzzy finder fails.
+    /// * Listing the events of the pod fails.
+    /// * With `--follow`, the user is not allowed to watch events in the
+    ///   namespace.
+    /// * Writing the output to `stdout` fails.
+    pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
+        let Self { namespace, pod_name, follow, since } = self;
+
+        // Resolve Identity
+        let ResolvedResources { namespace, .. } =
+            ResourceResolver::from((&kube_client, &config)).resolve(namespace, None);
+        let pod_name = if let Some(pod_name) = pod_name {
+            pod_name
+        } else {
+            let list_params = ListParams {
+                label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+                ..ListParams::default()
+            };
+            let selected = Api::<Pod>::namespaced(kube_client.clone(), &namespace)
+                .list(&list_params)
+                .await
+                .with_context(|_| error::ListPodsWithNamespaceSnafu {
+                    namespace: namespace.clone(),
+                })?
+                .find_pods()
+                .await;
+            let Some((_, pod_name)) = selected.into_iter().next() else {
+                return Ok(());
+            };
+            pod_name
+        };
+
+        let field_selector = format!("involvedObject.kind=Pod,involvedObject.name={pod_name}");
+        let api = Api::<Event>::namespaced(kube_client, &namespace);
+        let cutoff = since.map(|since| SystemTime::now() - since);
+        if follow {
+            return follow_events(api, &field_selector, cutoff, &namespace).await;
+        }
+
+        let list_params =
+            ListParams { field_selector: Some(field_selector), ..ListParams::default() };
+        let mut events = api.list(&list_params).await.with_context(|_| error::ListEventsSnafu {
+            namespace: namespace.clone(),
+            pod_name: pod_name.clone(),
+        })?;
+        events.items.retain(|event| is_recent(event, cutoff));
+
+        let mut stdout = tokio::io::stdout();
+        if events.items.is_empty() {
+            let message =
+                format!("No events found for pod '{pod_name}' in namespace '{namespace}'\n");
+            return stdout.write_all(message.as_bytes()).await.context(error::WriteStdoutSnafu);
+        }
+        stdout
+            .write_all(events.render_table().as_bytes())
+            .await
+            .context(error::WriteStdoutSnafu)?;
+        stdout.write_u8(b'\n').await.context(error::WriteStdoutSnafu)
+    }
+}
+
+/// Streams the events matching `field_selector`, one line per event, until
+/// Ctrl-C is pressed or the process is asked to terminate.
+///
+/// Events already recorded when the stream starts are printed first. Watch
+/// errors are retried with a backoff, except when the user is not allowed to
+/// list or watch events, which no retry can fix.
+async fn follow_events(
+    api: Api<Event>,
+    field_selector: &str,
+    cutoff: Option<SystemTime>,
+    namespace: &str,
+) -> Result<(), Error> {
+    let events = watcher(api, watcher::Config::default().fields(field_selector)).default_backoff();
+    let mut events = pin!(events);
+    let mut shutdown = pin!(signal::shutdown_signal());
+
+    let mut stdout = tokio::io::stdout();
+    let header = format!("{}\n", Event::render_row_header());
+    stdout.write_all(header.as_bytes()).await.context(error::WriteStdoutSnafu)?;
+
+    loop {
+        let event = tokio::select! {
+            () = &mut shutdown => return Ok(()),
+            event = events.next() => event,
+        };
+        match event {
+            Some(Ok(watcher::Event::InitApply(event) | watcher::Event::Apply(event)))
+                if is_recent(&event, cutoff) =>
+            {
+                let row = format!("{}\n", event.render_row());
+                stdout.write_all(row.as_bytes()).await.context(error::WriteStdoutSnafu)?;
+            }
+            Some(Ok(_)) => {}
+            Some(Err(err)) if is_forbidden(&err) => {
+                return error::WatchEventsForbiddenSnafu { namespace }.fail();
+            }
+            Some(Err(err)) => tracing::warn!("Failed to watch events, retrying: {err}"),
+            None => return Ok(()),
+        }
+    }
+}
+
+/// Returns whether an event was last observed at or after `cutoff`.
+///
+/// Events without any timestamp are always kept.
+fn is_recent(event: &Event, cutoff: Option<SystemTime>) -> bool {
+    match (cutoff, event.last_seen()) {
+        (Some(cutoff), Some(time)) => SystemTime::from(time.0) >= cutoff,
+        _ => true,
+    }
+}
+
+/// Returns whether a watch error was caused by missing RBAC permissions.
+fn is_forbidden(err: &watcher::Error) -> bool {
+    match err {
+        watcher::Error::InitialListFailed(kube::Error::Api(status))
+        | watcher::Error::WatchStartFailed(kube::Error::Api(status))
+        | watcher::Error::WatchFailed(kube::Error::Api(status))
+        | watcher::Error::WatchError(status) => status.is_forbidden(),
+        _ => false,
+    }
+}
@@ -1,21 +1,40 @@
 //! Defines the `execute` command for running arbitrary commands within a
 //! Kubernetes pod.
+//!
+//! With `--all`, the command is run without a terminal on every running pod
+//! managed by Axon in the namespace, a few pods at a time, and each line of
+//! its output is prefixed with the name of the pod it comes from.
 
-use std::time::Duration;
+use std::{io::Write, num::NonZeroUsize};
 
 use clap::Args;
-use k8s_openapi::api::core::v1::Pod;
-use kube::Api;
+use futures::{StreamExt, stream};
+use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::Status};
+use kube::{
+    Api,
+    api::{AttachParams, ListParams},
+};
+use snafu::ResultExt;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
 
 use crate::{
+    PROJECT_NAME,
     cli::{
-        Error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
+        Error, error,
+        internal::{
+            ApiPodExt, RemoteEnv, ResolvedResources, ResourceResolver, change_dir_in_argv,
+            describe_exec_failure, export_to_argv, output, parse_workdir,
+        },
     },
     config::Config,
+    consts::{DEFAULT_POD_READY_TIMEOUT, k8s::labels},
+    ext::PodExt,
     pod_console::PodConsole,
 };
 
+/// The phase of a running pod.
+const RUNNING_PHASE: &str = "Running";
+
 /// Represents the `execute` command and its arguments.
 ///
 /// This command allows users to run arbitrary shell commands inside a specified
@@ -33,34 +52,97 @@ pub struct ExecuteCommand {
     )]
     pub namespace: Option<String>,
 
-    /// Name of the temporary pod to execute the command on.
+    /// Name of the pod to execute the command on.
     ///
-    /// If not specified, Axon's default pod naming convention will be used.
+    /// Any pod in the namespace can be targeted by its exact name, including
+    /// pods not created by Axon. If not specified, Axon's default pod naming
+    /// convention will be used.
     #[arg(
         short = 'p',
         long = "pod-name",
-        help = "Name of the temporary pod to execute the command on. If not specified, Axon's \
-                default pod name will be used."
+        help = "Name of the pod to execute the command on. Any pod in the namespace can be \
+                targeted by its exact name, including pods not created by Axon. If not specified, \
+                Axon's default pod name will be used."
     )]
     pub pod_name: Option<String>,
 
+    /// Run the command on every running pod managed by Axon in the namespace.
+    #[arg(
+        long = "all",
+        conflicts_with = "pod_name",
+        help = "Run the command on every running pod managed by Axon in the namespace, without a \
+                terminal, prefixing each line of output with the pod name. Fails if the command \
+                fails on any pod."
+    )]
+    pub all: bool,
+
+    /// The maximum number of pods the command runs on at the same time with
+    /// `--all`.
+    #[arg(
+        long = "max-concurrency",
+        default_value = "5",
+        requires = "all",
+        help = "The maximum number of pods the command runs on at the same time with --all."
+    )]
+    pub max_concurrency: NonZeroUsize,
+
     /// The maximum time in seconds to wait for the pod to be running before
     /// timing out.
+    ///
+    /// Defaults to the global `--pod-ready-timeout`, or to 15 seconds.
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "15",
-        help = "The maximum time in seconds to wait for the pod to be running before timing out."
+        help = "The maximum time in seconds to wait for the pod to be running before timing out. \
+                Defaults to the global --pod-ready-timeout, or 15 seconds."
+    )]
+    pub timeout_secs: Option<u64>,
+
+    /// Name of the container to execute the command in.
+    ///
+    /// If not specified, the pod's default container is used, falling back to
+    /// its first container.
+    #[arg(
+        short = 'c',
+        long = "container",
+        help = "Name of the container to execute the command in. If not specified, the pod's \
+                default container is used, falling back to its first container."
     )]
-    pub timeout_secs: u64,
+    pub container: Option<String>,
+
+    /// Environment variables to set for the command, as `KEY=VALUE`.
+    #[arg(
+        short = 'e',
+        long = "remote-env",
+        value_name = "KEY=VALUE",
+        help = "An environment variable to set for the command, as KEY=VALUE. Can be repeated. \
+                The command is run through env, which must be available in the container."
+    )]
+    pub remote_envs: Vec<RemoteEnv>,
+
+    /// The absolute path of the directory to execute the command in.
+    #[arg(
+        short = 'w',
+        long = "workdir",
+        value_name = "PATH",
+        value_parser = parse_workdir,
+        help = "The absolute path of the directory to execute the command in. The command is \
+                run through sh, which must be available in the container."
+    )]
+    pub workdir: Option<String>,
 
     /// The command and its arguments to execute inside the container.
     ///
     /// This argument is required and should be provided as a list of strings,
     /// where the first string is the command itself and subsequent strings are
-    /// its arguments.
+    /// its arguments. Everything after `--`, or after the first argument of the
+    /// command, is passed to the container verbatim, even if it looks like an
+    /// option of Axon.
     #[arg(
-        help = "The command and its arguments to execute inside the container.",
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = "The command and its arguments to execute inside the container. Everything after \
+                `--` is passed to the container verbatim, e.g. `axon execute -- ls -la`.",
         required = true
     )]
     pub command: Vec<String>,
@@ -71,7 +153,8 @@ impl ExecuteCommand {
     ///
     /// This asynchronous function resolves the target pod's namespace and name,
     /// waits for the pod to be in a running state, and then initiates a console
-    /// session to run the provided command.
+    /// session to run the provided command, with the `remote_envs` set in its
+    /// environment and in `workdir` if given.
     ///
     /// # Arguments
     ///
@@ -89,6 +172,8 @@ impl ExecuteCommand {
     ///   `timeout_secs`.
     /// * There's an issue connecting to the pod's console or executing the
     ///   command.
+    /// * With `--all`, listing the pods fails, or the command fails on any pod
+    ///   (`Error::CommandFailedOnPods`).
     ///
     /// # Panics
     ///
@@ -96,7 +181,17 @@ impl ExecuteCommand {
     /// operations could potentially panic in extreme error scenarios (e.g.,
     /// OOM).
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, pod_name, command, timeout_secs } = self;
+        let Self {
+            namespace,
+            pod_name,
+            all,
+            max_concurrency,
+            container,
+            remote_envs,
+            workdir,
+            command,
+            timeout_secs,
+        } = self;
 
         // Resolve Identity
         let ResolvedResources { namespace, pod_name } =
@@ -104,10 +199,188 @@ impl ExecuteCommand {
 
         // Resolve Pod API & Status
         let api = Api::<Pod>::namespaced(kube_client, &namespace);
-        let _pod = api
-            .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
+        let command = export_to_argv(&remote_envs, change_dir_in_argv(workdir.as_deref(), command));
+        if all {
+            return run_on_all(&api, &namespace, container, command, max_concurrency).await;
+        }
+        let pod = api
+            .await_running_status(
+                &pod_name,
+                &namespace,
+                config.pod_ready_timeout(timeout_secs, DEFAULT_POD_READY_TIMEOUT),
+            )
             .await?;
 
-        PodConsole::new(api, pod_name, namespace, command).run().await.map_err(Error::from)
+        // Resolve Container
+        let container = container.or_else(|| pod.default_container());
+
+        PodConsole::new(api, pod_name, namespace, command)
+            .container(container)
+            .run()
+            .await
+            .map_err(Error::from)
+    }
+}
+
+/// Runs `command` on every running pod managed by Axon behind `api`, on at
+/// most `max_concurrency` pods at a time.
+///
+/// Pods that are not running are skipped. The command runs in `container`, or
+/// in the default container of each pod.
+///
+/// # Errors
+///
+/// Returns `ListPodsWithNamespace` if the pods cannot be listed, or
+/// `CommandFailedOnPods` if the command cannot be run or exits with a non-zero
+/// code on any pod.
+async fn run_on_all(
+    api: &Api<Pod>,
+    namespace: &str,
+    container: Option<String>,
+    command: Vec<String>,
+    max_concurrency: NonZeroUsize,
+) -> Result<(), Error> {
+    let list_params = ListParams {
+        label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+        ..ListParams::default()
+    };
+    let pods = api
+        .list(&list_params)
+        .await
+        .with_context(|_| error::ListPodsWithNamespaceSnafu { namespace: namespace.to_string() })?;
+
+    let mut targets = Vec::new();
+    for pod in pods {
+        let pod_name = pod.metadata.name.clone().unwrap_or_default();
+        if pod.status.as_ref().and_then(|status| status.phase.as_deref()) == Some(RUNNING_PHASE) {
+            targets.push((pod_name, container.clone().or_else(|| pod.default_container())));
+        } else {
+            output::status(format_args!("pod/{pod_name} skipped: {}", pod.phase_summary()));
+        }
+    }
+    if targets.is_empty() {
+        output::status(format_args!("No running pods managed by Axon in namespace {namespace}"));
+        return Ok(());
+    }
+
+    let total = targets.len();
+    let mut failures = stream::iter(targets.into_iter().map(|(pod_name, container)| {
+        let command = command.clone();
+        async move {
+            let failure = match exec_prefixed(api, &pod_name, container, command).await {
+                Ok(Some(status)) => describe_exec_failure(&status),
+                Ok(None) => Some("no exit status was reported".to_string()),
+                Err(err) => Some(err.to_string()),
+            };
+            failure.map(|failure| format!("{pod_name} ({failure})"))
+        }
+    }))
+    .buffer_unordered(max_concurrency.get())
+    .filter_map(futures::future::ready)
+    .collect::<Vec<_>>()
+    .await;
+    failures.sort();
+
+    snafu::ensure!(failures.is_empty(), error::CommandFailedOnPodsSnafu { failures, total });
+    Ok(())
+}
+
+/// Executes `command` in `container` of the pod `pod_name` without a
+/// terminal, writing each line of its stdout and stderr to the local ones
+/// prefixed with the pod name.
+///
+/// Returns the status the command exited with, if reported.
+async fn exec_prefixed(
+    api: &Api<Pod>,
+    pod_name: &str,
+    container: Option<String>,
+    command: Vec<String>,
+) -> Result<Option<Status>, kube::Error> {
+    let attach_params = AttachParams { container, ..AttachParams::default() };
+    let mut attached = api.exec(pod_name, command, &attach_params).await?;
+
+    let prefix = format!("[{pod_name}] ");
+    let status = attached.take_status();
+    let _unused = tokio::join!(
+        copy_prefixed(attached.stdout(), &prefix, |line| std::io::stdout().lock().write_all(line)),
+        copy_prefixed(attached.stderr(), &prefix, |line| std::io::stderr().lock().write_all(line)),
+    );
+    let status = match status {
+        Some(status) => status.await,
+        None => None,
+    };
+    let _unused = attached.join().await;
+    Ok(status)
+}
+
+/// Copies `reader` line by line through `write_line`, prefixing every line
+/// with `prefix`.
+///
+/// Each line is written at once, so that the lines of several pods do not
+/// interleave.
+async fn copy_prefixed(
+    reader: Option<impl AsyncRead + Unpin>,
+    prefix: &str,
+    write_line: fn(&[u8]) -> std::io::Result<()>,
+) {
+    let Some(reader) = reader else {
+        return;
+    };
+    let mut segments = BufReader::new(reader).split(b'\n');
+    while let Ok(Some(segment)) = segments.next_segment().await {
+        let line = [prefix.as_bytes(), &segment, b"\n"].concat();
+        if write_line(&line).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        execute: ExecuteCommand,
+    }
+
+    #[test]
+    fn test_command_is_passed_verbatim() {
+        for (args, expected) in [
+            (
+                &["axon", "-p", "my-pod", "--", "rm", "-rf", "/tmp/x"][..],
+                &["rm", "-rf", "/tmp/x"][..],
+            ),
+            (&["axon", "-p", "my-pod", "--", "-p", "-w"], &["-p", "-w"]),
+            (&["axon", "-p", "my-pod", "ls", "-la", "--", "x"], &["ls", "-la", "--", "x"]),
+            (&["axon", "-e", "A=1", "--", "env"], &["env"]),
+        ] {
+            let Cli { execute } = Cli::try_parse_from(args).unwrap();
+            assert_eq!(execute.command, expected, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn test_all_pods() {
+        let Cli { execute } =
+            Cli::try_parse_from(["axon", "--all", "--max-concurrency", "2", "--", "uptime"])
+                .unwrap();
+        assert!(execute.all);
+        assert_eq!(execute.max_concurrency.get(), 2);
+
+        let Cli { execute } = Cli::try_parse_from(["axon", "--", "uptime"]).unwrap();
+        assert!(!execute.all);
+        assert_eq!(execute.max_concurrency.get(), 5);
+
+        for args in [
+            &["axon", "--all", "-p", "my-pod", "--", "uptime"][..],
+            &["axon", "--max-concurrency", "2", "--", "uptime"],
+            &["axon", "--all", "--max-concurrency", "0", "--", "uptime"],
+        ] {
+            assert!(Cli::try_parse_from(args).is_err(), "{args:?}");
+        }
     }
 }
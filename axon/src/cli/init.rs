@@ -0,0 +1,51 @@
+//! Defines the `init` subcommand, which writes a configuration file to start
+//! from.
+//!
+//! New users have no configuration, so `init` writes the basic template to the
+//! path Axon loads its configuration from, where it can be edited.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    cli::{Error, error, internal::output},
+    config::Config,
+};
+
+/// Represents the command to write the default configuration file.
+#[derive(Args, Clone)]
+pub struct InitCommand {
+    /// Overwrite the configuration file if it already exists.
+    #[arg(long = "force", help = "Overwrite the configuration file if it already exists.")]
+    pub force: bool,
+}
+
+impl InitCommand {
+    /// Writes the basic configuration template to `path`, creating its parent
+    /// directories, and prints the path written to stdout.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the configuration file, i.e. the `--config` path
+    ///   or the default path.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    /// * `path` already exists and `force` is not set.
+    /// * The file or its parent directories cannot be written.
+    /// * Writing the path to `stdout` fails.
+    pub async fn run(self, path: PathBuf) -> Result<(), Error> {
+        Config::write_template(&path, self.force)?;
+        if output::is_quiet() {
+            return Ok(());
+        }
+        tokio::io::stdout()
+            .write_all(format!("Wrote default configuration to {}\n", path.display()).as_bytes())
+            .await
+            .context(error::WriteStdoutSnafu)
+    }
+}
@@ -1,14 +1,36 @@
-//! This module provides extensions for the Kubernetes `Api<Pod>` type.
+//! This module provides extensions for the Kubernetes `Api<Pod>` type, and
+//! the pod conditions that can be awaited besides `is_pod_running`.
 use std::time::Duration;
 
 use k8s_openapi::api::core::v1::Pod;
 use kube::{
     Api,
-    runtime::{conditions::is_pod_running, wait::await_condition},
+    api::LogParams,
+    runtime::{
+        conditions::is_pod_running,
+        wait::{self, Condition, await_condition},
+        watcher,
+    },
 };
 use snafu::ResultExt;
 
-use crate::cli::{Error, error};
+use crate::{
+    cli::{Error, error, internal::output},
+    ext::PodExt,
+    ui::WaitSpinner,
+};
+
+/// The delay before the first retry of a watch failing transiently.
+const INITIAL_WATCH_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+/// The maximum delay between two retries of a watch failing transiently.
+const MAX_WATCH_RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Returns a condition that holds once the pod reports the `Ready` condition.
+pub fn is_pod_ready() -> impl Condition<Pod> { |pod: Option<&Pod>| pod.is_some_and(Pod::is_ready) }
+
+/// Returns a condition that holds once the pod no longer exists.
+pub fn is_pod_deleted() -> impl Condition<Pod> { |pod: Option<&Pod>| pod.is_none() }
 
 /// Extension trait for `kube::Api<Pod>` providing additional utility methods.
 pub trait ApiPodExt {
@@ -18,6 +40,11 @@ pub trait ApiPodExt {
     /// does not transition to a running state within the specified duration,
     /// an error is returned.
     ///
+    /// While waiting, a status line showing the elapsed time and the current
+    /// phase of the Pod is drawn on stderr, unless `--quiet` is set or stderr
+    /// is not a terminal. Watch errors that may go away, such as a dropped
+    /// connection, are retried with a backoff within the timeout.
+    ///
     /// # Arguments
     ///
     /// * `pod_name` - The name of the Pod to wait for.
@@ -45,6 +72,30 @@ pub trait ApiPodExt {
         namespace: &str,
         timeout: Duration,
     ) -> Result<Pod, Error>;
+
+    /// Reads the last `tail_lines` lines of the logs of a Pod's container,
+    /// e.g. to show why it crashed.
+    ///
+    /// The logs of the previous instance of the container are read if the
+    /// current one has none, as is the case of a container waiting to be
+    /// restarted after a crash.
+    ///
+    /// # Arguments
+    ///
+    /// * `pod_name` - The name of the Pod.
+    /// * `container` - The container to read the logs of, or `None` for the
+    ///   only or default container.
+    /// * `tail_lines` - The number of lines to read from the end of the logs.
+    ///
+    /// # Returns
+    ///
+    /// The logs, or `None` if there are none or they cannot be read.
+    async fn recent_logs(
+        &self,
+        pod_name: &str,
+        container: Option<&str>,
+        tail_lines: i64,
+    ) -> Option<String>;
 }
 
 impl ApiPodExt for Api<Pod> {
@@ -54,20 +105,35 @@ impl ApiPodExt for Api<Pod> {
         namespace: &str,
         timeout: Duration,
     ) -> Result<Pod, Error> {
+        let spinner = if output::is_quiet() {
+            WaitSpinner::hidden()
+        } else {
+            WaitSpinner::new(pod_name, namespace)
+        };
+        let is_running = is_pod_running();
+        let condition = |pod: Option<&Pod>| {
+            if let Some(pod) = pod {
+                spinner.set_phase(&pod.phase_summary());
+            }
+            is_running.matches_object(pod)
+        };
+
         // Wait until the pod is running, otherwise we get 500 error.
-        let maybe_pod = tokio::time::timeout(
+        let result = tokio::time::timeout(
             timeout,
-            await_condition(self.clone(), pod_name, is_pod_running()),
+            await_condition_with_retry(self.clone(), pod_name, &condition),
         )
-        .await
-        .map_err(|_| Error::WaitForPodStatus {
-            namespace: namespace.to_string(),
-            pod_name: pod_name.to_string(),
-        })?
-        .with_context(|_| error::GetPodStatusSnafu {
-            namespace: namespace.to_string(),
-            pod_name: pod_name.to_string(),
-        })?;
+        .await;
+        spinner.finish();
+        let maybe_pod = result
+            .map_err(|_| Error::WaitForPodStatus {
+                namespace: namespace.to_string(),
+                pod_name: pod_name.to_string(),
+            })?
+            .with_context(|_| error::GetPodStatusSnafu {
+                namespace: namespace.to_string(),
+                pod_name: pod_name.to_string(),
+            })?;
         match maybe_pod {
             Some(pod) => Ok(pod),
             None => self.get(pod_name).await.with_context(|_| error::GetPodSnafu {
@@ -76,4 +142,134 @@ impl ApiPodExt for Api<Pod> {
             }),
         }
     }
+
+    async fn recent_logs(
+        &self,
+        pod_name: &str,
+        container: Option<&str>,
+        tail_lines: i64,
+    ) -> Option<String> {
+        for previous in [false, true] {
+            let params = LogParams {
+                container: container.map(String::from),
+                tail_lines: Some(tail_lines),
+                previous,
+                ..LogParams::default()
+            };
+            match self.logs(pod_name, &params).await {
+                Ok(logs) if !logs.trim().is_empty() => return Some(logs),
+                Ok(_) => {}
+                Err(err) => tracing::debug!("Failed to read the logs of pod {pod_name}: {err}"),
+            }
+        }
+        None
+    }
+}
+
+/// Waits for `condition` to hold for the Pod `pod_name`, like
+/// `await_condition`, but watches the Pod again after transient errors.
+///
+/// The delay between two watches starts at `INITIAL_WATCH_RETRY_BACKOFF` and
+/// doubles up to `MAX_WATCH_RETRY_BACKOFF`. The wait is unbounded, so callers
+/// are expected to apply a timeout.
+///
+/// # Errors
+///
+/// Returns the first error that `is_transient_wait_error` does not classify
+/// as transient.
+async fn await_condition_with_retry(
+    api: Api<Pod>,
+    pod_name: &str,
+    condition: impl Condition<Pod> + Copy + Send + Sync,
+) -> Result<Option<Pod>, wait::Error> {
+    let mut backoff = INITIAL_WATCH_RETRY_BACKOFF;
+    loop {
+        match Box::pin(await_condition(api.clone(), pod_name, condition)).await {
+            Err(err) if is_transient_wait_error(&err) => {
+                tracing::debug!("Watching pod {pod_name} failed, retrying in {backoff:?}: {err}");
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2).min(MAX_WATCH_RETRY_BACKOFF);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Returns whether a failure to wait for a condition may go away when the
+/// object is watched again, e.g. a dropped connection or an overloaded API
+/// server, as opposed to a missing permission or an unknown resource.
+fn is_transient_wait_error(err: &wait::Error) -> bool {
+    let wait::Error::ProbeFailed(err) = err;
+    match err {
+        watcher::Error::InitialListFailed(err)
+        | watcher::Error::WatchStartFailed(err)
+        | watcher::Error::WatchFailed(err) => match err {
+            kube::Error::Api(status) => is_transient_status(status.code),
+            kube::Error::HyperError(_) | kube::Error::Service(_) | kube::Error::ReadEvents(_) => {
+                true
+            }
+            _ => false,
+        },
+        watcher::Error::WatchError(status) => is_transient_status(status.code),
+        watcher::Error::NoResourceVersion => false,
+    }
+}
+
+/// Returns whether an API status code indicates a failure that may go away
+/// when the object is watched again, including `410 Gone` for a watch whose
+/// resource version has expired.
+const fn is_transient_status(code: u16) -> bool { matches!(code, 410 | 429 | 502 | 503 | 504) }
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{PodCondition, PodStatus};
+    use kube::core::Status;
+
+    use super::*;
+
+    #[test]
+    fn test_conditions() {
+        let pod = |ready: &str| Pod {
+            status: Some(PodStatus {
+                conditions: Some(vec![PodCondition {
+                    type_: "Ready".to_string(),
+                    status: ready.to_string(),
+                    ..PodCondition::default()
+                }]),
+                ..PodStatus::default()
+            }),
+            ..Pod::default()
+        };
+        assert!(is_pod_ready().matches_object(Some(&pod("True"))));
+        assert!(!is_pod_ready().matches_object(Some(&pod("False"))));
+        assert!(!is_pod_ready().matches_object(None));
+        assert!(is_pod_deleted().matches_object(None));
+        assert!(!is_pod_deleted().matches_object(Some(&pod("True"))));
+    }
+
+    #[test]
+    fn test_is_transient_wait_error() {
+        let status = |code| Status::failure("failed", "Failure").with_code(code).boxed();
+        let api = |code| kube::Error::Api(status(code));
+        let disconnected =
+            || kube::Error::ReadEvents(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        for (err, transient) in [
+            (watcher::Error::WatchFailed(disconnected()), true),
+            (watcher::Error::WatchStartFailed(api(503)), true),
+            (watcher::Error::InitialListFailed(api(429)), true),
+            (watcher::Error::WatchError(status(410)), true),
+            (watcher::Error::InitialListFailed(api(403)), false),
+            (watcher::Error::WatchStartFailed(api(404)), false),
+            (watcher::Error::WatchError(status(401)), false),
+            (watcher::Error::WatchFailed(kube::Error::LinesCodecMaxLineLengthExceeded), false),
+            (watcher::Error::NoResourceVersion, false),
+        ] {
+            let message = err.to_string();
+            assert_eq!(
+                is_transient_wait_error(&wait::Error::ProbeFailed(err)),
+                transient,
+                "{message}"
+            );
+        }
+    }
 }
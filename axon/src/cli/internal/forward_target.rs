@@ -0,0 +1,259 @@
+//! Targets of port forwarding.
+//!
+//! Port forwarding always ends at a pod. A `ForwardTarget` names either a pod
+//! directly or a service, which is resolved to one of its ready backing pods
+//! through its endpoint slices, the same way `kubectl port-forward svc/NAME`
+//! does.
+
+use std::{fmt, str::FromStr};
+
+use k8s_openapi::{
+    api::{
+        core::v1::{Pod, Service},
+        discovery::v1::EndpointSlice,
+    },
+    apimachinery::pkg::util::intstr::IntOrString,
+};
+use kube::{Api, api::ListParams};
+use snafu::ResultExt;
+
+use crate::{
+    cli::{Error, error},
+    ext::PodExt,
+};
+
+/// The label linking an endpoint slice to the service it belongs to.
+const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// The resource that port forwarding is directed at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ForwardTarget {
+    /// A pod, given by name.
+    Pod(String),
+    /// A service, forwarded to one of its ready backing pods.
+    Service(String),
+}
+
+impl FromStr for ForwardTarget {
+    type Err = String;
+
+    /// Parses a target in the form `pod/NAME` or `svc/NAME`. A bare `NAME`
+    /// refers to a pod.
+    ///
+    /// The long and plural resource names accepted by `kubectl`, such as
+    /// `pods/NAME` or `service/NAME`, are accepted as well.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once('/') {
+            None if !input.is_empty() => Ok(Self::Pod(input.to_string())),
+            Some(("pod" | "pods" | "po", name)) if !name.is_empty() => {
+                Ok(Self::Pod(name.to_string()))
+            }
+            Some(("service" | "services" | "svc", name)) if !name.is_empty() => {
+                Ok(Self::Service(name.to_string()))
+            }
+            _ => Err(format!("'{input}' is not a valid target, expected pod/NAME or svc/NAME")),
+        }
+    }
+}
+
+impl fmt::Display for ForwardTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pod(name) => write!(f, "pod/{name}"),
+            Self::Service(name) => write!(f, "svc/{name}"),
+        }
+    }
+}
+
+/// A service resolved for port forwarding.
+#[derive(Clone)]
+pub struct ServiceTarget {
+    /// The namespace of the service.
+    namespace: String,
+    /// The service as read from the API server.
+    service: Service,
+}
+
+impl ServiceTarget {
+    /// Reads the service `name` from the API server.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::GetService` if the service cannot be read.
+    pub async fn get(client: kube::Client, namespace: &str, name: &str) -> Result<Self, Error> {
+        let service = Api::<Service>::namespaced(client, namespace)
+            .get(name)
+            .await
+            .with_context(|_| error::GetServiceSnafu { namespace, service_name: name })?;
+        Ok(Self { namespace: namespace.to_string(), service })
+    }
+
+    /// Returns the namespace of the service.
+    pub fn namespace(&self) -> &str { &self.namespace }
+
+    /// Returns the name of the service.
+    pub fn name(&self) -> &str { self.service.metadata.name.as_deref().unwrap_or_default() }
+
+    /// Returns the ports exposed by the service.
+    pub fn ports(&self) -> Vec<u16> {
+        self.service
+            .spec
+            .iter()
+            .flat_map(|spec| spec.ports.iter().flatten())
+            .filter_map(|port| u16::try_from(port.port).ok())
+            .collect()
+    }
+
+    /// Checks that the service exposes `port`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ServicePortNotFound` if it does not.
+    pub fn ensure_port(&self, port: u16) -> Result<(), Error> {
+        snafu::ensure!(
+            self.ports().contains(&port),
+            error::ServicePortNotFoundSnafu {
+                namespace: &self.namespace,
+                service_name: self.name(),
+                port
+            }
+        );
+        Ok(())
+    }
+
+    /// Returns the name of a ready pod backing the service, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ListEndpointSlices` if the endpoint slices of the
+    /// service cannot be listed.
+    pub async fn ready_pod(&self, client: kube::Client) -> Result<Option<String>, Error> {
+        let service_name = self.name();
+        let slices = Api::<EndpointSlice>::namespaced(client, &self.namespace)
+            .list(&ListParams::default().labels(&format!("{SERVICE_NAME_LABEL}={service_name}")))
+            .await
+            .with_context(|_| error::ListEndpointSlicesSnafu {
+                namespace: &self.namespace,
+                service_name,
+            })?;
+
+        Ok(slices
+            .items
+            .into_iter()
+            .flat_map(|slice| slice.endpoints)
+            .filter(|endpoint| {
+                endpoint.conditions.as_ref().and_then(|conditions| conditions.ready) != Some(false)
+            })
+            .filter_map(|endpoint| endpoint.target_ref)
+            .find(|target| target.kind.as_deref() == Some("Pod"))
+            .and_then(|target| target.name))
+    }
+
+    /// Translates the service `port` to the port of the container in `pod`
+    /// it is routed to.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::ServicePortNotFound` if the service does not expose
+    /// `port`, or `Error::ContainerPortNotFound` if the service targets a named
+    /// port that no container of `pod` declares.
+    pub fn container_port(&self, port: u16, pod: &Pod) -> Result<u16, Error> {
+        let service_port = self
+            .service
+            .spec
+            .iter()
+            .flat_map(|spec| spec.ports.iter().flatten())
+            .find(|service_port| service_port.port == i32::from(port))
+            .ok_or_else(|| {
+                error::ServicePortNotFoundSnafu {
+                    namespace: &self.namespace,
+                    service_name: self.name(),
+                    port,
+                }
+                .build()
+            })?;
+
+        match &service_port.target_port {
+            None => Ok(port),
+            Some(IntOrString::Int(target_port)) => Ok(u16::try_from(*target_port).unwrap_or(port)),
+            Some(IntOrString::String(port_name)) => {
+                pod.container_port_named(port_name).ok_or_else(|| {
+                    error::ContainerPortNotFoundSnafu {
+                        namespace: &self.namespace,
+                        pod_name: pod.metadata.name.clone().unwrap_or_default(),
+                        port_name,
+                        available: pod.container_port_names(),
+                    }
+                    .build()
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec, ServicePort, ServiceSpec};
+
+    use super::*;
+
+    fn service_target(target_port: Option<IntOrString>) -> ServiceTarget {
+        let mut service = Service::default();
+        service.metadata.name = Some("web".to_string());
+        service.spec = Some(ServiceSpec {
+            ports: Some(vec![ServicePort { port: 80, target_port, ..ServicePort::default() }]),
+            ..ServiceSpec::default()
+        });
+        ServiceTarget { namespace: "dev".to_string(), service }
+    }
+
+    fn pod_with_port(name: &str, container_port: i32) -> Pod {
+        Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    ports: Some(vec![ContainerPort {
+                        name: Some(name.to_string()),
+                        container_port,
+                        ..ContainerPort::default()
+                    }]),
+                    ..Container::default()
+                }],
+                ..PodSpec::default()
+            }),
+            ..Pod::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_forward_target() {
+        assert_eq!("pod/axon".parse(), Ok(ForwardTarget::Pod("axon".to_string())));
+        assert_eq!("svc/web".parse(), Ok(ForwardTarget::Service("web".to_string())));
+        assert_eq!("services/web".parse(), Ok(ForwardTarget::Service("web".to_string())));
+        assert_eq!("axon".parse(), Ok(ForwardTarget::Pod("axon".to_string())));
+        assert!("deployment/web".parse::<ForwardTarget>().is_err());
+        assert!("svc/".parse::<ForwardTarget>().is_err());
+    }
+
+    #[test]
+    fn test_container_port() {
+        let pod = pod_with_port("http", 8080);
+
+        assert_eq!(service_target(None).container_port(80, &pod).ok(), Some(80));
+        assert_eq!(
+            service_target(Some(IntOrString::Int(9090))).container_port(80, &pod).ok(),
+            Some(9090)
+        );
+        assert_eq!(
+            service_target(Some(IntOrString::String("http".into()))).container_port(80, &pod).ok(),
+            Some(8080)
+        );
+        assert!(matches!(
+            service_target(Some(IntOrString::String("grpc".into()))).container_port(80, &pod),
+            Err(Error::ContainerPortNotFound { .. })
+        ));
+        assert!(matches!(
+            service_target(None).container_port(443, &pod),
+            Err(Error::ServicePortNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,295 @@
+//! Forwarders shared by the commands that forward local ports.
+//!
+//! `ForwarderOptions` configures and runs a single `PortForwarder` to a pod,
+//! reporting its status on stderr. `ServiceForward` builds on it to forward to
+//! a service, following the ready pods backing the service. `serve_metrics`
+//! exposes the metrics of the forwarders of a command over HTTP, and
+//! `ReadySender` reports the local address of every forwarder once it is
+//! bound.
+
+use std::{future::Future, net::SocketAddr, num::NonZeroUsize, sync::Arc, time::Duration};
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    Api,
+    runtime::wait::{Condition, await_condition},
+};
+use sigfinn::{ExitStatus, LifecycleManager, Shutdown};
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::{
+        Error,
+        internal::{ServiceTarget, is_pod_ready},
+    },
+    port_forwarder::{
+        self, ConnectionEvent, ConnectionLimitPolicy, ForwarderMetrics, MetricsRegistry,
+        PortForwarderBuilder,
+    },
+};
+
+/// How often a service is resolved again while it has no ready endpoint.
+const ENDPOINT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Spawns a worker serving the metrics of the forwarders of a command at
+/// `http://ADDR/metrics`, returning the registry the forwarders report to.
+pub fn serve_metrics(
+    lifecycle_manager: &LifecycleManager<Error>,
+    addr: SocketAddr,
+) -> Arc<MetricsRegistry> {
+    let registry = Arc::new(MetricsRegistry::default());
+    let server = Arc::clone(&registry);
+    let _handle = lifecycle_manager.spawn("metrics-server", move |shutdown_signal| async move {
+        match server.serve(addr, shutdown_signal).await {
+            Ok(()) => ExitStatus::Success,
+            Err(err) => ExitStatus::Error(Error::from(err)),
+        }
+    });
+    registry
+}
+
+/// Reports the local address a forwarder is bound to, along with the index of
+/// the forwarder among those of the command, once it is ready.
+#[derive(Clone)]
+pub struct ReadySender {
+    /// The channel the addresses are sent to.
+    sender: mpsc::UnboundedSender<(usize, SocketAddr)>,
+    /// The index of the forwarder.
+    index: usize,
+}
+
+impl ReadySender {
+    /// Creates a sender for the forwarder at index `0`, and the receiver of
+    /// the addresses of every forwarder.
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<(usize, SocketAddr)>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender, index: 0 }, receiver)
+    }
+
+    /// Returns a sender reporting for the forwarder at `index`.
+    pub fn with_index(&self, index: usize) -> Self { Self { sender: self.sender.clone(), index } }
+
+    /// Reports that the forwarder is bound to `addr`.
+    fn send(&self, addr: SocketAddr) { let _unused = self.sender.send((self.index, addr)); }
+}
+
+/// Options shared by every forwarder started by a command.
+#[derive(Clone)]
+pub struct ForwarderOptions {
+    /// Whether to report every opened and closed connection on stderr.
+    pub verbose: bool,
+    /// Whether to print the bound local port to stdout.
+    pub print_port: bool,
+    /// How long to wait for the pod port to accept a test connection, if at
+    /// all.
+    pub wait_for_port: Option<Duration>,
+    /// The maximum number of concurrent connections, if any.
+    pub max_connections: Option<NonZeroUsize>,
+    /// What to do with new connections while `max_connections` is reached.
+    pub connection_limit_policy: ConnectionLimitPolicy,
+    /// The registry the forwarders report their metrics to, if metrics are
+    /// enabled.
+    pub metrics: Option<Arc<MetricsRegistry>>,
+    /// Where to report the bound local address once the forwarder is ready, if
+    /// anywhere.
+    pub ready_sender: Option<ReadySender>,
+}
+
+impl ForwarderOptions {
+    /// Forwards `local_addr` to `container_port` of a pod until
+    /// `shutdown_signal` completes.
+    ///
+    /// Returns the local address the forwarder was bound to, if it got that
+    /// far.
+    pub async fn run(
+        self,
+        api: Api<Pod>,
+        pod_name: String,
+        container_port: u16,
+        local_addr: SocketAddr,
+        shutdown_signal: impl Future<Output = ()> + Send + Unpin + 'static,
+    ) -> Result<Option<SocketAddr>, port_forwarder::Error> {
+        let metrics = self
+            .metrics
+            .as_ref()
+            .map(|registry| registry.register(local_addr, &format!("{pod_name}:{container_port}")));
+        self.forward(api, pod_name, container_port, local_addr, metrics, shutdown_signal).await
+    }
+
+    /// Same as `run`, but reports to the given `metrics` instead of
+    /// registering the forwarder in the registry.
+    async fn forward(
+        self,
+        api: Api<Pod>,
+        pod_name: String,
+        container_port: u16,
+        local_addr: SocketAddr,
+        metrics: Option<Arc<ForwarderMetrics>>,
+        shutdown_signal: impl Future<Output = ()> + Send + Unpin + 'static,
+    ) -> Result<Option<SocketAddr>, port_forwarder::Error> {
+        let Self {
+            verbose,
+            print_port,
+            wait_for_port,
+            max_connections,
+            connection_limit_policy,
+            ready_sender,
+            ..
+        } = self;
+        let target = format!("{pod_name}:{container_port}");
+        let mut builder = PortForwarderBuilder::new(api, pod_name, container_port)
+            .local_address(local_addr)
+            .connection_limit_policy(connection_limit_policy);
+        if let Some(max_connections) = max_connections {
+            builder = builder.max_connections(max_connections);
+        }
+        if let Some(metrics) = metrics {
+            builder = builder.metrics(metrics);
+        }
+        if let Some(timeout) = wait_for_port {
+            builder = builder.wait_for_port(timeout);
+        }
+        if verbose {
+            let target = target.clone();
+            builder = builder.on_connection(move |event| match event {
+                ConnectionEvent::Opened { peer } => {
+                    eprintln!("Connection opened: {peer} -> {target}");
+                }
+                ConnectionEvent::Closed { peer } => {
+                    eprintln!("Connection closed: {peer} -> {target}");
+                }
+            });
+        }
+
+        let (addr_sender, addr_receiver) = oneshot::channel();
+        builder
+            .on_ready(move |addr| {
+                eprintln!("Forwarding {addr} -> {target}, press Ctrl-C to stop");
+                if print_port {
+                    println!("{}", addr.port());
+                }
+                if let Some(ready_sender) = &ready_sender {
+                    ready_sender.send(addr);
+                }
+                let _unused = addr_sender.send(addr);
+            })
+            .build()
+            .run(shutdown_signal)
+            .await?;
+        Ok(addr_receiver.await.ok())
+    }
+}
+
+/// A forwarder directed at a service port, following the ready pods backing
+/// the service.
+pub struct ServiceForward {
+    /// The client used to look up the pods backing the service.
+    pub client: kube::Client,
+    /// The service being forwarded to.
+    pub service: ServiceTarget,
+    /// The service port being forwarded to.
+    pub service_port: u16,
+    /// The ready pod to forward to first.
+    pub pod_name: String,
+    /// The options of the forwarder.
+    pub options: ForwarderOptions,
+}
+
+impl ServiceForward {
+    /// Forwards `local_addr` to the service port until `shutdown_signal`
+    /// completes.
+    ///
+    /// Whenever the current pod stops being ready, its connections are drained
+    /// and the service is resolved again, so that new connections go to a
+    /// healthy endpoint. The local port stays the same, even if it was chosen
+    /// by the system.
+    pub async fn run(
+        self,
+        mut local_addr: SocketAddr,
+        shutdown_signal: Shutdown,
+    ) -> ExitStatus<Error> {
+        let Self { client, service, service_port, mut pod_name, mut options } = self;
+        let shutdown = CancellationToken::new();
+        let _watcher = tokio::spawn({
+            let shutdown = shutdown.clone();
+            async move {
+                shutdown_signal.await;
+                shutdown.cancel();
+            }
+        });
+        let api = Api::<Pod>::namespaced(client.clone(), service.namespace());
+        let metrics = options.metrics.as_ref().map(|registry| {
+            registry.register(local_addr, &format!("svc/{}:{service_port}", service.name()))
+        });
+
+        loop {
+            let result = match api.get(&pod_name).await {
+                Ok(pod) => service.container_port(service_port, &pod),
+                Err(source) => Err(Error::GetPod {
+                    namespace: service.namespace().to_string(),
+                    pod_name: pod_name.clone(),
+                    source: Box::new(source),
+                }),
+            };
+            let container_port = match result {
+                Ok(container_port) => container_port,
+                Err(err) => return ExitStatus::Error(err),
+            };
+
+            let forwarder_shutdown = Box::pin({
+                let shutdown = shutdown.clone();
+                let api = api.clone();
+                let pod_name = pod_name.clone();
+                async move {
+                    let endpoint_lost = await_condition(api, &pod_name, is_pod_ready().not());
+                    tokio::select! {
+                        () = shutdown.cancelled() => {}
+                        _ = endpoint_lost => {}
+                    }
+                }
+            });
+            let result = options
+                .clone()
+                .forward(
+                    api.clone(),
+                    pod_name.clone(),
+                    container_port,
+                    local_addr,
+                    metrics.clone(),
+                    forwarder_shutdown,
+                )
+                .await;
+            match result {
+                Ok(bound_addr) => local_addr = bound_addr.unwrap_or(local_addr),
+                Err(err) => return ExitStatus::Error(Error::from(err)),
+            }
+            options.print_port = false;
+            options.ready_sender = None;
+            if shutdown.is_cancelled() {
+                return ExitStatus::Success;
+            }
+
+            eprintln!(
+                "Pod {pod_name} of service {} is no longer ready, waiting for another endpoint",
+                service.name()
+            );
+            pod_name = loop {
+                tokio::select! {
+                    () = shutdown.cancelled() => return ExitStatus::Success,
+                    () = tokio::time::sleep(ENDPOINT_POLL_INTERVAL) => {}
+                }
+                match service.ready_pod(client.clone()).await {
+                    Ok(Some(pod_name)) => {
+                        if let Some(metrics) = &metrics {
+                            metrics.reconnected();
+                        }
+                        break pod_name;
+                    }
+                    Ok(None) => {}
+                    Err(err) => tracing::warn!("{err}"),
+                }
+            };
+        }
+    }
+}
@@ -4,12 +4,31 @@
 //! various CLI commands to interact with the Axon API and resolve resources.
 //!
 //! It re-exports key components from its sub-modules, `api_pod` and `resource`,
-//! to facilitate their use across the CLI.
+//! to facilitate their use across the CLI. Signal handling shared by
+//! long-running commands lives in `signal`. The environment variables and the
+//! working directory of commands run in a pod, as well as their argument
+//! vector, are handled by `remote_command`. Informational output, which
+//! `--quiet` suppresses, goes through `output`. Random identifiers come from
+//! `random`.
 
 mod api_pod;
+mod forward_target;
+mod forwarder;
+pub mod output;
+mod random;
+mod remote_command;
 mod resource;
+pub mod signal;
 
 pub use self::{
-    api_pod::ApiPodExt,
+    api_pod::{ApiPodExt, is_pod_deleted, is_pod_ready},
+    forward_target::{ForwardTarget, ServiceTarget},
+    forwarder::{ForwarderOptions, ReadySender, ServiceForward, serve_metrics},
+    random::random_hex,
+    remote_command::{
+        RemoteEnv, ShellWords, change_dir_in_argv, change_dir_in_command_line,
+        describe_exec_failure, export_to_argv, export_to_command_line, parse_workdir, to_argv,
+        to_command_line,
+    },
     resource::{ResolvedResources, ResourceResolver},
 };
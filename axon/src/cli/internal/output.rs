@@ -0,0 +1,28 @@
+//! Informational output of the commands.
+//!
+//! Commands report what they did, e.g. `pod/axon created in namespace dev`,
+//! through `status`, so that the global `--quiet` flag can suppress these
+//! lines. Errors, warnings and the data a command was asked for, such as the
+//! table of `list` or the port of `--print-port`, are always written.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether informational output is suppressed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether informational output is suppressed for the rest of the
+/// process.
+pub fn set_quiet(quiet: bool) { QUIET.store(quiet, Ordering::Relaxed); }
+
+/// Returns whether informational output is suppressed.
+pub fn is_quiet() -> bool { QUIET.load(Ordering::Relaxed) }
+
+/// Prints an informational line to stdout, unless `--quiet` is set.
+pub fn status(message: impl fmt::Display) {
+    if !is_quiet() {
+        println!("{message}");
+    }
+}
@@ -0,0 +1,27 @@
+//! Short random identifiers, such as the suffix of a pod name or the id of a
+//! command invocation.
+
+use std::{
+    hash::{BuildHasher, RandomState},
+    time::SystemTime,
+};
+
+/// Generates a random string of eight lowercase hexadecimal digits.
+///
+/// The value is not cryptographically secure; it only has to make collisions
+/// between concurrent invocations unlikely.
+pub fn random_hex() -> String {
+    format!("{:08x}", RandomState::new().hash_one(SystemTime::now()) & 0xffff_ffff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_hex() {
+        let hex = random_hex();
+        assert_eq!(hex.len(), 8);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+    }
+}
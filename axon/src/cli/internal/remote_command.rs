@@ -0,0 +1,285 @@
+//! The argument vector, the environment and the working directory of commands
+//! run in a pod.
+//!
+//! A command, such as the interactive shell, is always kept as an argument
+//! vector: that is what the Kubernetes exec API runs and what the pod
+//! annotations store. It is only escaped into a single command line where one
+//! is required, for the SSH `exec` request.
+//!
+//! sshd usually restricts the variables a client may set with `SetEnv`, and
+//! the Kubernetes exec API has no way to set any variable or the working
+//! directory, so both are passed by rewriting the command instead. A shell
+//! command line is prefixed with `export` and `cd` statements, while a plain
+//! argument vector is wrapped in `env` and `sh -c`.
+//!
+//! The exec API reports how such a command exited as a `Status`, which
+//! `describe_exec_failure` turns into a reason for an error.
+
+use std::{fmt, str::FromStr};
+
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Status;
+
+/// A command given as a single value on the command line, e.g.
+/// `bash -lc 'exec zsh'`, split into words with the quoting rules of the
+/// shell.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShellWords(Vec<String>);
+
+impl FromStr for ShellWords {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let words = shell_words::split(input).map_err(|err| format!("'{input}': {err}"))?;
+        if words.is_empty() {
+            return Err("the command must not be empty".to_string());
+        }
+        Ok(Self(words))
+    }
+}
+
+/// Joins the words of every value of a repeatable command option into a
+/// single argument vector.
+pub fn to_argv(values: Vec<ShellWords>) -> Vec<String> {
+    values.into_iter().flat_map(|ShellWords(words)| words).collect()
+}
+
+/// Escapes the argument vector `argv` into a command line for a shell, such
+/// that the shell runs the same argument vector.
+pub fn to_command_line(argv: &[String]) -> String {
+    argv.iter().map(|arg| shell_escape::escape(arg.into())).collect::<Vec<_>>().join(" ")
+}
+
+/// An environment variable given as `KEY=VALUE` on the command line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemoteEnv {
+    /// The name of the variable, a valid shell identifier.
+    pub key: String,
+    /// The value of the variable.
+    pub value: String,
+}
+
+impl FromStr for RemoteEnv {
+    type Err = String;
+
+    /// Parses a variable in the form `KEY=VALUE`, where `KEY` is a valid shell
+    /// identifier: letters, digits and underscores, not starting with a digit.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let Some((key, value)) = input.split_once('=') else {
+            return Err(format!("'{input}' is not a valid variable, expected KEY=VALUE"));
+        };
+        let is_identifier = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_identifier {
+            return Err(format!(
+                "'{key}' is not a valid variable name, expected letters, digits and underscores \
+                 not starting with a digit"
+            ));
+        }
+        Ok(Self { key: key.to_string(), value: value.to_string() })
+    }
+}
+
+impl fmt::Display for RemoteEnv {
+    /// Formats the variable as `KEY=VALUE`, unescaped.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.key, self.value)
+    }
+}
+
+/// Prepends the `export` statements of `envs` to the shell command line
+/// `command`, with the values escaped for the shell.
+pub fn export_to_command_line(envs: &[RemoteEnv], command: &str) -> String {
+    envs.iter()
+        .map(|RemoteEnv { key, value }| {
+            format!("export {key}={}; ", shell_escape::escape(value.into()))
+        })
+        .chain(std::iter::once(command.to_string()))
+        .collect()
+}
+
+/// Prepends `env KEY=VALUE...` to the argument vector `command`, so that the
+/// variables of `envs` are set without a shell being involved.
+pub fn export_to_argv(envs: &[RemoteEnv], command: Vec<String>) -> Vec<String> {
+    if envs.is_empty() {
+        return command;
+    }
+    std::iter::once("env".to_string())
+        .chain(envs.iter().map(ToString::to_string))
+        .chain(command)
+        .collect()
+}
+
+/// Parses the working directory of a remote command, which must be an
+/// absolute path as the directory a relative path resolves against depends on
+/// how the command is started.
+///
+/// # Errors
+///
+/// Returns an error message if `input` is not an absolute path.
+pub fn parse_workdir(input: &str) -> Result<String, String> {
+    if input.starts_with('/') {
+        Ok(input.to_string())
+    } else {
+        Err(format!("'{input}' is not an absolute path"))
+    }
+}
+
+/// Prefixes the shell command line `command` with a `cd` to `workdir`, so that
+/// the command is only run if the directory can be entered.
+pub fn change_dir_in_command_line(workdir: Option<&str>, command: &str) -> String {
+    let Some(workdir) = workdir else {
+        return command.to_string();
+    };
+    format!("cd {} && {command}", shell_escape::escape(workdir.into()))
+}
+
+/// Wraps the argument vector `command` in `sh -c`, so that it is executed in
+/// `workdir`. The directory and the arguments are passed as positional
+/// parameters and need no escaping.
+pub fn change_dir_in_argv(workdir: Option<&str>, command: Vec<String>) -> Vec<String> {
+    let Some(workdir) = workdir else {
+        return command;
+    };
+    ["sh", "-c", r#"cd -- "$1" && shift && exec "$@""#, "sh", workdir]
+        .into_iter()
+        .map(String::from)
+        .chain(command)
+        .collect()
+}
+
+/// Describes why a command run with the exec API exited with `status`, or
+/// returns `None` if it succeeded.
+pub fn describe_exec_failure(status: &Status) -> Option<String> {
+    if status.status.as_deref() == Some("Success") {
+        return None;
+    }
+    let exit_code = status
+        .details
+        .as_ref()
+        .and_then(|details| details.causes.as_ref())
+        .and_then(|causes| causes.iter().find(|cause| cause.reason.as_deref() == Some("ExitCode")))
+        .and_then(|cause| cause.message.as_deref());
+    Some(exit_code.map_or_else(
+        || status.message.clone().unwrap_or_else(|| "failed".to_string()),
+        |exit_code| format!("exit code {exit_code}"),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(key: &str, value: &str) -> RemoteEnv {
+        RemoteEnv { key: key.to_string(), value: value.to_string() }
+    }
+
+    #[test]
+    fn test_shell_argv() {
+        let argv = ["bash", "-lc", "exec zsh"].map(String::from).to_vec();
+        let parse = |value: &str| value.parse::<ShellWords>().unwrap();
+
+        // A single value, repeated values and a configured vector are all the same argv
+        assert_eq!(to_argv(vec![parse("bash -lc 'exec zsh'")]), argv);
+        assert_eq!(to_argv(vec![parse("bash"), parse("-lc"), parse("\"exec zsh\"")]), argv);
+        assert_eq!(to_argv(vec![parse("/bin/sh")]), ["/bin/sh"]);
+        assert!("".parse::<ShellWords>().is_err());
+        assert!("bash -c 'unterminated".parse::<ShellWords>().is_err());
+
+        // The SSH command line runs the same argv in the remote shell
+        assert_eq!(to_command_line(&argv), "bash -lc 'exec zsh'");
+        assert_eq!(shell_words::split(&to_command_line(&argv)).unwrap(), argv);
+    }
+
+    #[test]
+    fn test_parse_remote_env() {
+        assert_eq!("DEBUG=1".parse(), Ok(env("DEBUG", "1")));
+        assert_eq!("_PATH=/a=b".parse(), Ok(env("_PATH", "/a=b")));
+        assert_eq!("EMPTY=".parse(), Ok(env("EMPTY", "")));
+        assert!("DEBUG".parse::<RemoteEnv>().is_err());
+        assert!("=1".parse::<RemoteEnv>().is_err());
+        assert!("1DEBUG=1".parse::<RemoteEnv>().is_err());
+        assert!("MY-VAR=1".parse::<RemoteEnv>().is_err());
+        assert!("A;rm=1".parse::<RemoteEnv>().is_err());
+    }
+
+    #[test]
+    fn test_export_to_command_line() {
+        let envs = [env("DEBUG", "1"), env("GREETING", "hello world; rm -rf /")];
+        assert_eq!(
+            export_to_command_line(&envs, "mytool --verbose"),
+            "export DEBUG=1; export GREETING='hello world; rm -rf /'; mytool --verbose"
+        );
+        assert_eq!(export_to_command_line(&[], "mytool"), "mytool");
+    }
+
+    #[test]
+    fn test_export_to_argv() {
+        let command = vec!["mytool".to_string(), "--verbose".to_string()];
+        assert_eq!(
+            export_to_argv(&[env("DEBUG", "1"), env("NAME", "a b")], command.clone()),
+            ["env", "DEBUG=1", "NAME=a b", "mytool", "--verbose"]
+        );
+        assert_eq!(export_to_argv(&[], command.clone()), command);
+    }
+
+    #[test]
+    fn test_parse_workdir() {
+        assert_eq!(parse_workdir("/src/project"), Ok("/src/project".to_string()));
+        assert!(parse_workdir("src/project").is_err());
+        assert!(parse_workdir("").is_err());
+    }
+
+    #[test]
+    fn test_change_dir() {
+        assert_eq!(
+            change_dir_in_command_line(Some("/my project"), "make test"),
+            "cd '/my project' && make test"
+        );
+        assert_eq!(change_dir_in_command_line(None, "make test"), "make test");
+
+        let command = vec!["make".to_string(), "test".to_string()];
+        assert_eq!(
+            change_dir_in_argv(Some("/my project"), command.clone()),
+            [
+                "sh",
+                "-c",
+                r#"cd -- "$1" && shift && exec "$@""#,
+                "sh",
+                "/my project",
+                "make",
+                "test"
+            ]
+        );
+        assert_eq!(change_dir_in_argv(None, command.clone()), command);
+    }
+
+    #[test]
+    fn test_describe_exec_failure() {
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::{StatusCause, StatusDetails};
+
+        let success = Status { status: Some("Success".to_string()), ..Status::default() };
+        assert_eq!(describe_exec_failure(&success), None);
+
+        let exited = Status {
+            status: Some("Failure".to_string()),
+            message: Some("command terminated with non-zero exit code".to_string()),
+            details: Some(StatusDetails {
+                causes: Some(vec![StatusCause {
+                    reason: Some("ExitCode".to_string()),
+                    message: Some("3".to_string()),
+                    ..StatusCause::default()
+                }]),
+                ..StatusDetails::default()
+            }),
+            ..Status::default()
+        };
+        assert_eq!(describe_exec_failure(&exited).as_deref(), Some("exit code 3"));
+
+        let failed = Status {
+            status: Some("Failure".to_string()),
+            message: Some("container not found".to_string()),
+            ..Status::default()
+        };
+        assert_eq!(describe_exec_failure(&failed).as_deref(), Some("container not found"));
+    }
+}
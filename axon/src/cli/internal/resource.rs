@@ -3,6 +3,12 @@
 //! This module provides [`ResourceResolver`] for determining the target
 //! namespace and pod name, falling back to defaults from the Kubernetes
 //! client and application configuration when not explicitly specified.
+//!
+//! The namespace is the first of:
+//!
+//! 1. The namespace given on the command line with `--namespace`.
+//! 2. The namespace of the spec a pod is created from, if any.
+//! 3. The namespace of the current Kubernetes context.
 
 use crate::config::Config;
 
@@ -12,6 +18,7 @@ use crate::config::Config;
 pub struct ResourceResolver<'k, 'c> {
     kube_client: &'k kube::Client,
     config: &'c Config,
+    spec_namespace: Option<String>,
 }
 
 /// Contains the resolved namespace and pod name for a Kubernetes resource.
@@ -31,23 +38,30 @@ impl<'k, 'c> From<(&'k kube::Client, &'c Config)> for ResourceResolver<'k, 'c> {
     /// * `(kube_client, config)` - A tuple containing a reference to a
     ///   Kubernetes client and a reference to the application's configuration.
     fn from((kube_client, config): (&'k kube::Client, &'c Config)) -> Self {
-        Self { kube_client, config }
+        Self { kube_client, config, spec_namespace: None }
     }
 }
 
 impl ResourceResolver<'_, '_> {
+    /// Sets the namespace of the spec a pod is created from, which takes
+    /// precedence over the default namespace of the Kubernetes client.
+    pub fn spec_namespace(mut self, namespace: Option<String>) -> Self {
+        self.spec_namespace = namespace;
+        self
+    }
+
     /// Resolves the Kubernetes namespace and pod name.
     ///
     /// If the provided `namespace` or `pod_name` are `None` or empty,
-    /// this method falls back to the default namespace from the Kubernetes
-    /// client or the default pod name from the application configuration,
-    /// respectively.
+    /// this method falls back to the namespace of the spec, then to the
+    /// default namespace from the Kubernetes client, or to the default pod
+    /// name from the application configuration, respectively.
     ///
     /// # Arguments
     ///
     /// * `namespace` - An optional `String` representing the desired Kubernetes
-    ///   namespace. If `None` or empty, the Kubernetes client's default
-    ///   namespace is used.
+    ///   namespace. If `None` or empty, the namespace of the spec or the
+    ///   Kubernetes client's default namespace is used.
     /// * `pod_name` - An optional `String` representing the desired pod name.
     ///   If `None` or empty, the application's default pod name is used.
     ///
@@ -60,9 +74,10 @@ impl ResourceResolver<'_, '_> {
         namespace: Option<String>,
         pod_name: Option<String>,
     ) -> ResolvedResources {
-        let Self { kube_client, config } = self;
+        let Self { kube_client, config, spec_namespace } = self;
         let namespace = namespace
             .filter(|s| !s.is_empty())
+            .or_else(|| spec_namespace.clone().filter(|s| !s.is_empty()))
             .unwrap_or_else(|| kube_client.default_namespace().to_string());
         let pod_name =
             pod_name.filter(|s| !s.is_empty()).unwrap_or_else(|| config.default_pod_name.clone());
@@ -70,3 +85,31 @@ impl ResourceResolver<'_, '_> {
         ResolvedResources { namespace, pod_name }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve() {
+        let mut kube_config = kube::Config::new("http://127.0.0.1:1".parse().unwrap());
+        kube_config.default_namespace = "context".to_string();
+        let kube_client = kube::Client::try_from(kube_config).unwrap();
+        let config = Config::default();
+        let resolve = |namespace: Option<&str>, spec_namespace: Option<&str>| {
+            ResourceResolver::from((&kube_client, &config))
+                .spec_namespace(spec_namespace.map(String::from))
+                .resolve(namespace.map(String::from), None)
+                .namespace
+        };
+
+        assert_eq!(resolve(None, None), "context");
+        assert_eq!(resolve(None, Some("data")), "data");
+        assert_eq!(resolve(Some(""), Some("data")), "data");
+        assert_eq!(resolve(Some("dev"), Some("data")), "dev");
+        assert_eq!(resolve(None, Some("")), "context");
+
+        let resolved = ResourceResolver::from((&kube_client, &config)).resolve(None, None);
+        assert_eq!(resolved.pod_name, config.default_pod_name);
+    }
+}
@@ -0,0 +1,79 @@
+//! Process signal handling shared by long-running CLI commands.
+//!
+//! Commands such as `port-forward` are often run under a process supervisor
+//! (systemd, a container runtime, `timeout(1)`), which asks them to stop with
+//! `SIGTERM` rather than `SIGINT`. The helpers in this module make `SIGINT`,
+//! `SIGTERM` and `SIGHUP` all trigger the same graceful shutdown path.
+
+use futures::future::{self, FutureExt};
+use sigfinn::LifecycleManager;
+use tokio::signal::unix::{SignalKind, signal};
+
+/// Waits until the process is asked to terminate by `SIGINT`, `SIGTERM` or
+/// `SIGHUP`.
+///
+/// A signal whose listener cannot be installed is logged and ignored, so the
+/// future still completes on the remaining ones.
+pub async fn shutdown_signal() {
+    let listeners = [
+        ("SIGINT", SignalKind::interrupt()),
+        ("SIGTERM", SignalKind::terminate()),
+        ("SIGHUP", SignalKind::hangup()),
+    ]
+    .into_iter()
+    .filter_map(|(name, kind)| match signal(kind) {
+        Ok(mut listener) => Some(
+            async move {
+                let _ = listener.recv().await;
+                tracing::info!("`{name}` received, starting graceful shutdown");
+            }
+            .boxed(),
+        ),
+        Err(err) => {
+            tracing::warn!("Failed to listen for `{name}`: {err}");
+            None
+        }
+    })
+    .collect::<Vec<_>>();
+
+    if listeners.is_empty() {
+        future::pending::<()>().await;
+    }
+    let (_signal, _index, _remaining) = future::select_all(listeners).await;
+}
+
+/// Runs all workers of `lifecycle_manager` to completion.
+///
+/// `sigfinn` already shuts the workers down gracefully on `SIGINT` and
+/// `SIGTERM`; this additionally treats `SIGHUP` the same way, so closing the
+/// controlling terminal does not leave forwarders half torn down.
+///
+/// # Errors
+///
+/// Returns an error if a signal listener cannot be created or a worker task
+/// cannot be joined. Errors reported by the workers themselves are returned
+/// in the inner `Result`.
+pub async fn serve<E>(lifecycle_manager: LifecycleManager<E>) -> sigfinn::Result<Result<(), E>>
+where
+    E: Send + 'static,
+{
+    let handle = lifecycle_manager.handle();
+    let hangup_watcher = match signal(SignalKind::hangup()) {
+        Ok(mut listener) => Some(tokio::spawn(async move {
+            if listener.recv().await.is_some() {
+                tracing::info!("`SIGHUP` received, starting graceful shutdown");
+                handle.shutdown();
+            }
+        })),
+        Err(err) => {
+            tracing::warn!("Failed to listen for `SIGHUP`: {err}");
+            None
+        }
+    };
+
+    let result = lifecycle_manager.serve().await;
+    if let Some(hangup_watcher) = hangup_watcher {
+        hangup_watcher.abort();
+    }
+    result
+}
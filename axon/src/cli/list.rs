@@ -1,27 +1,52 @@
 //! This module provides the `ListCommand` for listing Kubernetes pods managed
 //! by Axon.
 
+use std::{
+    io::{IsTerminal, Write},
+    pin::pin,
+    str::FromStr,
+    time::Duration,
+};
+
 use clap::Args;
-use k8s_openapi::api::core::v1::Pod;
-use kube::{Api, api::ListParams};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    style::Print,
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use futures::StreamExt;
+use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::ListMeta};
+use kube::{
+    Api,
+    api::{ListParams, ObjectList, TypeMeta},
+    runtime::{
+        WatchStreamExt, reflector,
+        watcher::{self, Event, watcher},
+    },
+};
 use snafu::ResultExt;
-use tokio::io::AsyncWriteExt;
+use tokio::{io::AsyncWriteExt, time::MissedTickBehavior};
 
 use crate::{
     PROJECT_NAME,
     cli::{
         error::{self, Error},
-        internal::{ResolvedResources, ResourceResolver},
+        internal::{ResolvedResources, ResourceResolver, signal},
     },
     config::Config,
     consts::k8s::labels,
-    ui::table::PodListExt,
+    ui::{
+        PodSortKey,
+        table::{CustomColumns, PodListExt},
+        terminal,
+    },
 };
 
 /// Represents the command to list Kubernetes pods managed by Axon.
 ///
 /// This struct defines the command-line arguments available for listing pods.
 #[derive(Args, Clone)]
+#[expect(clippy::struct_excessive_bools, reason = "Every bool is an independent command-line flag")]
 pub struct ListCommand {
     #[arg(
         short,
@@ -37,6 +62,117 @@ pub struct ListCommand {
         help = "List all temporary pods created by Axon across all Kubernetes namespaces."
     )]
     pub all_namespaces: bool,
+
+    #[arg(
+        long = "sort-by",
+        value_enum,
+        default_value_t = PodSortKey::default(),
+        help = "Sort the listed pods by name, age (youngest first), status or namespace."
+    )]
+    pub sort_by: PodSortKey,
+
+    #[arg(
+        long = "no-color",
+        help = "Disable colored output. Colors are also disabled when the NO_COLOR environment \
+                variable is set or when stdout is not a terminal."
+    )]
+    pub no_color: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Keep watching the pods and redraw the table whenever one of them changes. Press \
+                Ctrl-C to stop."
+    )]
+    pub watch: bool,
+
+    #[arg(
+        long = "watch-interval",
+        requires = "watch",
+        value_parser = humantime::parse_duration,
+        help = "Poll the pods at this interval (e.g. '2s') instead of using a Kubernetes watch. \
+                Useful when watches are not permitted or are dropped by a proxy."
+    )]
+    pub watch_interval: Option<Duration>,
+
+    #[arg(
+        long = "truncate",
+        overrides_with = "no_truncate",
+        help = "Truncate long images with an ellipsis so that the table fits the width of the \
+                terminal. This is the default when stdout is a terminal."
+    )]
+    pub truncate: bool,
+
+    #[arg(
+        long = "no-truncate",
+        overrides_with = "truncate",
+        help = "Show every value in full, even if the table is wider than the terminal."
+    )]
+    pub no_truncate: bool,
+
+    #[arg(
+        short,
+        long,
+        default_value = "table",
+        value_name = "FORMAT",
+        help = "The output format: table, or custom-columns=HEADER:PATH,... to show arbitrary \
+                fields of the pods, e.g. \
+                custom-columns=NAME:.metadata.name,IMAGE:.spec.containers[0].image. Paths support \
+                dotted fields and [n] indices."
+    )]
+    pub output: ListOutput,
+}
+
+/// The format in which the pods are listed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ListOutput {
+    /// The default table of the pods.
+    #[default]
+    Table,
+    /// A table of user-defined columns.
+    CustomColumns(CustomColumns),
+}
+
+impl FromStr for ListOutput {
+    type Err = String;
+
+    /// Parses `table` or `custom-columns=HEADER:PATH,...`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.split_once('=') {
+            None if input == "table" => Ok(Self::Table),
+            Some(("custom-columns", columns)) => columns.parse().map(Self::CustomColumns),
+            _ => Err(format!(
+                "'{input}' is not a valid output format, expected table or \
+                 custom-columns=HEADER:PATH,..."
+            )),
+        }
+    }
+}
+
+/// Renders the pods in the format chosen on the command line.
+struct PodRenderer {
+    /// The output format.
+    output: ListOutput,
+    /// Whether the STATUS column of the default table is colored.
+    colorize: bool,
+    /// Whether the default table is truncated to the width of the terminal.
+    truncate: bool,
+}
+
+impl PodRenderer {
+    /// Renders `pods`.
+    ///
+    /// The width of the terminal is read on every call, so that redrawn tables
+    /// follow resizes.
+    fn render(&self, pods: &ObjectList<Pod>) -> String {
+        match &self.output {
+            ListOutput::Table => pods.render_table(
+                self.colorize,
+                self.truncate.then(|| usize::from(terminal::size().0)),
+            ),
+            ListOutput::CustomColumns(columns) => columns.render_table(&pods.items),
+        }
+    }
 }
 
 impl ListCommand {
@@ -47,7 +183,9 @@ impl ListCommand {
     /// target namespace (if not specified, it uses the current context's
     /// namespace), and then lists pods that are labeled as managed by
     /// `PROJECT_NAME`. The results are then rendered to standard output in
-    /// a tabular format.
+    /// a tabular format, truncated to the width of the terminal unless
+    /// disabled. In watch mode, the table is redrawn on the alternate screen
+    /// whenever the pods change, until Ctrl-C is pressed.
     ///
     /// # Arguments
     ///
@@ -67,28 +205,172 @@ impl ListCommand {
     /// * Resolving the Kubernetes namespace fails.
     /// * Writing the output to `stdout` fails.
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, all_namespaces } = self;
+        let Self {
+            namespace,
+            all_namespaces,
+            sort_by,
+            no_color,
+            watch,
+            watch_interval,
+            truncate,
+            no_truncate,
+            output,
+        } = self;
 
         // Resolve Identity
         let ResolvedResources { namespace, .. } =
             ResourceResolver::from((&kube_client, &config)).resolve(namespace, None);
 
-        let list_params = ListParams {
-            label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
-            ..ListParams::default()
-        };
-
-        let pods = if all_namespaces {
-            Api::<Pod>::all(kube_client).list(&list_params).await.context(error::ListPodsSnafu)?
+        let label_selector = format!("{}={PROJECT_NAME}", labels::MANAGED_BY);
+        let (api, namespace) = if all_namespaces {
+            (Api::<Pod>::all(kube_client), None)
         } else {
-            Api::<Pod>::namespaced(kube_client, &namespace)
-                .list(&list_params)
-                .await
-                .context(error::ListPodsWithNamespaceSnafu { namespace })?
+            (Api::<Pod>::namespaced(kube_client, &namespace), Some(namespace))
         };
+        let renderer = PodRenderer {
+            output,
+            colorize: terminal::use_color(no_color),
+            truncate: !no_truncate && (truncate || std::io::stdout().is_terminal()),
+        };
+
+        if let Some(interval) = watch_interval {
+            return poll_pods(
+                &api,
+                &label_selector,
+                namespace.as_deref(),
+                interval,
+                sort_by,
+                &renderer,
+            )
+            .await;
+        }
+        if watch {
+            return watch_pods(api, &label_selector, sort_by, &renderer).await;
+        }
+
+        let mut pods = list_pods(&api, &label_selector, namespace.as_deref()).await?;
+        pods.sort_by_key(sort_by);
 
         let mut stdout = tokio::io::stdout();
-        stdout.write_all(pods.render_table().as_bytes()).await.context(error::WriteStdoutSnafu)?;
+        stdout
+            .write_all(renderer.render(&pods).as_bytes())
+            .await
+            .context(error::WriteStdoutSnafu)?;
         stdout.write_u8(b'\n').await.context(error::WriteStdoutSnafu)
     }
 }
+
+/// Lists the pods matching `label_selector`.
+///
+/// `namespace` is only used to describe the failure and should be `None` when
+/// `api` spans all namespaces.
+async fn list_pods(
+    api: &Api<Pod>,
+    label_selector: &str,
+    namespace: Option<&str>,
+) -> Result<ObjectList<Pod>, Error> {
+    let list_params =
+        ListParams { label_selector: Some(label_selector.to_string()), ..ListParams::default() };
+    let result = api.list(&list_params).await;
+    match namespace {
+        Some(namespace) => result.context(error::ListPodsWithNamespaceSnafu { namespace }),
+        None => result.context(error::ListPodsSnafu),
+    }
+}
+
+/// Re-lists the pods every `interval` and redraws the table until Ctrl-C is
+/// pressed or the process is asked to terminate.
+async fn poll_pods(
+    api: &Api<Pod>,
+    label_selector: &str,
+    namespace: Option<&str>,
+    interval: Duration,
+    sort_by: PodSortKey,
+    renderer: &PodRenderer,
+) -> Result<(), Error> {
+    let _screen = WatchScreen::enter()?;
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut shutdown = pin!(signal::shutdown_signal());
+
+    loop {
+        tokio::select! {
+            () = &mut shutdown => return Ok(()),
+            _ = ticker.tick() => {
+                let mut pods = list_pods(api, label_selector, namespace).await?;
+                pods.sort_by_key(sort_by);
+                redraw(&pods, renderer)?;
+            }
+        }
+    }
+}
+
+/// Watches the pods matching `label_selector` and redraws the table whenever
+/// one of them changes, until Ctrl-C is pressed or the process is asked to
+/// terminate.
+///
+/// Watch errors are retried with a backoff, so a temporary loss of the API
+/// server only delays the next redraw.
+async fn watch_pods(
+    api: Api<Pod>,
+    label_selector: &str,
+    sort_by: PodSortKey,
+    renderer: &PodRenderer,
+) -> Result<(), Error> {
+    let (reader, writer) = reflector::store();
+    let events = reflector(writer, watcher(api, watcher::Config::default().labels(label_selector)))
+        .default_backoff();
+    let mut events = pin!(events);
+
+    let _screen = WatchScreen::enter()?;
+    let mut shutdown = pin!(signal::shutdown_signal());
+
+    loop {
+        tokio::select! {
+            () = &mut shutdown => return Ok(()),
+            event = events.next() => match event {
+                Some(Ok(Event::Init | Event::InitApply(_))) => {}
+                Some(Ok(Event::InitDone | Event::Apply(_) | Event::Delete(_))) => {
+                    let items = reader.state().iter().map(|pod| Pod::clone(pod)).collect();
+                    let mut pods = ObjectList {
+                        types: TypeMeta::default(),
+                        metadata: ListMeta::default(),
+                        items,
+                    };
+                    pods.sort_by_key(sort_by);
+                    redraw(&pods, renderer)?;
+                }
+                Some(Err(err)) => tracing::warn!("Failed to watch pods, retrying: {err}"),
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Clears the screen and draws the table of `pods` from the top-left corner.
+fn redraw(pods: &ObjectList<Pod>, renderer: &PodRenderer) -> Result<(), Error> {
+    let mut stdout = std::io::stdout().lock();
+    crossterm::queue!(stdout, Clear(ClearType::All), MoveTo(0, 0))
+        .context(error::WriteStdoutSnafu)?;
+    crossterm::queue!(stdout, Print(renderer.render(pods)), Print('\n'))
+        .context(error::WriteStdoutSnafu)?;
+    stdout.flush().context(error::WriteStdoutSnafu)
+}
+
+/// Switches to the alternate screen for the duration of a watch and restores
+/// the original screen when dropped.
+struct WatchScreen;
+
+impl WatchScreen {
+    fn enter() -> Result<Self, Error> {
+        crossterm::execute!(std::io::stdout(), EnterAlternateScreen, Hide)
+            .context(error::WriteStdoutSnafu)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for WatchScreen {
+    fn drop(&mut self) {
+        let _unused = crossterm::execute!(std::io::stdout(), Show, LeaveAlternateScreen);
+    }
+}
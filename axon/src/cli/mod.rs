@@ -7,44 +7,111 @@
 //! # Examples
 //!
 //! ```bash
+//! # Write a configuration file to start from, at ~/.config/axon/config.yaml
+//! axon init
+//!
+//! # Check the configuration, the cluster connection, the SSH key and local
+//! # tools
+//! axon doctor
+//!
 //! # List all temporary pods managed by Axon
 //! axon list
 //!
+//! # List the pods with columns of your choice
+//! axon list -o custom-columns=NAME:.metadata.name,IMAGE:.spec.containers[0].image
+//!
 //! # Create a new temporary pod with a specific image
 //! axon create --image my-repo/my-image:latest
 //!
+//! # Print the manifest of the pod a spec produces, without a cluster
+//! axon create preset my-spec --dump-manifest
+//!
+//! # Create a pod from a full Pod manifest, for debug pods beyond what a spec
+//! # can express
+//! axon create -f debug-pod.yaml --container tools --auto-attach
+//!
 //! # Attach to a running pod's console
 //! axon attach my-pod-name
 //!
-//! # Execute a command inside a pod
-//! axon execute my-pod-name -- ls -la /app
+//! # Show the Kubernetes events of a pod
+//! axon events my-pod-name
+//!
+//! # Check that a pod is healthy, failing unless it is running
+//! axon status my-pod-name
+//!
+//! # Block until a pod is ready, e.g. in a script
+//! axon wait my-pod-name --for ready --timeout 60s
+//!
+//! # Execute a command inside a pod, passing everything after `--` verbatim
+//! axon execute --pod-name my-pod-name -- ls -la /app
+//!
+//! # Show the CPU and memory usage of the temporary pods
+//! axon top
 //!
 //! # Forward a local port to a pod port
 //! axon port-forward my-pod-name 8080:80
+//!
+//! # Run a SOCKS5 proxy whose connections leave from a pod
+//! axon port-forward --socks 1080 my-pod-name
+//!
+//! # Forward port 9000 inside a pod back to local port 8080
+//! axon port-forward --reverse my-pod-name 8080:9000
+//!
+//! # Forward several local ports to pods and services at once
+//! axon proxy --rule 8080:svc/web:80 --rule 5432:pod/postgres:5432
+//!
+//! # Open an SSH shell in a pod only reachable through a bastion pod
+//! axon ssh shell --pod-name my-pod-name --jump my-bastion-pod
+//!
+//! # Generate an SSH key pair for the ssh commands
+//! axon ssh keygen --out ~/.ssh/id_axon
 //! ```
 
 mod attach;
+mod client;
 mod create;
 mod delete;
+mod doctor;
 pub mod error;
+mod events;
 mod execute;
 mod image;
+mod init;
 mod internal;
 mod list;
 mod port_forward;
+mod proxy;
 mod ssh;
+mod status;
+mod top;
+mod wait;
 
-use std::{io::Write, path::PathBuf};
+use std::{io::Write, path::PathBuf, time::Duration};
 
 use clap::{CommandFactory, Parser, Subcommand};
 use futures::FutureExt;
 use snafu::ResultExt;
 use tokio::runtime::Runtime;
+use tracing::Instrument;
 
-pub use self::error::Error;
+pub use self::error::{Error, ErrorFormat};
 use self::{
-    attach::AttachCommand, create::CreateCommand, delete::DeleteCommand, execute::ExecuteCommand,
-    image::ImageCommands, list::ListCommand, port_forward::PortForwardCommand, ssh::SshCommands,
+    attach::AttachCommand,
+    client::{ClientOptions, DEFAULT_API_RETRIES},
+    create::CreateCommand,
+    delete::DeleteCommand,
+    doctor::DoctorCommand,
+    events::EventsCommand,
+    execute::ExecuteCommand,
+    image::ImageCommands,
+    init::InitCommand,
+    list::ListCommand,
+    port_forward::PortForwardCommand,
+    proxy::ProxyCommand,
+    ssh::SshCommands,
+    status::StatusCommand,
+    top::TopCommand,
+    wait::WaitCommand,
 };
 use crate::{CLI_PROGRAM_NAME, config::Config, shadow};
 
@@ -84,6 +151,14 @@ pub struct Cli {
     )]
     config_file: Option<PathBuf>,
 
+    /// The profile of the configuration file to apply.
+    #[clap(
+        long = "profile",
+        env = "AXON_PROFILE",
+        help = "Apply the named profile of the configuration file, overriding its base settings."
+    )]
+    profile: Option<String>,
+
     /// Sets the logging level for the application.
     ///
     /// Supported levels include `info`, `debug`, and `trace`.
@@ -93,6 +168,101 @@ pub struct Cli {
         help = "Set the logging level (e.g., info, debug, trace)."
     )]
     log_level: Option<tracing::Level>,
+
+    /// Disables every log driver of the configuration file.
+    #[clap(
+        long = "no-log",
+        help = "Disable logging to stdout, stderr, journald and files, whatever the configuration \
+                file says."
+    )]
+    no_log: bool,
+
+    /// The default time to wait for a pod to be running, for every command.
+    ///
+    /// A command's own `--timeout-seconds` takes precedence.
+    #[clap(
+        long = "pod-ready-timeout",
+        global = true,
+        value_parser = humantime::parse_duration,
+        help = "The default time to wait for a pod to be running (e.g. '30s', '2m'), for every \
+                command. A command's own --timeout-seconds takes precedence."
+    )]
+    pod_ready_timeout: Option<Duration>,
+
+    /// Accept the certificate of the Kubernetes API server without verifying
+    /// it.
+    #[clap(
+        long = "insecure-skip-tls-verify",
+        global = true,
+        help = "Accept the certificate of the Kubernetes API server without verifying it. This \
+                makes the connection insecure."
+    )]
+    insecure_skip_tls_verify: bool,
+
+    /// The time to wait for a response of the Kubernetes API server.
+    #[clap(
+        long = "request-timeout",
+        global = true,
+        value_parser = humantime::parse_duration,
+        help = "The time to wait for a response of the Kubernetes API server (e.g. '30s'). \
+                Defaults to the timeout of the Kubernetes client."
+    )]
+    request_timeout: Option<Duration>,
+
+    /// The number of times a failed read request to the Kubernetes API server
+    /// is retried.
+    #[clap(
+        long = "api-retries",
+        global = true,
+        default_value_t = DEFAULT_API_RETRIES,
+        help = "The number of times a read request to the Kubernetes API server is retried when \
+                it fails transiently. Requests creating or deleting resources are never retried."
+    )]
+    api_retries: usize,
+
+    /// The user to impersonate for every request to the Kubernetes API
+    /// server.
+    ///
+    /// RBAC must allow the current identity to impersonate the user, otherwise
+    /// the API server rejects the requests.
+    #[clap(
+        long = "as",
+        global = true,
+        help = "Username to impersonate for every request to the Kubernetes API server. RBAC must \
+                allow the current identity to impersonate it, otherwise the requests are rejected."
+    )]
+    impersonate_user: Option<String>,
+
+    /// The groups to impersonate along with `--as`.
+    #[clap(
+        long = "as-group",
+        global = true,
+        requires = "impersonate_user",
+        help = "Group to impersonate along with --as. Can be repeated to specify multiple groups."
+    )]
+    impersonate_groups: Vec<String>,
+
+    /// Suppress informational output on stdout, such as the resources
+    /// created or deleted.
+    #[clap(
+        long = "quiet",
+        short = 'q',
+        global = true,
+        help = "Suppress informational output on stdout, such as `pod/NAME created`. Errors, \
+                warnings and requested data, like -o json or --print-port, are still written."
+    )]
+    quiet: bool,
+
+    /// The format in which errors are reported on stderr.
+    #[clap(
+        long = "error-format",
+        global = true,
+        value_enum,
+        default_value_t = ErrorFormat::default(),
+        help = "The format in which errors are reported on stderr. `json` reports a single line \
+                object with a stable `code` for tooling."
+    )]
+    error_format: ErrorFormat,
 }
 
 /// `Commands` enumerates the available subcommands for the Axon CLI.
@@ -123,6 +293,16 @@ pub enum Commands {
     #[command(about = "Output the default configuration in YAML format")]
     DefaultConfig,
 
+    /// Writes the default configuration to the configuration file path.
+    #[command(about = "Write the default configuration to the configuration file path")]
+    Init(InitCommand),
+
+    /// Diagnoses the configuration, the cluster connection, the SSH key and
+    /// the local tools.
+    #[command(about = "Check the configuration, the cluster connection, the SSH key and local \
+                       tools, printing a checklist with hints")]
+    Doctor(DoctorCommand),
+
     /// Creates a new temporary pod in a specified namespace or using a
     /// predefined spec.
     #[command(
@@ -146,10 +326,28 @@ pub enum Commands {
     )]
     Execute(ExecuteCommand),
 
+    /// Shows the Kubernetes events of a pod.
+    #[command(about = "Show the Kubernetes events of a pod, e.g. to diagnose scheduling failures")]
+    Events(EventsCommand),
+
+    /// Summarizes the status of a pod.
+    #[command(about = "Summarize the status of a pod, failing unless it is running so that it \
+                       can be used as a health check")]
+    Status(StatusCommand),
+
+    /// Waits until a pod meets a condition.
+    #[command(about = "Wait until a pod is running, ready or deleted, failing with the timeout \
+                       exit code otherwise")]
+    Wait(WaitCommand),
+
     /// Lists all temporary pods currently managed by Axon.
     #[command(alias = "l", about = "List all temporary pods managed by Axon")]
     List(ListCommand),
 
+    /// Shows the CPU and memory usage of pods.
+    #[command(about = "Show the CPU and memory usage of pods next to their requests and limits")]
+    Top(TopCommand),
+
     /// Forwards one or more local ports to a specific port on a temporary pod.
     #[command(
         aliases = ["p", "pf"],
@@ -157,6 +355,10 @@ pub enum Commands {
     )]
     PortForward(PortForwardCommand),
 
+    /// Forwards several local ports to pods and services at once.
+    #[command(about = "Forward several local ports to pods and services at once")]
+    Proxy(ProxyCommand),
+
     /// Manages container image specifications.
     #[command(alias = "i", about = "Manage container image specifications")]
     Image {
@@ -178,20 +380,43 @@ pub enum Commands {
 }
 
 impl Default for Cli {
-    /// Creates a new `Cli` instance by parsing command-line arguments.
+    /// Creates a new `Cli` instance by parsing the arguments of the process.
     ///
-    /// This method uses `clap::Parser::parse()` to initialize the `Cli` struct.
-    fn default() -> Self { Self::parse() }
+    /// This method uses [`Cli::parse_from`] with `std::env::args_os()`.
+    fn default() -> Self { Self::parse_from(std::env::args_os()) }
 }
 
 impl Cli {
+    /// Creates a new `Cli` instance by parsing `args`, whose first item is the
+    /// program name, instead of the arguments of the process.
+    ///
+    /// Like `clap::Parser::parse_from`, it exits the process with a usage
+    /// message if the arguments are invalid.
+    pub fn parse_from<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        <Self as Parser>::parse_from(args)
+    }
+
+    /// Returns the format in which errors returned by [`Cli::run`] should be
+    /// reported.
+    pub const fn error_format(&self) -> ErrorFormat { self.error_format }
+
     /// Loads the application configuration, applying any overrides from CLI
     /// arguments.
     ///
     /// If a configuration file path is provided via the `--config` flag or
-    /// `AXON_CONFIG_FILE_PATH` environment variable, it is used. Otherwise,
-    /// Axon searches for a default configuration file. The `log_level` from
-    /// CLI arguments (if present) overrides the configuration file's setting.
+    /// `AXON_CONFIG_FILE_PATH` environment variable, it is used and must
+    /// exist. Otherwise, Axon searches for a default configuration file, and
+    /// uses the default configuration if there is none. The profile selected
+    /// by `--profile` or `AXON_PROFILE` overlays the configuration file. The
+    /// environment variables `AXON_DEFAULT_POD_NAME`, `AXON_DEFAULT_SPEC` and
+    /// `AXON_SSH_PRIVATE_KEY_FILE` override the configuration file's settings,
+    /// and `--log-level`, `--no-log` and `--pod-ready-timeout` from CLI
+    /// arguments (if present) override both. See [`crate::config`] for the full
+    /// precedence.
     ///
     /// # Errors
     ///
@@ -204,23 +429,65 @@ impl Cli {
     /// A `Result` containing the loaded and potentially overridden `Config` on
     /// success, or an `Error` if any step fails.
     fn load_config(&self) -> Result<Config, Error> {
-        let mut config =
-            Config::load(self.config_file.clone().unwrap_or_else(Config::search_config_file_path))?;
+        let mut config = match &self.config_file {
+            Some(config_file) => Config::load(config_file)?,
+            None => Config::load_or_default(Config::search_config_file_path())?,
+        };
+        if let Some(profile) = &self.profile {
+            config.apply_profile(profile)?;
+        }
+        config.apply_env_overrides(|name| std::env::var(name).ok())?;
 
         if let Some(log_level) = self.log_level {
             config.log.level = log_level;
         }
+        if self.no_log {
+            config.log.disable_all();
+        }
+        if let Some(timeout) = self.pod_ready_timeout {
+            config.timeout = Some(timeout);
+        }
 
         Ok(config)
     }
 
+    /// Returns whether the command runs without the configuration and a
+    /// cluster, such as printing the client version or generating a key pair.
+    const fn is_local(&self) -> bool {
+        matches!(
+            self.commands,
+            Some(
+                Commands::Version { client: true }
+                    | Commands::Completions { .. }
+                    | Commands::DefaultConfig
+                    | Commands::Init(_)
+                    | Commands::Ssh { commands: SshCommands::Keygen(_) }
+            )
+        )
+    }
+
+    /// Returns the options of the Kubernetes client given on the command line.
+    fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            insecure_skip_tls_verify: self.insecure_skip_tls_verify,
+            request_timeout: self.request_timeout,
+            api_retries: self.api_retries,
+            impersonate_user: self.impersonate_user.clone(),
+            impersonate_groups: self.impersonate_groups.clone(),
+        }
+    }
+
     /// Executes the main logic of the CLI application based on the parsed
     /// command and arguments.
     ///
-    /// This function initializes the Kubernetes client, loads the
-    /// configuration, and dispatches to the appropriate subcommand's `run`
-    /// method. It handles special cases for `Version` (client-only),
-    /// `Completions`, and `DefaultConfig` output.
+    /// This function initializes the Kubernetes client from the kubeconfig or
+    /// the in-cluster environment, loads the configuration, and dispatches to
+    /// [`Cli::run_with_client`]. Commands that need neither, such as
+    /// `Completions`, `DefaultConfig`, `Init` and `ssh keygen`, are run
+    /// without them. `doctor` is run with whatever loading the configuration
+    /// and building the client yield, as diagnosing their failures is its
+    /// purpose. Everything logged by the command is recorded in a
+    /// `command` span with a random `id`, shared by all of its log lines.
     ///
     /// # Returns
     ///
@@ -231,87 +498,221 @@ impl Cli {
     /// # Errors
     ///
     /// Returns an `Error` if:
-    /// - The Kubernetes client cannot be initialized (e.g., `KubeConfigSnafu`).
+    /// - The Kubernetes client cannot be initialized (e.g.,
+    ///   `InferKubeConfigSnafu` or `KubeConfigSnafu`).
     /// - The Tokio runtime fails to initialize (`InitializeTokioRuntimeSnafu`).
     /// - Any subcommand's `run` method returns an error.
     /// - Configuration loading fails via `load_config`.
+    pub fn run(self) -> Result<i32, Error> {
+        let runtime = Runtime::new().context(error::InitializeTokioRuntimeSnafu)?;
+        internal::output::set_quiet(self.quiet);
+        if self.is_local() {
+            return runtime.block_on(self.run_local());
+        }
+        if let Some(Commands::Doctor(cmd)) = &self.commands {
+            let config_path =
+                self.config_file.clone().unwrap_or_else(Config::search_config_file_path);
+            let config = self.load_config();
+            return runtime.block_on(async {
+                let kube_client = client::build_client(&self.client_options()).await;
+                cmd.clone().run(config_path, config, kube_client).await.map(|()| 0)
+            });
+        }
+
+        let config = self.load_config()?;
+        config.log.registry();
+        if let Some(Commands::Create(cmd)) = &self.commands
+            && cmd.dump_manifest
+        {
+            // Dumping the manifest needs no cluster, not even a client
+            return runtime.block_on(cmd.clone().dump_manifest(config)).map(|()| 0);
+        }
+
+        // Lets the logs of one invocation be told apart from those of others
+        let span = tracing::info_span!("command", id = %internal::random_hex());
+        runtime.block_on(
+            async move {
+                let kube_client = client::build_client(&self.client_options()).await?;
+                self.run_with_client(kube_client, config).boxed().await
+            }
+            .instrument(span),
+        )
+    }
+
+    /// Executes the parsed command with the given Kubernetes client and
+    /// configuration, instead of the ones of the environment.
+    ///
+    /// This lets the commands be driven programmatically, e.g. against a
+    /// mock API server in tests. The client options given on the command
+    /// line, such as `--as`, are ignored as the client is already built.
+    ///
+    /// # Returns
+    ///
+    /// The exit code of the command.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the command fails.
     ///
     /// # Panics
     ///
     /// - This method `expect`s on `std::io::stdout().write_all()` operations.
     ///   In a typical CLI environment, writing to `stdout` or `stderr` is
     ///   expected to succeed.
-    pub fn run(self) -> Result<i32, Error> {
-        let client_version = Self::command().get_version().unwrap_or_default().to_string();
+    pub async fn run_with_client(
+        self,
+        kube_client: kube::Client,
+        config: Config,
+    ) -> Result<i32, Error> {
+        if self.is_local() {
+            return self.run_local().await;
+        }
         match self.commands {
-            Some(Commands::Version { client }) if client => {
+            Some(Commands::Version { .. }) => {
+                let client_version = Self::command().get_version().unwrap_or_default().to_string();
+                let server_version = kube_client.apiserver_version().await.map_or_else(
+                    |_| "unknown".to_string(),
+                    |info| format!("{}.{}", info.major, info.minor),
+                );
+                let info = format!(
+                    "Client Version: {client_version}\nServer Version: {server_version}\n",
+                );
                 std::io::stdout()
                     .write_all(Self::command().render_long_version().as_bytes())
                     .expect("Failed to write to stdout");
-                std::io::stdout()
-                    .write_all(format!("Client Version: {client_version}\n").as_bytes())
-                    .expect("Failed to write to stdout");
+                std::io::stdout().write_all(info.as_bytes()).expect("Failed to write to stdout");
 
                 return Ok(0);
             }
+            Some(Commands::Create(cmd)) if cmd.dump_manifest => cmd.dump_manifest(config).await?,
+            Some(Commands::Create(cmd)) => cmd.run(kube_client, config).boxed().await?,
+            Some(Commands::List(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Attach(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Execute(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Events(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Status(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Wait(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Top(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::PortForward(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Proxy(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Delete(cmd)) => cmd.run(kube_client, config).await?,
+            Some(Commands::Doctor(cmd)) => {
+                let config_path = self.config_file.unwrap_or_else(Config::search_config_file_path);
+                cmd.run(config_path, Ok(config), Ok(kube_client)).await?;
+            }
+            Some(Commands::Image { commands }) => commands.run(config).await?,
+            Some(Commands::Ssh { commands }) => commands.run(kube_client, config).await?,
+            _ => {
+                let help = Self::command().render_long_help().ansi().to_string();
+                std::io::stderr().write_all(help.as_bytes()).expect("Failed to write to stdout");
+                return Ok(-1);
+            }
+        }
+
+        Ok(0)
+    }
+
+    /// Executes a command for which [`Cli::is_local`] holds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if writing the configuration file or generating an
+    /// SSH key pair fails.
+    ///
+    /// # Panics
+    ///
+    /// - This method `expect`s on `std::io::stdout().write_all()` operations.
+    async fn run_local(self) -> Result<i32, Error> {
+        match self.commands {
             Some(Commands::Completions { shell }) => {
                 let mut app = Self::command();
                 let bin_name = app.get_name().to_string();
                 clap_complete::generate(shell, &mut app, bin_name, &mut std::io::stdout());
-                return Ok(0);
             }
             Some(Commands::DefaultConfig) => {
                 std::io::stdout()
                     .write_all(Config::template_basic().as_slice())
                     .expect("Failed to write to stdout");
-                return Ok(0);
             }
-            _ => {}
+            Some(Commands::Init(cmd)) => {
+                cmd.run(self.config_file.unwrap_or_else(Config::default_path)).await?;
+            }
+            Some(Commands::Ssh { commands: SshCommands::Keygen(cmd) }) => {
+                // Generating a key pair needs neither the configuration nor a cluster
+                cmd.run().await?;
+            }
+            _ => {
+                let client_version = Self::command().get_version().unwrap_or_default().to_string();
+                std::io::stdout()
+                    .write_all(Self::command().render_long_version().as_bytes())
+                    .expect("Failed to write to stdout");
+                std::io::stdout()
+                    .write_all(format!("Client Version: {client_version}\n").as_bytes())
+                    .expect("Failed to write to stdout");
+            }
         }
+        Ok(0)
+    }
+}
 
-        let config = self.load_config()?;
-        config.log.registry();
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-        let fut = async move {
-            let kube_client = kube::Client::try_default().await.context(error::KubeConfigSnafu)?;
-            match self.commands {
-                Some(Commands::Version { .. }) => {
-                    let server_version = kube_client.apiserver_version().await.map_or_else(
-                        |_| "unknown".to_string(),
-                        |info| format!("{}.{}", info.major, info.minor),
-                    );
-                    let info = format!(
-                        "Client Version: {client_version}\nServer Version: {server_version}\n",
-                    );
-                    std::io::stdout()
-                        .write_all(Self::command().render_long_version().as_bytes())
-                        .expect("Failed to write to stdout");
-                    std::io::stdout()
-                        .write_all(info.as_bytes())
-                        .expect("Failed to write to stdout");
-
-                    return Ok(0);
-                }
-                Some(Commands::Create(cmd)) => cmd.run(kube_client, config).boxed().await?,
-                Some(Commands::List(cmd)) => cmd.run(kube_client, config).await?,
-                Some(Commands::Attach(cmd)) => cmd.run(kube_client, config).await?,
-                Some(Commands::Execute(cmd)) => cmd.run(kube_client, config).await?,
-                Some(Commands::PortForward(cmd)) => cmd.run(kube_client, config).await?,
-                Some(Commands::Delete(cmd)) => cmd.run(kube_client, config).await?,
-                Some(Commands::Image { commands }) => commands.run(config).await?,
-                Some(Commands::Ssh { commands }) => commands.run(kube_client, config).await?,
-                _ => {
-                    let help = Self::command().render_long_help().ansi().to_string();
-                    std::io::stderr()
-                        .write_all(help.as_bytes())
-                        .expect("Failed to write to stdout");
-                    return Ok(-1);
+    use super::*;
+
+    /// Serves `body` as the response to every request on a local port, and
+    /// returns a client of that mock API server.
+    async fn mock_client(body: &'static str) -> kube::Client {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let _handle = tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
                 }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: \
+                     {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _unused = stream.write_all(response.as_bytes()).await;
             }
+        });
+        let config = kube::Config::new(format!("http://{addr}").parse().unwrap());
+        kube::Client::try_from(config).unwrap()
+    }
 
-            Ok(0)
-        };
+    #[tokio::test]
+    async fn test_run_with_client() {
+        let kube_client = mock_client(
+            r#"{"apiVersion":"v1","kind":"PodList","metadata":{},"items":[{"metadata":{"name":"axon"}}]}"#,
+        )
+        .await;
+        let cli = Cli::parse_from(["axon", "list", "-o", "custom-columns=NAME:.metadata.name"]);
+        assert_eq!(cli.run_with_client(kube_client, Config::default()).boxed().await.unwrap(), 0);
+
+        let kube_client = mock_client(
+            r#"{"apiVersion":"v1","kind":"Pod","metadata":{"name":"axon"},"status":{"phase":"Pending"}}"#,
+        )
+        .await;
+        let cli = Cli::parse_from(["axon", "status", "axon"]);
+        let err = cli.run_with_client(kube_client, Config::default()).boxed().await.unwrap_err();
+        assert_eq!(err.code(), "PodNotRunning");
+    }
 
-        Runtime::new().context(error::InitializeTokioRuntimeSnafu)?.block_on(fut)
+    #[test]
+    fn test_pod_ready_timeout() {
+        let cli = Cli::parse_from(["axon", "create", "--pod-ready-timeout", "30s"]);
+        assert_eq!(cli.pod_ready_timeout, Some(Duration::from_secs(30)));
+        let cli =
+            Cli::parse_from(["axon", "--pod-ready-timeout", "90s", "wait", "--timeout", "1m"]);
+        assert_eq!(cli.pod_ready_timeout, Some(Duration::from_secs(90)));
+        assert!(Cli::try_parse_from(["axon", "create", "--timeout", "30s"]).is_err());
     }
 }
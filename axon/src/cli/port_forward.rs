@@ -10,29 +10,88 @@
 //! port forwarding connections between the local machine and a Kubernetes
 //! pod based on port mappings defined in pod annotations.
 
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    fmt::Write as _,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::NonZeroUsize,
+    path::PathBuf,
+    time::Duration,
+};
 
-use clap::Args;
+use clap::{Args, ValueEnum};
 use k8s_openapi::api::core::v1::Pod;
 use kube::Api;
+use serde::Serialize;
 use sigfinn::{ExitStatus, LifecycleManager};
+use snafu::ResultExt;
+use tokio::{io::AsyncWriteExt, sync::mpsc};
 
 use crate::{
     cli::{
-        Error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
+        Error, error,
+        internal::{
+            ApiPodExt, ForwardTarget, ForwarderOptions, ReadySender, ResolvedResources,
+            ResourceResolver, ServiceForward, ServiceTarget, serve_metrics, signal,
+        },
+        ssh::internal::{ReverseForward, SshTunnel, TunnelMode},
     },
     config::{Config, PortMapping},
+    consts::DEFAULT_POD_READY_TIMEOUT,
     ext::PodExt,
-    port_forwarder::PortForwarderBuilder,
+    port_forwarder::ConnectionLimitPolicy,
 };
 
+/// A pair of local and remote ports.
+type PortPair = (u16, u16);
+
+/// A pair of local and remote ports as given on the command line, where the
+/// remote port may be named.
+type PortArg = (u16, RemotePort);
+
+/// A forwarder spawned by the command.
+struct Forward {
+    /// The local address the forwarder binds to.
+    local_addr: SocketAddr,
+    /// The pod or service port forwarded to, e.g. `svc/web:80`.
+    target: String,
+    /// The pod or service port forwarded to.
+    remote_port: u16,
+}
+
+/// The format in which `--print-forwards` prints the forwarders.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ForwardsFormat {
+    /// A JSON array of objects with the `local_addr` and `remote_port` of
+    /// every forwarder.
+    Json,
+}
+
+/// A forwarder as printed by `--print-forwards json`.
+#[derive(Debug, Serialize)]
+struct BoundForward {
+    /// The local address the forwarder is bound to.
+    local_addr: SocketAddr,
+    /// The pod or service port forwarded to.
+    remote_port: u16,
+}
+
+/// What is written once every forwarder is bound.
+struct ReadyOutput {
+    /// Whether to write the `export` lines of `--print-env`.
+    print_env: bool,
+    /// The file the `export` lines are written to instead of stdout.
+    env_file: Option<PathBuf>,
+    /// The format in which the forwarders are printed to stdout, if at all.
+    print_forwards: Option<ForwardsFormat>,
+}
+
 /// Command-line arguments for port forwarding.
 ///
 /// This struct defines the parameters that can be passed to the `port-forward`
 /// command, allowing users to specify the target pod, namespace, and connection
 /// timeout.
 #[derive(Args, Clone)]
+#[expect(clippy::struct_excessive_bools, reason = "Every bool is an independent command-line flag")]
 pub struct PortForwardCommand {
     /// Kubernetes namespace of the target pod. If not specified, the default
     /// namespace will be used.
@@ -50,19 +109,222 @@ pub struct PortForwardCommand {
         short = 'p',
         long = "pod-name",
         help = "Name of the temporary pod to forward ports for. If not specified, Axon's default \
-                pod name will be used."
+                pod name will be used. Must not differ from a pod/NAME target."
     )]
     pub pod_name: Option<String>,
 
     /// The maximum time in seconds to wait for the pod to be running before
     /// timing out.
+    ///
+    /// Defaults to the global `--pod-ready-timeout`, or to 15 seconds.
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "15",
-        help = "The maximum time in seconds to wait for the pod to be running before timing out."
+        help = "The maximum time in seconds to wait for the pod to be running before timing out. \
+                Defaults to the global --pod-ready-timeout, or 15 seconds."
+    )]
+    pub timeout_secs: Option<u64>,
+
+    /// Print a line to stderr whenever a connection is opened or closed.
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        help = "Print a line to stderr whenever a connection is opened or closed."
+    )]
+    pub verbose: bool,
+
+    /// The local address to bind forwarded ports to, replacing the loopback
+    /// address of the pod's port mappings.
+    #[arg(
+        long = "address",
+        help = "The local address to bind forwarded ports to, e.g. 0.0.0.0 to share them on the \
+                network. Applies to every port mapping bound to a loopback address; mappings with \
+                another explicit address are kept."
+    )]
+    pub address: Option<IpAddr>,
+
+    /// An optional target, `NAME`, `pod/NAME` or `svc/NAME`, followed by the
+    /// ports to forward, overriding the port mappings of the pod. A local port
+    /// of `0`, or no local port at all, binds a free port chosen by the
+    /// system.
+    #[arg(
+        value_name = "TARGET|[LOCAL]:REMOTE",
+        value_parser = parse_forward_arg,
+        help = "An optional target, NAME, pod/NAME or svc/NAME, followed by the ports to \
+                forward, e.g. svc/web 8080:80. Ports override the port mappings of the pod; for \
+                a service they are service ports and default to all of them. Use 0:80 or :80 to \
+                bind a free local port chosen by the system. A pod port may be given by the name \
+                of a container port, e.g. 8080:http. A pod whose name consists of digits only \
+                has to be given as pod/NAME."
+    )]
+    pub args: Vec<ForwardArg>,
+
+    /// Print the local port of every forwarder to stdout once it is bound.
+    #[arg(
+        long = "print-port",
+        help = "Print the local port of every forwarder to stdout, one per line, once it is \
+                bound. Useful with automatically chosen ports, e.g. PORT=$(axon port-forward :80 \
+                --print-port)."
+    )]
+    pub print_port: bool,
+
+    /// Print an `export NAME_PORT=PORT` line for every forwarded port to
+    /// stdout once all forwarders are bound.
+    #[arg(
+        long = "print-env",
+        conflicts_with = "print_port",
+        help = "Print an export NAME_PORT=PORT line for every forwarded port to stdout once all \
+                forwarders are bound, where NAME is the pod or service name, e.g. eval \"$(axon \
+                port-forward svc/my-svc --print-env)\" sets MY_SVC_PORT. With several ports, the \
+                remote port is appended, e.g. MY_SVC_PORT_80."
+    )]
+    pub print_env: bool,
+
+    /// Write the `export` lines of `--print-env` to this file instead of
+    /// stdout.
+    #[arg(
+        long = "env-file",
+        value_name = "PATH",
+        help = "Write the export lines of --print-env to PATH instead of stdout, once all \
+                forwarders are bound."
+    )]
+    pub env_file: Option<PathBuf>,
+
+    /// Print every forwarder to stdout in this format once all forwarders are
+    /// bound.
+    #[arg(
+        long = "print-forwards",
+        value_name = "FORMAT",
+        conflicts_with_all = ["print_port", "print_env"],
+        help = "Print every forwarder to stdout in FORMAT once all forwarders are bound, e.g. a \
+                JSON array of {\"local_addr\": \"127.0.0.1:41234\", \"remote_port\": 80} with \
+                json, so that a parent process can learn the ports chosen by the system. Status \
+                lines are written to stderr."
+    )]
+    pub print_forwards: Option<ForwardsFormat>,
+
+    /// Wait until the pod port accepts a test connection before reporting a
+    /// forwarder as ready, for at most the given duration.
+    #[arg(
+        long = "wait-for-port",
+        value_name = "TIMEOUT",
+        num_args = 0..=1,
+        default_missing_value = "10s",
+        value_parser = humantime::parse_duration,
+        help = "Wait until the pod port accepts a test connection through the tunnel before \
+                reporting the forwarder as ready. Fails if the port is not reachable within \
+                TIMEOUT, 10s if omitted."
+    )]
+    pub wait_for_port: Option<Duration>,
+
+    /// The maximum number of connections each forwarder bridges at the same
+    /// time.
+    #[arg(
+        long = "max-connections",
+        value_name = "N",
+        help = "The maximum number of connections each forwarder bridges at the same time. \
+                Further connections are handled according to --connection-limit-policy."
+    )]
+    pub max_connections: Option<NonZeroUsize>,
+
+    /// What to do with new connections while `--max-connections` is
+    /// reached.
+    #[arg(
+        long = "connection-limit-policy",
+        value_enum,
+        default_value_t = ConnectionLimitPolicy::Queue,
+        help = "What to do with new connections while --max-connections is reached."
+    )]
+    pub connection_limit_policy: ConnectionLimitPolicy,
+
+    /// The address to serve Prometheus metrics of the forwarders on.
+    #[arg(
+        long = "metrics-addr",
+        value_name = "HOST:PORT",
+        conflicts_with = "ssh_tunnel",
+        help = "Serve Prometheus metrics of the forwarders, such as active connections and \
+                transferred bytes, at http://HOST:PORT/metrics."
+    )]
+    pub metrics_addr: Option<SocketAddr>,
+
+    /// Run a local SOCKS5 proxy on this port instead of forwarding fixed
+    /// ports, tunneling every connection through the pod over SSH.
+    #[arg(
+        long = "socks",
+        value_name = "PORT",
+        group = "ssh_tunnel",
+        conflicts_with_all = [
+            "print_port",
+            "print_env",
+            "env_file",
+            "print_forwards",
+            "wait_for_port",
+        ],
+        help = "Run a local SOCKS5 proxy on PORT, like ssh -D, whose connections are opened from \
+                the pod's network namespace through an SSH session. The pod must run an SSH \
+                server; no ports may be given."
+    )]
+    pub socks: Option<u16>,
+
+    /// Forward ports of the pod back to the local host instead, over SSH.
+    #[arg(
+        long = "reverse",
+        group = "ssh_tunnel",
+        conflicts_with_all = [
+            "socks",
+            "print_port",
+            "print_env",
+            "env_file",
+            "print_forwards",
+            "wait_for_port",
+        ],
+        help = "Forward ports of the pod back to the local host, like ssh -R: with 8080:9000, \
+                connections to port 9000 inside the pod reach local port 8080. A remote port of \
+                0 lets the pod choose one, which is reported. --address sets the local address \
+                to connect to. The pod must run an SSH server."
+    )]
+    pub reverse: bool,
+
+    /// Paths to SSH private key files used by `--socks` and `--reverse`, tried
+    /// in order before `sshPrivateKeyFilePath` of the configuration.
+    #[arg(
+        short = 'i',
+        long = "ssh-private-key-file",
+        visible_alias = "identity",
+        value_name = "SSH_PRIVATE_KEY_FILE",
+        requires = "ssh_tunnel",
+        help = "Path to an SSH private key file used by --socks and --reverse. Can be repeated to \
+                try several keys in order, before `sshPrivateKeyFilePath` of the configuration. \
+                If neither is set, ~/.ssh/id_ed25519, ~/.ssh/id_rsa and ~/.ssh/id_ecdsa are tried."
+    )]
+    pub ssh_private_key_files: Vec<PathBuf>,
+
+    /// User name to connect as via SSH when using `--socks` or `--reverse`.
+    #[arg(
+        short = 'u',
+        long = "user",
+        default_value = "root",
+        help = "User name to connect as via SSH when using --socks or --reverse."
     )]
-    pub timeout_secs: u64,
+    pub user: String,
+}
+
+/// A positional argument of the `port-forward` command.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ForwardArg {
+    /// The pod or service to forward to.
+    Target(ForwardTarget),
+    /// A pair of local and remote ports.
+    Ports(u16, RemotePort),
+}
+
+/// The remote port of a port pair.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RemotePort {
+    /// A port number.
+    Number(u16),
+    /// The name of a container port of the pod, e.g. `http`.
+    Name(String),
 }
 
 impl PortForwardCommand {
@@ -72,8 +334,42 @@ impl PortForwardCommand {
     /// This function resolves the target pod and namespace, retrieves port
     /// mappings from the pod's annotations, and then establishes
     /// port-forwarding connections using a `LifecycleManager`. It continues
-    /// to forward ports until an interrupt signal (like Ctrl+C) is
-    /// received.
+    /// to forward ports until the process is interrupted (Ctrl+C) or receives
+    /// `SIGTERM` or `SIGHUP`, after which every forwarder is drained before
+    /// returning.
+    ///
+    /// A status line showing where each port is bound is printed to stderr as
+    /// soon as it is ready, independently of the log configuration. With
+    /// `verbose`, every opened and closed connection is reported as well.
+    ///
+    /// `address` replaces the loopback address of the port mappings, and a
+    /// warning is printed for every port bound to a non-loopback address.
+    ///
+    /// When ports are given, they replace the port mappings of the pod. A
+    /// local port of `0` is bound ephemerally, and with `print_port` the port
+    /// actually bound is written to stdout. A named remote port is looked up
+    /// in the container ports of the pod.
+    ///
+    /// With `print_env`, or `env_file`, an `export` line setting an environment
+    /// variable to the local port of every forwarder is written to stdout, or
+    /// to that file, once all forwarders are bound. With `print_forwards`, the
+    /// local address and remote port of every forwarder are printed to stdout
+    /// in that format at the same point, while status lines stay on stderr.
+    ///
+    /// With `wait_for_port`, a forwarder is only reported as ready once the pod
+    /// port accepts a test connection.
+    ///
+    /// With `metrics_addr`, the metrics of every forwarder are served over
+    /// HTTP at `/metrics` on that address.
+    ///
+    /// When the target is a service, each forwarder is directed at a ready
+    /// pod backing it, and moves to another ready pod whenever that one stops
+    /// being ready.
+    ///
+    /// With `socks`, a single SOCKS5 proxy is run instead, whose connections
+    /// are opened by the pod through an SSH session. With `reverse`, the pod
+    /// listens on the remote ports and forwards their connections back to the
+    /// local ports over such a session.
     ///
     /// # Arguments
     ///
@@ -88,58 +384,732 @@ impl PortForwardCommand {
     /// This function returns an `Error` in the following cases:
     ///
     /// * If there's an issue resolving the Kubernetes namespace or pod name.
+    /// * If a `pod/NAME` target names another pod than `pod_name`.
     /// * If the specified pod cannot be found or is not in a running state
     ///   within the given `timeout_secs`.
+    /// * If the specified service cannot be found, does not expose a requested
+    ///   port or has no ready endpoint.
+    /// * If a named port is not declared by any container of the pod
+    ///   (`Error::ContainerPortNotFound`), or is given with a service target,
+    ///   `--socks` or `--reverse`.
     /// * If there are issues connecting to the Kubernetes API.
     /// * If an error occurs during the port-forwarding setup or during the
     ///   lifetime of a port-forwarding session.
+    /// * If the `export` lines cannot be written to `env_file`, or the output
+    ///   of `print_env` or `print_forwards` to stdout.
+    /// * With `socks` or `reverse`, if no SSH key pair can be loaded, or if the
+    ///   SSH key cannot be uploaded to the pod or the SSH session cannot be
+    ///   established.
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, pod_name, timeout_secs } = self;
+        let Self {
+            namespace,
+            pod_name,
+            timeout_secs,
+            verbose,
+            address: address_override,
+            args,
+            print_port,
+            print_env,
+            env_file,
+            print_forwards,
+            wait_for_port,
+            max_connections,
+            connection_limit_policy,
+            metrics_addr,
+            socks,
+            reverse,
+            ssh_private_key_files,
+            user,
+        } = self;
+
+        let (target, ports) = split_forward_args(args)?;
+        let (pod_name, service_name) = resolve_target(pod_name, target)?;
 
         // Resolve Identity
         let ResolvedResources { namespace, pod_name } =
             ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name);
 
-        let api = Api::<Pod>::namespaced(kube_client, &namespace);
-        let port_mappings = api
-            .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
+        let pod_ready_timeout = config.pod_ready_timeout(timeout_secs, DEFAULT_POD_READY_TIMEOUT);
+        if socks.is_some() || reverse {
+            let mode =
+                tunnel_mode(socks, reverse, service_name.is_some(), ports, address_override)?;
+            return SshTunnel {
+                api: Api::namespaced(kube_client, &namespace),
+                namespace,
+                pod_name,
+                pod_ready_timeout,
+                ssh_private_key_files: ssh_private_key_files
+                    .into_iter()
+                    .chain(config.ssh_private_key_file_path)
+                    .collect(),
+                user,
+                verbose,
+            }
+            .run(mode)
+            .await;
+        }
+
+        let lifecycle_manager = LifecycleManager::<Error>::new();
+        let ready_output = ReadyOutput { print_env, env_file, print_forwards };
+        let (ready_sender, ready_receiver) = ready_output.channel();
+        let options = ForwarderOptions {
+            verbose,
+            print_port,
+            wait_for_port,
+            max_connections,
+            connection_limit_policy,
+            metrics: metrics_addr.map(|addr| serve_metrics(&lifecycle_manager, addr)),
+            ready_sender,
+        };
+        let forwards = if let Some(service_name) = &service_name {
+            spawn_service_forwarders(
+                &lifecycle_manager,
+                kube_client,
+                &namespace,
+                service_name,
+                numeric_ports(ports)?,
+                address_override,
+                options,
+            )
             .await?
-            .port_mappings();
+        } else {
+            let api = Api::<Pod>::namespaced(kube_client, &namespace);
+            let pod = api.await_running_status(&pod_name, &namespace, pod_ready_timeout).await?;
+            spawn_pod_forwarders(
+                &lifecycle_manager,
+                &api,
+                &pod,
+                &pod_name,
+                resolve_ports(&pod, &namespace, &pod_name, ports)?,
+                address_override,
+                &options,
+            )
+        };
 
-        if port_mappings.is_empty() {
+        if forwards.is_empty() {
             return Ok(());
         }
+        warn_exposed(&forwards);
+        if let Some(ready_receiver) = ready_receiver {
+            let name = service_name.as_deref().unwrap_or(&pod_name);
+            spawn_ready_writer(&lifecycle_manager, ready_receiver, name, &forwards, ready_output);
+        }
 
-        let lifecycle_manager = LifecycleManager::<Error>::new();
+        tracing::info!("Forwarders started. Use Ctrl+C to stop.");
 
-        for PortMapping { container_port, local_port, address } in port_mappings {
-            let local_sock_addr = SocketAddr::new(address, local_port);
-            let api = api.clone();
-            let pod_name = pod_name.clone();
-            let worker_name = format!("forwarder-{local_sock_addr}/{pod_name}:{container_port}");
-            let create_fn = move |shutdown_signal| async move {
-                let result = PortForwarderBuilder::new(api, pod_name, container_port)
-                    .local_address(local_sock_addr)
-                    .on_ready(|_| {})
+        if let Ok(Err(err)) = signal::serve(lifecycle_manager).await {
+            tracing::error!("{err}");
+            Err(err)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Returns what the SSH tunnel of `--socks` or `--reverse` does.
+///
+/// A SOCKS5 proxy takes no ports, while reverse forwarding requires at least
+/// one pair of local and remote ports; neither can target a service.
+fn tunnel_mode(
+    socks: Option<u16>,
+    reverse: bool,
+    has_service: bool,
+    ports: Vec<PortArg>,
+    address_override: Option<IpAddr>,
+) -> Result<TunnelMode, Error> {
+    let local_ip = address_override.unwrap_or(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    let message = match socks {
+        _ if has_service => "--socks and --reverse only accept a pod target",
+        Some(socks_port) if ports.is_empty() => {
+            return Ok(TunnelMode::Socks(SocketAddr::new(local_ip, socks_port)));
+        }
+        Some(_) => "--socks takes no ports",
+        None if reverse && ports.is_empty() => "--reverse requires at least one LOCAL:REMOTE port",
+        None if ports.iter().any(|(local_port, _)| *local_port == 0) => {
+            "--reverse requires a local port to forward to"
+        }
+        None => {
+            return Ok(TunnelMode::Reverse(
+                numeric_ports(ports)?
+                    .into_iter()
+                    .map(|(local_port, remote_port)| ReverseForward {
+                        remote_port,
+                        local_addr: SocketAddr::new(local_ip, local_port),
+                    })
+                    .collect(),
+            ));
+        }
+    };
+    error::GenericSnafu { message }.fail()
+}
+
+/// Splits the positional arguments of the command into the target, if any,
+/// and the port pairs.
+fn split_forward_args(
+    args: Vec<ForwardArg>,
+) -> Result<(Option<ForwardTarget>, Vec<PortArg>), Error> {
+    let mut target = None;
+    let mut ports = Vec::new();
+    for arg in args {
+        match arg {
+            ForwardArg::Target(other) if target.is_some() => {
+                let message = format!("Only one target can be given, found '{other}' as well");
+                return error::GenericSnafu { message }.fail();
+            }
+            ForwardArg::Target(other) => target = Some(other),
+            ForwardArg::Ports(local_port, remote_port) => ports.push((local_port, remote_port)),
+        }
+    }
+    Ok((target, ports))
+}
+
+/// Combines `--pod-name` with the positional target, if any, into the pod
+/// name and the name of the service to forward to.
+///
+/// # Errors
+///
+/// Returns an `Error` if a `pod/NAME` target names another pod than
+/// `pod_name`, rather than silently overriding it.
+fn resolve_target(
+    pod_name: Option<String>,
+    target: Option<ForwardTarget>,
+) -> Result<(Option<String>, Option<String>), Error> {
+    match target {
+        Some(ForwardTarget::Pod(name)) => {
+            if let Some(pod_name) = pod_name.filter(|pod_name| *pod_name != name) {
+                let message =
+                    format!("The target 'pod/{name}' conflicts with '--pod-name {pod_name}'");
+                return error::GenericSnafu { message }.fail();
+            }
+            Ok((Some(name), None))
+        }
+        Some(ForwardTarget::Service(name)) => Ok((pod_name, Some(name))),
+        None => Ok((pod_name, None)),
+    }
+}
+
+/// Resolves the named remote ports of `ports` to the container ports of `pod`
+/// declared with these names.
+///
+/// # Errors
+///
+/// Returns `Error::ContainerPortNotFound`, listing the names the pod declares,
+/// if no container of `pod` declares a port with one of the names.
+fn resolve_ports(
+    pod: &Pod,
+    namespace: &str,
+    pod_name: &str,
+    ports: Vec<PortArg>,
+) -> Result<Vec<PortPair>, Error> {
+    ports
+        .into_iter()
+        .map(|(local_port, remote_port)| match remote_port {
+            RemotePort::Number(port) => Ok((local_port, port)),
+            RemotePort::Name(port_name) => pod
+                .container_port_named(&port_name)
+                .map(|port| (local_port, port))
+                .ok_or_else(|| {
+                    error::ContainerPortNotFoundSnafu {
+                        namespace,
+                        pod_name,
+                        port_name,
+                        available: pod.container_port_names(),
+                    }
                     .build()
-                    .run(shutdown_signal)
-                    .await;
+                }),
+        })
+        .collect()
+}
+
+/// Returns the port pairs of `ports`, which must all be numeric as named ports
+/// are only resolved against the containers of a pod.
+fn numeric_ports(ports: Vec<PortArg>) -> Result<Vec<PortPair>, Error> {
+    ports
+        .into_iter()
+        .map(|(local_port, remote_port)| match remote_port {
+            RemotePort::Number(port) => Ok((local_port, port)),
+            RemotePort::Name(port_name) => error::GenericSnafu {
+                message: format!(
+                    "The port name '{port_name}' can only be used to forward local ports to a pod"
+                ),
+            }
+            .fail(),
+        })
+        .collect()
+}
+
+/// Spawns one forwarder per port of the service `service_name`, each
+/// following the ready pods backing the service.
+///
+/// `ports` are pairs of local and service ports; all service ports are
+/// forwarded if it is empty. Returns every forwarder spawned.
+async fn spawn_service_forwarders(
+    lifecycle_manager: &LifecycleManager<Error>,
+    kube_client: kube::Client,
+    namespace: &str,
+    service_name: &str,
+    ports: Vec<PortPair>,
+    address_override: Option<IpAddr>,
+    options: ForwarderOptions,
+) -> Result<Vec<Forward>, Error> {
+    let service = ServiceTarget::get(kube_client.clone(), namespace, service_name).await?;
+    let ports = if ports.is_empty() {
+        service.ports().into_iter().map(|port| (port, port)).collect()
+    } else {
+        ports
+    };
+    for &(_, service_port) in &ports {
+        service.ensure_port(service_port)?;
+    }
+    let Some(pod_name) = service.ready_pod(kube_client.clone()).await? else {
+        return error::ServiceHasNoReadyEndpointSnafu { namespace, service_name }.fail();
+    };
+
+    Ok(ports
+        .into_iter()
+        .enumerate()
+        .map(|(index, (local_port, service_port))| {
+            let local_addr = SocketAddr::new(
+                bind_address(IpAddr::V4(Ipv4Addr::LOCALHOST), address_override),
+                local_port,
+            );
+            let target = format!("svc/{service_name}:{service_port}");
+            let forward = ServiceForward {
+                client: kube_client.clone(),
+                service: service.clone(),
+                service_port,
+                pod_name: pod_name.clone(),
+                options: ForwarderOptions {
+                    ready_sender: options
+                        .ready_sender
+                        .as_ref()
+                        .map(|sender| sender.with_index(index)),
+                    ..options.clone()
+                },
+            };
+            let _handle = lifecycle_manager
+                .spawn(format!("forwarder-{local_addr}/{target}"), move |shutdown_signal| {
+                    forward.run(local_addr, shutdown_signal)
+                });
+            Forward { local_addr, target, remote_port: service_port }
+        })
+        .collect())
+}
+
+/// Spawns one forwarder per port of the pod `pod_name`.
+///
+/// `ports` are pairs of local and container ports; the port mappings of the
+/// pod are forwarded if it is empty. Returns every forwarder spawned.
+fn spawn_pod_forwarders(
+    lifecycle_manager: &LifecycleManager<Error>,
+    api: &Api<Pod>,
+    pod: &Pod,
+    pod_name: &str,
+    ports: Vec<PortPair>,
+    address_override: Option<IpAddr>,
+    options: &ForwarderOptions,
+) -> Vec<Forward> {
+    let port_mappings = if ports.is_empty() {
+        pod.port_mappings()
+    } else {
+        ports
+            .into_iter()
+            .map(|(local_port, container_port)| PortMapping {
+                container_port,
+                local_port,
+                address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            })
+            .collect()
+    };
 
+    port_mappings
+        .into_iter()
+        .enumerate()
+        .map(|(index, PortMapping { container_port, local_port, address })| {
+            let local_addr = SocketAddr::new(bind_address(address, address_override), local_port);
+            let target = format!("{pod_name}:{container_port}");
+            let worker_name = format!("forwarder-{local_addr}/{target}");
+            let api = api.clone();
+            let pod_name = pod_name.to_string();
+            let options = ForwarderOptions {
+                ready_sender: options.ready_sender.as_ref().map(|sender| sender.with_index(index)),
+                ..options.clone()
+            };
+            let _handle = lifecycle_manager.spawn(worker_name, move |shutdown_signal| async move {
+                let result =
+                    options.run(api, pod_name, container_port, local_addr, shutdown_signal).await;
                 match result {
-                    Ok(()) => ExitStatus::Success,
+                    Ok(_) => ExitStatus::Success,
                     Err(err) => ExitStatus::Error(Error::from(err)),
                 }
-            };
-            let _handle = lifecycle_manager.spawn(worker_name, create_fn);
+            });
+            Forward { local_addr, target, remote_port: container_port }
+        })
+        .collect()
+}
+
+/// Prints a warning for every forwarder bound to a non-loopback address.
+fn warn_exposed(forwards: &[Forward]) {
+    for Forward { local_addr, target, .. } in forwards {
+        if !local_addr.ip().is_loopback() {
+            eprintln!("Warning: binding {local_addr} exposes {target} to the network");
         }
+    }
+}
 
-        tracing::info!("Forwarders started. Use Ctrl+C to stop.");
+/// Spawns a worker that waits until every forwarder is bound, then writes
+/// `output` for them.
+fn spawn_ready_writer(
+    lifecycle_manager: &LifecycleManager<Error>,
+    mut ready_receiver: mpsc::UnboundedReceiver<(usize, SocketAddr)>,
+    name: &str,
+    forwards: &[Forward],
+    output: ReadyOutput,
+) {
+    let name = name.to_string();
+    let remote_ports = forwards.iter().map(|forward| forward.remote_port).collect::<Vec<_>>();
+    let _handle = lifecycle_manager.spawn("ready-writer", move |shutdown_signal| async move {
+        tokio::pin!(shutdown_signal);
+        let mut local_addrs = vec![None; remote_ports.len()];
+        while local_addrs.iter().any(Option::is_none) {
+            tokio::select! {
+                () = &mut shutdown_signal => return ExitStatus::Success,
+                ready = ready_receiver.recv() => match ready {
+                    Some((index, addr)) => local_addrs[index] = Some(addr),
+                    None => return ExitStatus::Success,
+                },
+            }
+        }
 
-        if let Ok(Err(err)) = lifecycle_manager.serve().await {
-            tracing::error!("{err}");
-            Err(err)
+        let bound = local_addrs
+            .into_iter()
+            .flatten()
+            .zip(remote_ports)
+            .map(|(local_addr, remote_port)| BoundForward { local_addr, remote_port })
+            .collect::<Vec<_>>();
+        if let Err(err) = output.write(&name, &bound).await {
+            return ExitStatus::Error(err);
+        }
+        shutdown_signal.await;
+        ExitStatus::Success
+    });
+}
+
+impl ReadyOutput {
+    /// Returns the sender the forwarders report their bound addresses to, and
+    /// its receiver, if anything is written once every forwarder is bound.
+    fn channel(
+        &self,
+    ) -> (Option<ReadySender>, Option<mpsc::UnboundedReceiver<(usize, SocketAddr)>>) {
+        let is_requested =
+            self.print_env || self.env_file.is_some() || self.print_forwards.is_some();
+        is_requested.then(ReadySender::channel).unzip()
+    }
+
+    /// Writes the output for the forwarders `bound` to the pod or service
+    /// `name`.
+    ///
+    /// The `export` lines are written to `env_file`, or to stdout, with
+    /// variables named after `name`, see `env_var_name`. The forwarders are
+    /// then printed to stdout in the format of `print_forwards`.
+    async fn write(self, name: &str, bound: &[BoundForward]) -> Result<(), Error> {
+        let Self { print_env, env_file, print_forwards } = self;
+        if print_env || env_file.is_some() {
+            let variables = bound
+                .iter()
+                .map(|forward| env_var_name(name, forward.remote_port, bound.len() > 1))
+                .collect::<Vec<_>>();
+            let exports = render_env(
+                variables
+                    .iter()
+                    .map(String::as_str)
+                    .zip(bound.iter().map(|forward| forward.local_addr.port())),
+            );
+            if let Some(filename) = env_file {
+                tokio::fs::write(&filename, exports)
+                    .await
+                    .context(error::WriteEnvFileSnafu { filename })?;
+            } else {
+                write_stdout(&exports).await?;
+            }
+        }
+        match print_forwards {
+            Some(ForwardsFormat::Json) => write_stdout(&render_forwards_json(bound)?).await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Writes `output` to stdout and flushes it, so that a reading process sees
+/// it while the command keeps running.
+async fn write_stdout(output: &str) -> Result<(), Error> {
+    let mut stdout = tokio::io::stdout();
+    match stdout.write_all(output.as_bytes()).await {
+        Ok(()) => stdout.flush().await,
+        Err(err) => Err(err),
+    }
+    .context(error::WriteStdoutSnafu)
+}
+
+/// Renders `bound` as a line holding a JSON array.
+fn render_forwards_json(bound: &[BoundForward]) -> Result<String, Error> {
+    let mut line = serde_json::to_string(bound)
+        .map_err(|err| error::GenericSnafu { message: err.to_string() }.build())?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// Returns the name of the environment variable holding the local port of the
+/// forwarder to `remote_port` of the pod or service `name`, e.g.
+/// `MY_SVC_PORT`.
+///
+/// The name is upper-cased and every character that is not allowed in a shell
+/// identifier is replaced by `_`. With `several` forwarders, the remote port is
+/// appended to keep the names distinct, e.g. `MY_SVC_PORT_80`.
+fn env_var_name(name: &str, remote_port: u16, several: bool) -> String {
+    let mut variable = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect::<String>();
+    if variable.starts_with(|c: char| c.is_ascii_digit()) {
+        variable.insert(0, '_');
+    }
+    variable.push_str("_PORT");
+    if several {
+        let _ = write!(variable, "_{remote_port}");
+    }
+    variable
+}
+
+/// Renders an `export NAME=PORT` line for every pair of variable name and
+/// port.
+fn render_env<'a>(variables: impl IntoIterator<Item = (&'a str, u16)>) -> String {
+    variables.into_iter().fold(String::new(), |mut exports, (variable, port)| {
+        let _ = writeln!(exports, "export {variable}={port}");
+        exports
+    })
+}
+
+/// Parses a positional argument of the command, either a port pair or a
+/// target such as `svc/web` or a bare pod name.
+///
+/// An argument is a port pair if it has the shape of one, i.e. contains a `:`,
+/// which a target never does, or consists of digits only. Any other argument,
+/// including a pod name starting with a digit such as `1web`, is a target; a
+/// pod whose name consists of digits only has to be given as `pod/NAME`.
+fn parse_forward_arg(arg: &str) -> Result<ForwardArg, String> {
+    let is_port_pair = !arg.contains('/')
+        && (arg.contains(':') || (!arg.is_empty() && arg.chars().all(|c| c.is_ascii_digit())));
+    if is_port_pair {
+        parse_port_pair(arg)
+            .map(|(local_port, remote_port)| ForwardArg::Ports(local_port, remote_port))
+    } else {
+        arg.parse().map(ForwardArg::Target)
+    }
+}
+
+/// Parses a `[LOCAL]:REMOTE` port pair, e.g. `8080:80`, `0:80`, `:80` or
+/// `8080:http`.
+///
+/// A bare `REMOTE` port is forwarded from the same local port, and must be a
+/// number. A remote port starting with a letter is the name of a container
+/// port, which Kubernetes limits to 15 lowercase letters, digits and dashes.
+fn parse_port_pair(pair: &str) -> Result<PortArg, String> {
+    let parse = |port: &str| {
+        port.parse::<u16>().map_err(|_| format!("'{port}' is not a valid port in '{pair}'"))
+    };
+    let parse_remote = |port: &str| {
+        if port.starts_with(|c: char| c.is_ascii_lowercase()) {
+            let is_valid_name = port.len() <= 15
+                && !port.ends_with('-')
+                && port.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+            is_valid_name
+                .then(|| RemotePort::Name(port.to_string()))
+                .ok_or_else(|| format!("'{port}' is not a valid port name in '{pair}'"))
         } else {
-            Ok(())
+            parse(port).map(RemotePort::Number)
         }
+    };
+    match pair.split_once(':') {
+        Some(("", remote)) => Ok((0, parse_remote(remote)?)),
+        Some((local, remote)) => Ok((parse(local)?, parse_remote(remote)?)),
+        None => parse(pair).map(|port| (port, RemotePort::Number(port))),
+    }
+}
+
+/// Returns the address a port mapping is bound to, given the `--address`
+/// override.
+///
+/// Only mappings on a loopback address, the default, are overridden, so that
+/// an address chosen explicitly in the spec is kept.
+fn bind_address(address: IpAddr, address_override: Option<IpAddr>) -> IpAddr {
+    address_override.filter(|_| address.is_loopback()).unwrap_or(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_port_pair() {
+        assert_eq!(parse_port_pair("8080:80"), Ok((8080, RemotePort::Number(80))));
+        assert_eq!(parse_port_pair("0:80"), Ok((0, RemotePort::Number(80))));
+        assert_eq!(parse_port_pair(":80"), Ok((0, RemotePort::Number(80))));
+        assert_eq!(parse_port_pair("80"), Ok((80, RemotePort::Number(80))));
+        assert_eq!(parse_port_pair("8080:http"), Ok((8080, RemotePort::Name("http".into()))));
+        assert_eq!(parse_port_pair(":grpc-web"), Ok((0, RemotePort::Name("grpc-web".into()))));
+        assert!(parse_port_pair("8080:").is_err());
+        assert!(parse_port_pair("http:80").is_err());
+        assert!(parse_port_pair("70000").is_err());
+        assert!(parse_port_pair("8080:HTTP").is_err());
+        assert!(parse_port_pair("8080:http-").is_err());
+        assert!(parse_port_pair("8080:a-very-long-port-name").is_err());
+    }
+
+    #[test]
+    fn test_resolve_ports() {
+        use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec};
+
+        let pod = Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    ports: Some(vec![
+                        ContainerPort { container_port: 22, ..ContainerPort::default() },
+                        ContainerPort {
+                            name: Some("http".to_string()),
+                            container_port: 8080,
+                            ..ContainerPort::default()
+                        },
+                    ]),
+                    ..Container::default()
+                }],
+                ..PodSpec::default()
+            }),
+            ..Pod::default()
+        };
+        let ports = vec![(0, RemotePort::Name("http".into())), (2222, RemotePort::Number(22))];
+        assert_eq!(
+            resolve_ports(&pod, "dev", "axon", ports.clone()).ok(),
+            Some(vec![(0, 8080), (2222, 22)])
+        );
+        assert!(numeric_ports(ports).is_err());
+        assert_eq!(numeric_ports(vec![(0, RemotePort::Number(80))]).ok(), Some(vec![(0, 80)]));
+
+        let err = resolve_ports(&pod, "dev", "axon", vec![(0, RemotePort::Name("grpc".into()))])
+            .unwrap_err();
+        assert!(err.to_string().ends_with("no container port named 'grpc', available: http"));
+    }
+
+    #[test]
+    fn test_parse_forward_arg() {
+        assert_eq!(
+            parse_forward_arg("my-pod"),
+            Ok(ForwardArg::Target(ForwardTarget::Pod("my-pod".to_string())))
+        );
+        assert_eq!(
+            parse_forward_arg("svc/web"),
+            Ok(ForwardArg::Target(ForwardTarget::Service("web".to_string())))
+        );
+        assert_eq!(parse_forward_arg(":80"), Ok(ForwardArg::Ports(0, RemotePort::Number(80))));
+        assert_eq!(
+            parse_forward_arg("8080:http"),
+            Ok(ForwardArg::Ports(8080, RemotePort::Name("http".into())))
+        );
+        assert!(parse_forward_arg("8080:HTTP").is_err());
+        assert_eq!(parse_forward_arg("80"), Ok(ForwardArg::Ports(80, RemotePort::Number(80))));
+        assert!(parse_forward_arg("70000").is_err());
+
+        // Pod names starting with a digit are targets
+        assert_eq!(
+            parse_forward_arg("1web"),
+            Ok(ForwardArg::Target(ForwardTarget::Pod("1web".to_string())))
+        );
+        assert_eq!(
+            parse_forward_arg("8080-api"),
+            Ok(ForwardArg::Target(ForwardTarget::Pod("8080-api".to_string())))
+        );
+        assert_eq!(
+            parse_forward_arg("pod/123"),
+            Ok(ForwardArg::Target(ForwardTarget::Pod("123".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let pod = |name: &str| Some(ForwardTarget::Pod(name.to_string()));
+        let some = |name: &str| Some(name.to_string());
+
+        assert_eq!(resolve_target(some("axon"), None).unwrap(), (some("axon"), None));
+        assert_eq!(resolve_target(None, pod("web")).unwrap(), (some("web"), None));
+        assert_eq!(resolve_target(some("web"), pod("web")).unwrap(), (some("web"), None));
+        assert_eq!(
+            resolve_target(some("axon"), Some(ForwardTarget::Service("web".to_string()))).unwrap(),
+            (some("axon"), some("web"))
+        );
+
+        let err = resolve_target(some("axon"), pod("web")).unwrap_err();
+        assert!(err.to_string().contains("The target 'pod/web' conflicts with '--pod-name axon'"));
+    }
+
+    #[test]
+    fn test_env_var_name() {
+        assert_eq!(env_var_name("my-svc", 80, false), "MY_SVC_PORT");
+        assert_eq!(env_var_name("my-svc", 80, true), "MY_SVC_PORT_80");
+        assert_eq!(env_var_name("axon.debug", 22, false), "AXON_DEBUG_PORT");
+        assert_eq!(env_var_name("1st-pod", 22, false), "_1ST_POD_PORT");
+        assert_eq!(
+            render_env([("WEB_PORT_80", 8080), ("WEB_PORT_443", 8443)]),
+            "export WEB_PORT_80=8080\nexport WEB_PORT_443=8443\n"
+        );
+    }
+
+    #[test]
+    fn test_render_forwards_json() {
+        let bound = [
+            BoundForward { local_addr: "127.0.0.1:41234".parse().unwrap(), remote_port: 80 },
+            BoundForward { local_addr: "[::1]:8443".parse().unwrap(), remote_port: 443 },
+        ];
+        assert_eq!(
+            render_forwards_json(&bound).unwrap(),
+            "[{\"local_addr\":\"127.0.0.1:41234\",\"remote_port\":80},{\"local_addr\":\"[::1]:\
+             8443\",\"remote_port\":443}]\n"
+        );
+        assert_eq!(render_forwards_json(&[]).unwrap(), "[]\n");
+    }
+
+    #[test]
+    fn test_tunnel_mode() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert!(matches!(
+            tunnel_mode(Some(1080), false, false, Vec::new(), None),
+            Ok(TunnelMode::Socks(addr)) if addr == SocketAddr::new(localhost, 1080)
+        ));
+        assert!(matches!(
+            tunnel_mode(None, true, false, vec![(8080, RemotePort::Number(0))], None),
+            Ok(TunnelMode::Reverse(forwards)) if forwards == [ReverseForward {
+                remote_port: 0,
+                local_addr: SocketAddr::new(localhost, 8080),
+            }]
+        ));
+        assert!(
+            tunnel_mode(Some(1080), false, false, vec![(8080, RemotePort::Number(80))], None)
+                .is_err()
+        );
+        assert!(tunnel_mode(Some(1080), false, true, Vec::new(), None).is_err());
+        assert!(tunnel_mode(None, true, false, Vec::new(), None).is_err());
+        assert!(tunnel_mode(None, true, false, vec![(0, RemotePort::Number(9000))], None).is_err());
+    }
+
+    #[test]
+    fn test_bind_address_overrides_loopback_only() {
+        let unspecified = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        let lan = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 10));
+
+        assert_eq!(bind_address(IpAddr::V4(Ipv4Addr::LOCALHOST), Some(unspecified)), unspecified);
+        assert_eq!(bind_address(IpAddr::V6(Ipv6Addr::LOCALHOST), Some(unspecified)), unspecified);
+        assert_eq!(bind_address(lan, Some(unspecified)), lan);
+        assert_eq!(bind_address(IpAddr::V4(Ipv4Addr::LOCALHOST), None), Ipv4Addr::LOCALHOST);
     }
 }
@@ -0,0 +1,192 @@
+//! Defines the `proxy` subcommand, which forwards several local ports at once.
+//!
+//! Each forwarding rule binds a local port to a port of a pod or a service.
+//! Rules are given on the command line or read from the `proxyRules` section
+//! of the configuration file, and all their forwarders run until the process
+//! is interrupted.
+
+use std::net::SocketAddr;
+
+use clap::Args;
+use k8s_openapi::api::core::v1::Pod;
+use kube::Api;
+use sigfinn::{ExitStatus, LifecycleManager};
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    cli::{
+        Error, error,
+        internal::{
+            ForwardTarget, ForwarderOptions, ResolvedResources, ResourceResolver, ServiceForward,
+            ServiceTarget, serve_metrics, signal,
+        },
+    },
+    config::{Config, ProxyRule},
+    port_forwarder::ConnectionLimitPolicy,
+    ui::table::ProxyRuleExt,
+};
+
+/// Represents the command to forward several local ports to pods and services
+/// at once.
+#[derive(Args, Clone)]
+pub struct ProxyCommand {
+    /// Kubernetes namespace of the targets whose rule does not name one.
+    ///
+    /// If not specified, the default namespace will be used.
+    #[arg(
+        short,
+        long,
+        help = "Kubernetes namespace of the targets whose rule does not name one. If not \
+                specified, the default namespace will be used."
+    )]
+    pub namespace: Option<String>,
+
+    /// The forwarding rules to start. If none are given, the `proxyRules` of
+    /// the configuration file are used.
+    #[arg(
+        short = 'r',
+        long = "rule",
+        value_name = "[ADDRESS:]LOCAL_PORT:TARGET:REMOTE_PORT",
+        help = "A forwarding rule, e.g. 8080:svc/web:80 or 5432:pod/postgres:5432. Can be \
+                repeated. If not given, the proxyRules of the configuration file are used."
+    )]
+    pub rules: Vec<ProxyRule>,
+
+    /// Print a line to stderr whenever a connection is opened or closed.
+    #[arg(
+        short = 'v',
+        long = "verbose",
+        help = "Print a line to stderr whenever a connection is opened or closed."
+    )]
+    pub verbose: bool,
+
+    /// The address to serve Prometheus metrics of the forwarders on.
+    #[arg(
+        long = "metrics-addr",
+        value_name = "HOST:PORT",
+        help = "Serve Prometheus metrics of the forwarders, such as active connections and \
+                transferred bytes, at http://HOST:PORT/metrics."
+    )]
+    pub metrics_addr: Option<SocketAddr>,
+}
+
+impl ProxyCommand {
+    /// Starts a forwarder for every rule and runs them until the process is
+    /// interrupted (Ctrl+C) or receives `SIGTERM` or `SIGHUP`, after which all
+    /// of them are drained before returning.
+    ///
+    /// A table of the local bindings is printed to stdout once every rule has
+    /// been set up. With `metrics_addr`, the metrics of every forwarder are
+    /// served over HTTP at `/metrics` on that address.
+    ///
+    /// # Arguments
+    ///
+    /// * `kube_client` - A `kube::Client` instance used to interact with the
+    ///   Kubernetes API.
+    /// * `config` - The application's configuration.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` in the following cases:
+    ///
+    /// * If no rule is given, neither on the command line nor in the
+    ///   configuration file.
+    /// * If the target of a rule is invalid, or is a service that cannot be
+    ///   found, does not expose the port of the rule or has no ready endpoint.
+    /// * If a forwarder fails, e.g. because its local port cannot be bound.
+    pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
+        let Self { namespace, rules, verbose, metrics_addr } = self;
+        let rules = if rules.is_empty() { config.proxy_rules.clone() } else { rules };
+        if rules.is_empty() {
+            return error::NoProxyRulesSnafu.fail();
+        }
+
+        let ResolvedResources { namespace, .. } =
+            ResourceResolver::from((&kube_client, &config)).resolve(namespace, None);
+        let rules = rules
+            .into_iter()
+            .map(|rule| ProxyRule {
+                namespace: rule.namespace.or_else(|| Some(namespace.clone())),
+                ..rule
+            })
+            .collect::<Vec<_>>();
+
+        let lifecycle_manager = LifecycleManager::<Error>::new();
+        let options = ForwarderOptions {
+            verbose,
+            print_port: false,
+            wait_for_port: None,
+            max_connections: None,
+            connection_limit_policy: ConnectionLimitPolicy::Queue,
+            metrics: metrics_addr.map(|addr| serve_metrics(&lifecycle_manager, addr)),
+            ready_sender: None,
+        };
+        for rule in &rules {
+            spawn_forwarder(&lifecycle_manager, kube_client.clone(), rule, options.clone()).await?;
+        }
+
+        let table = format!("{}\n", rules.render_table());
+        tokio::io::stdout().write_all(table.as_bytes()).await.context(error::WriteStdoutSnafu)?;
+
+        if let Ok(Err(err)) = signal::serve(lifecycle_manager).await {
+            tracing::error!("{err}");
+            Err(err)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Spawns the forwarder of a single rule.
+///
+/// A service target is resolved to a ready pod backing it before the
+/// forwarder starts, so that misconfigured rules are reported right away.
+async fn spawn_forwarder(
+    lifecycle_manager: &LifecycleManager<Error>,
+    kube_client: kube::Client,
+    rule: &ProxyRule,
+    options: ForwarderOptions,
+) -> Result<(), Error> {
+    let namespace = rule.namespace.as_deref().unwrap_or_default();
+    let local_addr = SocketAddr::new(rule.address, rule.local_port);
+    let target = rule
+        .target
+        .parse::<ForwardTarget>()
+        .map_err(|message| error::GenericSnafu { message }.build())?;
+    if !local_addr.ip().is_loopback() {
+        eprintln!("Warning: binding {local_addr} exposes {target} to the network");
+    }
+    let worker_name = format!("forwarder-{local_addr}/{target}:{}", rule.remote_port);
+
+    match target {
+        ForwardTarget::Pod(pod_name) => {
+            let api = Api::<Pod>::namespaced(kube_client, namespace);
+            let remote_port = rule.remote_port;
+            let _handle = lifecycle_manager.spawn(worker_name, move |shutdown_signal| async move {
+                match options.run(api, pod_name, remote_port, local_addr, shutdown_signal).await {
+                    Ok(_) => ExitStatus::Success,
+                    Err(err) => ExitStatus::Error(Error::from(err)),
+                }
+            });
+        }
+        ForwardTarget::Service(service_name) => {
+            let service = ServiceTarget::get(kube_client.clone(), namespace, &service_name).await?;
+            service.ensure_port(rule.remote_port)?;
+            let Some(pod_name) = service.ready_pod(kube_client.clone()).await? else {
+                return error::ServiceHasNoReadyEndpointSnafu { namespace, service_name }.fail();
+            };
+            let forward = ServiceForward {
+                client: kube_client,
+                service,
+                service_port: rule.remote_port,
+                pod_name,
+                options,
+            };
+            let _handle = lifecycle_manager.spawn(worker_name, move |shutdown_signal| {
+                forward.run(local_addr, shutdown_signal)
+            });
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,11 @@
 //! This module defines the `GetCommand` structure and its associated logic
 //! for downloading files from a remote Kubernetes pod via SSH.
 
-use std::{path::PathBuf, time::Duration};
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use clap::Args;
 use k8s_openapi::api::core::v1::Pod;
@@ -11,12 +15,14 @@ use sigfinn::{ExitStatus, LifecycleManager};
 use crate::{
     cli::{
         Error, error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
+        internal::{ApiPodExt, ResolvedResources, ResourceResolver, signal},
         ssh::internal::{
-            Configurator, DEFAULT_SSH_PORT, FileTransfer, FileTransferRunner, setup_port_forwarding,
+            Configurator, DEFAULT_SSH_PORT, FileTransfer, FileTransferRunner, Overwrite,
+            STDIO_PATH, gzip, setup_port_forwarding,
         },
     },
     config::Config,
+    consts::DEFAULT_POD_READY_TIMEOUT,
     ext::PodExt,
     ssh,
 };
@@ -27,6 +33,7 @@ use crate::{
 /// the target pod, authentication details, source file path on the pod,
 /// and the destination path on the local machine.
 #[derive(Args, Clone)]
+#[expect(clippy::struct_excessive_bools, reason = "Every bool is an independent command-line flag")]
 pub struct GetCommand {
     /// Kubernetes namespace of the target pod. If not specified, the default
     /// namespace will be used.
@@ -50,23 +57,28 @@ pub struct GetCommand {
 
     /// The maximum time in seconds to wait for the pod to be running before
     /// timing out.
+    ///
+    /// Defaults to the global `--pod-ready-timeout`, or to 15 seconds.
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "15",
-        help = "The maximum time in seconds to wait for the pod to be running before timing out."
+        help = "The maximum time in seconds to wait for the pod to be running before timing out. \
+                Defaults to the global --pod-ready-timeout, or 15 seconds."
     )]
-    timeout_secs: u64,
+    timeout_secs: Option<u64>,
 
-    /// Path to the SSH private key file for authentication. If not specified,
-    /// Axon will look for `sshPrivateKeyFilePath` in the configuration.
+    /// Paths to SSH private key files for authentication, tried in order before
+    /// `sshPrivateKeyFilePath` of the configuration.
     #[arg(
         short = 'i',
         long = "ssh-private-key-file",
-        help = "Path to the SSH private key file for authentication. If not specified, Axon will \
-                look for `sshPrivateKeyFilePath` in the configuration."
+        visible_alias = "identity",
+        value_name = "SSH_PRIVATE_KEY_FILE",
+        help = "Path to an SSH private key file for authentication. Can be repeated to try \
+                several keys in order, before `sshPrivateKeyFilePath` of the configuration. If \
+                neither is set, ~/.ssh/id_ed25519, ~/.ssh/id_rsa and ~/.ssh/id_ecdsa are tried."
     )]
-    ssh_private_key_file: Option<PathBuf>,
+    ssh_private_key_files: Vec<PathBuf>,
 
     /// User name to connect as via SSH on the remote pod.
     #[arg(
@@ -77,13 +89,123 @@ pub struct GetCommand {
     )]
     user: String,
 
+    /// Number of SFTP requests kept in flight during the transfer. Values
+    /// greater than 1 pipeline the requests, which improves throughput on
+    /// high-latency links.
+    #[arg(
+        long = "sftp-concurrency",
+        default_value = "1",
+        help = "Number of SFTP requests kept in flight during the transfer. Values greater than 1 \
+                pipeline the requests, which improves throughput on high-latency links."
+    )]
+    sftp_concurrency: NonZeroUsize,
+
+    /// Maximum time the whole download may take, such as `30s` or `5m`. This
+    /// is independent of the pod running-status timeout. If not specified, the
+    /// transfer is not limited.
+    #[arg(
+        long = "timeout",
+        value_parser = humantime::parse_duration,
+        help = "Maximum time the whole download may take, such as `30s` or `5m`. This is independent \
+                of the pod running-status timeout. If not specified, the transfer is not limited."
+    )]
+    transfer_timeout: Option<Duration>,
+
+    /// Maximum time the SSH handshake and authentication may take.
+    #[arg(
+        long = "connect-timeout",
+        value_parser = humantime::parse_duration,
+        help = "Maximum time the SSH handshake and authentication may take, such as `10s`. \
+                Independent of --timeout, which only limits the transfer itself."
+    )]
+    connect_timeout: Option<Duration>,
+
+    /// Fail instead of overwriting an existing local file.
+    #[arg(
+        long = "no-clobber",
+        help = "Fail instead of overwriting an existing local file. The file is created \
+                exclusively, so that it is not overwritten even if it appears during the transfer."
+    )]
+    no_clobber: bool,
+
+    /// Ask before overwriting an existing local file, and skip the download
+    /// unless confirmed.
+    #[arg(
+        long = "interactive",
+        conflicts_with = "no_clobber",
+        help = "Ask before overwriting an existing local file, and skip the download unless \
+                confirmed. Without a terminal to ask on, the file is not overwritten."
+    )]
+    interactive: bool,
+
+    /// Position of the first byte of the remote file to download.
+    #[arg(
+        long = "offset",
+        help = "Position of the first byte of the remote file to download. Defaults to the start \
+                of the file."
+    )]
+    offset: Option<u64>,
+
+    /// Number of bytes of the remote file to download, starting at `--offset`.
+    #[arg(
+        long = "length",
+        help = "Number of bytes of the remote file to download, starting at --offset. Defaults to \
+                all bytes up to the end of the file."
+    )]
+    length: Option<u64>,
+
+    /// Decompress the remote file, which holds gzip data, after receiving it.
+    ///
+    /// The decompression is done locally, so only the compressed data goes
+    /// over the tunnel and no tool is needed on the remote pod.
+    #[arg(
+        long = "compress",
+        conflicts_with_all = ["offset", "length"],
+        help = "Decompress the remote file, which holds gzip data such as a file uploaded with \
+                `ssh put --compress` or a `.gz` log, after receiving it. The decompression is \
+                done locally, so only the compressed data goes over the tunnel and no tool is \
+                needed on the remote pod. A local file named after the remote one drops its \
+                `.gz` extension."
+    )]
+    compress: bool,
+
+    /// Transfer the source directory as a `tar` archive streamed from
+    /// `tar -c` on the remote pod.
+    #[arg(
+        long = "tar",
+        conflicts_with_all = ["no_clobber", "interactive", "compress", "offset", "length"],
+        help = "Transfer the source directory as a tar archive streamed from `tar -c` on the \
+                remote pod, which is far faster than per-file SFTP for many small files. The \
+                directory is extracted into the destination directory, which is created if \
+                needed, or into the current directory. Requires tar both locally and in the pod."
+    )]
+    tar: bool,
+
+    /// Print a JSON summary of the completed download to stdout instead of
+    /// displaying its progress.
+    #[arg(
+        long = "json",
+        help = "Print a JSON summary of the completed download to stdout, with the fields \
+                `bytes`, `duration_ms`, `source` and `destination`, instead of displaying its \
+                progress. Cannot be used when the file is written to standard output."
+    )]
+    json: bool,
+
     /// Path to the file on the remote pod to download.
     #[arg(help = "Path to the file on the remote pod to download.")]
     source: PathBuf,
 
-    /// Local path where the downloaded file will be saved.
-    #[arg(help = "Local path where the downloaded file will be saved.")]
-    destination: PathBuf,
+    /// Local path where the downloaded file will be saved, or `-` to write it
+    /// to standard output.
+    ///
+    /// Like `scp`, the file is saved under the file name of the source in the
+    /// current directory if not specified, or in the destination if it is an
+    /// existing directory.
+    #[arg(help = "Local path where the downloaded file will be saved, or `-` to write it to \
+                  standard output. If not specified, or if it is an existing directory, the \
+                  file is saved under the file name of the source in the current directory or \
+                  in the given directory.")]
+    destination: Option<PathBuf>,
 }
 
 impl GetCommand {
@@ -106,6 +228,8 @@ impl GetCommand {
     /// # Errors
     ///
     /// This function returns an `Err` if:
+    /// * The destination cannot be resolved, or its directory does not exist or
+    ///   is not writable.
     /// * The SSH key pair cannot be loaded.
     /// * The target pod cannot be found or does not reach a running state
     ///   within the specified timeout.
@@ -125,30 +249,59 @@ impl GetCommand {
             namespace,
             pod_name,
             timeout_secs,
-            ssh_private_key_file,
+            ssh_private_key_files,
             user,
+            sftp_concurrency,
+            transfer_timeout,
+            connect_timeout,
+            no_clobber,
+            interactive,
+            offset,
+            length,
+            compress,
+            tar,
+            json,
             source,
             destination,
         } = self;
+        let byte_range = (offset.is_some() || length.is_some())
+            .then(|| ssh::ByteRange { offset: offset.unwrap_or_default(), length });
+        // An archive is extracted into a directory, keeping the name of the source
+        let destination = if tar {
+            destination.unwrap_or_else(|| PathBuf::from("."))
+        } else if compress {
+            // The local file holds the decompressed data, so it drops the extension
+            resolve_destination(&gzip::decompressed_path(&source), destination).await?
+        } else {
+            resolve_destination(&source, destination).await?
+        };
+        snafu::ensure!(
+            !json || destination != Path::new(STDIO_PATH),
+            error::GenericSnafu {
+                message: "--json cannot be used when the file is written to standard output"
+            }
+        );
 
         // Resolve Identity
         let ResolvedResources { namespace, pod_name } =
             ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name);
 
         let (ssh_private_key, ssh_public_key) = ssh::resolve_ssh_key_pair(
-            [ssh_private_key_file.as_ref(), config.ssh_private_key_file_path.as_ref()]
-                .iter()
-                .flatten(),
+            ssh_private_key_files.iter().chain(&config.ssh_private_key_file_path),
         )
         .await?;
 
         let api = Api::<Pod>::namespaced(kube_client, &namespace);
         let pod = api
-            .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
+            .await_running_status(
+                &pod_name,
+                &namespace,
+                config.pod_ready_timeout(timeout_secs, DEFAULT_POD_READY_TIMEOUT),
+            )
             .await?;
         let remote_port = pod.service_ports().ssh.unwrap_or(DEFAULT_SSH_PORT);
 
-        Configurator::new(api.clone(), &namespace, &pod_name)
+        let _report = Configurator::new(api.clone(), &namespace, &pod_name)
             .upload_ssh_key(ssh_public_key)
             .await?;
 
@@ -172,7 +325,14 @@ impl GetCommand {
                 socket_addr,
                 ssh_private_key,
                 user,
-                transfer: FileTransfer::Download { source, destination },
+                sftp_concurrency,
+                timeout: transfer_timeout,
+                connect_timeout,
+                transfer: FileTransfer::Download { source, destination, byte_range },
+                overwrite: Overwrite::from_flags(no_clobber, interactive),
+                compress,
+                tar,
+                json,
             }
             .run(shutdown_signal)
             .await;
@@ -183,7 +343,7 @@ impl GetCommand {
             }
         });
 
-        if let Ok(Err(err)) = lifecycle_manager.serve().await {
+        if let Ok(Err(err)) = signal::serve(lifecycle_manager).await {
             tracing::error!("{err}");
             Err(err)
         } else {
@@ -191,3 +351,121 @@ impl GetCommand {
         }
     }
 }
+
+/// Resolves the local path the remote file `source` is downloaded to, like
+/// `scp` does.
+///
+/// The file keeps the file name of `source` if no `destination` is given, in
+/// the current directory, or if `destination` is an existing directory, in
+/// that directory. [`STDIO_PATH`] is returned as is.
+///
+/// # Errors
+///
+/// Returns an `Error` if `source` has no file name to keep, or
+/// `InvalidDestination` if the directory of the resolved destination does not
+/// exist, is not a directory or is not writable.
+async fn resolve_destination(
+    source: &Path,
+    destination: Option<PathBuf>,
+) -> Result<PathBuf, Error> {
+    if destination.as_deref() == Some(Path::new(STDIO_PATH)) {
+        return Ok(PathBuf::from(STDIO_PATH));
+    }
+    let is_dir = match &destination {
+        Some(destination) => {
+            tokio::fs::metadata(destination).await.is_ok_and(|metadata| metadata.is_dir())
+        }
+        None => true,
+    };
+    let destination = if is_dir {
+        let file_name = source.file_name().ok_or_else(|| {
+            error::GenericSnafu {
+                message: format!(
+                    "Cannot save {} under its file name, specify a destination",
+                    source.display()
+                ),
+            }
+            .build()
+        })?;
+        destination.unwrap_or_default().join(file_name)
+    } else {
+        destination.unwrap_or_default()
+    };
+
+    let directory = destination
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let reason = match tokio::fs::metadata(directory).await {
+        Ok(metadata) if !metadata.is_dir() => format!("{} is not a directory", directory.display()),
+        Ok(metadata) if metadata.permissions().readonly() => {
+            format!("{} is not writable", directory.display())
+        }
+        Ok(_) => return Ok(destination),
+        Err(err) => format!("cannot access {}, error: {err}", directory.display()),
+    };
+    error::InvalidDestinationSnafu { path: destination, reason }.fail()
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        get: GetCommand,
+    }
+
+    #[test]
+    fn test_transfer_modes_conflict() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["axon"], args].concat());
+        let Cli { get } = parse(&["--tar", "/var/log"]).unwrap();
+        assert!(get.tar);
+        assert_eq!(get.destination, None);
+        let Cli { get } = parse(&["--offset", "10", "--length", "5", "app.log"]).unwrap();
+        assert_eq!((get.offset, get.length), (Some(10), Some(5)));
+
+        for args in [
+            ["--tar", "--offset", "10", "/var/log"].as_slice(),
+            &["--tar", "--compress", "/var/log"],
+            &["--compress", "--length", "5", "app.log.gz"],
+            &["--no-clobber", "--interactive", "app.log"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolve_destination() {
+        let dir = std::env::temp_dir().join(format!("axon-get-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("existing.txt");
+        std::fs::write(&file, "").unwrap();
+        let source = Path::new("/var/log/app.log");
+
+        let resolve = |destination: Option<&Path>| {
+            resolve_destination(source, destination.map(Path::to_path_buf))
+        };
+        assert_eq!(resolve(None).await.unwrap(), Path::new("app.log"));
+        assert_eq!(resolve(Some(Path::new("-"))).await.unwrap(), Path::new("-"));
+        assert_eq!(resolve(Some(&dir)).await.unwrap(), dir.join("app.log"));
+        assert_eq!(resolve(Some(&dir.join("renamed.log"))).await.unwrap(), dir.join("renamed.log"));
+        assert_eq!(resolve(Some(&file)).await.unwrap(), file);
+        for destination in [dir.join("missing/app.log"), file.join("app.log")] {
+            assert!(
+                matches!(
+                    resolve(Some(&destination)).await,
+                    Err(Error::InvalidDestination { path, .. }) if path == destination
+                ),
+                "{}",
+                destination.display()
+            );
+        }
+        assert!(resolve_destination(Path::new("/"), None).await.is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
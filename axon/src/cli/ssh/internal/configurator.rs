@@ -3,11 +3,107 @@
 
 use std::fmt;
 
-use k8s_openapi::api::core::v1::Pod;
-use kube::{Api, api::AttachParams};
-use snafu::ResultExt;
+use k8s_openapi::{api::core::v1::Pod, jiff::Timestamp};
+use kube::{
+    Api,
+    api::{AttachParams, Patch, PatchParams},
+};
+use snafu::{IntoError, OptionExt, ResultExt};
+use tokio::io::AsyncReadExt;
 
-use crate::cli::{Error, error};
+use crate::{
+    cli::{Error, error, internal::describe_exec_failure},
+    consts::k8s::annotations,
+};
+
+/// The default mode of the `authorized_keys` file.
+const DEFAULT_AUTHORIZED_KEYS_FILE_MODE: u32 = 0o600;
+
+/// How the uploaded SSH public key is written to the `authorized_keys` file.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AuthorizedKeysMode {
+    /// Appends the key, unless the file already contains it.
+    #[default]
+    Append,
+    /// Replaces the content of the file with the key.
+    Replace,
+}
+
+/// What uploading the SSH public key did to the `authorized_keys` file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyStatus {
+    /// The key was appended to the file.
+    Added,
+    /// The file already contained the key.
+    AlreadyAuthorized,
+    /// The content of the file was replaced with the key.
+    Replaced,
+    /// The commands did not confirm that the key was written.
+    Unconfirmed,
+}
+
+impl KeyStatus {
+    /// Parses the status reported by the upload script.
+    fn parse(output: &str) -> Self {
+        match output.trim() {
+            "added" => Self::Added,
+            "present" => Self::AlreadyAuthorized,
+            "replaced" => Self::Replaced,
+            _ => Self::Unconfirmed,
+        }
+    }
+}
+
+impl fmt::Display for KeyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Added => "added",
+            Self::AlreadyAuthorized => "already authorized",
+            Self::Replaced => "replaced the existing keys",
+            Self::Unconfirmed => "not confirmed, the commands in the pod may have failed",
+        })
+    }
+}
+
+/// The state of the sshd host keys of the pod.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HostKeyStatus {
+    /// Host keys already existed.
+    Existing,
+    /// Host keys were generated with `ssh-keygen -A`.
+    Generated,
+}
+
+impl fmt::Display for HostKeyStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Existing => "already present",
+            Self::Generated => "generated",
+        })
+    }
+}
+
+/// The steps taken by [`Configurator::upload_ssh_key`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SetupReport {
+    /// The path of the `authorized_keys` file in the pod.
+    pub authorized_keys: String,
+    /// What was done to the `authorized_keys` file.
+    pub key: KeyStatus,
+    /// The state of the sshd host keys, if they were checked.
+    pub host_keys: Option<HostKeyStatus>,
+}
+
+impl fmt::Display for SetupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { authorized_keys, key, host_keys } = self;
+        write!(f, "SSH public key: {key} ({authorized_keys})")?;
+        if let Some(host_keys) = host_keys {
+            write!(f, "\nSSH host keys: {host_keys}")?;
+        }
+        Ok(())
+    }
+}
 
 /// Manages configuration tasks for a specific Kubernetes pod, such as uploading
 /// SSH keys.
@@ -18,11 +114,25 @@ pub struct Configurator {
     namespace: String,
     /// The name of the target pod.
     pod_name: String,
+    /// The user whose `authorized_keys` file is updated, or `None` for the
+    /// user the container runs as.
+    user: Option<String>,
+    /// How the key is written to the `authorized_keys` file.
+    authorized_keys_mode: AuthorizedKeysMode,
+    /// The mode the `authorized_keys` file is set to.
+    file_mode: u32,
+    /// Whether missing sshd host keys are generated.
+    generate_host_keys: bool,
+    /// Whether the time of the setup is recorded in an annotation of the pod.
+    record_setup: bool,
 }
 
 impl Configurator {
     /// Creates a new `Configurator` instance.
     ///
+    /// By default, the key is appended to the `authorized_keys` file of the
+    /// user the container runs as, and the file mode is set to 600.
+    ///
     /// # Arguments
     ///
     /// * `api` - A Kubernetes API client configured for `Pod` resources.
@@ -34,18 +144,86 @@ impl Configurator {
     ///
     /// A new `Configurator` instance.
     pub fn new(api: Api<Pod>, namespace: impl Into<String>, pod_name: impl Into<String>) -> Self {
-        Self { api, namespace: namespace.into(), pod_name: pod_name.into() }
+        Self {
+            api,
+            namespace: namespace.into(),
+            pod_name: pod_name.into(),
+            user: None,
+            authorized_keys_mode: AuthorizedKeysMode::default(),
+            file_mode: DEFAULT_AUTHORIZED_KEYS_FILE_MODE,
+            generate_host_keys: false,
+            record_setup: false,
+        }
+    }
+
+    /// Sets the user whose `authorized_keys` file is updated.
+    ///
+    /// The user's home directory is looked up in the pod. `None` selects the
+    /// user the container runs as.
+    #[must_use]
+    pub fn user(mut self, user: Option<String>) -> Self {
+        self.user = user;
+        self
+    }
+
+    /// Sets whether the key is appended to or replaces the `authorized_keys`
+    /// file.
+    #[must_use]
+    pub const fn authorized_keys_mode(mut self, authorized_keys_mode: AuthorizedKeysMode) -> Self {
+        self.authorized_keys_mode = authorized_keys_mode;
+        self
+    }
+
+    /// Sets the mode of the `authorized_keys` file, e.g. `0o600`.
+    #[must_use]
+    pub const fn file_mode(mut self, file_mode: u32) -> Self {
+        self.file_mode = file_mode;
+        self
+    }
+
+    /// Sets whether [`Configurator::upload_ssh_key`] also generates the
+    /// sshd host keys of the pod when none exist.
+    ///
+    /// Minimal images often ship sshd without host keys, in which case sshd
+    /// refuses to start.
+    #[must_use]
+    pub const fn generate_host_keys(mut self, generate_host_keys: bool) -> Self {
+        self.generate_host_keys = generate_host_keys;
+        self
+    }
+
+    /// Sets whether [`Configurator::upload_ssh_key`] records the time of the
+    /// setup in the `SSH_SETUP` annotation of the pod.
+    ///
+    /// This is off by default, as it patches the pod: the key is also uploaded
+    /// before every `ssh shell`, `put` and `get`, which should not require
+    /// permission to patch pods.
+    #[must_use]
+    pub const fn record_setup(mut self, record_setup: bool) -> Self {
+        self.record_setup = record_setup;
+        self
     }
 
     /// Uploads an SSH public key to the `authorized_keys` file within the
-    /// target pod's `~/.ssh` directory.
+    /// target user's `~/.ssh` directory.
     ///
     /// This function executes a series of shell commands on the remote pod to:
-    /// 1. Create the `~/.ssh` directory if it doesn't exist.
-    /// 2. Set appropriate permissions (700 for `~/.ssh`, 600 for
-    ///    `authorized_keys`).
-    /// 3. Append the provided `ssh_public_key` to `~/.ssh/authorized_keys`.
-    /// 4. Sort and deduplicate entries in `authorized_keys`.
+    /// 1. Look up the home directory of the target user.
+    /// 2. Create the `~/.ssh` directory if it doesn't exist, with mode 700.
+    /// 3. Append the provided `ssh_public_key` to `~/.ssh/authorized_keys` if
+    ///    it is not already present, or replace the file with it.
+    /// 4. Set the configured mode on `authorized_keys`.
+    /// 5. Hand `~/.ssh` over to the target user, if one is set.
+    ///
+    /// If enabled with [`Configurator::record_setup`], the time of the setup is
+    /// then recorded in an annotation of the pod, on a best-effort basis,
+    /// provided the commands confirmed that the key was written.
+    ///
+    /// If enabled with [`Configurator::generate_host_keys`], the sshd host keys
+    /// are generated first when none exist.
+    ///
+    /// Returns a [`SetupReport`] telling whether the key was added or already
+    /// authorized, and whether host keys were generated.
     ///
     /// # Arguments
     ///
@@ -59,40 +237,238 @@ impl Configurator {
     /// - There is an issue attaching to the pod or executing the commands
     ///   (e.g., pod not found, permission issues). This will be wrapped in an
     ///   `error::UploadSshKeySnafu`.
-    pub async fn upload_ssh_key<P>(&self, ssh_public_key: P) -> Result<(), Error>
+    /// - The commands exit unsuccessfully in the pod.
+    /// - The home directory of the target user cannot be determined.
+    /// - Host keys have to be generated but `ssh-keygen` is not available in
+    ///   the pod, or fails.
+    pub async fn upload_ssh_key<P>(&self, ssh_public_key: P) -> Result<SetupReport, Error>
     where
         P: fmt::Display,
     {
-        let Self { api, namespace, pod_name } = self;
+        let Self { namespace, pod_name, user, authorized_keys_mode, file_mode, .. } = self;
+
+        let host_keys =
+            if self.generate_host_keys { Some(self.ensure_host_keys().await?) } else { None };
+
+        let home = self.home_directory().await?;
+        let authorized_keys_path = format!("{home}/.ssh/authorized_keys");
+        let ssh_dir = shell_escape::escape(format!("{home}/.ssh").into()).into_owned();
+        let authorized_keys =
+            shell_escape::escape(authorized_keys_path.as_str().into()).into_owned();
+        let key =
+            shell_escape::escape(ssh_public_key.to_string().trim().to_string().into()).into_owned();
 
+        let write_key = match authorized_keys_mode {
+            AuthorizedKeysMode::Append => format!(
+                "{{ if grep -qxF {key} {authorized_keys} 2>/dev/null; then status=present; else \
+                 echo {key} >> {authorized_keys} && status=added; fi; }}"
+            ),
+            AuthorizedKeysMode::Replace => {
+                format!("echo {key} > {authorized_keys} && status=replaced")
+            }
+        };
         // We use a single shell command to:
         // 1. Create .ssh directory
-        // 2. Append the key to authorized_keys
+        // 2. Write the key to authorized_keys
         // 3. Set correct permissions (SSH is picky about 700/600)
-        let auth_command = [
-            "sh".to_string(),
-            "-c".to_string(),
-            [
-                "mkdir -p ~/.ssh",
-                "chmod 700 ~/.ssh",
-                &format!("echo '{ssh_public_key}' >> ~/.ssh/authorized_keys"),
-                "chmod 600 ~/.ssh/authorized_keys",
-                "sort -u ~/.ssh/authorized_keys -o ~/.ssh/authorized_keys",
-            ]
-            .join(" && "),
+        // 4. Hand the files over to the target user
+        // 5. Report what was done to the key, once every step succeeded
+        let mut commands = vec![
+            format!("mkdir -p {ssh_dir}"),
+            format!("chmod 700 {ssh_dir}"),
+            write_key,
+            format!("chmod {file_mode:o} {authorized_keys}"),
         ];
+        if let Some(user) = user {
+            let user = shell_escape::escape(user.into());
+            commands.push(format!("chown -R {user} {ssh_dir}"));
+        }
+        commands.push("echo \"$status\"".to_string());
 
-        let attached = api
-            .exec(pod_name, auth_command, &AttachParams::default())
-            .await
-            .with_context(|_| error::UploadSshKeySnafu {
+        let output = self
+            .exec_output(
+                commands.join(" && "),
+                error::UploadSshKeySnafu {
+                    namespace: namespace.clone(),
+                    pod_name: pod_name.clone(),
+                },
+            )
+            .await?;
+
+        let key = KeyStatus::parse(&output);
+        if self.record_setup && key != KeyStatus::Unconfirmed {
+            self.record_setup_time().await;
+        }
+        Ok(SetupReport { authorized_keys: authorized_keys_path, key, host_keys })
+    }
+
+    /// Records the current time in the `SSH_SETUP` annotation of the pod.
+    ///
+    /// A failure, e.g. because the user may not patch pods, is only logged, as
+    /// the annotation is informational.
+    async fn record_setup_time(&self) {
+        let patch = serde_json::json!({
+            "metadata": {
+                "annotations": { annotations::SSH_SETUP.as_str(): Timestamp::now().to_string() }
+            }
+        });
+        if let Err(err) =
+            self.api.patch(&self.pod_name, &PatchParams::default(), &Patch::Merge(patch)).await
+        {
+            tracing::warn!("Failed to record the SSH setup of pod {}: {err}", self.pod_name);
+        }
+    }
+
+    /// Looks up the home directory of the target user in the pod.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` if the lookup cannot be executed in the pod, if it
+    /// exits unsuccessfully, or if it does not yield an absolute path.
+    async fn home_directory(&self) -> Result<String, Error> {
+        let Self { namespace, pod_name, user, .. } = self;
+
+        let script = user.as_ref().map_or_else(
+            || "printf '%s' \"$HOME\"".to_string(),
+            |user| format!("getent passwd {} | cut -d: -f6", shell_escape::escape(user.into())),
+        );
+        let output = self
+            .exec_output(
+                script,
+                error::UploadSshKeySnafu {
+                    namespace: namespace.clone(),
+                    pod_name: pod_name.clone(),
+                },
+            )
+            .await?;
+
+        // A trailing slash is dropped so that `/` yields `/.ssh`
+        Some(output.trim())
+            .filter(|home| home.starts_with('/'))
+            .map(|home| home.trim_end_matches('/').to_string())
+            .context(error::DetermineHomeDirectorySnafu {
                 namespace: namespace.clone(),
                 pod_name: pod_name.clone(),
-            })?;
+                user: user.clone(),
+            })
+    }
+
+    /// Generates the sshd host keys of the pod with `ssh-keygen -A`, unless
+    /// some already exist, and returns which of the two happened.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Err` if the check cannot be executed in the pod, if
+    /// `ssh-keygen` is not available in the pod, or if it fails.
+    async fn ensure_host_keys(&self) -> Result<HostKeyStatus, Error> {
+        let Self { namespace, pod_name, .. } = self;
+
+        let script = [
+            "if ls /etc/ssh/ssh_host_*_key >/dev/null 2>&1; then echo present",
+            "elif ! command -v ssh-keygen >/dev/null 2>&1; then echo no-ssh-keygen",
+            "elif ssh-keygen -A >/dev/null 2>&1; then echo generated",
+            "else echo failed; fi",
+        ]
+        .join("; ");
+        let output = self
+            .exec_output(
+                script,
+                error::GenerateHostKeysSnafu {
+                    namespace: namespace.clone(),
+                    pod_name: pod_name.clone(),
+                },
+            )
+            .await?;
 
-        // Wait for the command to complete. The output is ignored for this operation.
+        match output.trim() {
+            "present" => Ok(HostKeyStatus::Existing),
+            "generated" => Ok(HostKeyStatus::Generated),
+            "no-ssh-keygen" => error::SshKeygenNotFoundSnafu {
+                namespace: namespace.clone(),
+                pod_name: pod_name.clone(),
+            }
+            .fail(),
+            output => error::HostKeyGenerationFailedSnafu {
+                namespace: namespace.clone(),
+                pod_name: pod_name.clone(),
+                output: output.to_string(),
+            }
+            .fail(),
+        }
+    }
+
+    /// Runs a shell script in the pod and returns what it writes to stdout.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error built by `context` if the script cannot be executed
+    /// in the pod, and an `error::SshSetupCommandFailedSnafu` if it exits
+    /// unsuccessfully or its exit status is not reported.
+    async fn exec_output<C>(&self, script: String, context: C) -> Result<String, Error>
+    where
+        C: IntoError<Error, Source = kube::Error>,
+    {
+        let Self { namespace, pod_name, .. } = self;
+
+        let mut attached = self
+            .api
+            .exec(pod_name, ["sh".to_string(), "-c".to_string(), script], &AttachParams::default())
+            .await
+            .context(context)?;
+        let status = attached.take_status();
+
+        let mut output = String::new();
+        if let Some(mut stdout) = attached.stdout() {
+            let _n = stdout.read_to_string(&mut output).await.ok();
+        }
+        let status = match status {
+            Some(status) => status.await,
+            None => None,
+        };
         let _unused = attached.join().await;
 
-        Ok(())
+        let reason = status.map_or_else(
+            || Some("the exit status was not reported".to_string()),
+            |status| describe_exec_failure(&status),
+        );
+        if let Some(reason) = reason {
+            return error::SshSetupCommandFailedSnafu {
+                namespace: namespace.clone(),
+                pod_name: pod_name.clone(),
+                reason,
+            }
+            .fail();
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_setup_report() {
+        assert_eq!(KeyStatus::parse("added\n"), KeyStatus::Added);
+        assert_eq!(KeyStatus::parse("present\n"), KeyStatus::AlreadyAuthorized);
+        assert_eq!(KeyStatus::parse("replaced\n"), KeyStatus::Replaced);
+        assert_eq!(KeyStatus::parse(""), KeyStatus::Unconfirmed);
+
+        let report = SetupReport {
+            authorized_keys: "/root/.ssh/authorized_keys".to_string(),
+            key: KeyStatus::AlreadyAuthorized,
+            host_keys: None,
+        };
+        assert_eq!(
+            report.to_string(),
+            "SSH public key: already authorized (/root/.ssh/authorized_keys)"
+        );
+
+        let report = SetupReport { host_keys: Some(HostKeyStatus::Generated), ..report };
+        assert_eq!(
+            report.to_string(),
+            "SSH public key: already authorized (/root/.ssh/authorized_keys)\nSSH host keys: \
+             generated"
+        );
     }
 }
@@ -4,14 +4,32 @@
 //! executing file upload and download operations over SSH connections,
 //! with progress bar support and automatic resource cleanup.
 
-use std::{net::SocketAddr, path::PathBuf};
+use std::{
+    net::SocketAddr,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+use snafu::ResultExt;
+use tokio::io::{AsyncRead, AsyncWriteExt};
+use tokio_util::either::Either as AsyncEither;
 
 use crate::{
-    cli::{Error, ssh::internal::HandleGuard},
+    cli::{
+        Error, error,
+        internal::output,
+        ssh::internal::{HandleGuard, connect_within, gzip, tar},
+    },
     ssh,
-    ui::FileTransferProgressBar,
+    ui::{self, FileTransferProgressBar},
 };
 
+/// The path used to denote the local standard input (for uploads) or standard
+/// output (for downloads) instead of a local file.
+pub const STDIO_PATH: &str = "-";
+
 /// Represents the type of file transfer to be performed.
 ///
 /// This enum distinguishes between uploading a file from a local source to a
@@ -22,15 +40,72 @@ pub enum FileTransfer {
     /// Specifies an upload operation.
     ///
     /// # Fields
-    /// - `source`: The local path of the file to be uploaded.
+    /// - `source`: The local path of the file to be uploaded, or [`STDIO_PATH`]
+    ///   to read from the local standard input.
     /// - `destination`: The remote path where the file will be stored.
     Upload { source: PathBuf, destination: PathBuf },
     /// Specifies a download operation.
     ///
     /// # Fields
     /// - `source`: The remote path of the file to be downloaded.
-    /// - `destination`: The local path where the downloaded file will be saved.
-    Download { source: PathBuf, destination: PathBuf },
+    /// - `destination`: The local path where the downloaded file will be saved,
+    ///   or [`STDIO_PATH`] to write to the local standard output.
+    /// - `byte_range`: The range of bytes of the remote file to download, or
+    ///   `None` to download the whole file.
+    Download { source: PathBuf, destination: PathBuf, byte_range: Option<ssh::ByteRange> },
+}
+
+impl FileTransfer {
+    /// Returns the remote path of the transfer.
+    fn remote_path(&self) -> &Path {
+        match self {
+            Self::Upload { destination, .. } => destination,
+            Self::Download { source, .. } => source,
+        }
+    }
+
+    /// Returns the source and the destination of the transfer.
+    const fn paths(&self) -> (&PathBuf, &PathBuf) {
+        match self {
+            Self::Upload { source, destination } | Self::Download { source, destination, .. } => {
+                (source, destination)
+            }
+        }
+    }
+
+    /// Returns the range of bytes of the remote file the transfer is limited
+    /// to.
+    const fn byte_range(&self) -> Option<ssh::ByteRange> {
+        match self {
+            Self::Upload { .. } => None,
+            Self::Download { byte_range, .. } => *byte_range,
+        }
+    }
+}
+
+/// Represents what a file transfer does when its destination already exists.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Overwrite {
+    /// Overwrites the destination.
+    #[default]
+    Always,
+    /// Fails instead of overwriting the destination.
+    Never,
+    /// Asks the user whether to overwrite the destination, and skips the
+    /// transfer otherwise.
+    Prompt,
+}
+
+impl Overwrite {
+    /// Selects the behavior requested by the `--no-clobber` and
+    /// `--interactive` flags, overwriting if neither is set.
+    pub const fn from_flags(no_clobber: bool, interactive: bool) -> Self {
+        match (no_clobber, interactive) {
+            (true, _) => Self::Never,
+            (false, true) => Self::Prompt,
+            (false, false) => Self::Always,
+        }
+    }
 }
 
 /// A runner responsible for executing file transfer operations over an SSH
@@ -53,9 +128,49 @@ pub struct FileTransferRunner {
     /// The username for SSH authentication on the remote server.
     pub user: String,
 
+    /// The number of SFTP requests kept in flight during the transfer.
+    pub sftp_concurrency: NonZeroUsize,
+
+    /// The maximum time the whole upload or download may take, independent of
+    /// the SSH inactivity timeout. `None` means no limit.
+    pub timeout: Option<Duration>,
+
+    /// The maximum time to establish the SSH session. `None` means no limit.
+    pub connect_timeout: Option<Duration>,
+
     /// The specific file transfer operation (upload or download) to be
     /// performed.
     pub transfer: FileTransfer,
+
+    /// What to do when the destination of the transfer already exists.
+    pub overwrite: Overwrite,
+
+    /// Whether the data is gzip-compressed on the wire. The compression is
+    /// done locally, so an upload stores gzip data on the remote pod and a
+    /// download expects it there.
+    pub compress: bool,
+
+    /// Whether the source directory is transferred as a `tar` archive, see
+    /// [`tar::transfer`].
+    pub tar: bool,
+
+    /// Whether a JSON summary of the transfer is printed to stdout on success
+    /// instead of displaying its progress.
+    pub json: bool,
+}
+
+/// The summary of a completed transfer printed with `--json`.
+#[derive(Debug, Serialize)]
+struct TransferSummary {
+    /// The number of bytes written to the destination, or of the archive with
+    /// `--tar`.
+    bytes: u64,
+    /// The time the transfer took, in milliseconds.
+    duration_ms: u64,
+    /// The source of the transfer, as given.
+    source: String,
+    /// The destination of the transfer, as given.
+    destination: String,
 }
 
 impl FileTransferRunner {
@@ -82,58 +197,278 @@ impl FileTransferRunner {
     ///
     /// This method can return an `Error` in several scenarios:
     /// - If the SSH session cannot be established (e.g., connection failure,
-    ///   authentication issues, invalid private key).
+    ///   authentication issues, invalid private key), or not within
+    ///   `connect_timeout` (`Error::SshConnectTimeout`).
     /// - If the file upload or download operation fails (e.g., file not found,
     ///   permission denied, network issues during transfer).
+    /// - If the destination exists and `overwrite` is `Overwrite::Never`
+    ///   (`Error::DestinationExists`).
+    /// - If the transfer does not complete within `timeout`
+    ///   (`Error::TransferTimeout`).
     /// - If the SSH session cannot be cleanly closed after the transfer.
     pub async fn run(self, shutdown_signal: impl Future<Output = ()> + Unpin) -> Result<(), Error> {
-        let Self { handle, socket_addr, ssh_private_key, user, transfer } = self;
+        let Self {
+            handle,
+            socket_addr,
+            ssh_private_key,
+            user,
+            sftp_concurrency,
+            timeout,
+            connect_timeout,
+            transfer,
+            overwrite,
+            compress,
+            tar,
+            json,
+        } = self;
 
         // Automatically shuts down the port forwarder when this scope ends
         let _handle_guard = HandleGuard::from(handle);
 
-        let session = ssh::Session::connect(ssh_private_key, user, socket_addr).await?;
-
-        let transfer_result = match transfer {
-            FileTransfer::Upload { source, destination } => {
-                let pb = FileTransferProgressBar::new_upload();
-                let n = session
-                    .upload(
-                        source,
-                        destination,
-                        Some(|len| pb.set_length(len)),
-                        Some(|file| pb.wrap_async_read(file)),
-                        Some(shutdown_signal),
-                    )
-                    .await;
-                if n.is_ok() {
-                    pb.finish();
-                }
-                n
-            }
-            FileTransfer::Download { source, destination } => {
-                let pb = FileTransferProgressBar::new_download();
-                let n = session
-                    .download(
-                        source,
-                        destination,
-                        Some(|len| pb.set_length(len)),
-                        Some(|file| pb.wrap_async_read(file)),
-                        Some(shutdown_signal),
-                    )
-                    .await;
-                if n.is_ok() {
-                    pb.finish();
-                }
-                n
+        let session = connect_within(
+            connect_timeout,
+            ssh::Session::connect_accepting_any_host_key(ssh_private_key, user, socket_addr),
+        )
+        .await?
+        .with_sftp_concurrency(sftp_concurrency);
+        let Some(no_clobber) = check_destination(&session, &transfer, overwrite).await? else {
+            return session.close().await.map_err(Error::from);
+        };
+        let session = session.with_no_clobber(no_clobber).with_byte_range(transfer.byte_range());
+
+        let remote_path = transfer.remote_path().to_path_buf();
+        let (source, destination) = transfer.paths();
+        let (source, destination) =
+            (source.display().to_string(), destination.display().to_string());
+
+        let started = Instant::now();
+        let transfer = async {
+            if tar {
+                tar::transfer(&session, transfer, json, shutdown_signal).await
+            } else {
+                transfer_file(&session, transfer, compress, json, shutdown_signal)
+                    .await
+                    .map_err(Error::from)
             }
         };
 
+        // The timeout drops the transfer, cancelling every request still in flight
+        let transfer_result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, transfer).await.unwrap_or_else(|_| {
+                Err(error::TransferTimeoutSnafu { path: remote_path, elapsed: timeout }.build())
+            }),
+            None => transfer.await,
+        };
+
         // Attempt to close the session cleanly
         let close_result = session.close().await;
 
         // Return the execution error if it exists, otherwise the closing error
-        transfer_result.map(|_n| ()).map_err(Error::from)?;
-        close_result.map_err(Error::from)
+        let bytes = transfer_result?;
+        close_result?;
+
+        if json {
+            let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            write_summary(&TransferSummary { bytes, duration_ms, source, destination }).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes `summary` to stdout as a line of JSON.
+async fn write_summary(summary: &TransferSummary) -> Result<(), Error> {
+    let mut line = serde_json::to_vec(summary)
+        .map_err(|err| error::GenericSnafu { message: err.to_string() }.build())?;
+    line.push(b'\n');
+    tokio::io::stdout().write_all(&line).await.context(error::WriteStdoutSnafu)
+}
+
+/// Performs `transfer` over `session`, displaying its progress unless
+/// `hide_progress` is set, and returns the number of bytes written to the
+/// destination.
+///
+/// With `compress`, uploads are compressed before being sent and downloads
+/// decompressed after being received, so the progress reflects the bytes on
+/// the wire.
+///
+/// # Errors
+///
+/// Returns an `Error` if the transfer fails or is cancelled by
+/// `shutdown_signal`.
+async fn transfer_file(
+    session: &ssh::Session,
+    transfer: FileTransfer,
+    compress: bool,
+    hide_progress: bool,
+    shutdown_signal: impl Future<Output = ()> + Unpin,
+) -> Result<u64, ssh::Error> {
+    match transfer {
+        FileTransfer::Upload { source, destination } if is_stdio(&source) => {
+            // The size of the piped data is unknown, so only a spinner is shown
+            let pb = FileTransferProgressBar::new_upload_spinner().hidden_if(hide_progress);
+            let n = session
+                .upload_from(
+                    tokio::io::stdin(),
+                    destination,
+                    Some(|stdin| pb.wrap_async_read(compress_if(compress, stdin))),
+                    Some(shutdown_signal),
+                )
+                .await;
+            if n.is_ok() {
+                pb.finish();
+            }
+            n
+        }
+        FileTransfer::Upload { source, destination } => {
+            // The size of the compressed data is unknown until it has been sent
+            let pb = if compress {
+                FileTransferProgressBar::new_upload_spinner()
+            } else {
+                FileTransferProgressBar::new_upload()
+            }
+            .hidden_if(hide_progress);
+            let n = session
+                .upload(
+                    source,
+                    destination,
+                    Some(|len| {
+                        if !compress {
+                            pb.set_length(len);
+                        }
+                    }),
+                    Some(|file| pb.wrap_async_read(compress_if(compress, file))),
+                    Some(shutdown_signal),
+                )
+                .await;
+            if n.is_ok() {
+                pb.finish();
+            }
+            n
+        }
+        FileTransfer::Download { source, destination, .. } if is_stdio(&destination) => {
+            let pb = FileTransferProgressBar::new_download().hidden_if(hide_progress);
+            let n = session
+                .download_to(
+                    source,
+                    async { Ok(tokio::io::stdout()) },
+                    Some(|len| pb.set_length(len)),
+                    Some(|file| decompress_if(compress, pb.wrap_async_read(file))),
+                    Some(shutdown_signal),
+                )
+                .await;
+            if n.is_ok() {
+                pb.finish();
+            }
+            n
+        }
+        FileTransfer::Download { source, destination, .. } => {
+            let pb = FileTransferProgressBar::new_download().hidden_if(hide_progress);
+            let n = session
+                .download(
+                    source,
+                    destination,
+                    Some(|len| pb.set_length(len)),
+                    Some(|file| decompress_if(compress, pb.wrap_async_read(file))),
+                    Some(shutdown_signal),
+                )
+                .await;
+            if n.is_ok() {
+                pb.finish();
+            }
+            n
+        }
+    }
+}
+
+/// Compresses the data of `reader` if `compress` is set.
+fn compress_if<R>(compress: bool, reader: R) -> AsyncEither<impl AsyncRead + Send + Unpin, R>
+where
+    R: AsyncRead + Send + Unpin,
+{
+    if compress { AsyncEither::Left(gzip::compress(reader)) } else { AsyncEither::Right(reader) }
+}
+
+/// Decompresses the data of `reader` if `compress` is set.
+fn decompress_if<R>(compress: bool, reader: R) -> AsyncEither<impl AsyncRead + Send + Unpin, R>
+where
+    R: AsyncRead + Send + Unpin,
+{
+    if compress { AsyncEither::Left(gzip::decompress(reader)) } else { AsyncEither::Right(reader) }
+}
+
+/// Checks the destination of `transfer` against `overwrite` before the
+/// transfer starts.
+///
+/// Returns whether the transfer must not overwrite the destination, or `None`
+/// if the user declined to overwrite it and the transfer is skipped. Standard
+/// output is never checked.
+///
+/// # Errors
+///
+/// Returns `DestinationExists` if the destination exists and `overwrite` is
+/// `Overwrite::Never`, or an `Error` if the existence of a remote destination
+/// cannot be determined.
+async fn check_destination(
+    session: &ssh::Session,
+    transfer: &FileTransfer,
+    overwrite: Overwrite,
+) -> Result<Option<bool>, Error> {
+    if overwrite == Overwrite::Always {
+        return Ok(Some(false));
+    }
+    let (destination, exists) = match transfer {
+        FileTransfer::Download { destination, .. } if is_stdio(destination) => {
+            return Ok(Some(false));
+        }
+        FileTransfer::Download { destination, .. } => {
+            (destination, tokio::fs::try_exists(destination).await.unwrap_or(true))
+        }
+        FileTransfer::Upload { destination, .. } => {
+            (destination, session.exists(destination).await?)
+        }
+    };
+    match (exists, overwrite) {
+        (false, _) => Ok(Some(true)),
+        (true, Overwrite::Never) => {
+            error::DestinationExistsSnafu { path: destination.clone() }.fail()
+        }
+        (true, _) => {
+            if ui::confirm(format!("Overwrite {}?", destination.display())).await {
+                Ok(Some(false))
+            } else {
+                output::status(format_args!("Skipped {}", destination.display()));
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Returns `true` if `path` denotes the local standard I/O rather than a local
+/// file.
+fn is_stdio(path: &Path) -> bool { path == Path::new(STDIO_PATH) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overwrite_from_flags() {
+        assert_eq!(Overwrite::from_flags(false, false), Overwrite::Always);
+        assert_eq!(Overwrite::from_flags(true, false), Overwrite::Never);
+        assert_eq!(Overwrite::from_flags(false, true), Overwrite::Prompt);
+    }
+
+    #[test]
+    fn test_transfer_summary() {
+        let summary = TransferSummary {
+            bytes: 1024,
+            duration_ms: 250,
+            source: "/var/log/app.log".to_string(),
+            destination: "app.log".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_string(&summary).unwrap(),
+            r#"{"bytes":1024,"duration_ms":250,"source":"/var/log/app.log","destination":"app.log"}"#
+        );
     }
 }
@@ -0,0 +1,119 @@
+//! Gzip compression of file transfers.
+//!
+//! The data is compressed and decompressed entirely on the local machine, so
+//! no tool is needed in the pod. As the remote file holds gzip data, its name
+//! carries the `.gz` extension: an upload appends it to the remote path, and a
+//! download drops it from the local file name taken from the remote path, so
+//! that `ssh put --compress` and `ssh get --compress` round-trip a file.
+
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+
+use async_compression::tokio::bufread::{GzipDecoder, GzipEncoder};
+use tokio::io::{AsyncRead, BufReader};
+
+/// The extension of a file holding gzip data.
+const EXTENSION: &str = "gz";
+
+/// Creates a reader yielding the gzip-compressed content of `reader`.
+pub fn compress<R>(reader: R) -> impl AsyncRead + Send + Unpin
+where
+    R: AsyncRead + Send + Unpin,
+{
+    GzipEncoder::new(BufReader::new(reader))
+}
+
+/// Creates a reader yielding the decompressed content of `reader`, which
+/// holds gzip data of one or more members.
+///
+/// Reading fails if the data is not gzip data, is truncated or does not match
+/// its checksum.
+pub fn decompress<R>(reader: R) -> impl AsyncRead + Send + Unpin
+where
+    R: AsyncRead + Send + Unpin,
+{
+    let mut decoder = GzipDecoder::new(BufReader::new(reader));
+    decoder.multiple_members(true);
+    decoder
+}
+
+/// Returns `path` with the `.gz` extension appended, unless it has it
+/// already.
+pub fn compressed_path(path: &Path) -> PathBuf {
+    if has_extension(path) {
+        return path.to_path_buf();
+    }
+    let mut path = OsString::from(path);
+    path.push(".");
+    path.push(EXTENSION);
+    PathBuf::from(path)
+}
+
+/// Returns `path` without its `.gz` extension, if it has one.
+pub fn decompressed_path(path: &Path) -> PathBuf {
+    if has_extension(path) { path.with_extension("") } else { path.to_path_buf() }
+}
+
+/// Returns whether `path` has the `.gz` extension.
+fn has_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == EXTENSION)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    async fn read_all(mut reader: impl AsyncRead + Unpin) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        let _n = reader.read_to_end(&mut data).await?;
+        Ok(data)
+    }
+
+    #[tokio::test]
+    async fn test_compress_decompress() {
+        let data = "2024-01-01 INFO request served\n".repeat(10_000).into_bytes();
+        let compressed = read_all(compress(data.as_slice())).await.unwrap();
+        assert!(compressed.len() < data.len() / 10);
+        assert_eq!(compressed[..2], [0x1f, 0x8b]);
+        assert_eq!(read_all(decompress(compressed.as_slice())).await.unwrap(), data);
+
+        // Concatenated members, and a member of no data
+        let empty = read_all(compress([].as_slice())).await.unwrap();
+        let concatenated = [compressed.as_slice(), &empty, &compressed].concat();
+        assert_eq!(
+            read_all(decompress(concatenated.as_slice())).await.unwrap(),
+            [data.as_slice(), &data].concat()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_decompress_invalid_data() {
+        let compressed = read_all(compress(b"hello".as_slice())).await.unwrap();
+
+        let truncated = &compressed[..compressed.len() - 1];
+        assert!(read_all(decompress(truncated)).await.is_err());
+
+        let mut corrupted = compressed.clone();
+        let crc = corrupted.len() - 8;
+        corrupted[crc] ^= 0xff;
+        assert!(read_all(decompress(corrupted.as_slice())).await.is_err());
+
+        assert!(read_all(decompress(b"plain text, not gzip".as_slice())).await.is_err());
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(compressed_path(Path::new("/tmp/app.log")), Path::new("/tmp/app.log.gz"));
+        assert_eq!(compressed_path(Path::new("/tmp/app.log.gz")), Path::new("/tmp/app.log.gz"));
+        assert_eq!(compressed_path(Path::new("app")), Path::new("app.gz"));
+        assert_eq!(decompressed_path(Path::new("/tmp/app.log.gz")), Path::new("/tmp/app.log"));
+        assert_eq!(decompressed_path(Path::new("/tmp/app.log")), Path::new("/tmp/app.log"));
+        assert_eq!(decompressed_path(Path::new(".gz")), Path::new(".gz"));
+    }
+}
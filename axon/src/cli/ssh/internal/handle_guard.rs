@@ -12,8 +12,15 @@ use crate::cli::Error;
 /// the `HandleGuard` goes out of scope. It leverages Rust's `Drop`
 /// trait for automatic resource management.
 pub struct HandleGuard {
-    /// The `sigfinn` handle that this guard is responsible for shutting down.
-    handle: sigfinn::Handle<Error>,
+    /// The `sigfinn` handle that this guard is responsible for shutting down,
+    /// or `None` once the guard is disarmed.
+    handle: Option<sigfinn::Handle<Error>>,
+}
+
+impl HandleGuard {
+    /// Disarms the guard, so that the tasks of the handle keep running after
+    /// it is dropped.
+    pub fn disarm(mut self) { self.handle = None; }
 }
 
 impl From<sigfinn::Handle<Error>> for HandleGuard {
@@ -29,7 +36,7 @@ impl From<sigfinn::Handle<Error>> for HandleGuard {
     /// # Returns
     ///
     /// A new `HandleGuard` instance.
-    fn from(handle: sigfinn::Handle<Error>) -> Self { Self { handle } }
+    fn from(handle: sigfinn::Handle<Error>) -> Self { Self { handle: Some(handle) } }
 }
 
 impl Drop for HandleGuard {
@@ -37,6 +44,11 @@ impl Drop for HandleGuard {
     ///
     /// This implementation ensures that the background task or resource
     /// associated with the `sigfinn::Handle` is gracefully terminated
-    /// when the `HandleGuard` instance goes out of scope.
-    fn drop(&mut self) { self.handle.shutdown(); }
+    /// when the `HandleGuard` instance goes out of scope, unless the guard has
+    /// been disarmed.
+    fn drop(&mut self) {
+        if let Some(handle) = &self.handle {
+            handle.shutdown();
+        }
+    }
 }
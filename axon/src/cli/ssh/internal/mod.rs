@@ -1,11 +1,15 @@
 //! This module provides internal utilities for managing SSH connections within
-//! the CLI, including port forwarding setup and file transfer mechanisms.
+//! the CLI, including port forwarding setup, file transfer mechanisms and
+//! tunnels over SSH sessions.
 
 pub mod configurator;
 pub mod file_transfer;
+pub mod gzip;
 pub mod handle_guard;
+mod tar;
+pub mod tunnel;
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 use k8s_openapi::api::core::v1::Pod;
 use kube::Api;
@@ -13,15 +17,44 @@ use sigfinn::ExitStatus;
 use tokio::sync::oneshot;
 
 pub use self::{
-    configurator::Configurator,
-    file_transfer::{FileTransfer, FileTransferRunner},
+    configurator::{AuthorizedKeysMode, Configurator},
+    file_transfer::{FileTransfer, FileTransferRunner, Overwrite, STDIO_PATH},
     handle_guard::HandleGuard,
+    tunnel::{ReverseForward, SshTunnel, TunnelMode},
+};
+use crate::{
+    cli::{Error, error},
+    port_forwarder::PortForwarderBuilder,
+    ssh,
 };
-use crate::{cli::Error, port_forwarder::PortForwarderBuilder};
 
 /// The default SSH port.
 pub const DEFAULT_SSH_PORT: u16 = 22;
 
+/// Establishes an SSH session with `connect`, giving up after
+/// `connect_timeout` if set.
+///
+/// A port forwarding accepts connections even when nothing answers behind it,
+/// and the SSH inactivity timeout does not cover the handshake, so without a
+/// limit the connection can hang forever.
+///
+/// # Errors
+///
+/// Returns `Error::SshConnectTimeout` if the session is not established in
+/// time, or the error of `connect`.
+pub async fn connect_within(
+    connect_timeout: Option<Duration>,
+    connect: impl Future<Output = Result<ssh::Session, ssh::Error>>,
+) -> Result<ssh::Session, Error> {
+    match connect_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connect)
+            .await
+            .map_err(|_| error::SshConnectTimeoutSnafu { timeout }.build())?
+            .map_err(Error::from),
+        None => connect.await.map_err(Error::from),
+    }
+}
+
 /// Sets up port forwarding to a specified remote port on a Kubernetes pod.
 ///
 /// This function initializes a port forwarder that listens on a local address
@@ -76,3 +109,21 @@ pub fn setup_port_forwarding(
     });
     receiver
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connect_within() {
+        let timeout = Duration::from_millis(10);
+        let err = connect_within(Some(timeout), std::future::pending()).await.err().unwrap();
+        assert!(matches!(err, Error::SshConnectTimeout { timeout: t } if t == timeout));
+
+        let err = connect_within(Some(timeout), async { Err(ssh::Error::Cancelled) })
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), Error::from(ssh::Error::Cancelled).code());
+    }
+}
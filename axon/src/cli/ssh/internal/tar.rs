@@ -0,0 +1,228 @@
+//! Directory transfers as a stream of `tar` archive.
+//!
+//! Transferring a directory file by file over SFTP costs several round trips
+//! per file. With `--tar`, the directory is instead archived by `tar` on the
+//! sending side and the archive is piped through a single exec channel into
+//! `tar` on the receiving side, so `tar` is required both locally and in the
+//! pod.
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use futures::future;
+use snafu::ResultExt;
+use tokio::process::{Child, Command};
+
+use crate::{
+    cli::{
+        Error, error,
+        ssh::internal::{FileTransfer, STDIO_PATH},
+    },
+    ssh,
+    ui::FileTransferProgressBar,
+};
+
+/// The location of `tar` in errors when it runs on the local machine.
+const LOCAL: &str = "local machine";
+
+/// The location of `tar` in errors when it runs in the pod.
+const REMOTE: &str = "remote pod";
+
+/// Transfers the directory of `transfer` as a `tar` archive, displaying its
+/// progress unless `hide_progress` is set, and returns the number of bytes of
+/// the archive.
+///
+/// An upload extracts the source directory into the destination directory on
+/// the pod, and a download extracts the source directory of the pod into the
+/// local destination directory. Both destination directories are created if
+/// they do not exist.
+///
+/// # Errors
+///
+/// Returns `TarNotFound` if `tar` is not installed in the pod, `RunLocalTar`
+/// or `TarFailed` if `tar` cannot be run or fails on either side, or an
+/// `Error` if the transfer fails or is cancelled by `shutdown_signal`.
+pub async fn transfer(
+    session: &ssh::Session,
+    transfer: FileTransfer,
+    hide_progress: bool,
+    shutdown_signal: impl Future<Output = ()> + Unpin,
+) -> Result<u64, Error> {
+    let transfer = async {
+        let (FileTransfer::Upload { source: local, .. }
+        | FileTransfer::Download { destination: local, .. }) = &transfer;
+        snafu::ensure!(
+            local != Path::new(STDIO_PATH),
+            error::GenericSnafu { message: "--tar cannot transfer standard I/O" }
+        );
+        ensure_remote_tar(session).await?;
+        match transfer {
+            FileTransfer::Upload { source, destination } => {
+                upload(session, &source, &destination, hide_progress).await
+            }
+            FileTransfer::Download { source, destination, .. } => {
+                download(session, &source, &destination, hide_progress).await
+            }
+        }
+    };
+    match future::select(Box::pin(transfer), shutdown_signal).await {
+        future::Either::Left((result, _)) => result,
+        future::Either::Right(((), _)) => Err(ssh::Error::Cancelled.into()),
+    }
+}
+
+/// Archives the local directory `source` and extracts it into the remote
+/// directory `destination`.
+async fn upload(
+    session: &ssh::Session,
+    source: &Path,
+    destination: &Path,
+    hide_progress: bool,
+) -> Result<u64, Error> {
+    let (parent, name) = split_path(source)?;
+    let mut child = spawn_local_tar(|command| {
+        command.arg("-cf").arg("-").arg("-C").arg(parent).arg(name).stdout(Stdio::piped())
+    })?;
+    let archive = child.stdout.take().expect("stdout of tar is piped");
+
+    let destination = escape(destination);
+    let command = format!("mkdir -p {destination} && tar -xf - -C {destination}");
+    let pb = FileTransferProgressBar::new_upload_spinner().hidden_if(hide_progress);
+    let output = session.exec_piped(&command, pb.wrap_async_read(archive), tokio::io::sink()).await;
+
+    // The archive is complete only if the local `tar` succeeded
+    check_local_tar(child).await?;
+    check_remote_tar(&output?)?;
+    let bytes = pb.position();
+    pb.finish();
+    Ok(bytes)
+}
+
+/// Archives the remote directory `source` and extracts it into the local
+/// directory `destination`.
+async fn download(
+    session: &ssh::Session,
+    source: &Path,
+    destination: &Path,
+    hide_progress: bool,
+) -> Result<u64, Error> {
+    let (parent, name) = split_path(source)?;
+    tokio::fs::create_dir_all(destination).await.map_err(|err| {
+        error::InvalidDestinationSnafu { path: destination, reason: err.to_string() }.build()
+    })?;
+    let mut child = spawn_local_tar(|command| {
+        command.arg("-xf").arg("-").arg("-C").arg(destination).stdin(Stdio::piped())
+    })?;
+    let extractor = child.stdin.take().expect("stdin of tar is piped");
+
+    let command = format!("tar -cf - -C {} {}", escape(&parent), escape(&name));
+    let pb = FileTransferProgressBar::new_download_spinner().hidden_if(hide_progress);
+    let output =
+        session.exec_piped(&command, tokio::io::empty(), pb.wrap_async_write(extractor)).await;
+
+    match output {
+        // A failure of the remote `tar` also truncates the archive, so it is reported first
+        Ok(output) => check_remote_tar(&output)?,
+        // The pipe breaks if the local `tar` fails, which then explains why
+        Err(err @ ssh::Error::PipeCommandData { .. }) => {
+            check_local_tar(child).await?;
+            return Err(err.into());
+        }
+        Err(err) => return Err(err.into()),
+    }
+    check_local_tar(child).await?;
+    let bytes = pb.position();
+    pb.finish();
+    Ok(bytes)
+}
+
+/// Ensures that `tar` is installed in the pod.
+async fn ensure_remote_tar(session: &ssh::Session) -> Result<(), Error> {
+    let output = session.exec_capture("command -v tar").await?;
+    snafu::ensure!(output.outcome.exit_code == Some(0), error::TarNotFoundSnafu);
+    Ok(())
+}
+
+/// Spawns `tar` on the local machine with the arguments and pipes set by
+/// `configure`, capturing its standard error.
+fn spawn_local_tar(configure: impl FnOnce(&mut Command) -> &mut Command) -> Result<Child, Error> {
+    let mut command = Command::new("tar");
+    let _command =
+        configure(command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::piped()));
+    command.kill_on_drop(true).spawn().context(error::RunLocalTarSnafu)
+}
+
+/// Waits for the local `tar` to exit and checks that it succeeded.
+async fn check_local_tar(child: Child) -> Result<(), Error> {
+    let output = child.wait_with_output().await.context(error::RunLocalTarSnafu)?;
+    snafu::ensure!(
+        output.status.success(),
+        error::TarFailedSnafu {
+            location: LOCAL,
+            reason: failure_reason(&output.stderr, || output.status.to_string()),
+        }
+    );
+    Ok(())
+}
+
+/// Checks that the remote `tar` succeeded.
+fn check_remote_tar(output: &ssh::CommandOutput) -> Result<(), Error> {
+    snafu::ensure!(
+        output.outcome.exit_code == Some(0),
+        error::TarFailedSnafu {
+            location: REMOTE,
+            reason: failure_reason(&output.stderr, || output.outcome.to_string()),
+        }
+    );
+    Ok(())
+}
+
+/// Returns the error output of a failed `tar`, or the description of how it
+/// terminated if it wrote nothing.
+fn failure_reason(stderr: &[u8], termination: impl FnOnce() -> String) -> String {
+    let stderr = String::from_utf8_lossy(stderr).trim().to_string();
+    if stderr.is_empty() { termination() } else { stderr }
+}
+
+/// Splits `path` into its parent directory, which `tar` changes into, and the
+/// name archived there, so that the archive holds the directory itself.
+fn split_path(path: &Path) -> Result<(PathBuf, PathBuf), Error> {
+    let name = path.file_name().ok_or_else(|| {
+        error::GenericSnafu {
+            message: format!("Cannot transfer {} with --tar, name a directory", path.display()),
+        }
+        .build()
+    })?;
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    Ok((parent.to_path_buf(), PathBuf::from(name)))
+}
+
+/// Quotes `path` for the remote shell.
+fn escape(path: &Path) -> String { shell_escape::escape(path.to_string_lossy()).into_owned() }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_path() {
+        let split = |path: &str| split_path(Path::new(path)).unwrap();
+        assert_eq!(split("/var/log/app"), (PathBuf::from("/var/log"), PathBuf::from("app")));
+        assert_eq!(split("app/"), (PathBuf::from("."), PathBuf::from("app")));
+        assert_eq!(split("/app"), (PathBuf::from("/"), PathBuf::from("app")));
+        assert!(split_path(Path::new("/")).is_err());
+        assert!(split_path(Path::new("app/..")).is_err());
+
+        assert_eq!(escape(Path::new("/tmp/my dir")), "'/tmp/my dir'");
+        assert_eq!(
+            failure_reason(b" tar: app: Cannot open\n", String::new),
+            "tar: app: Cannot open"
+        );
+        assert_eq!(failure_reason(b"", || "exited with code 2".to_string()), "exited with code 2");
+    }
+}
@@ -0,0 +1,286 @@
+//! Tunnels relying on an SSH session to a pod: a SOCKS5 proxy whose
+//! connections are made from the pod, and reverse port forwarding, where the
+//! pod listens on ports forwarded back to the local host.
+
+use std::{collections::HashMap, io, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::Api;
+use russh::ChannelStream;
+use sigfinn::{ExitStatus, LifecycleManager};
+use tokio::{net::TcpStream, task::JoinSet};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cli::{
+        Error, error,
+        internal::{ApiPodExt, signal},
+        ssh::internal::{Configurator, DEFAULT_SSH_PORT, HandleGuard, setup_port_forwarding},
+    },
+    ext::PodExt,
+    port_forwarder::{ConnectionEvent, Connector, SocksProxy, TargetAddr},
+    ssh::{self, ForwardedConnection, Session},
+};
+
+/// The address the pod listens on for reverse forwarded ports.
+const REMOTE_FORWARD_ADDRESS: &str = "localhost";
+
+/// What an `SshTunnel` does with its SSH session.
+pub enum TunnelMode {
+    /// Runs a SOCKS5 proxy on the given local address, opening every
+    /// requested connection from the pod.
+    Socks(SocketAddr),
+    /// Listens on ports of the pod and forwards their connections back to
+    /// local addresses.
+    Reverse(Vec<ReverseForward>),
+}
+
+/// A port of the pod forwarded back to a local address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReverseForward {
+    /// The port the pod listens on, `0` to let the SSH server choose one.
+    pub remote_port: u16,
+    /// The local address the connections are forwarded to.
+    pub local_addr: SocketAddr,
+}
+
+/// A tunnel through an SSH session to a pod.
+pub struct SshTunnel {
+    /// The Kubernetes API client for interacting with Pods.
+    pub api: Api<Pod>,
+    /// The namespace of the pod.
+    pub namespace: String,
+    /// The name of the pod.
+    pub pod_name: String,
+    /// How long to wait for the pod to be running.
+    pub pod_ready_timeout: Duration,
+    /// The SSH private key files to try, in priority order.
+    pub ssh_private_key_files: Vec<PathBuf>,
+    /// The username to use for the SSH connection.
+    pub user: String,
+    /// Whether to report every opened and closed connection on stderr.
+    pub verbose: bool,
+}
+
+impl SshTunnel {
+    /// Runs the tunnel until the process is interrupted.
+    ///
+    /// The SSH public key is uploaded to the pod, whose SSH server is then
+    /// reached through a port forwarder. All connections of the tunnel are
+    /// channels of a single SSH session.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if no SSH key pair can be loaded, if the pod is not
+    /// running within the timeout, if the SSH key cannot be uploaded or the
+    /// SSH session cannot be established, or if the tunnel itself fails, e.g.
+    /// because a local or remote port cannot be listened on.
+    pub async fn run(self, mode: TunnelMode) -> Result<(), Error> {
+        let Self {
+            api,
+            namespace,
+            pod_name,
+            pod_ready_timeout,
+            ssh_private_key_files,
+            user,
+            verbose,
+        } = self;
+
+        let (ssh_private_key, ssh_public_key) =
+            ssh::resolve_ssh_key_pair(&ssh_private_key_files).await?;
+        let pod = api.await_running_status(&pod_name, &namespace, pod_ready_timeout).await?;
+        let ssh_port = pod.service_ports().ssh.unwrap_or(DEFAULT_SSH_PORT);
+        let _report = Configurator::new(api.clone(), &namespace, &pod_name)
+            .upload_ssh_key(ssh_public_key)
+            .await?;
+
+        let lifecycle_manager = LifecycleManager::<Error>::new();
+        let handle = lifecycle_manager.handle();
+        let ssh_local_socket_addr_receiver =
+            setup_port_forwarding(api, pod_name.clone(), ssh_port, &handle);
+        let _handle = lifecycle_manager.spawn("ssh-tunnel", move |shutdown_signal| async move {
+            // Automatically shuts down the port forwarder when this scope ends
+            let _handle_guard = HandleGuard::from(handle);
+
+            let result = async {
+                let socket_addr = ssh_local_socket_addr_receiver.await.map_err(|_| {
+                    error::GenericSnafu { message: "SSH local socket address receiver failed" }
+                        .build()
+                })?;
+                let session = Arc::new(
+                    Session::connect_accepting_any_host_key(ssh_private_key, user, socket_addr)
+                        .await?,
+                );
+
+                match mode {
+                    TunnelMode::Socks(local_addr) => {
+                        run_socks_proxy(&session, &pod_name, local_addr, verbose, shutdown_signal)
+                            .await?;
+                    }
+                    TunnelMode::Reverse(forwards) => {
+                        run_reverse_forwards(
+                            &session,
+                            &pod_name,
+                            forwards,
+                            verbose,
+                            shutdown_signal,
+                        )
+                        .await?;
+                    }
+                }
+
+                if let Ok(session) = Arc::try_unwrap(session) {
+                    session.close().await?;
+                }
+                Ok::<_, Error>(())
+            }
+            .await;
+            match result {
+                Ok(()) => ExitStatus::Success,
+                Err(err) => ExitStatus::Error(err),
+            }
+        });
+
+        if let Ok(Err(err)) = signal::serve(lifecycle_manager).await {
+            tracing::error!("{err}");
+            Err(err)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Runs a SOCKS5 proxy on `local_addr` until `shutdown_signal` completes,
+/// opening every requested connection as a `direct-tcpip` channel of
+/// `session`.
+async fn run_socks_proxy(
+    session: &Arc<Session>,
+    pod_name: &str,
+    local_addr: SocketAddr,
+    verbose: bool,
+    shutdown_signal: impl Future<Output = ()>,
+) -> Result<(), Error> {
+    if !local_addr.ip().is_loopback() {
+        eprintln!("Warning: binding {local_addr} lets anyone on the network use {pod_name}");
+    }
+
+    let mut proxy = SocksProxy::new(local_addr, SshConnector(Arc::clone(session)));
+    if verbose {
+        proxy = proxy.on_connection(move |event| match event {
+            ConnectionEvent::Opened { peer } => eprintln!("Connection opened: {peer} -> SOCKS5"),
+            ConnectionEvent::Closed { peer } => eprintln!("Connection closed: {peer} -> SOCKS5"),
+        });
+    }
+    proxy
+        .run(
+            |addr| eprintln!("SOCKS5 proxy on {addr} -> {pod_name}, press Ctrl-C to stop"),
+            shutdown_signal,
+        )
+        .await?;
+    Ok(())
+}
+
+/// Has the pod listen on the remote port of every forward and bridges the
+/// connections it accepts to their local address until `shutdown_signal`
+/// completes.
+///
+/// The remote listeners are stopped before returning.
+async fn run_reverse_forwards(
+    session: &Session,
+    pod_name: &str,
+    forwards: Vec<ReverseForward>,
+    verbose: bool,
+    shutdown_signal: impl Future<Output = ()>,
+) -> Result<(), Error> {
+    let mut local_addrs = HashMap::new();
+    for ReverseForward { remote_port, local_addr } in forwards {
+        let bound_port =
+            session.request_remote_forward(REMOTE_FORWARD_ADDRESS, remote_port).await?;
+        eprintln!("Forwarding {pod_name}:{bound_port} -> {local_addr}, press Ctrl-C to stop");
+        let _unused = local_addrs.insert(bound_port, local_addr);
+    }
+
+    let cancel_token = CancellationToken::new();
+    let mut join_set = JoinSet::new();
+    tokio::pin!(shutdown_signal);
+    let result = loop {
+        let connection = tokio::select! {
+            () = &mut shutdown_signal => break Ok(()),
+            Some(_) = join_set.join_next(), if !join_set.is_empty() => continue,
+            connection = session.accept_forwarded() => connection,
+        };
+        let Some(connection) = connection else {
+            break error::GenericSnafu { message: "The SSH session to the pod has been closed" }
+                .fail();
+        };
+        if let Some(&local_addr) = local_addrs.get(&connection.connected_port) {
+            let _unused = join_set.spawn(bridge_forwarded_connection(
+                connection,
+                local_addr,
+                cancel_token.clone(),
+                verbose,
+            ));
+        }
+    };
+
+    cancel_token.cancel();
+    while join_set.join_next().await.is_some() {}
+    for &bound_port in local_addrs.keys() {
+        if let Err(err) = session.cancel_remote_forward(REMOTE_FORWARD_ADDRESS, bound_port).await {
+            tracing::warn!("{err}");
+        }
+    }
+    result
+}
+
+/// Bridges a connection accepted by the pod to `local_addr` until either side
+/// closes it or `cancel_token` is cancelled.
+async fn bridge_forwarded_connection(
+    connection: ForwardedConnection,
+    local_addr: SocketAddr,
+    cancel_token: CancellationToken,
+    verbose: bool,
+) {
+    let ForwardedConnection { mut stream, originator, .. } = connection;
+    let mut local_stream = match TcpStream::connect(local_addr).await {
+        Ok(local_stream) => local_stream,
+        Err(err) => {
+            tracing::warn!("Failed to connect to {local_addr}, error: {err}");
+            return;
+        }
+    };
+
+    tracing::info!("Bridging connection: {originator} <-> {local_addr}");
+    if verbose {
+        eprintln!("Connection opened: {originator} -> {local_addr}");
+    }
+    tokio::select! {
+        () = cancel_token.cancelled() => {
+            tracing::debug!("Closing connection {originator} due to shutdown");
+        }
+        res = tokio::io::copy_bidirectional(&mut stream, &mut local_stream) => {
+            if let Err(err) = res {
+                tracing::debug!("Connection {originator} closed with error: {err}");
+            }
+        }
+    }
+    if verbose {
+        eprintln!("Connection closed: {originator} -> {local_addr}");
+    }
+}
+
+/// Opens the connections of the SOCKS5 proxy as `direct-tcpip` channels of an
+/// SSH session.
+#[derive(Clone)]
+struct SshConnector(Arc<Session>);
+
+impl Connector for SshConnector {
+    type Stream = ChannelStream<russh::client::Msg>;
+
+    async fn connect(&self, target: &TargetAddr, peer: SocketAddr) -> io::Result<Self::Stream> {
+        self.0
+            .open_direct_tcpip(&target.host(), target.port(), peer)
+            .await
+            .map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,64 @@
+//! Provides the `keygen` command for generating a new SSH key pair.
+//!
+//! The key pair can then be used by the other `ssh` commands, so that SSH
+//! access to a pod can be bootstrapped without `ssh-keygen` being installed.
+
+use std::path::PathBuf;
+
+use clap::Args;
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    cli::{Error, error},
+    ssh::{self, KeyAlgorithm},
+};
+
+/// Arguments for the `keygen` command, used to generate a new SSH key pair.
+#[derive(Args, Clone)]
+pub struct KeygenCommand {
+    /// Path of the private key file to create.
+    #[arg(
+        short = 'o',
+        long = "out",
+        help = "Path of the private key file to create, in the OpenSSH format. An existing file \
+                is never overwritten."
+    )]
+    pub out: PathBuf,
+
+    /// The algorithm of the key pair.
+    #[arg(
+        short = 't',
+        long = "type",
+        value_enum,
+        default_value_t = KeyAlgorithm::Ed25519,
+        help = "The algorithm of the key pair."
+    )]
+    pub algorithm: KeyAlgorithm,
+}
+
+impl KeygenCommand {
+    /// Generates a new SSH key pair, writes its private key to `out` and
+    /// prints its public key to stdout.
+    ///
+    /// The private key file is created readable and writable by its owner
+    /// only. No Kubernetes cluster is needed.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    /// * The key pair cannot be generated or serialized.
+    /// * `out` already exists or cannot be written.
+    /// * The public key cannot be written to stdout.
+    pub async fn run(self) -> Result<(), Error> {
+        let Self { out, algorithm } = self;
+
+        let (private_key, public_key) = ssh::generate_key_pair(algorithm)?;
+        ssh::save_secret_key(&out, &private_key).await?;
+
+        tokio::io::stdout()
+            .write_all(format!("{public_key}\n").as_bytes())
+            .await
+            .context(error::WriteStdoutSnafu)
+    }
+}
@@ -1,18 +1,22 @@
 //! Defines the commands available under the `ssh` subcommand.
 //!
 //! This module groups functionalities related to SSH interactions with
-//! temporary pods, including setup, interactive shell access, file upload, and
-//! file download.
+//! temporary pods, including setup, interactive shell access, file upload,
+//! file download, and the generation of key pairs.
 
 mod get;
-mod internal;
+pub mod internal;
+mod keygen;
 mod put;
 mod setup;
 mod shell;
 
 use clap::Subcommand;
 
-pub use self::{get::GetCommand, put::PutCommand, setup::SetupCommand, shell::ShellCommand};
+pub use self::{
+    get::GetCommand, keygen::KeygenCommand, put::PutCommand, setup::SetupCommand,
+    shell::ShellCommand,
+};
 use crate::{cli::Error, config::Config};
 
 /// Represents the various subcommands available for SSH operations.
@@ -32,6 +36,9 @@ pub enum SshCommands {
 
     /// Uploads a file to a temporary pod via SSH.
     Put(PutCommand),
+
+    /// Generates a new SSH key pair.
+    Keygen(KeygenCommand),
 }
 
 impl SshCommands {
@@ -55,14 +62,15 @@ impl SshCommands {
     ///
     /// This method can return an `Error` if the underlying subcommand's
     /// execution fails. Refer to the documentation of `SetupCommand::run`,
-    /// `ShellCommand::run`, `GetCommand::run`, and `PutCommand::run` for
-    /// specific error conditions.
+    /// `ShellCommand::run`, `GetCommand::run`, `PutCommand::run`, and
+    /// `KeygenCommand::run` for specific error conditions.
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
         match self {
             Self::Setup(cmd) => cmd.run(kube_client, config).await,
             Self::Shell(cmd) => cmd.run(kube_client, config).await,
             Self::Get(cmd) => cmd.run(kube_client, config).await,
             Self::Put(cmd) => cmd.run(kube_client, config).await,
+            Self::Keygen(cmd) => cmd.run().await,
         }
     }
 }
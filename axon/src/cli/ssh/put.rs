@@ -6,7 +6,7 @@
 //! a Kubernetes cluster, leveraging SSH. It handles pod resolution, SSH key
 //! management, port forwarding, and the actual file transfer operation.
 
-use std::{path::PathBuf, time::Duration};
+use std::{num::NonZeroUsize, path::PathBuf, time::Duration};
 
 use clap::Args;
 use k8s_openapi::api::core::v1::Pod;
@@ -16,12 +16,14 @@ use sigfinn::{ExitStatus, LifecycleManager};
 use crate::{
     cli::{
         Error, error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
+        internal::{ApiPodExt, ResolvedResources, ResourceResolver, signal},
         ssh::internal::{
-            Configurator, DEFAULT_SSH_PORT, FileTransfer, FileTransferRunner, setup_port_forwarding,
+            Configurator, DEFAULT_SSH_PORT, FileTransfer, FileTransferRunner, Overwrite, gzip,
+            setup_port_forwarding,
         },
     },
     config::Config,
+    consts::DEFAULT_POD_READY_TIMEOUT,
     ext::PodExt,
     ssh,
 };
@@ -32,6 +34,7 @@ use crate::{
 /// command to upload a file to a specified Kubernetes pod. It includes options
 /// for targeting the pod, configuring SSH, and specifying file paths.
 #[derive(Args, Clone)]
+#[expect(clippy::struct_excessive_bools, reason = "Every bool is an independent command-line flag")]
 pub struct PutCommand {
     #[arg(
         short,
@@ -52,18 +55,23 @@ pub struct PutCommand {
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "15",
-        help = "The maximum time in seconds to wait for the pod to be running before timing out."
+        help = "The maximum time in seconds to wait for the pod to be running before timing out. \
+                Defaults to the global --pod-ready-timeout, or 15 seconds."
     )]
-    pub timeout_secs: u64,
+    pub timeout_secs: Option<u64>,
 
+    /// Paths to SSH private key files for authentication, tried in order before
+    /// `sshPrivateKeyFilePath` of the configuration.
     #[arg(
         short = 'i',
         long = "ssh-private-key-file",
-        help = "Path to the SSH private key file for authentication. If not specified, Axon will \
-                look for `sshPrivateKeyFilePath` in the configuration."
+        visible_alias = "identity",
+        value_name = "SSH_PRIVATE_KEY_FILE",
+        help = "Path to an SSH private key file for authentication. Can be repeated to try \
+                several keys in order, before `sshPrivateKeyFilePath` of the configuration. If \
+                neither is set, ~/.ssh/id_ed25519, ~/.ssh/id_rsa and ~/.ssh/id_ecdsa are tried."
     )]
-    pub ssh_private_key_file: Option<PathBuf>,
+    pub ssh_private_key_files: Vec<PathBuf>,
 
     #[arg(
         short = 'u',
@@ -73,7 +81,75 @@ pub struct PutCommand {
     )]
     pub user: String,
 
-    #[arg(help = "Local path to the file to upload.")]
+    #[arg(
+        long = "sftp-concurrency",
+        default_value = "1",
+        help = "Number of SFTP requests kept in flight during the transfer. Values greater than 1 \
+                pipeline the requests, which improves throughput on high-latency links."
+    )]
+    pub sftp_concurrency: NonZeroUsize,
+
+    #[arg(
+        long = "timeout",
+        value_parser = humantime::parse_duration,
+        help = "Maximum time the whole upload may take, such as `30s` or `5m`. This is independent \
+                of the pod running-status timeout. If not specified, the transfer is not limited."
+    )]
+    pub transfer_timeout: Option<Duration>,
+
+    /// Maximum time the SSH handshake and authentication may take.
+    #[arg(
+        long = "connect-timeout",
+        value_parser = humantime::parse_duration,
+        help = "Maximum time the SSH handshake and authentication may take, such as `10s`. \
+                Independent of --timeout, which only limits the transfer itself."
+    )]
+    pub connect_timeout: Option<Duration>,
+
+    #[arg(
+        long = "no-clobber",
+        help = "Fail instead of overwriting an existing file on the remote pod. The file is \
+                created exclusively, so that it is not overwritten even if it appears during the \
+                transfer."
+    )]
+    pub no_clobber: bool,
+
+    #[arg(
+        long = "interactive",
+        conflicts_with = "no_clobber",
+        help = "Ask before overwriting an existing file on the remote pod, and skip the upload \
+                unless confirmed. Without a terminal to ask on, the file is not overwritten."
+    )]
+    pub interactive: bool,
+
+    #[arg(
+        long = "compress",
+        help = "Compress the file with gzip before sending it, which speeds up the transfer of \
+                large text files such as logs. The compression is done locally, so no tool is \
+                needed on the remote pod. The file is saved there compressed, with `.gz` appended \
+                to the destination unless it ends with it, e.g. to be read back with `ssh get \
+                --compress` or `gunzip`."
+    )]
+    pub compress: bool,
+
+    #[arg(
+        long = "tar",
+        conflicts_with_all = ["no_clobber", "interactive", "compress"],
+        help = "Transfer the source directory as a tar archive streamed into `tar -x` on the \
+                remote pod, which is far faster than per-file SFTP for many small files. The \
+                directory is extracted into the destination directory, which is created if \
+                needed. Requires tar both locally and in the pod."
+    )]
+    pub tar: bool,
+
+    #[arg(
+        long = "json",
+        help = "Print a JSON summary of the completed upload to stdout, with the fields `bytes`, \
+                `duration_ms`, `source` and `destination`, instead of displaying its progress."
+    )]
+    pub json: bool,
+
+    #[arg(help = "Local path to the file to upload, or `-` to read from standard input.")]
     pub source: PathBuf,
 
     #[arg(help = "Path on the remote pod where the file will be saved.")]
@@ -118,30 +194,42 @@ impl PutCommand {
             namespace,
             pod_name,
             timeout_secs,
-            ssh_private_key_file,
+            ssh_private_key_files,
             user,
+            sftp_concurrency,
+            transfer_timeout,
+            connect_timeout,
+            no_clobber,
+            interactive,
+            compress,
+            tar,
+            json,
             source,
             destination,
         } = self;
+        // The remote file holds gzip data, so its name says so
+        let destination = if compress { gzip::compressed_path(&destination) } else { destination };
 
         // Resolve Identity
         let ResolvedResources { namespace, pod_name } =
             ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name);
 
         let (ssh_private_key, ssh_public_key) = ssh::resolve_ssh_key_pair(
-            [ssh_private_key_file.as_ref(), config.ssh_private_key_file_path.as_ref()]
-                .iter()
-                .flatten(),
+            ssh_private_key_files.iter().chain(&config.ssh_private_key_file_path),
         )
         .await?;
 
         let api = Api::<Pod>::namespaced(kube_client, &namespace);
         let pod = api
-            .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
+            .await_running_status(
+                &pod_name,
+                &namespace,
+                config.pod_ready_timeout(timeout_secs, DEFAULT_POD_READY_TIMEOUT),
+            )
             .await?;
         let remote_port = pod.service_ports().ssh.unwrap_or(DEFAULT_SSH_PORT);
 
-        Configurator::new(api.clone(), &namespace, &pod_name)
+        let _report = Configurator::new(api.clone(), &namespace, &pod_name)
             .upload_ssh_key(ssh_public_key)
             .await?;
 
@@ -165,7 +253,14 @@ impl PutCommand {
                 socket_addr,
                 ssh_private_key,
                 user,
+                sftp_concurrency,
+                timeout: transfer_timeout,
+                connect_timeout,
                 transfer: FileTransfer::Upload { source, destination },
+                overwrite: Overwrite::from_flags(no_clobber, interactive),
+                compress,
+                tar,
+                json,
             }
             .run(shutdown_signal)
             .await;
@@ -175,7 +270,7 @@ impl PutCommand {
             }
         });
 
-        if let Ok(Err(err)) = lifecycle_manager.serve().await {
+        if let Ok(Err(err)) = signal::serve(lifecycle_manager).await {
             tracing::error!("{err}");
             Err(err)
         } else {
@@ -1,21 +1,36 @@
 //! Provides the `setup` command for configuring SSH access to a running pod.
+//!
+//! The steps taken are reported once the key is uploaded, followed by the
+//! result of a probe that logs in to the SSH server of the pod with the key.
 
 use std::{path::PathBuf, time::Duration};
 
 use clap::Args;
 use k8s_openapi::api::core::v1::Pod;
 use kube::Api;
+use sigfinn::{ExitStatus, LifecycleManager};
 
 use crate::{
     cli::{
-        Error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
-        ssh::internal::Configurator,
+        Error, error,
+        internal::{ApiPodExt, ResolvedResources, ResourceResolver, output},
+        ssh::internal::{
+            AuthorizedKeysMode, Configurator, DEFAULT_SSH_PORT, HandleGuard, setup_port_forwarding,
+        },
     },
     config::Config,
+    consts::DEFAULT_POD_READY_TIMEOUT,
+    ext::PodExt,
     ssh,
 };
 
+/// The user the connectivity probe logs in as when `--user` is not set, like
+/// the other `ssh` commands.
+const DEFAULT_PROBE_USER: &str = "root";
+
+/// How long the connectivity probe may take.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Arguments for the `setup` command, used to configure SSH access to a
 /// specified Kubernetes pod.
 #[derive(Args, Clone)]
@@ -42,25 +57,74 @@ pub struct SetupCommand {
 
     /// The maximum time in seconds to wait for the pod to be running before
     /// timing out.
+    ///
+    /// Defaults to the global `--pod-ready-timeout`, or to 15 seconds.
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "15",
-        help = "The maximum time in seconds to wait for the pod to be running before timing out."
+        help = "The maximum time in seconds to wait for the pod to be running before timing out. \
+                Defaults to the global --pod-ready-timeout, or 15 seconds."
     )]
-    pub timeout_secs: u64,
+    pub timeout_secs: Option<u64>,
 
-    /// Path to the SSH private key file whose corresponding public key will be
-    /// authorized on the pod. If not specified, Axon will look for
-    /// `sshPrivateKeyFilePath` in the configuration.
+    /// Paths to SSH private key files whose public key is authorized on the
+    /// pod, tried in order before `sshPrivateKeyFilePath` of the configuration.
     #[arg(
         short = 'i',
         long = "ssh-private-key-file",
-        help = "Path to the SSH private key file whose corresponding public key will be \
-                authorized on the pod. If not specified, Axon will look for \
-                `sshPrivateKeyFilePath` in the configuration."
+        visible_alias = "identity",
+        value_name = "SSH_PRIVATE_KEY_FILE",
+        help = "Path to an SSH private key file whose public key is authorized on the pod. Can be \
+                repeated to try several keys in order, before `sshPrivateKeyFilePath` of the \
+                configuration. If neither is set, ~/.ssh/id_ed25519, ~/.ssh/id_rsa and \
+                ~/.ssh/id_ecdsa are tried."
+    )]
+    pub ssh_private_key_files: Vec<PathBuf>,
+
+    /// The user whose `authorized_keys` file is updated. If not specified, the
+    /// user the container runs as is used.
+    #[arg(
+        short = 'u',
+        long = "user",
+        help = "The user whose authorized_keys file is updated. If not specified, the user the \
+                container runs as is used."
+    )]
+    pub user: Option<String>,
+
+    /// Replace the content of the `authorized_keys` file instead of appending
+    /// the key to it.
+    #[arg(
+        long = "replace-authorized-keys",
+        help = "Replace the content of the authorized_keys file instead of appending the key to \
+                it. Keys authorized by other means are revoked."
     )]
-    pub ssh_private_key_file: Option<PathBuf>,
+    pub replace_authorized_keys: bool,
+
+    /// The mode of the `authorized_keys` file, in octal.
+    #[arg(
+        long = "authorized-keys-mode",
+        default_value = "600",
+        value_parser = parse_file_mode,
+        help = "The mode of the authorized_keys file, in octal."
+    )]
+    pub authorized_keys_file_mode: u32,
+
+    /// Generate the sshd host keys of the pod with `ssh-keygen -A` when none
+    /// exist.
+    #[arg(
+        long = "generate-host-keys",
+        help = "Generate the sshd host keys of the pod with `ssh-keygen -A` when none exist. \
+                Needed on minimal images where sshd refuses to start without host keys."
+    )]
+    pub generate_host_keys: bool,
+
+    /// Skip logging in to the SSH server of the pod after the setup.
+    #[arg(
+        long = "no-probe",
+        help = "Skip logging in to the SSH server of the pod with the key after the setup, which \
+                otherwise checks that sshd is reachable and accepts the key."
+    )]
+    pub no_probe: bool,
 }
 
 impl SetupCommand {
@@ -68,7 +132,12 @@ impl SetupCommand {
     ///
     /// This function resolves the target pod's identity, loads the SSH key
     /// pair, waits for the pod to be in a running state, and then uploads
-    /// the public SSH key to the pod to authorize access.
+    /// the public SSH key to the pod to authorize access. The steps taken are
+    /// printed, followed by the result of a connectivity probe that logs in to
+    /// the SSH server of the pod with the key, unless `--no-probe` is set.
+    ///
+    /// A failed probe is reported but does not fail the command, as sshd may
+    /// legitimately not be started yet.
     ///
     /// # Arguments
     ///
@@ -87,24 +156,120 @@ impl SetupCommand {
     /// * There's an issue communicating with the Kubernetes API.
     /// * The public SSH key cannot be uploaded to the pod.
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, pod_name, timeout_secs, ssh_private_key_file } = self;
+        let Self {
+            namespace,
+            pod_name,
+            timeout_secs,
+            ssh_private_key_files,
+            user,
+            replace_authorized_keys,
+            authorized_keys_file_mode,
+            generate_host_keys,
+            no_probe,
+        } = self;
 
         // Resolve Identity
         let ResolvedResources { namespace, pod_name } =
             ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name);
 
-        let (_ssh_private_key, ssh_public_key) = ssh::resolve_ssh_key_pair(
-            [ssh_private_key_file.as_ref(), config.ssh_private_key_file_path.as_ref()]
-                .iter()
-                .flatten(),
+        let (ssh_private_key, ssh_public_key) = ssh::resolve_ssh_key_pair(
+            ssh_private_key_files.iter().chain(&config.ssh_private_key_file_path),
         )
         .await?;
 
         let api = Api::<Pod>::namespaced(kube_client, &namespace);
-        let _unused = api
-            .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
+        let pod = api
+            .await_running_status(
+                &pod_name,
+                &namespace,
+                config.pod_ready_timeout(timeout_secs, DEFAULT_POD_READY_TIMEOUT),
+            )
+            .await?;
+
+        let authorized_keys_mode = if replace_authorized_keys {
+            AuthorizedKeysMode::Replace
+        } else {
+            AuthorizedKeysMode::Append
+        };
+        let report = Configurator::new(api.clone(), &namespace, &pod_name)
+            .user(user.clone())
+            .authorized_keys_mode(authorized_keys_mode)
+            .file_mode(authorized_keys_file_mode)
+            .generate_host_keys(generate_host_keys)
+            .record_setup(true)
+            .upload_ssh_key(ssh_public_key)
             .await?;
+        output::status(format_args!("{report}"));
 
-        Configurator::new(api, namespace, pod_name).upload_ssh_key(ssh_public_key).await
+        if !no_probe {
+            let user = user.unwrap_or_else(|| DEFAULT_PROBE_USER.to_string());
+            let ssh_port = pod.service_ports().ssh.unwrap_or(DEFAULT_SSH_PORT);
+            let probe = tokio::time::timeout(
+                PROBE_TIMEOUT,
+                probe_ssh(api, pod_name, ssh_port, ssh_private_key, user.clone()),
+            )
+            .await;
+            match probe {
+                Ok(Ok(())) => output::status(format_args!("SSH connectivity: logged in as {user}")),
+                Ok(Err(err)) => output::status(format_args!("SSH connectivity: failed, {err}")),
+                Err(_elapsed) => output::status(format_args!(
+                    "SSH connectivity: failed, no answer within {}s",
+                    PROBE_TIMEOUT.as_secs()
+                )),
+            }
+        }
+        Ok(())
     }
 }
+
+/// Logs in to the SSH server of the pod as `user` through a port forwarding to
+/// `ssh_port`, then closes the session.
+///
+/// # Errors
+///
+/// Returns an `Error` if the port forwarding or the SSH session cannot be
+/// established.
+async fn probe_ssh(
+    api: Api<Pod>,
+    pod_name: String,
+    ssh_port: u16,
+    ssh_private_key: russh::keys::PrivateKey,
+    user: String,
+) -> Result<(), Error> {
+    let lifecycle_manager = LifecycleManager::<Error>::new();
+    let handle = lifecycle_manager.handle();
+    let ssh_local_socket_addr_receiver = setup_port_forwarding(api, pod_name, ssh_port, &handle);
+    let _handle = lifecycle_manager.spawn("ssh-probe", move |_| async move {
+        // Automatically shuts down the port forwarder when this scope ends
+        let _handle_guard = HandleGuard::from(handle);
+
+        let result = async {
+            let socket_addr = ssh_local_socket_addr_receiver.await.map_err(|_| {
+                error::GenericSnafu { message: "SSH local socket address receiver failed" }.build()
+            })?;
+            ssh::Session::connect_accepting_any_host_key(ssh_private_key, user, socket_addr)
+                .await?
+                .close()
+                .await?;
+            Ok::<_, Error>(())
+        }
+        .await;
+        match result {
+            Ok(()) => ExitStatus::Success,
+            Err(err) => ExitStatus::Error(err),
+        }
+    });
+
+    lifecycle_manager
+        .serve()
+        .await
+        .map_err(|err| error::GenericSnafu { message: err.to_string() }.build())?
+}
+
+/// Parses a file mode given in octal, e.g. `600`.
+fn parse_file_mode(mode: &str) -> Result<u32, String> {
+    u32::from_str_radix(mode, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| format!("'{mode}' is not an octal file mode"))
+}
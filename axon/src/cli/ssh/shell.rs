@@ -11,14 +11,22 @@ use clap::{ArgAction, Args};
 use k8s_openapi::api::core::v1::Pod;
 use kube::Api;
 use sigfinn::{ExitStatus, LifecycleManager};
+use snafu::{OptionExt, ResultExt};
+use tokio::io::AsyncWriteExt;
 
 use crate::{
     cli::{
         Error, error,
-        internal::{ApiPodExt, ResolvedResources, ResourceResolver},
-        ssh::internal::{Configurator, DEFAULT_SSH_PORT, HandleGuard, setup_port_forwarding},
+        internal::{
+            ApiPodExt, RemoteEnv, ResolvedResources, ResourceResolver, change_dir_in_command_line,
+            export_to_command_line, output, parse_workdir, signal, to_command_line,
+        },
+        ssh::internal::{
+            Configurator, DEFAULT_SSH_PORT, HandleGuard, connect_within, setup_port_forwarding,
+        },
     },
     config::Config,
+    consts::DEFAULT_POD_READY_TIMEOUT,
     ext::PodExt,
     ssh,
     ui::terminal::TerminalRawModeGuard,
@@ -53,24 +61,39 @@ pub struct ShellCommand {
 
     /// The maximum time in seconds to wait for the pod to be running before
     /// timing out.
+    ///
+    /// Defaults to the global `--pod-ready-timeout`, or to 15 seconds.
     #[arg(
         short = 't',
         long = "timeout-seconds",
-        default_value = "15",
-        help = "The maximum time in seconds to wait for the pod to be running before timing out."
+        help = "The maximum time in seconds to wait for the pod to be running before timing out. \
+                Defaults to the global --pod-ready-timeout, or 15 seconds."
+    )]
+    pub timeout_secs: Option<u64>,
+
+    /// The maximum time to establish the SSH session, independent of the pod
+    /// running-status timeout.
+    #[arg(
+        long = "connect-timeout",
+        value_parser = humantime::parse_duration,
+        help = "Maximum time the SSH handshake and authentication may take, such as `10s`. \
+                Independent of the pod running-status timeout. If not specified, the connection \
+                is not limited."
     )]
-    pub timeout_secs: u64,
+    pub connect_timeout: Option<Duration>,
 
-    /// Path to the SSH private key file for authentication.
-    /// If not specified, Axon will look for `sshPrivateKeyFilePath` in the
-    /// configuration.
+    /// Paths to SSH private key files for authentication, tried in order before
+    /// `sshPrivateKeyFilePath` of the configuration.
     #[arg(
         short = 'i',
         long = "ssh-private-key-file",
-        help = "Path to the SSH private key file for authentication. If not specified, Axon will \
-                look for `sshPrivateKeyFilePath` in the configuration."
+        visible_alias = "identity",
+        value_name = "SSH_PRIVATE_KEY_FILE",
+        help = "Path to an SSH private key file for authentication. Can be repeated to try \
+                several keys in order, before `sshPrivateKeyFilePath` of the configuration. If \
+                neither is set, ~/.ssh/id_ed25519, ~/.ssh/id_rsa and ~/.ssh/id_ecdsa are tried."
     )]
-    pub ssh_private_key_file: Option<PathBuf>,
+    pub ssh_private_key_files: Vec<PathBuf>,
 
     /// User name to connect as via SSH on the remote pod.
     #[arg(
@@ -81,13 +104,72 @@ pub struct ShellCommand {
     )]
     pub user: String,
 
+    /// Disable pseudo-terminal allocation, like `ssh -T`.
+    #[arg(
+        short = 'T',
+        long = "no-tty",
+        help = "Disable pseudo-terminal allocation, like ssh -T. The stdout and stderr of the \
+                command are kept apart and free of terminal control codes, which suits capturing \
+                the output of non-interactive commands."
+    )]
+    pub no_tty: bool,
+
+    /// Name of a bastion pod to reach the target pod through, like `ssh -J`.
+    #[arg(
+        short = 'J',
+        long = "jump",
+        value_name = "POD",
+        help = "Name of a bastion pod to reach the target pod through, like ssh -J. The SSH \
+                session to the target pod is tunneled through an SSH session to the bastion pod, \
+                which must be able to reach the IP address of the target pod."
+    )]
+    pub jump: Option<String>,
+
+    /// Environment variables to set for the command, as `KEY=VALUE`.
+    #[arg(
+        short = 'e',
+        long = "remote-env",
+        value_name = "KEY=VALUE",
+        help = "An environment variable to set for the command, as KEY=VALUE. Can be repeated. \
+                The variable is exported by the remote shell before the command runs, as sshd \
+                usually rejects variables sent with SetEnv."
+    )]
+    pub remote_envs: Vec<RemoteEnv>,
+
+    /// The absolute path of the directory to execute the command in.
+    #[arg(
+        short = 'w',
+        long = "workdir",
+        value_name = "PATH",
+        value_parser = parse_workdir,
+        help = "The absolute path of the directory to execute the command in, instead of the \
+                login directory."
+    )]
+    pub workdir: Option<String>,
+
+    /// Keep the port forwarding running after the SSH session ends, until
+    /// Ctrl-C is pressed.
+    #[arg(
+        long = "keep-forward",
+        conflicts_with = "jump",
+        help = "Keep the port forwarding to the SSH server of the pod running after the session \
+                ends, and print its local address to stdout, even with --quiet, so that another \
+                axon ssh or a plain ssh client can reconnect through it without setting it up \
+                again. Runs until Ctrl-C is pressed, and is only useful as long as the pod keeps \
+                running."
+    )]
+    pub keep_forward: bool,
+
     /// The command and its arguments to execute as the interactive SSH shell.
-    /// If not specified, Axon will attempt to detect the shell.
+    /// If not specified, the interactive shell of the pod is used. Everything
+    /// after `--` is passed to the pod verbatim.
     #[arg(
         action = ArgAction::Append,
-        default_value = "/bin/zsh",
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
         help = "The command and its arguments to execute as the interactive SSH shell. \
-                If not specified, Axon will attempt to detect the shell."
+                If not specified, the interactive shell of the pod is used. Everything after \
+                `--` is passed to the pod verbatim, e.g. `axon ssh shell -- ls -la`."
     )]
     pub command: Vec<String>,
 }
@@ -103,11 +185,14 @@ impl ShellCommand {
     ///    timeout.
     /// 4. Determines the remote SSH port and the command to execute on the pod.
     /// 5. Uploads the SSH public key to the pod for authentication.
-    /// 6. Sets up port forwarding to the pod's SSH service.
+    /// 6. Sets up port forwarding to the pod's SSH service. With `jump`, the
+    ///    SSH public key is uploaded to the bastion pod as well, and the port
+    ///    forwarding ends at the bastion pod's SSH service instead.
     /// 7. Spawns an SSH client runner task to establish and manage the SSH
     ///    session.
     /// 8. Manages the lifecycle of the port forwarding and SSH client, handling
-    ///    potential errors.
+    ///    potential errors. With `keep_forward`, the port forwarding outlives a
+    ///    successful SSH session until a shutdown signal is received.
     ///
     /// # Arguments
     ///
@@ -122,8 +207,10 @@ impl ShellCommand {
     ///
     /// This function can return an `Error` in the following cases:
     /// * If the SSH key pair cannot be loaded.
-    /// * If the target pod cannot be found or does not reach a running state
-    ///   within the timeout.
+    /// * If the target pod or the bastion pod cannot be found or does not reach
+    ///   a running state within the timeout.
+    /// * If the target pod has no IP address to reach it at from the bastion
+    ///   pod.
     /// * If the SSH public key cannot be uploaded to the pod.
     /// * If port forwarding setup fails.
     /// * If the SSH client fails to connect or execute the command.
@@ -138,34 +225,62 @@ impl ShellCommand {
     /// returns `Ok(Err(err))` and `lifecycle_manager.serve()` itself returns
     /// `Err`.
     pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
-        let Self { namespace, pod_name, timeout_secs, ssh_private_key_file, user, command } = self;
+        let Self {
+            namespace,
+            pod_name,
+            timeout_secs,
+            connect_timeout,
+            ssh_private_key_files,
+            user,
+            no_tty,
+            jump,
+            remote_envs,
+            workdir,
+            keep_forward,
+            command,
+        } = self;
 
         // Resolve Identity
         let ResolvedResources { namespace, pod_name } =
             ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name);
 
         let (ssh_private_key, ssh_public_key) = ssh::resolve_ssh_key_pair(
-            [ssh_private_key_file.as_ref(), config.ssh_private_key_file_path.as_ref()]
-                .iter()
-                .flatten(),
+            ssh_private_key_files.iter().chain(&config.ssh_private_key_file_path),
         )
         .await?;
 
+        let pod_ready_timeout = config.pod_ready_timeout(timeout_secs, DEFAULT_POD_READY_TIMEOUT);
         let api = Api::<Pod>::namespaced(kube_client, &namespace);
-        let pod = api
-            .await_running_status(&pod_name, &namespace, Duration::from_secs(timeout_secs))
-            .await?;
+        let pod = api.await_running_status(&pod_name, &namespace, pod_ready_timeout).await?;
         let remote_port = pod.service_ports().ssh.unwrap_or(DEFAULT_SSH_PORT);
         let remote_command = if command.is_empty() { pod.interactive_shell() } else { command };
 
-        Configurator::new(api.clone(), &namespace, &pod_name)
-            .upload_ssh_key(ssh_public_key)
+        let _report = Configurator::new(api.clone(), &namespace, &pod_name)
+            .upload_ssh_key(&ssh_public_key)
             .await?;
 
+        // With a bastion pod, the port forwarding ends at the bastion pod, which then
+        // connects to the target pod
+        let (forward_pod_name, forward_port, jump_target) = if let Some(bastion_name) = jump {
+            let pod_ip = pod
+                .status
+                .and_then(|status| status.pod_ip)
+                .context(error::PodHasNoIpSnafu { namespace: &namespace, pod_name: &pod_name })?;
+            let bastion =
+                api.await_running_status(&bastion_name, &namespace, pod_ready_timeout).await?;
+            let _report = Configurator::new(api.clone(), &namespace, &bastion_name)
+                .upload_ssh_key(&ssh_public_key)
+                .await?;
+            let bastion_port = bastion.service_ports().ssh.unwrap_or(DEFAULT_SSH_PORT);
+            (bastion_name, bastion_port, Some((pod_ip, remote_port)))
+        } else {
+            (pod_name, remote_port, None)
+        };
+
         let lifecycle_manager = LifecycleManager::<Error>::new();
         let handle = lifecycle_manager.handle();
         let ssh_local_socket_addr_receiver =
-            setup_port_forwarding(api, pod_name, remote_port, &handle);
+            setup_port_forwarding(api, forward_pod_name, forward_port, &handle);
         let _handle = lifecycle_manager.spawn("ssh-client", move |_| async move {
             let socket_addr = match ssh_local_socket_addr_receiver.await {
                 Ok(a) => a,
@@ -180,8 +295,14 @@ impl ShellCommand {
             let result = SshClientRunner {
                 handle,
                 socket_addr,
+                connect_timeout,
                 ssh_private_key,
                 user,
+                jump_target,
+                tty: !no_tty,
+                remote_envs,
+                workdir,
+                keep_forward,
                 command: remote_command,
             }
             .run()
@@ -192,7 +313,7 @@ impl ShellCommand {
             }
         });
 
-        if let Ok(Err(err)) = lifecycle_manager.serve().await {
+        if let Ok(Err(err)) = signal::serve(lifecycle_manager).await {
             tracing::error!("{err}");
             Err(err)
         } else {
@@ -212,10 +333,23 @@ struct SshClientRunner {
     /// The local socket address to connect to for the SSH session,
     /// typically established via port forwarding.
     socket_addr: SocketAddr,
+    /// The maximum time to establish each SSH session, if limited.
+    connect_timeout: Option<Duration>,
     /// The SSH private key used for authentication with the remote host.
     ssh_private_key: russh::keys::PrivateKey,
     /// The username to use for the SSH connection.
     user: String,
+    /// The address and SSH port of the target pod, if `socket_addr` leads to
+    /// a bastion pod to reach it through.
+    jump_target: Option<(String, u16)>,
+    /// Whether to allocate a pseudo-terminal for the command.
+    tty: bool,
+    /// The environment variables to export before executing the command.
+    remote_envs: Vec<RemoteEnv>,
+    /// The directory to execute the command in, if not the login directory.
+    workdir: Option<String>,
+    /// Whether to keep the port forwarder running after a successful session.
+    keep_forward: bool,
     /// The command and its arguments to execute on the remote host.
     command: Vec<String>,
 }
@@ -226,13 +360,20 @@ impl SshClientRunner {
     ///
     /// This function performs the following actions:
     /// 1. Creates a `HandleGuard` to ensure the associated port forwarder is
-    ///    shut down when this runner's scope ends.
+    ///    shut down when this runner's scope ends. With `keep_forward`, the
+    ///    guard is disarmed once the session has ended successfully, and the
+    ///    local address of the port forwarder is printed to stdout instead,
+    ///    even with `--quiet`.
     /// 2. Establishes an SSH session to the `socket_addr` using the provided
-    ///    private key and user.
-    /// 3. Enters terminal raw mode to correctly handle interactive SSH shell
-    ///    input/output.
-    /// 4. Escapes the command arguments and joins them into a single string for
-    ///    execution.
+    ///    private key and user. With a `jump_target`, this session is to the
+    ///    bastion pod, and a second session to the target pod is tunneled
+    ///    through it.
+    /// 3. Escapes the command arguments and joins them into a single string for
+    ///    execution, prefixed with an `export` statement per remote variable
+    ///    and a `cd` to the working directory.
+    /// 4. With a TTY, enters terminal raw mode to correctly handle interactive
+    ///    SSH shell input/output. Without one, captures the output of the
+    ///    command instead.
     /// 5. Executes the command on the remote SSH session.
     /// 6. Attempts to gracefully close the SSH session.
     /// 7. Returns any error encountered during command execution or session
@@ -251,30 +392,165 @@ impl SshClientRunner {
     /// * If setting up terminal raw mode fails.
     /// * If executing the remote command fails.
     /// * If closing the SSH session fails.
+    /// * If the address of the kept port forwarding cannot be written to
+    ///   stdout.
     async fn run(self) -> Result<(), Error> {
-        let Self { handle, socket_addr, ssh_private_key, user, command } = self;
+        let Self {
+            handle,
+            socket_addr,
+            connect_timeout,
+            ssh_private_key,
+            user,
+            jump_target,
+            tty,
+            remote_envs,
+            workdir,
+            keep_forward,
+            command,
+        } = self;
 
         // Automatically shuts down the port forwarder when this scope ends
-        let _handle_guard = HandleGuard::from(handle);
-
-        let session = ssh::Session::connect(ssh_private_key, user, socket_addr).await?;
+        let handle_guard = HandleGuard::from(handle);
 
-        // Enter raw mode to handle TTY interactions correctly
-        let _raw_mode_guard = TerminalRawModeGuard::setup()?;
+        let first_session = connect_within(
+            connect_timeout,
+            ssh::Session::connect_accepting_any_host_key(
+                ssh_private_key.clone(),
+                user.clone(),
+                socket_addr,
+            ),
+        )
+        .await?;
+        let (session, bastion_session) = if let Some((host, port)) = jump_target {
+            let result = connect_within(
+                connect_timeout,
+                connect_through(
+                    &first_session,
+                    &host,
+                    port,
+                    socket_addr,
+                    ssh_private_key,
+                    user.clone(),
+                ),
+            )
+            .await;
+            match result {
+                Ok(session) => (session, Some(first_session)),
+                Err(err) => {
+                    // The error of the jump matters more than that of closing the bastion session
+                    drop(first_session.close().await);
+                    return Err(err);
+                }
+            }
+        } else {
+            (first_session, None)
+        };
 
-        let escaped_command = command
-            .into_iter()
-            .map(|x| shell_escape::escape(x.into()))
-            .collect::<Vec<_>>()
-            .join(" ");
+        let escaped_command = export_to_command_line(
+            &remote_envs,
+            &change_dir_in_command_line(workdir.as_deref(), &to_command_line(&command)),
+        );
 
-        let call_result = session.call(&escaped_command).await;
+        let call_result = if tty {
+            // Enter raw mode to handle TTY interactions correctly
+            let _raw_mode_guard = TerminalRawModeGuard::setup()?;
+            session.call(&escaped_command).await.map_err(Error::from)
+        } else {
+            exec_without_tty(&session, &escaped_command).await
+        };
 
-        // Attempt to close the session cleanly
-        let close_result = session.close().await;
+        // Attempt to close the sessions cleanly, the one to the bastion pod last
+        let mut close_result = session.close().await;
+        if let Some(bastion_session) = bastion_session {
+            close_result = close_result.and(bastion_session.close().await);
+        }
 
         // Return the execution error if it exists, otherwise the closing error
-        call_result.map(|_| ()).map_err(Error::from)?;
-        close_result.map_err(Error::from)
+        let outcome = call_result?;
+        tracing::debug!("Remote command {outcome}");
+        close_result?;
+
+        if keep_forward {
+            handle_guard.disarm();
+            // The address is what the flag is for, so it is not subject to `--quiet`
+            let mut stdout = tokio::io::stdout();
+            stdout
+                .write_all(format!("{socket_addr}\n").as_bytes())
+                .await
+                .context(error::WriteStdoutSnafu)?;
+            stdout.flush().await.context(error::WriteStdoutSnafu)?;
+            output::status(format_args!(
+                "Port forwarding kept running at {socket_addr}, reconnect with `ssh -p {} \
+                 {user}@{}`. Press Ctrl-C to stop it.",
+                socket_addr.port(),
+                socket_addr.ip()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Connects an SSH session to `host:port` through an SSH session to a bastion
+/// pod, like `ssh -J` does.
+async fn connect_through(
+    bastion_session: &ssh::Session,
+    host: &str,
+    port: u16,
+    originator: SocketAddr,
+    ssh_private_key: russh::keys::PrivateKey,
+    user: String,
+) -> Result<ssh::Session, ssh::Error> {
+    let stream = bastion_session.open_direct_tcpip(host, port, originator).await?;
+    ssh::Session::connect_stream(ssh_private_key, user, stream, None).await
+}
+
+/// Executes `command` without a pseudo-terminal and writes its captured
+/// stdout and stderr to the local stdout and stderr.
+async fn exec_without_tty(
+    session: &ssh::Session,
+    command: &str,
+) -> Result<ssh::CommandOutcome, Error> {
+    let ssh::CommandOutput { outcome, stdout, stderr } = session.exec_capture(command).await?;
+    tokio::io::stdout().write_all(&stdout).await.context(error::WriteStdoutSnafu)?;
+    tokio::io::stderr().write_all(&stderr).await.context(error::WriteStderrSnafu)?;
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        shell: ShellCommand,
+    }
+
+    #[test]
+    fn test_command_is_passed_verbatim() {
+        let Cli { shell } =
+            Cli::try_parse_from(["axon", "-p", "my-pod", "--", "rm", "-rf", "/tmp/x"]).unwrap();
+        assert_eq!(shell.command, ["rm", "-rf", "/tmp/x"]);
+        assert_eq!(to_command_line(&shell.command), "rm -rf /tmp/x");
+
+        let Cli { shell } = Cli::try_parse_from(["axon", "--", "-u", "admin"]).unwrap();
+        assert_eq!(shell.command, ["-u", "admin"]);
+        assert_eq!(shell.user, "root");
+    }
+
+    #[test]
+    fn test_connect_timeout() {
+        let Cli { shell } = Cli::try_parse_from(["axon", "--connect-timeout", "10s"]).unwrap();
+        assert_eq!(shell.connect_timeout, Some(Duration::from_secs(10)));
+        assert!(Cli::try_parse_from(["axon", "--connect-timeout", "soon"]).is_err());
+    }
+
+    #[test]
+    fn test_keep_forward() {
+        let Cli { shell } = Cli::try_parse_from(["axon", "--keep-forward"]).unwrap();
+        assert!(shell.keep_forward);
+        assert!(Cli::try_parse_from(["axon", "--keep-forward", "-J", "bastion"]).is_err());
     }
 }
@@ -0,0 +1,239 @@
+//! Defines the `status` subcommand for summarizing the health of a pod.
+//!
+//! The summary gathers what `list` shows across several columns and what
+//! Axon recorded in the annotations of the pod. The command fails unless the
+//! pod is running, so that it can be used as a health gate in scripts.
+
+use std::fmt::Write as _;
+
+use clap::Args;
+use k8s_openapi::{api::core::v1::Pod, jiff::Timestamp};
+use kube::{Api, api::ListParams};
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    PROJECT_NAME,
+    cli::{
+        Error, error,
+        internal::{ResolvedResources, ResourceResolver},
+    },
+    config::Config,
+    consts::k8s::{annotations, labels},
+    ext::PodExt,
+    ui::{format_age_at, fuzzy_finder::PodListExt as _},
+};
+
+/// The phase of a running pod.
+const RUNNING_PHASE: &str = "Running";
+
+/// Represents the command to summarize the status of a pod.
+#[derive(Args, Clone)]
+pub struct StatusCommand {
+    /// Kubernetes namespace of the target pod.
+    ///
+    /// If not specified, the default namespace will be used.
+    #[arg(
+        short,
+        long,
+        help = "Kubernetes namespace of the target pod. If not specified, the default namespace \
+                will be used."
+    )]
+    pub namespace: Option<String>,
+
+    /// Name of the pod to summarize.
+    ///
+    /// If not specified, a fuzzy finder will be used to select a pod managed by
+    /// Axon.
+    #[arg(help = "Name of the pod to summarize. If not specified, a fuzzy finder will be used \
+                  to select a pod managed by Axon.")]
+    pub pod_name: Option<String>,
+}
+
+impl StatusCommand {
+    /// Executes the `status` command, printing a summary of the target pod to
+    /// stdout.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `StatusCommand` instance containing the parsed
+    ///   command-line arguments.
+    /// * `kube_client` - A Kubernetes client used to interact with the API
+    ///   server.
+    /// * `config` - The application's configuration, used for resolving
+    ///   resources.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    ///
+    /// * Listing the pods for the fuzzy finder fails, or no pod is selected in
+    ///   it (`Error::NoPodSelected`).
+    /// * The pod cannot be read.
+    /// * Writing the output to `stdout` fails.
+    /// * The pod is not running, after its summary has been printed.
+    pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
+        let Self { namespace, pod_name } = self;
+
+        // Resolve Identity
+        let ResolvedResources { namespace, .. } =
+            ResourceResolver::from((&kube_client, &config)).resolve(namespace, None);
+        let api = Api::<Pod>::namespaced(kube_client, &namespace);
+        let pod_name = if let Some(pod_name) = pod_name {
+            pod_name
+        } else {
+            let list_params = ListParams {
+                label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+                ..ListParams::default()
+            };
+            let selected = api
+                .list(&list_params)
+                .await
+                .with_context(|_| error::ListPodsWithNamespaceSnafu {
+                    namespace: namespace.clone(),
+                })?
+                .find_pods()
+                .await;
+            let Some((_, pod_name)) = selected.into_iter().next() else {
+                return error::NoPodSelectedSnafu.fail();
+            };
+            pod_name
+        };
+
+        let pod = api.get(&pod_name).await.with_context(|_| error::GetPodSnafu {
+            namespace: namespace.clone(),
+            pod_name: pod_name.clone(),
+        })?;
+        tokio::io::stdout()
+            .write_all(render_status(&pod, Timestamp::now()).as_bytes())
+            .await
+            .context(error::WriteStdoutSnafu)?;
+
+        let phase = phase(&pod);
+        snafu::ensure!(
+            phase == RUNNING_PHASE,
+            error::PodNotRunningSnafu { namespace, pod_name, phase }
+        );
+        Ok(())
+    }
+}
+
+/// Returns the phase of `pod`, or `Unknown` if it has not been reported.
+fn phase(pod: &Pod) -> &str {
+    pod.status.as_ref().and_then(|status| status.phase.as_deref()).unwrap_or("Unknown")
+}
+
+/// Renders the summary of `pod` as one `FIELD: value` line per field, with the
+/// age computed relative to `now`.
+fn render_status(pod: &Pod, now: Timestamp) -> String {
+    let metadata = &pod.metadata;
+    let annotation = |key: &str| {
+        metadata.annotations.as_ref().and_then(|annotations| annotations.get(key)).cloned()
+    };
+    let (ready, containers) = pod.ready_containers();
+    let age = metadata
+        .creation_timestamp
+        .as_ref()
+        .map_or_else(|| "<unknown>".to_string(), |time| format_age_at(time, now));
+    let service_ports = pod.service_ports();
+    let service_ports =
+        [("ssh", service_ports.ssh), ("http", service_ports.http), ("https", service_ports.https)]
+            .into_iter()
+            .filter_map(|(name, port)| port.map(|port| format!("{name}={port}")))
+            .collect::<Vec<_>>();
+
+    let fields = [
+        ("Name", metadata.name.clone().unwrap_or_default()),
+        ("Namespace", metadata.namespace.clone().unwrap_or_default()),
+        ("Phase", phase(pod).to_string()),
+        ("Ready", format!("{ready}/{containers}")),
+        ("Restarts", pod.restart_count().to_string()),
+        ("Node", pod.spec.as_ref().and_then(|spec| spec.node_name.clone()).unwrap_or_else(none)),
+        ("Age", age),
+        (
+            "Shell",
+            annotation(&annotations::SHELL_INTERACTIVE)
+                .map_or_else(none, |_| pod.interactive_shell().join(" ")),
+        ),
+        ("Axon version", annotation(&annotations::VERSION).unwrap_or_else(none)),
+        ("Service ports", if service_ports.is_empty() { none() } else { service_ports.join(", ") }),
+        (
+            "SSH setup",
+            annotation(&annotations::SSH_SETUP)
+                .map_or_else(|| "not applied".to_string(), |time| format!("applied at {time}")),
+        ),
+    ];
+    let width = fields.iter().map(|(field, _)| field.len()).max().unwrap_or_default() + 1;
+    fields.into_iter().fold(String::new(), |mut output, (field, value)| {
+        let _ = writeln!(output, "{:<width$} {value}", format!("{field}:"));
+        output
+    })
+}
+
+/// Returns the placeholder of a field without a value.
+fn none() -> String { "<none>".to_string() }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use k8s_openapi::{
+        api::core::v1::{Container, ContainerStatus, PodSpec, PodStatus},
+        apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time},
+        jiff::Timestamp,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_render_status() {
+        let container_status = |name: &str, ready, restart_count| ContainerStatus {
+            name: name.to_string(),
+            ready,
+            restart_count,
+            ..ContainerStatus::default()
+        };
+        let pod = Pod {
+            metadata: ObjectMeta {
+                name: Some("axon".to_string()),
+                namespace: Some("dev".to_string()),
+                creation_timestamp: Some(Time(Timestamp::from_second(1_000).unwrap())),
+                annotations: Some(BTreeMap::from([
+                    (annotations::VERSION.clone(), "0.1.0".to_string()),
+                    (format!("{}/ssh", *annotations::SERVICE_PORT_PREFIX), "22".to_string()),
+                ])),
+                ..ObjectMeta::default()
+            },
+            spec: Some(PodSpec {
+                containers: vec![Container::default(), Container::default()],
+                node_name: Some("worker-1".to_string()),
+                ..PodSpec::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some("Running".to_string()),
+                container_statuses: Some(vec![
+                    container_status("axon", true, 2),
+                    container_status("sidecar", false, 1),
+                ]),
+                ..PodStatus::default()
+            }),
+        };
+
+        let status = render_status(&pod, Timestamp::from_second(1_000 + 300).unwrap());
+        for line in [
+            "Name:          axon",
+            "Namespace:     dev",
+            "Phase:         Running",
+            "Ready:         1/2",
+            "Restarts:      3",
+            "Node:          worker-1",
+            "Age:           5m",
+            "Shell:         <none>",
+            "Axon version:  0.1.0",
+            "Service ports: ssh=22",
+            "SSH setup:     not applied",
+        ] {
+            assert!(status.lines().any(|status_line| status_line == line), "missing {line}");
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Defines the `top` subcommand for showing the resource usage of pods.
+//!
+//! Usage is queried from the resource metrics API (`metrics.k8s.io`), which is
+//! served by metrics-server, and shown next to the resource requests and
+//! limits of the pods.
+
+use std::collections::HashMap;
+
+use clap::Args;
+use k8s_openapi::api::core::v1::Pod;
+use kube::{Api, api::ListParams};
+use snafu::ResultExt;
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    PROJECT_NAME,
+    cli::{
+        Error, error,
+        internal::{ResolvedResources, ResourceResolver},
+    },
+    config::Config,
+    consts::k8s::labels,
+    metrics::{PodMetrics, PodUsage},
+    ui::table::PodUsageExt,
+};
+
+/// Represents the command to show the CPU and memory usage of pods.
+#[derive(Args, Clone)]
+pub struct TopCommand {
+    /// Kubernetes namespace of the pods.
+    ///
+    /// If not specified, the default namespace will be used.
+    #[arg(
+        short,
+        long,
+        help = "Kubernetes namespace of the pods. If not specified, the default namespace will be \
+                used."
+    )]
+    pub namespace: Option<String>,
+
+    /// Show the usage of the temporary pods in all Kubernetes namespaces.
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = ["namespace", "pod_name"],
+        help = "Show the usage of the temporary pods created by Axon across all Kubernetes \
+                namespaces."
+    )]
+    pub all_namespaces: bool,
+
+    /// Name of the pod to show the usage of.
+    ///
+    /// Any pod in the namespace can be targeted by its exact name. If not
+    /// specified, all pods managed by Axon are shown.
+    #[arg(help = "Name of the pod to show the usage of. Any pod in the namespace can be \
+                  targeted by its exact name. If not specified, all pods managed by Axon are \
+                  shown.")]
+    pub pod_name: Option<String>,
+}
+
+impl TopCommand {
+    /// Executes the `top` command, rendering the CPU and memory usage of the
+    /// selected pods next to their requests and limits.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `TopCommand` instance containing the parsed command-line
+    ///   arguments.
+    /// * `kube_client` - A Kubernetes client used to interact with the API
+    ///   server.
+    /// * `config` - The application's configuration, used for resolving
+    ///   resources.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    ///
+    /// * The pods cannot be retrieved from the Kubernetes API.
+    /// * The resource metrics API is not available, typically because
+    ///   metrics-server is not installed in the cluster.
+    /// * The pod metrics cannot be listed for any other reason.
+    /// * Writing the output to `stdout` fails.
+    pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
+        let Self { namespace, all_namespaces, pod_name } = self;
+
+        // Resolve Identity
+        let ResolvedResources { namespace, .. } =
+            ResourceResolver::from((&kube_client, &config)).resolve(namespace, None);
+
+        let managed_by = ListParams {
+            label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+            ..ListParams::default()
+        };
+        let (pods, metrics) = if all_namespaces {
+            let pods = Api::<Pod>::all(kube_client.clone())
+                .list(&managed_by)
+                .await
+                .context(error::ListPodsSnafu)?
+                .items;
+            let metrics = Api::<PodMetrics>::all(kube_client).list(&managed_by).await;
+            (pods, metrics)
+        } else if let Some(pod_name) = pod_name {
+            let pod = Api::<Pod>::namespaced(kube_client.clone(), &namespace)
+                .get(&pod_name)
+                .await
+                .with_context(|_| error::GetPodSnafu {
+                    namespace: namespace.clone(),
+                    pod_name: pod_name.clone(),
+                })?;
+            // Listing rather than getting the metrics tells a missing metrics API
+            // apart from a pod without metrics yet
+            let list_params = ListParams {
+                field_selector: Some(format!("metadata.name={pod_name}")),
+                ..ListParams::default()
+            };
+            let metrics =
+                Api::<PodMetrics>::namespaced(kube_client, &namespace).list(&list_params).await;
+            (vec![pod], metrics)
+        } else {
+            let pods = Api::<Pod>::namespaced(kube_client.clone(), &namespace)
+                .list(&managed_by)
+                .await
+                .with_context(|_| error::ListPodsWithNamespaceSnafu {
+                    namespace: namespace.clone(),
+                })?
+                .items;
+            let metrics =
+                Api::<PodMetrics>::namespaced(kube_client, &namespace).list(&managed_by).await;
+            (pods, metrics)
+        };
+
+        let metrics = match metrics {
+            Ok(metrics) => metrics.items,
+            Err(kube::Error::Api(status)) if status.is_not_found() || status.code == 503 => {
+                return error::MetricsApiUnavailableSnafu.fail();
+            }
+            Err(source) => return Err(source).context(error::ListPodMetricsSnafu),
+        };
+        let metrics = metrics
+            .iter()
+            .map(|metrics| {
+                ((metrics.metadata.namespace.as_deref(), metrics.metadata.name.as_deref()), metrics)
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut usages = pods
+            .iter()
+            .map(|pod| {
+                let key = (pod.metadata.namespace.as_deref(), pod.metadata.name.as_deref());
+                PodUsage::new(pod, metrics.get(&key).copied())
+            })
+            .collect::<Vec<_>>();
+        usages.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+
+        let mut stdout = tokio::io::stdout();
+        stdout
+            .write_all(usages.render_table().as_bytes())
+            .await
+            .context(error::WriteStdoutSnafu)?;
+        stdout.write_u8(b'\n').await.context(error::WriteStdoutSnafu)
+    }
+}
@@ -0,0 +1,187 @@
+//! Defines the `wait` subcommand for blocking until a pod meets a condition.
+//!
+//! Like `kubectl wait`, the command exits successfully once the condition is
+//! met and with the timeout exit code otherwise, which decouples waiting from
+//! `create` and `attach` in scripts.
+
+use std::{fmt, io::IsTerminal, time::Duration};
+
+use clap::{Args, ValueEnum};
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    Api,
+    api::ListParams,
+    runtime::{
+        conditions::is_pod_running,
+        wait::{Condition, await_condition},
+    },
+};
+use snafu::ResultExt;
+
+use crate::{
+    PROJECT_NAME,
+    cli::{
+        Error, error,
+        internal::{ResolvedResources, ResourceResolver, is_pod_deleted, is_pod_ready, output},
+    },
+    config::Config,
+    consts::{DEFAULT_WAIT_TIMEOUT, k8s::labels},
+    ui::fuzzy_finder::PodListExt as _,
+};
+
+/// The condition of a pod to wait for.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum WaitCondition {
+    /// The pod is in the `Running` phase.
+    #[default]
+    Running,
+    /// The pod reports the `Ready` condition.
+    Ready,
+    /// The pod no longer exists.
+    Deleted,
+}
+
+impl fmt::Display for WaitCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Running => "running",
+            Self::Ready => "ready",
+            Self::Deleted => "deleted",
+        })
+    }
+}
+
+/// Represents the command to wait until a pod meets a condition.
+#[derive(Args, Clone)]
+pub struct WaitCommand {
+    /// Kubernetes namespace of the target pod.
+    ///
+    /// If not specified, the default namespace will be used.
+    #[arg(
+        short,
+        long,
+        help = "Kubernetes namespace of the target pod. If not specified, the default namespace \
+                will be used."
+    )]
+    pub namespace: Option<String>,
+
+    /// Name of the pod to wait for.
+    ///
+    /// If not specified and stdin is a terminal, a fuzzy finder will be used to
+    /// select a pod managed by Axon. Otherwise the default pod is used.
+    #[arg(help = "Name of the pod to wait for. If not specified and stdin is a terminal, a \
+                  fuzzy finder will be used to select a pod managed by Axon; otherwise the \
+                  default pod is used.")]
+    pub pod_name: Option<String>,
+
+    /// The condition to wait for.
+    #[arg(
+        long = "for",
+        value_enum,
+        default_value_t,
+        help = "The condition to wait for: the pod is running, ready, or deleted."
+    )]
+    pub condition: WaitCondition,
+
+    /// The maximum time to wait for the condition.
+    ///
+    /// Defaults to the global `--pod-ready-timeout`, or to 30 seconds.
+    #[arg(
+        long = "timeout",
+        value_parser = humantime::parse_duration,
+        help = "The maximum time to wait for the condition (e.g. '60s', '2m'). Defaults to the \
+                global --pod-ready-timeout, or 30 seconds."
+    )]
+    pub timeout: Option<Duration>,
+}
+
+impl WaitCommand {
+    /// Executes the `wait` command, returning once the target pod meets the
+    /// condition.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The `WaitCommand` instance containing the parsed command-line
+    ///   arguments.
+    /// * `kube_client` - A Kubernetes client used to interact with the API
+    ///   server.
+    /// * `config` - The application's configuration, used for resolving
+    ///   resources and the default timeout.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    ///
+    /// * Listing the pods for the fuzzy finder fails, or no pod is selected in
+    ///   it (`Error::NoPodSelected`).
+    /// * Watching the pod fails.
+    /// * The condition is not met within the timeout.
+    pub async fn run(self, kube_client: kube::Client, config: Config) -> Result<(), Error> {
+        let Self { namespace, pod_name, condition, timeout } = self;
+        let timeout = timeout.or(config.timeout).unwrap_or(DEFAULT_WAIT_TIMEOUT);
+
+        // Resolve Identity
+        let ResolvedResources { namespace, pod_name } =
+            if pod_name.is_none() && std::io::stdin().is_terminal() {
+                let list_params = ListParams {
+                    label_selector: Some(format!("{}={PROJECT_NAME}", labels::MANAGED_BY)),
+                    ..ListParams::default()
+                };
+                let ResolvedResources { namespace, .. } =
+                    ResourceResolver::from((&kube_client, &config)).resolve(namespace, None);
+                let selected = Api::<Pod>::namespaced(kube_client.clone(), &namespace)
+                    .list(&list_params)
+                    .await
+                    .with_context(|_| error::ListPodsWithNamespaceSnafu {
+                        namespace: namespace.clone(),
+                    })?
+                    .find_pods()
+                    .await;
+                let Some((_, pod_name)) = selected.into_iter().next() else {
+                    return error::NoPodSelectedSnafu.fail();
+                };
+                ResolvedResources { namespace, pod_name }
+            } else {
+                ResourceResolver::from((&kube_client, &config)).resolve(namespace, pod_name)
+            };
+
+        let api = Api::<Pod>::namespaced(kube_client, &namespace);
+        match condition {
+            WaitCondition::Running => {
+                wait_for(api, &namespace, &pod_name, condition, is_pod_running(), timeout).await?;
+            }
+            WaitCondition::Ready => {
+                wait_for(api, &namespace, &pod_name, condition, is_pod_ready(), timeout).await?;
+            }
+            WaitCondition::Deleted => {
+                wait_for(api, &namespace, &pod_name, condition, is_pod_deleted(), timeout).await?;
+            }
+        }
+        output::status(format_args!("pod/{pod_name} condition met"));
+        Ok(())
+    }
+}
+
+/// Waits until the pod `pod_name` meets `predicate`, the implementation of
+/// `condition`, for at most `timeout`.
+async fn wait_for(
+    api: Api<Pod>,
+    namespace: &str,
+    pod_name: &str,
+    condition: WaitCondition,
+    predicate: impl Condition<Pod> + Send + Sync,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let _maybe_pod = tokio::time::timeout(timeout, await_condition(api, pod_name, predicate))
+        .await
+        .map_err(|_| Error::WaitForPodCondition {
+            namespace: namespace.to_string(),
+            pod_name: pod_name.to_string(),
+            condition: condition.to_string(),
+        })?
+        .with_context(|_| error::GetPodStatusSnafu {
+            namespace: namespace.to_string(),
+            pod_name: pod_name.to_string(),
+        })?;
+    Ok(())
+}
@@ -25,14 +25,43 @@ pub enum Error {
     /// Error returned when the content of the configuration file specified by
     /// `filename` fails to be parsed (e.g., due to invalid YAML syntax).
     ///
+    /// An unknown field is reported with its location and, if one is close
+    /// enough, the valid field the user most likely meant.
+    ///
     /// # Arguments
     ///
     /// * `filename` - The path to the configuration file that failed to parse.
     /// * `source` - The underlying [`serde_yaml::Error`] that occurred during
     ///   parsing.
-    #[snafu(display("Failed to parse config from {}, error: {source}", filename.display()))]
+    #[snafu(display(
+        "Failed to parse config from {}, error: {}",
+        filename.display(),
+        describe_parse_error(source)
+    ))]
     ParseConfig { filename: PathBuf, source: serde_yaml::Error },
 
+    /// Error returned when a configuration file is not written because it
+    /// already exists.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The path to the existing configuration file.
+    #[snafu(display(
+        "Config file {} already exists, use --force to overwrite it",
+        filename.display()
+    ))]
+    ConfigExists { filename: PathBuf },
+
+    /// Error returned when a configuration file or its directory fails to be
+    /// written.
+    ///
+    /// # Arguments
+    ///
+    /// * `filename` - The path to the configuration file.
+    /// * `source` - The underlying [`std::io::Error`] that occurred.
+    #[snafu(display("Failed to write config to {}, error: {source}", filename.display()))]
+    WriteConfig { filename: PathBuf, source: std::io::Error },
+
     /// Error returned when a file path cannot be resolved to its canonical
     /// form. This might happen if the path does not exist or if there are
     /// insufficient permissions to access it.
@@ -44,4 +73,152 @@ pub enum Error {
     ///   resolution.
     #[snafu(display("Failed to resolve file path {}, error: {source}", file_path.display()))]
     ResolveFilePath { file_path: PathBuf, source: std::io::Error },
+
+    /// Error returned when the image of a spec contains a placeholder that is
+    /// not a known variable.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image containing the placeholder.
+    /// * `variable` - The name of the unknown variable.
+    #[snafu(display(
+        "Unknown variable '{{{variable}}}' in image '{image}', expected '{{arch}}' or '{{os}}'"
+    ))]
+    UnknownImageVariable { image: String, variable: String },
+
+    /// Error returned when the image of a spec contains a `{` that is not
+    /// closed.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The image containing the unclosed placeholder.
+    #[snafu(display("Unclosed placeholder in image '{image}'"))]
+    UnclosedImageVariable { image: String },
+
+    /// Error returned when the selected profile is not defined in the
+    /// configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `profile` - The name of the selected profile.
+    /// * `available` - The names of the defined profiles.
+    #[snafu(display(
+        "Profile '{profile}' is not defined in the configuration, available profiles: {available}"
+    ))]
+    ProfileNotFound { profile: String, available: String },
+}
+
+/// Describes a parse error of the configuration.
+///
+/// `serde` reports an unknown field as ``unknown field `x`, expected one of
+/// `a`, `b` ``, which is hard to read for structs with many fields. Such an
+/// error is rewritten to point at the location of the field and suggest the
+/// closest valid field; any other error is described as is.
+fn describe_parse_error(error: &serde_yaml::Error) -> String {
+    let message = error.to_string();
+    let Some((prefix, rest)) = message.split_once("unknown field `") else {
+        return message;
+    };
+    let Some((field, expected)) = rest.split_once('`') else {
+        return message;
+    };
+    let Some(suggestion) = suggest_field(field, expected.split('`').skip(1).step_by(2)) else {
+        return message;
+    };
+    let location = error.location().map_or_else(String::new, |location| {
+        format!(" at line {} column {}", location.line(), location.column())
+    });
+    format!("{prefix}unknown field `{field}`{location} — did you mean `{suggestion}`?")
+}
+
+/// Returns the candidate closest to `field`, if it is close enough to be a
+/// plausible typo of it, i.e. within an edit distance of a third of its
+/// length.
+fn suggest_field<'a>(
+    field: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = (field.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            (edit_distance(&field.to_lowercase(), &candidate.to_lowercase()), candidate)
+        })
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Computes the optimal string alignment distance between `a` and `b`: the
+/// number of insertions, deletions, substitutions and transpositions of
+/// adjacent characters needed to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.chars().collect::<Vec<_>>(), b.chars().collect::<Vec<_>>());
+    let mut rows = vec![(0..=b.len()).collect::<Vec<_>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1).min(row[j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        for (a, b, expected) in [
+            ("", "", 0),
+            ("spec", "", 4),
+            ("defaultSpec", "defaultSpec", 0),
+            ("defualtSpec", "defaultSpec", 1),
+            ("specz", "specs", 1),
+            ("timeout", "timeoutSeconds", 7),
+            ("kitten", "sitting", 3),
+        ] {
+            assert_eq!(edit_distance(a, b), expected, "{a} -> {b}");
+        }
+    }
+
+    #[test]
+    fn test_suggest_field() {
+        let fields = ["defaultPodName", "defaultSpec", "log", "specs", "proxyRules"];
+        assert_eq!(suggest_field("defualtSpec", fields), Some("defaultSpec"));
+        assert_eq!(suggest_field("DefaultPodname", fields), Some("defaultPodName"));
+        assert_eq!(suggest_field("spec", fields), Some("specs"));
+        assert_eq!(suggest_field("lgo", fields), Some("log"));
+        assert_eq!(suggest_field("containers", fields), None);
+        assert_eq!(suggest_field("x", []), None);
+    }
+
+    #[test]
+    fn test_describe_parse_error() {
+        #[derive(Debug, serde::Deserialize)]
+        #[serde(deny_unknown_fields, rename_all = "camelCase")]
+        #[expect(dead_code, reason = "only deserialized")]
+        struct Config {
+            default_spec: Option<String>,
+            specs: Option<Vec<String>>,
+        }
+
+        let describe =
+            |yaml: &str| describe_parse_error(&serde_yaml::from_str::<Config>(yaml).unwrap_err());
+        assert_eq!(
+            describe("specs: []\n\ndefualtSpec: basic\n"),
+            "unknown field `defualtSpec` at line 3 column 1 — did you mean `defaultSpec`?"
+        );
+        assert_eq!(
+            describe("containers: []\n"),
+            "unknown field `containers`, expected `defaultSpec` or `specs`"
+        );
+        assert!(describe("specs: [").starts_with("did not find expected"));
+    }
 }
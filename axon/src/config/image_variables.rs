@@ -0,0 +1,94 @@
+//! Defines `ImageVariables`, the placeholders available in the images of a
+//! spec.
+//!
+//! An image such as `my-repo/tools:latest-{arch}` is rendered when a pod is
+//! created, so that a single spec can target several platforms. Placeholders
+//! that are not known are an error instead of being left in the image.
+
+use snafu::OptionExt;
+
+use crate::config::{Error, error};
+
+/// The values of the placeholders of spec images.
+///
+/// # Fields
+///
+/// - `arch`: The CPU architecture, named like in image platforms (`amd64`,
+///   `arm64`, ...), substituted for `{arch}`.
+/// - `os`: The operating system, named like in image platforms (`linux`,
+///   `darwin`, ...), substituted for `{os}`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImageVariables {
+    /// The value of `{arch}`.
+    pub arch: String,
+
+    /// The value of `{os}`.
+    pub os: String,
+}
+
+impl ImageVariables {
+    /// Returns the variables of the local platform, with `arch` overridden
+    /// if given.
+    pub fn local(arch: Option<String>) -> Self {
+        let local_arch = match std::env::consts::ARCH {
+            "x86_64" => "amd64",
+            "aarch64" => "arm64",
+            "x86" => "386",
+            "powerpc64" => "ppc64le",
+            arch => arch,
+        };
+        let os = match std::env::consts::OS {
+            "macos" => "darwin",
+            os => os,
+        };
+        Self { arch: arch.unwrap_or_else(|| local_arch.to_string()), os: os.to_string() }
+    }
+
+    /// Substitutes the placeholders of `image`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if `image` contains an unknown placeholder or a `{`
+    /// that is not closed.
+    pub fn render(&self, image: &str) -> Result<String, Error> {
+        let mut rendered = String::with_capacity(image.len());
+        let mut rest = image;
+        while let Some((literal, placeholder)) = rest.split_once('{') {
+            let (variable, tail) =
+                placeholder.split_once('}').context(error::UnclosedImageVariableSnafu { image })?;
+            rendered.push_str(literal);
+            rendered.push_str(match variable {
+                "arch" => &self.arch,
+                "os" => &self.os,
+                _ => return error::UnknownImageVariableSnafu { image, variable }.fail(),
+            });
+            rest = tail;
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let variables = ImageVariables { arch: "arm64".to_string(), os: "linux".to_string() };
+        assert_eq!(variables.render("alpine:3.23").unwrap(), "alpine:3.23");
+        assert_eq!(variables.render("tools:latest-{arch}").unwrap(), "tools:latest-arm64");
+        assert_eq!(variables.render("tools-{os}:{os}-{arch}").unwrap(), "tools-linux:linux-arm64");
+
+        assert!(matches!(
+            variables.render("tools:{version}"),
+            Err(Error::UnknownImageVariable { variable, .. }) if variable == "version"
+        ));
+        assert!(matches!(
+            variables.render("tools:{arch"),
+            Err(Error::UnclosedImageVariable { .. })
+        ));
+
+        assert_eq!(ImageVariables::local(Some("s390x".to_string())).arch, "s390x");
+    }
+}
@@ -0,0 +1,43 @@
+//! Defines the `InitContainer` structure, which describes a setup step run
+//! before the main container of a pod starts.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ImagePullPolicy;
+
+/// Represents an init container to run to completion before the main
+/// container of a pod starts.
+///
+/// Init containers are useful for setup steps, such as installing and
+/// configuring `sshd` in a shared volume, that must finish before the main
+/// container runs.
+///
+/// # Fields
+///
+/// - `name`: The name of the init container.
+/// - `image`: The Docker image to use for the init container.
+/// - `image_pull_policy`: Defines when the Docker image should be pulled.
+/// - `command`: The command to execute inside the init container.
+/// - `args`: Additional arguments to pass to the command.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InitContainer {
+    /// The name of the init container. Must be unique within the pod.
+    pub name: String,
+
+    /// The Docker image to use for the init container.
+    pub image: String,
+
+    /// Defines when the Docker image should be pulled.
+    #[serde(default)]
+    pub image_pull_policy: ImagePullPolicy,
+
+    /// The command to execute inside the init container. Defaults to the
+    /// entrypoint of the image.
+    #[serde(default)]
+    pub command: Vec<String>,
+
+    /// Additional arguments to pass to the command.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
@@ -1,10 +1,13 @@
 //! Configuration and initialization for application logging.
 //!
 //! This module provides the `LogConfig` struct for defining logging
-//! preferences, such as output targets (stdout, stderr, journald, file) and log
+//! preferences, such as output targets (stdout, stderr, journald, syslog,
+//! file) and log
 //! level. It also includes the `LogDriver` enum and associated logic for
-//! creating `tracing` layers based on the configured `LogConfig`.
-use std::{fs::OpenOptions, path::PathBuf};
+//! creating `tracing` layers based on the configured `LogConfig`. When every
+//! driver is disabled, no subscriber is installed at all, so that the `tracing`
+//! macros only check the absent subscriber and return.
+use std::{fs::OpenOptions, io::IsTerminal, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_with::{DisplayFromStr, serde_as};
@@ -12,14 +15,17 @@ use tracing_subscriber::{
     Layer, layer::SubscriberExt, registry::LookupSpan, util::SubscriberInitExt,
 };
 
+use crate::config::{SyslogFacility, syslog::SyslogWriter};
+
 /// Represents the configuration for the application's logging system.
 ///
 /// This struct allows specifying where log messages should be emitted (e.g.,
-/// stdout, stderr, journald, or a file) and at what level (e.g., INFO, DEBUG).
-/// It integrates with `serde` for easy serialization and deserialization from
-/// configuration sources.
+/// stdout, stderr, journald, syslog, or a file) and at what level (e.g., INFO,
+/// DEBUG). It integrates with `serde` for easy serialization and
+/// deserialization from configuration sources.
 #[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize)]
+#[expect(clippy::struct_excessive_bools, reason = "Every bool toggles an independent log driver")]
 pub struct LogConfig {
     /// Optional path to a file where logs should be written.
     /// If `None`, logs will not be written to a file.
@@ -30,6 +36,15 @@ pub struct LogConfig {
     #[serde(default = "LogConfig::default_emit_journald")]
     pub emit_journald: bool,
 
+    /// A boolean indicating whether logs should be emitted to the local syslog
+    /// daemon, for hosts without `journald`.
+    #[serde(default = "LogConfig::default_emit_syslog")]
+    pub emit_syslog: bool,
+
+    /// The syslog facility logs are emitted with.
+    #[serde(default)]
+    pub syslog_facility: SyslogFacility,
+
     /// A boolean indicating whether logs should be emitted to standard output.
     #[serde(default = "LogConfig::default_emit_stdout")]
     pub emit_stdout: bool,
@@ -54,6 +69,8 @@ impl Default for LogConfig {
         Self {
             file_path: Self::default_file_path(),
             emit_journald: Self::default_emit_journald(),
+            emit_syslog: Self::default_emit_syslog(),
+            syslog_facility: SyslogFacility::default(),
             emit_stdout: Self::default_emit_stdout(),
             emit_stderr: Self::default_emit_stderr(),
             level: Self::default_log_level(),
@@ -77,6 +94,11 @@ impl LogConfig {
     #[must_use]
     pub const fn default_emit_journald() -> bool { false }
 
+    /// Returns the default setting for `emit_syslog`, which is `false`.
+    #[inline]
+    #[must_use]
+    pub const fn default_emit_syslog() -> bool { false }
+
     /// Returns the default setting for `emit_stdout`, which is `false`.
     #[inline]
     #[must_use]
@@ -87,26 +109,59 @@ impl LogConfig {
     #[must_use]
     pub const fn default_emit_stderr() -> bool { true }
 
+    /// Disables every log driver, e.g. for `--no-log`.
+    pub fn disable_all(&mut self) {
+        self.file_path = None;
+        self.emit_journald = false;
+        self.emit_syslog = false;
+        self.emit_stdout = false;
+        self.emit_stderr = false;
+    }
+
+    /// Returns whether any log driver is enabled.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.file_path.is_some()
+            || self.emit_journald
+            || self.emit_syslog
+            || self.emit_stdout
+            || self.emit_stderr
+    }
+
     /// Initializes the global `tracing` subscriber registry based on this
     /// `LogConfig`.
     ///
     /// This method sets up the logging infrastructure, directing logs to the
-    /// specified outputs (journald, file, stdout, stderr) and applying the
-    /// configured log level.
+    /// specified outputs (journald, syslog, file, stdout, stderr) and applying
+    /// the configured log level. Nothing is initialized if no driver is
+    /// enabled.
     ///
     /// # Panics
     ///
     /// This method panics if called more than once in the same application
-    /// lifetime, as `tracing_subscriber::util::SubscriberInitExt::init()`
-    /// will panic if a global subscriber is already set.
+    /// lifetime with a driver enabled, as
+    /// `tracing_subscriber::util::SubscriberInitExt::init()` will panic if a
+    /// global subscriber is already set.
     pub fn registry(&self) {
-        let Self { emit_journald, file_path, emit_stdout, emit_stderr, level: log_level } = self;
+        if !self.is_enabled() {
+            return;
+        }
+        let Self {
+            emit_journald,
+            emit_syslog,
+            syslog_facility,
+            file_path,
+            emit_stdout,
+            emit_stderr,
+            level: log_level,
+        } = self;
 
         let filter_layer = tracing_subscriber::filter::LevelFilter::from_level(*log_level);
 
         tracing_subscriber::registry()
             .with(filter_layer)
             .with(emit_journald.then(|| LogDriver::Journald.layer()))
+            .with(emit_syslog.then(|| LogDriver::Syslog(*syslog_facility).layer()))
             .with(file_path.clone().map(|path| LogDriver::File(path).layer()))
             .with(emit_stdout.then(|| LogDriver::Stdout.layer()))
             .with(emit_stderr.then(|| LogDriver::Stderr.layer()))
@@ -126,6 +181,9 @@ enum LogDriver {
     Stderr,
     /// Logs will be written to the system's `journald` service.
     Journald,
+    /// Logs will be written to the local syslog daemon with the given
+    /// facility.
+    Syslog(SyslogFacility),
     /// Logs will be written to a specified file path.
     File(PathBuf),
 }
@@ -134,7 +192,9 @@ impl LogDriver {
     /// Creates a `tracing_subscriber::Layer` for the specific log driver.
     ///
     /// This method configures a `tracing` layer that directs formatted log
-    /// messages to the output specified by the `LogDriver` variant.
+    /// messages to the output specified by the `LogDriver` variant. Colors are
+    /// only used for stdout and stderr, and only while they are attached to a
+    /// terminal, so that log files contain plain text.
     ///
     /// # Type Parameters
     ///
@@ -154,6 +214,8 @@ impl LogDriver {
     ///   appending or creation.
     /// - For `LogDriver::Journald`, `tracing_journald::layer()` fails to
     ///   initialize the layer.
+    /// - For `LogDriver::Syslog`, the socket of the syslog daemon is not
+    ///   available.
     #[expect(
         clippy::type_repetition_in_bounds,
         reason = "Trait bounds require both Subscriber and LookupSpan for tracing-subscriber \
@@ -170,13 +232,68 @@ impl LogDriver {
 
         // Configure the writer based on the desired log target:
         match self {
-            Self::Stdout => Some(Box::new(fmt.with_writer(std::io::stdout))),
-            Self::Stderr => Some(Box::new(fmt.with_writer(std::io::stderr))),
+            Self::Stdout => Some(Box::new(
+                fmt.with_ansi(std::io::stdout().is_terminal()).with_writer(std::io::stdout),
+            )),
+            Self::Stderr => Some(Box::new(
+                fmt.with_ansi(std::io::stderr().is_terminal()).with_writer(std::io::stderr),
+            )),
             Self::File(path) => {
                 let file = OpenOptions::new().create(true).append(true).open(path).ok()?;
-                Some(Box::new(fmt.with_writer(file)))
+                Some(Box::new(fmt.with_ansi(false).with_writer(file)))
             }
             Self::Journald => Some(Box::new(tracing_journald::layer().ok()?)),
+            Self::Syslog(facility) => {
+                // Syslog adds its own timestamp and expects one line per message
+                let writer = SyslogWriter::connect(facility)?;
+                Some(Box::new(
+                    tracing_subscriber::fmt::layer()
+                        .compact()
+                        .with_ansi(false)
+                        .without_time()
+                        .with_level(false)
+                        .with_writer(writer),
+                ))
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disable_all() {
+        let mut config = LogConfig {
+            file_path: Some(PathBuf::from("axon.log")),
+            emit_journald: true,
+            emit_syslog: true,
+            emit_stdout: true,
+            ..LogConfig::default()
+        };
+        assert!(config.is_enabled());
+        config.disable_all();
+        assert!(!config.is_enabled());
+        assert_eq!(config.file_path, None);
+
+        // Without a subscriber, the macros are disabled for every level
+        config.registry();
+        assert!(!tracing::enabled!(tracing::Level::ERROR));
+    }
+
+    #[test]
+    fn test_file_layer_without_ansi() {
+        let path = std::env::temp_dir().join(format!("axon-log-test-{}.log", std::process::id()));
+        let _unused = std::fs::remove_file(&path);
+        let layer = LogDriver::File(path.clone()).layer().unwrap();
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(layer), || {
+            tracing::warn!(pod_name = "axon", "Pod is not ready");
+        });
+
+        let logs = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(logs.contains("Pod is not ready"), "{logs}");
+        assert!(!logs.contains('\x1b'), "{logs}");
+    }
+}
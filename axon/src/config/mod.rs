@@ -4,23 +4,59 @@
 //! configuration, including default pod names, specifications, SSH keys, and
 //! logging settings. It also provides utilities to locate the configuration
 //! file and retrieve specific specifications.
+//!
+//! # Precedence
+//!
+//! Every setting is taken from the first of these sources that provides it:
+//!
+//! | Setting                 | CLI                           | Environment variable        | Configuration file      |
+//! |-------------------------|-------------------------------|-----------------------------|-------------------------|
+//! | Configuration file path | `--config`                    | `AXON_CONFIG_FILE_PATH`     |                         |
+//! | Profile                 | `--profile`                   | `AXON_PROFILE`              |                         |
+//! | Log level               | `--log-level`                 | `AXON_LOG_LEVEL`            | `log.level`             |
+//! | Log drivers             | `--no-log`                    |                             | `log`                   |
+//! | Pod name                | `--pod-name` or `POD_NAME`    | `AXON_DEFAULT_POD_NAME`     | `defaultPodName`        |
+//! | Namespace of `create`   | `--namespace`                 |                             | `namespace` of the spec |
+//! | Spec of `create`        | `create preset SPEC_NAME`     | `AXON_DEFAULT_SPEC`         | `defaultSpec`           |
+//! | SSH private key file    | `--ssh-private-key-file`      | `AXON_SSH_PRIVATE_KEY_FILE` | `sshPrivateKeyFilePath` |
+//! | Pod timeout             | `--pod-ready-timeout`         |                             | `timeoutSeconds`        |
+//!
+//! The settings of the selected profile take precedence over the rest of the
+//! configuration file. Settings given by none of them fall back to their
+//! built-in default, or for the namespace to the one of the current
+//! Kubernetes context.
 
 mod error;
 mod image_pull_policy;
+mod image_variables;
+mod init_container;
 mod log;
 mod port_mapping;
+mod probe;
+mod profile;
+mod proxy_rule;
 mod service_ports;
+mod sidecar;
 mod spec;
+mod spread;
+mod syslog;
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use resolve_path::PathResolveExt;
 use serde::{Deserialize, Serialize};
-use snafu::ResultExt;
+use serde_with::{DurationSeconds, serde_as};
+use snafu::{IntoError, ResultExt};
 
 pub use self::{
-    error::Error, image_pull_policy::ImagePullPolicy, log::LogConfig, port_mapping::PortMapping,
-    service_ports::ServicePorts, spec::Spec,
+    error::Error, image_pull_policy::ImagePullPolicy, image_variables::ImageVariables,
+    init_container::InitContainer, log::LogConfig, port_mapping::PortMapping, probe::Probe,
+    profile::Profile, proxy_rule::ProxyRule, service_ports::ServicePorts, sidecar::Sidecar,
+    spec::Spec, spread::Spread, syslog::SyslogFacility,
 };
 use crate::{
     CLI_CONFIG_NAME, PROJECT_CONFIG_DIR, PROJECT_NAME, consts::DEFAULT_POD_NAME,
@@ -32,6 +68,7 @@ use crate::{
 /// This struct holds various settings such as the default pod name,
 /// default specification, SSH private key path, logging configuration,
 /// and a list of defined specifications.
+#[serde_as]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase", deny_unknown_fields)]
 pub struct Config {
@@ -46,6 +83,13 @@ pub struct Config {
     /// An optional path to the SSH private key file to be used for connections.
     pub ssh_private_key_file_path: Option<PathBuf>,
 
+    /// The default time to wait for a pod to be running, used by commands
+    /// whose `--timeout-seconds` is not given. Overridden by the global
+    /// `--pod-ready-timeout` flag.
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    #[serde(default, rename = "timeoutSeconds", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+
     /// Configuration for application logging.
     #[serde(default)]
     pub log: LogConfig,
@@ -54,9 +98,43 @@ pub struct Config {
     /// configurations.
     #[serde(default)]
     pub specs: Vec<Spec>,
+
+    /// The forwarding rules started by the `proxy` command when none are
+    /// given on the command line.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub proxy_rules: Vec<ProxyRule>,
+
+    /// Named profiles that can be selected with `--profile` to overlay the
+    /// settings above.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for Config {
+    /// Creates the configuration used when no configuration file exists, with
+    /// the default pod name and spec, and no specs or proxy rules.
+    fn default() -> Self {
+        Self {
+            default_pod_name: default_pod_name(),
+            default_spec: default_spec(),
+            ssh_private_key_file_path: None,
+            timeout: None,
+            log: LogConfig::default(),
+            specs: Vec::new(),
+            proxy_rules: Vec::new(),
+            profiles: BTreeMap::new(),
+        }
+    }
 }
 
 impl Config {
+    /// The environment variable overriding `default_pod_name`.
+    pub const ENV_DEFAULT_POD_NAME: &str = "AXON_DEFAULT_POD_NAME";
+    /// The environment variable overriding `default_spec`.
+    pub const ENV_DEFAULT_SPEC: &str = "AXON_DEFAULT_SPEC";
+    /// The environment variable overriding `ssh_private_key_file_path`.
+    pub const ENV_SSH_PRIVATE_KEY_FILE: &str = "AXON_SSH_PRIVATE_KEY_FILE";
+
     /// Searches for the application configuration file in various predefined
     /// locations.
     ///
@@ -190,6 +268,138 @@ impl Config {
         Ok(config)
     }
 
+    /// Overlays the profile `name` onto the configuration. The settings the
+    /// profile defines replace those of the base configuration, and specs are
+    /// merged by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProfileNotFoundSnafu` if no profile is named `name`, or
+    /// `ResolveFilePathSnafu` if the SSH private key path of the profile cannot
+    /// be resolved.
+    pub fn apply_profile(&mut self, name: &str) -> Result<(), Error> {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let available = self.profiles.keys().map(String::as_str).collect::<Vec<_>>();
+            return error::ProfileNotFoundSnafu {
+                profile: name,
+                available: if available.is_empty() {
+                    "<none>".to_string()
+                } else {
+                    available.join(", ")
+                },
+            }
+            .fail();
+        };
+        let Profile {
+            default_pod_name,
+            default_spec,
+            ssh_private_key_file_path,
+            timeout,
+            specs,
+            proxy_rules,
+        } = profile;
+
+        if let Some(default_pod_name) = default_pod_name {
+            self.default_pod_name = default_pod_name;
+        }
+        if let Some(default_spec) = default_spec {
+            self.default_spec = default_spec;
+        }
+        if let Some(file_path) = ssh_private_key_file_path {
+            let resolved = file_path
+                .try_resolve()
+                .map(|path| path.to_path_buf())
+                .with_context(|_| error::ResolveFilePathSnafu { file_path: file_path.clone() })?;
+            self.ssh_private_key_file_path = Some(resolved);
+        }
+        if timeout.is_some() {
+            self.timeout = timeout;
+        }
+        for spec in specs {
+            if let Some(base) = self.specs.iter_mut().find(|base| base.name == spec.name) {
+                *base = spec;
+            } else {
+                self.specs.push(spec);
+            }
+        }
+        if let Some(proxy_rules) = proxy_rules {
+            self.proxy_rules = proxy_rules;
+        }
+        Ok(())
+    }
+
+    /// Overrides the settings of the configuration file with the environment
+    /// variables `AXON_DEFAULT_POD_NAME`, `AXON_DEFAULT_SPEC` and
+    /// `AXON_SSH_PRIVATE_KEY_FILE`, looked up with `var`. Variables that are
+    /// unset or empty are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ResolveFilePathSnafu` if the path of
+    /// `AXON_SSH_PRIVATE_KEY_FILE` cannot be resolved.
+    pub fn apply_env_overrides<F>(&mut self, var: F) -> Result<(), Error>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+        if let Some(pod_name) = var(Self::ENV_DEFAULT_POD_NAME) {
+            self.default_pod_name = pod_name;
+        }
+        if let Some(spec) = var(Self::ENV_DEFAULT_SPEC) {
+            self.default_spec = spec;
+        }
+        if let Some(file_path) = var(Self::ENV_SSH_PRIVATE_KEY_FILE).map(PathBuf::from) {
+            let resolved = file_path
+                .try_resolve()
+                .map(|path| path.to_path_buf())
+                .with_context(|_| error::ResolveFilePathSnafu { file_path: file_path.clone() })?;
+            self.ssh_private_key_file_path = Some(resolved);
+        }
+        Ok(())
+    }
+
+    /// Loads the application configuration like [`Config::load`], but returns
+    /// the default configuration if the file does not exist, so that Axon
+    /// works without any configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the configuration file.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Config::load`], except `OpenConfig` for a file
+    /// that does not exist. A file that cannot be read for another reason,
+    /// e.g. its permissions, or that is malformed is still an error.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        match Self::load(path) {
+            Err(Error::OpenConfig { filename, source })
+                if source.kind() == std::io::ErrorKind::NotFound =>
+            {
+                tracing::debug!("No configuration at {}, using defaults", filename.display());
+                Ok(Self::default())
+            }
+            result => result,
+        }
+    }
+
+    /// Resolves the time to wait for a pod to be running.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout_secs` - The timeout given to the command, in seconds, which
+    ///   takes precedence.
+    /// * `fallback` - The default of the command, used when neither the command
+    ///   nor the configuration sets a timeout.
+    ///
+    /// # Returns
+    ///
+    /// The timeout given to the command, or else the configured default
+    /// `timeout`, or else `fallback`.
+    pub fn pod_ready_timeout(&self, timeout_secs: Option<u64>, fallback: Duration) -> Duration {
+        timeout_secs.map_or_else(|| self.timeout.unwrap_or(fallback), Duration::from_secs)
+    }
+
     /// Finds and returns the default `Spec` based on the `default_spec` field.
     ///
     /// If a `Spec` with a matching name is found in the `specs` list, it is
@@ -209,8 +419,11 @@ impl Config {
     ///     default_pod_name: "my-pod".to_string(),
     ///     default_spec: "custom-spec".to_string(),
     ///     ssh_private_key_file_path: None,
+    ///     timeout: None,
     ///     log: Default::default(),
     ///     specs: vec![Spec { name: "custom-spec".to_string(), ..Default::default() }],
+    ///     proxy_rules: Vec::new(),
+    ///     profiles: Default::default(),
     /// };
     ///
     /// let default_spec: Spec = config.find_default_spec();
@@ -244,11 +457,14 @@ impl Config {
     ///     default_pod_name: "my-pod".to_string(),
     ///     default_spec: "my-spec".to_string(),
     ///     ssh_private_key_file_path: None,
+    ///     timeout: None,
     ///     log: Default::default(),
     ///     specs: vec![
     ///         Spec { name: "my-spec".to_string(), ..Default::default() },
     ///         Spec { name: "another-spec".to_string(), ..Default::default() },
     ///     ],
+    ///     proxy_rules: Vec::new(),
+    ///     profiles: Default::default(),
     /// };
     ///
     /// let found_spec: Option<Spec> = config.find_spec_by_name("my-spec");
@@ -262,6 +478,38 @@ impl Config {
         self.specs.iter().find(|img| img.name == name).cloned()
     }
 
+    /// Writes the basic configuration template to `path`, creating its parent
+    /// directories.
+    ///
+    /// An existing file is only replaced if `force` is `true`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ConfigExistsSnafu` if `path` exists and `force` is `false`, or
+    /// `WriteConfigSnafu` if the directories or the file cannot be written.
+    pub fn write_template<P: AsRef<Path>>(path: P, force: bool) -> Result<(), Error> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .with_context(|_| error::WriteConfigSnafu { filename: path.to_path_buf() })?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(force)
+            .create_new(!force)
+            .open(path)
+            .map_err(|source| {
+                if source.kind() == std::io::ErrorKind::AlreadyExists {
+                    error::ConfigExistsSnafu { filename: path.to_path_buf() }.build()
+                } else {
+                    error::WriteConfigSnafu { filename: path.to_path_buf() }.into_error(source)
+                }
+            })?;
+        std::io::Write::write_all(&mut file, &Self::template_basic())
+            .with_context(|_| error::WriteConfigSnafu { filename: path.to_path_buf() })
+    }
+
     /// Provides a basic YAML template for the application's configuration.
     ///
     /// This template can be used as a starting point for creating a new
@@ -295,10 +543,145 @@ fn default_spec() -> String { PROJECT_NAME.to_string() }
 
 #[cfg(test)]
 mod tests {
-    use super::Config;
+    use std::path::PathBuf;
+
+    use super::{Config, Error};
 
     #[test]
     fn test_templates() {
         let _basic = serde_yaml::from_slice::<Config>(&Config::template_basic()).unwrap();
     }
+
+    #[test]
+    fn test_load_or_default() {
+        let dir = std::env::temp_dir().join(format!("axon-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let config = Config::load_or_default(dir.join("missing.yaml")).unwrap();
+        assert_eq!(config.default_pod_name, Config::default().default_pod_name);
+        assert!(config.specs.is_empty());
+        assert!(matches!(Config::load(dir.join("missing.yaml")), Err(Error::OpenConfig { .. })));
+
+        let malformed = dir.join("malformed.yaml");
+        std::fs::write(&malformed, "specs: [unclosed").unwrap();
+        assert!(matches!(Config::load_or_default(&malformed), Err(Error::ParseConfig { .. })));
+
+        let unknown_field = dir.join("unknown-field.yaml");
+        std::fs::write(&unknown_field, "defaultPodNmae: axon\n").unwrap();
+        assert!(matches!(Config::load_or_default(&unknown_field), Err(Error::ParseConfig { .. })));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_profile() {
+        let yaml = r"
+defaultPodName: base
+defaultSpec: basic
+sshPrivateKeyFilePath: /keys/base
+timeoutSeconds: 10
+specs:
+  - name: basic
+    image: alpine
+  - name: tools
+    image: busybox
+profiles:
+  dev:
+    defaultSpec: tools
+    specs:
+      - name: tools
+        image: nicolaka/netshoot
+  staging:
+    defaultPodName: staging
+    sshPrivateKeyFilePath: /keys/staging
+    timeoutSeconds: 60
+";
+        let base = serde_yaml::from_str::<Config>(yaml).unwrap();
+
+        let mut dev = base.clone();
+        dev.apply_profile("dev").unwrap();
+        assert_eq!(dev.default_pod_name, "base");
+        assert_eq!(dev.default_spec, "tools");
+        assert_eq!(dev.ssh_private_key_file_path, Some(PathBuf::from("/keys/base")));
+        assert_eq!(dev.specs.len(), 2);
+        assert_eq!(dev.find_default_spec().image, "nicolaka/netshoot");
+
+        let mut staging = base.clone();
+        staging.apply_profile("staging").unwrap();
+        assert_eq!(staging.default_pod_name, "staging");
+        assert_eq!(staging.default_spec, "basic");
+        assert_eq!(staging.ssh_private_key_file_path, Some(PathBuf::from("/keys/staging")));
+        assert_eq!(staging.timeout, Some(std::time::Duration::from_mins(1)));
+
+        let mut config = base;
+        let err = config.apply_profile("prod").unwrap_err();
+        assert!(matches!(err, Error::ProfileNotFound { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Profile 'prod' is not defined in the configuration, available profiles: dev, staging"
+        );
+    }
+
+    #[test]
+    fn test_write_template() {
+        let dir = std::env::temp_dir().join(format!("axon-template-test-{}", std::process::id()));
+        let path = dir.join("nested").join("config.yaml");
+
+        Config::write_template(&path, false).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), Config::template_basic());
+        let _config = Config::load(&path).unwrap();
+
+        std::fs::write(&path, "edited").unwrap();
+        assert!(matches!(Config::write_template(&path, false), Err(Error::ConfigExists { .. })));
+        assert_eq!(std::fs::read(&path).unwrap(), b"edited");
+
+        Config::write_template(&path, true).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), Config::template_basic());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_apply_env_overrides() {
+        let file = Config {
+            default_pod_name: "from-file".to_string(),
+            default_spec: "file-spec".to_string(),
+            ssh_private_key_file_path: Some(PathBuf::from("/keys/file")),
+            ..Config::default()
+        };
+        let apply = |vars: &[(&str, &str)]| {
+            let mut config = file.clone();
+            config
+                .apply_env_overrides(|name| {
+                    vars.iter().find(|(var, _)| *var == name).map(|(_, value)| value.to_string())
+                })
+                .unwrap();
+            config
+        };
+
+        let config = apply(&[]);
+        assert_eq!(config.default_pod_name, "from-file");
+        assert_eq!(config.default_spec, "file-spec");
+        assert_eq!(config.ssh_private_key_file_path, Some(PathBuf::from("/keys/file")));
+
+        let config = apply(&[(Config::ENV_DEFAULT_POD_NAME, "from-env")]);
+        assert_eq!(config.default_pod_name, "from-env");
+        assert_eq!(config.default_spec, "file-spec");
+
+        let config = apply(&[(Config::ENV_DEFAULT_SPEC, "env-spec")]);
+        assert_eq!(config.default_spec, "env-spec");
+        assert_eq!(config.default_pod_name, "from-file");
+
+        let config = apply(&[(Config::ENV_SSH_PRIVATE_KEY_FILE, "/keys/env")]);
+        assert_eq!(config.ssh_private_key_file_path, Some(PathBuf::from("/keys/env")));
+
+        let config = apply(&[(Config::ENV_DEFAULT_POD_NAME, ""), (Config::ENV_DEFAULT_SPEC, "")]);
+        assert_eq!(config.default_pod_name, "from-file");
+        assert_eq!(config.default_spec, "file-spec");
+
+        let mut config = Config::default();
+        config.apply_env_overrides(|_| None).unwrap();
+        assert_eq!(config.default_pod_name, Config::default().default_pod_name);
+        assert_eq!(config.ssh_private_key_file_path, None);
+    }
 }
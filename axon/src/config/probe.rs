@@ -0,0 +1,147 @@
+//! Defines the `Probe` structure, which describes how Kubernetes checks that
+//! the container of a spec is ready or alive.
+//!
+//! A probe is written like its Kubernetes counterpart, with exactly one of
+//! `httpGet`, `tcpSocket` or `exec` and the usual timing fields, e.g.
+//!
+//! ```yaml
+//! readinessProbe:
+//!   tcpSocket:
+//!     port: 22
+//!   periodSeconds: 2
+//! ```
+
+use k8s_openapi::{api::core::v1 as core, apimachinery::pkg::util::intstr::IntOrString};
+use serde::{Deserialize, Serialize};
+
+/// Represents a readiness or liveness probe of a container.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Probe {
+    /// The check performed by the probe.
+    #[serde(flatten)]
+    pub action: ProbeAction,
+
+    /// The number of seconds after the container has started before the
+    /// first check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_delay_seconds: Option<i32>,
+
+    /// How often, in seconds, the check is performed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub period_seconds: Option<i32>,
+
+    /// The number of seconds after which a check times out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<i32>,
+
+    /// The number of consecutive successful checks for the probe to succeed
+    /// after having failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success_threshold: Option<i32>,
+
+    /// The number of consecutive failed checks for the probe to fail.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_threshold: Option<i32>,
+}
+
+/// Represents the check performed by a `Probe`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProbeAction {
+    /// An HTTP `GET` request, which succeeds with a status code from 200 to
+    /// 399.
+    HttpGet {
+        /// The path of the request. Defaults to `/`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+        /// The port of the container the request is sent to.
+        port: u16,
+    },
+    /// A TCP connection, which succeeds once it is established.
+    TcpSocket {
+        /// The port of the container to connect to.
+        port: u16,
+    },
+    /// A command run inside the container, which succeeds if it exits with
+    /// status 0.
+    Exec {
+        /// The command and its arguments.
+        command: Vec<String>,
+    },
+}
+
+impl Probe {
+    /// Creates a probe that connects to `port` of the container every two
+    /// seconds.
+    pub const fn tcp_socket(port: u16) -> Self {
+        Self {
+            action: ProbeAction::TcpSocket { port },
+            initial_delay_seconds: None,
+            period_seconds: Some(2),
+            timeout_seconds: None,
+            success_threshold: None,
+            failure_threshold: None,
+        }
+    }
+
+    /// Converts the probe into a Kubernetes `Probe`.
+    pub fn to_kubernetes_probe(&self) -> core::Probe {
+        let mut probe = core::Probe {
+            initial_delay_seconds: self.initial_delay_seconds,
+            period_seconds: self.period_seconds,
+            timeout_seconds: self.timeout_seconds,
+            success_threshold: self.success_threshold,
+            failure_threshold: self.failure_threshold,
+            ..core::Probe::default()
+        };
+        match &self.action {
+            ProbeAction::HttpGet { path, port } => {
+                probe.http_get = Some(core::HTTPGetAction {
+                    path: path.clone(),
+                    port: IntOrString::Int(i32::from(*port)),
+                    ..core::HTTPGetAction::default()
+                });
+            }
+            ProbeAction::TcpSocket { port } => {
+                probe.tcp_socket = Some(core::TCPSocketAction {
+                    port: IntOrString::Int(i32::from(*port)),
+                    ..core::TCPSocketAction::default()
+                });
+            }
+            ProbeAction::Exec { command } => {
+                probe.exec = Some(core::ExecAction { command: Some(command.clone()) });
+            }
+        }
+        probe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_kubernetes_probe() {
+        let probe: Probe = serde_yaml::from_str(
+            "httpGet:\n  path: /healthz\n  port: 8080\nperiodSeconds: 5\nfailureThreshold: 3\n",
+        )
+        .unwrap();
+        let probe = probe.to_kubernetes_probe();
+        let http_get = probe.http_get.unwrap();
+        assert_eq!(http_get.path.as_deref(), Some("/healthz"));
+        assert_eq!(http_get.port, IntOrString::Int(8080));
+        assert_eq!((probe.period_seconds, probe.failure_threshold), (Some(5), Some(3)));
+        assert_eq!(probe.initial_delay_seconds, None);
+
+        let probe: Probe = serde_yaml::from_str("exec:\n  command: [pg_isready]\n").unwrap();
+        assert_eq!(probe.to_kubernetes_probe().exec.unwrap().command.unwrap(), ["pg_isready"]);
+
+        let probe = Probe::tcp_socket(22).to_kubernetes_probe();
+        assert_eq!(probe.tcp_socket.unwrap().port, IntOrString::Int(22));
+        assert_eq!(probe.http_get, None);
+
+        assert!(serde_yaml::from_str::<Probe>("periodSeconds: 5\n").is_err());
+        assert!(serde_yaml::from_str::<Probe>("grpc:\n  port: 9090\n").is_err());
+    }
+}
@@ -0,0 +1,49 @@
+//! Named sections of the configuration that overlay the base configuration.
+//!
+//! This module defines the `Profile` struct, which lets a single configuration
+//! file hold the settings of several environments, e.g. `dev` and `staging`.
+//! A profile selected with `--profile` or `AXON_PROFILE` replaces the settings
+//! it defines and inherits all others from the base configuration.
+
+use std::{path::PathBuf, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_with::{DurationSeconds, serde_as};
+
+use crate::config::{ProxyRule, Spec};
+
+/// Represents the settings of a named profile.
+///
+/// Every field is optional, and a field that is not set inherits the value of
+/// the base configuration.
+#[serde_as]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Profile {
+    /// The default name to use for new pods.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_pod_name: Option<String>,
+
+    /// The name of the default `Spec`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_spec: Option<String>,
+
+    /// The path to the SSH private key file to be used for connections.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_private_key_file_path: Option<PathBuf>,
+
+    /// The default time to wait for a pod to be running.
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    #[serde(default, rename = "timeoutSeconds", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Duration>,
+
+    /// Specifications added to those of the base configuration. A spec with
+    /// the name of a base spec replaces it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub specs: Vec<Spec>,
+
+    /// The forwarding rules of the `proxy` command, replacing those of the
+    /// base configuration.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_rules: Option<Vec<ProxyRule>>,
+}
@@ -0,0 +1,164 @@
+//! Defines the `ProxyRule` struct, a forwarding rule of the `proxy` command.
+//!
+//! A rule binds a local port to a port of a pod or a service. Rules can be
+//! listed in the `proxyRules` section of the configuration file or given on
+//! the command line as `[ADDRESS:]LOCAL_PORT:TARGET:REMOTE_PORT`.
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+
+/// A rule forwarding a local port to a port of a pod or a service.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProxyRule {
+    /// The IP address on which the `local_port` is exposed.
+    #[serde(default = "default_address")]
+    pub address: IpAddr,
+
+    /// The port number on the local host machine. `0` binds a free port
+    /// chosen by the system.
+    pub local_port: u16,
+
+    /// The target of the rule, `pod/NAME` or `svc/NAME`. A bare `NAME` refers
+    /// to a pod.
+    pub target: String,
+
+    /// The port of the pod, or of the service, to forward to.
+    pub remote_port: u16,
+
+    /// The namespace of the target. If not specified, the namespace of the
+    /// command is used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+impl FromStr for ProxyRule {
+    type Err = ProxyRuleError;
+
+    /// Parses a `ProxyRule` from a string in the format
+    /// `[ADDRESS:]LOCAL_PORT:TARGET:REMOTE_PORT`, e.g. `8080:svc/web:80`.
+    ///
+    /// The string is split from the right, so IPv6 addresses need no brackets.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ProxyRuleError` if the string does not have the expected
+    /// format, or if a port or the address is invalid.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = input.rsplitn(4, ':').collect();
+        let (remote_port, target, local_port, address) = match parts.as_slice() {
+            [remote_port, target, local_port] => (remote_port, target, local_port, None),
+            [remote_port, target, local_port, address] => {
+                (remote_port, target, local_port, Some(address))
+            }
+            _ => return InvalidFormatSnafu { input }.fail(),
+        };
+        if target.is_empty() {
+            return InvalidFormatSnafu { input }.fail();
+        }
+
+        let remote_port =
+            remote_port.parse::<u16>().context(InvalidPortSnafu { value: *remote_port })?;
+        let local_port =
+            local_port.parse::<u16>().context(InvalidPortSnafu { value: *local_port })?;
+        let address = address
+            .map(|address| {
+                address.parse::<IpAddr>().context(InvalidAddressSnafu { value: *address })
+            })
+            .transpose()?
+            .unwrap_or_else(default_address);
+
+        Ok(Self {
+            address,
+            local_port,
+            target: (*target).to_string(),
+            remote_port,
+            namespace: None,
+        })
+    }
+}
+
+/// Returns the default address of a rule, the IPv4 loopback address.
+const fn default_address() -> IpAddr { IpAddr::V4(Ipv4Addr::LOCALHOST) }
+
+/// Represents possible errors that can occur when parsing a `ProxyRule`.
+#[expect(
+    clippy::enum_variant_names,
+    reason = "Variant names intentionally verbose to match error types across crates"
+)]
+#[derive(Debug, Snafu, PartialEq, Eq)]
+#[snafu(visibility(pub))]
+pub enum ProxyRuleError {
+    /// Indicates that the input string for a `ProxyRule` had an invalid
+    /// format.
+    #[snafu(display(
+        "Invalid format: expected '[ADDRESS:]LOCAL_PORT:TARGET:REMOTE_PORT', got '{input}'"
+    ))]
+    InvalidFormat {
+        /// The input string that caused the error.
+        input: String,
+    },
+
+    /// Indicates that a port value could not be parsed as a valid `u16`.
+    #[snafu(display("Invalid port value '{value}', error: {source}"))]
+    InvalidPort {
+        /// The invalid string value that was attempted to be parsed as a port.
+        value: String,
+        /// The underlying parsing error.
+        source: std::num::ParseIntError,
+    },
+
+    /// Indicates that an IP address string could not be parsed as a valid
+    /// `IpAddr`.
+    #[snafu(display("Invalid IP address '{value}', error: {source}"))]
+    InvalidAddress {
+        /// The invalid string value that was attempted to be parsed as an IP
+        /// address.
+        value: String,
+        /// The underlying parsing error.
+        source: std::net::AddrParseError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        let rule: ProxyRule = "8080:svc/web:80".parse().expect("valid rule");
+        assert_eq!(rule.address, default_address());
+        assert_eq!(rule.local_port, 8080);
+        assert_eq!(rule.target, "svc/web");
+        assert_eq!(rule.remote_port, 80);
+
+        let rule: ProxyRule = "::1:5432:db:5432".parse().expect("valid rule");
+        assert_eq!(rule.address, "::1".parse::<IpAddr>().unwrap());
+        assert_eq!(rule.target, "db");
+    }
+
+    #[test]
+    fn test_parse_rule_errors() {
+        assert!(matches!(
+            "8080:80".parse::<ProxyRule>(),
+            Err(ProxyRuleError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            "8080::80".parse::<ProxyRule>(),
+            Err(ProxyRuleError::InvalidFormat { .. })
+        ));
+        assert!(matches!(
+            "http:svc/web:80".parse::<ProxyRule>(),
+            Err(ProxyRuleError::InvalidPort { .. })
+        ));
+        assert!(matches!(
+            "localhost:8080:svc/web:80".parse::<ProxyRule>(),
+            Err(ProxyRuleError::InvalidAddress { .. })
+        ));
+    }
+}
@@ -1,10 +1,11 @@
 //! This module defines the `ServicePorts` struct, which represents a collection
 //! of optional service ports for SSH, HTTP, and HTTPS. It provides
 //! functionality to convert between this struct and Kubernetes annotation
-//! key-value pairs.
+//! key-value pairs, and to expose the ports through a Kubernetes Service.
 
 use std::fmt;
 
+use k8s_openapi::{api::core::v1::ServicePort, apimachinery::pkg::util::intstr::IntOrString};
 use serde::{Deserialize, Serialize};
 
 use crate::consts::k8s::annotations;
@@ -159,6 +160,31 @@ impl ServicePorts {
         }
         kv
     }
+
+    /// Converts the `ServicePorts` instance into the ports of a Kubernetes
+    /// Service.
+    ///
+    /// Each defined port (ssh, http, https) is exposed over TCP under its own
+    /// name, targeting the same port on the pod.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<ServicePort>`, empty if no port is defined.
+    pub fn to_kubernetes_service_ports(&self) -> Vec<ServicePort> {
+        let Self { ssh, http, https } = self;
+        [("ssh", ssh), ("http", http), ("https", https)]
+            .into_iter()
+            .filter_map(|(name, port)| {
+                port.map(|port| ServicePort {
+                    name: Some(name.to_string()),
+                    port: i32::from(port),
+                    target_port: Some(IntOrString::Int(i32::from(port))),
+                    protocol: Some("TCP".to_string()),
+                    ..ServicePort::default()
+                })
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -209,6 +235,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_to_kubernetes_service_ports() {
+        let ports = ServicePorts { ssh: Some(2222), http: None, https: Some(8443) };
+
+        let result = ports.to_kubernetes_service_ports();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name.as_deref(), Some("ssh"));
+        assert_eq!(result[0].port, 2222);
+        assert_eq!(result[0].target_port, Some(IntOrString::Int(2222)));
+        assert_eq!(result[1].name.as_deref(), Some("https"));
+        assert_eq!(result[1].port, 8443);
+        assert_eq!(ServicePorts::default().to_kubernetes_service_ports(), Vec::new());
+    }
+
     #[test]
     fn test_round_trip() {
         // Testing that what we output can be read back in
@@ -0,0 +1,49 @@
+//! Defines the `Sidecar` structure, which describes a container run alongside
+//! the main container of a pod.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ImagePullPolicy;
+
+/// Represents an additional container running alongside the main container of
+/// a pod.
+///
+/// Sidecars are useful for debug setups that need a companion process, such
+/// as a proxy, next to the shell of the main container. The main container
+/// stays the one attached to by default, and a sidecar is reached with
+/// `--container`.
+///
+/// # Fields
+///
+/// - `name`: The name of the sidecar container.
+/// - `image`: The Docker image to use for the sidecar container.
+/// - `image_pull_policy`: Defines when the Docker image should be pulled.
+/// - `command`: The command to execute inside the sidecar container.
+/// - `args`: Additional arguments to pass to the command.
+/// - `ports`: The ports the sidecar container listens on.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sidecar {
+    /// The name of the sidecar container. Must be unique within the pod.
+    pub name: String,
+
+    /// The Docker image to use for the sidecar container.
+    pub image: String,
+
+    /// Defines when the Docker image should be pulled.
+    #[serde(default)]
+    pub image_pull_policy: ImagePullPolicy,
+
+    /// The command to execute inside the sidecar container. Defaults to the
+    /// entrypoint of the image.
+    #[serde(default)]
+    pub command: Vec<String>,
+
+    /// Additional arguments to pass to the command.
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// The ports the sidecar container listens on, declared on the container.
+    #[serde(default)]
+    pub ports: Vec<u16>,
+}
@@ -3,13 +3,17 @@
 //!
 //! This module provides the `Spec` struct, used for deserializing and
 //! serializing configuration related to container deployment, including image,
-//! command, arguments, port mappings, and interactive shell settings.
+//! command, arguments, port mappings, init containers, and interactive shell
+//! settings.
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
     PROJECT_NAME,
-    config::{ImagePullPolicy, PortMapping, ServicePorts},
+    config::{
+        Error, ImagePullPolicy, ImageVariables, InitContainer, PortMapping, Probe, ServicePorts,
+        Sidecar, Spread,
+    },
     consts,
 };
 
@@ -32,6 +36,14 @@ use crate::{
 /// - `command`: The command to execute inside the container.
 /// - `args`: Additional arguments to pass to the command.
 /// - `interactive_shell`: The command to use for an interactive shell session.
+/// - `init_containers`: Setup steps to run before the container starts.
+/// - `sidecars`: Additional containers running alongside the container.
+/// - `spread`: Whether pods of the spec avoid nodes running other Axon pods.
+/// - `service_account`: The service account the pods of the spec run as.
+/// - `namespace`: The namespace the pods of the spec are created in.
+/// - `container_name`: The name of the container in the pods of the spec.
+/// - `readiness_probe`: How Kubernetes checks that the container is ready.
+/// - `liveness_probe`: How Kubernetes checks that the container is alive.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Spec {
@@ -40,6 +52,9 @@ pub struct Spec {
 
     /// The Docker image to use for the container (e.g., "ubuntu:latest",
     /// "my-repo/my-image:1.0").
+    ///
+    /// It may contain the placeholders of `ImageVariables`, e.g.
+    /// "my-repo/my-image:latest-{arch}".
     pub image: String,
 
     /// Defines when the Docker image should be pulled.
@@ -70,6 +85,82 @@ pub struct Spec {
     /// The command to use for an interactive shell session.
     #[serde(default)]
     pub interactive_shell: Vec<String>,
+
+    /// Init containers to run to completion, in order, before the container
+    /// starts.
+    ///
+    /// Defaults to an empty list.
+    #[serde(default)]
+    pub init_containers: Vec<InitContainer>,
+
+    /// Additional containers running alongside the container, e.g. a proxy.
+    /// The container stays the one attached to by default.
+    ///
+    /// Defaults to an empty list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sidecars: Vec<Sidecar>,
+
+    /// Spreads the pods of the spec across nodes, with an anti-affinity to the
+    /// other pods managed by Axon.
+    ///
+    /// Defaults to no spreading.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spread: Option<Spread>,
+
+    /// The service account the pod runs as, e.g. to call the Kubernetes API
+    /// with particular permissions from inside the pod.
+    ///
+    /// Defaults to the default service account of the namespace.
+    #[serde(default, rename = "serviceAccount", skip_serializing_if = "Option::is_none")]
+    pub service_account: Option<String>,
+
+    /// The namespace the pods of the spec are created in, unless another one
+    /// is given with `--namespace`.
+    ///
+    /// Defaults to the namespace of the current Kubernetes context.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// The name of the container in the pods of the spec, which is also the
+    /// container attached to by default.
+    ///
+    /// Defaults to `axon-container`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container_name: Option<String>,
+
+    /// How Kubernetes checks that the container is ready, e.g. that `sshd`
+    /// accepts connections, before the pod is reported as ready.
+    ///
+    /// Defaults to no probe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readiness_probe: Option<Probe>,
+
+    /// How Kubernetes checks that the container is alive, restarting it
+    /// otherwise.
+    ///
+    /// Defaults to no probe.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub liveness_probe: Option<Probe>,
+}
+
+impl Spec {
+    /// Substitutes `variables` for the placeholders in the images of the spec
+    /// and of its init containers.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if an image contains an unknown or unclosed
+    /// placeholder.
+    pub fn render_images(mut self, variables: &ImageVariables) -> Result<Self, Error> {
+        self.image = variables.render(&self.image)?;
+        for init_container in &mut self.init_containers {
+            init_container.image = variables.render(&init_container.image)?;
+        }
+        for sidecar in &mut self.sidecars {
+            sidecar.image = variables.render(&sidecar.image)?;
+        }
+        Ok(self)
+    }
 }
 
 impl Default for Spec {
@@ -86,6 +177,14 @@ impl Default for Spec {
     /// - `args`: `["-c", "while true; do sleep 1; done"]` to keep the container
     ///   running indefinitely.
     /// - `interactive_shell`: `["/bin/sh"]`.
+    /// - `init_containers`: An empty vector.
+    /// - `sidecars`: An empty vector.
+    /// - `spread`: `None`.
+    /// - `service_account`: `None`.
+    /// - `namespace`: `None`.
+    /// - `container_name`: `None`.
+    /// - `readiness_probe`: `None`.
+    /// - `liveness_probe`: `None`.
     ///
     /// # Returns
     ///
@@ -100,6 +199,14 @@ impl Default for Spec {
             command: vec!["sh".to_string()],
             args: vec!["-c".to_string(), "while true; do sleep 1; done".to_string()],
             interactive_shell: vec!["/bin/sh".to_string()],
+            init_containers: Vec::new(),
+            sidecars: Vec::new(),
+            spread: None,
+            service_account: None,
+            namespace: None,
+            container_name: None,
+            readiness_probe: None,
+            liveness_probe: None,
         }
     }
 }
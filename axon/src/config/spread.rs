@@ -0,0 +1,106 @@
+//! Defines the `Spread` enum, which spreads the pods managed by Axon across
+//! nodes.
+//!
+//! Spreading is implemented with a pod anti-affinity on the
+//! `app.kubernetes.io/managed-by=axon` label, so that several debug pods land
+//! on different nodes.
+
+use std::{collections::BTreeMap, fmt};
+
+use clap::ValueEnum;
+use k8s_openapi::{
+    api::core::v1::{Affinity, PodAffinityTerm, PodAntiAffinity, PodSpec, WeightedPodAffinityTerm},
+    apimachinery::pkg::apis::meta::v1::LabelSelector,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{PROJECT_NAME, consts::k8s::labels};
+
+/// The node label whose values the pods are spread across.
+const TOPOLOGY_KEY: &str = "kubernetes.io/hostname";
+
+/// The weight of the preferred anti-affinity rule, the highest allowed.
+const PREFERRED_WEIGHT: i32 = 100;
+
+/// Represents how strictly the pods managed by Axon are spread across nodes.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+pub enum Spread {
+    /// The scheduler prefers a node without another Axon pod, but may still
+    /// use one.
+    #[default]
+    Preferred,
+    /// The pod is only scheduled on a node without another Axon pod, and stays
+    /// pending otherwise.
+    Required,
+}
+
+impl Spread {
+    /// Adds the pod anti-affinity rule of the spread to `spec`, replacing any
+    /// pod anti-affinity it already has.
+    pub fn apply(self, spec: &mut PodSpec) {
+        let term = PodAffinityTerm {
+            label_selector: Some(LabelSelector {
+                match_labels: Some(BTreeMap::from([(
+                    labels::MANAGED_BY.to_string(),
+                    PROJECT_NAME.to_string(),
+                )])),
+                ..LabelSelector::default()
+            }),
+            topology_key: TOPOLOGY_KEY.to_string(),
+            ..PodAffinityTerm::default()
+        };
+        let anti_affinity = match self {
+            Self::Preferred => PodAntiAffinity {
+                preferred_during_scheduling_ignored_during_execution: Some(vec![
+                    WeightedPodAffinityTerm { pod_affinity_term: term, weight: PREFERRED_WEIGHT },
+                ]),
+                ..PodAntiAffinity::default()
+            },
+            Self::Required => PodAntiAffinity {
+                required_during_scheduling_ignored_during_execution: Some(vec![term]),
+                ..PodAntiAffinity::default()
+            },
+        };
+        spec.affinity.get_or_insert_with(Affinity::default).pod_anti_affinity = Some(anti_affinity);
+    }
+}
+
+impl fmt::Display for Spread {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Preferred => "preferred",
+            Self::Required => "required",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let mut spec = PodSpec::default();
+        Spread::Preferred.apply(&mut spec);
+        let anti_affinity = spec.affinity.as_ref().unwrap().pod_anti_affinity.as_ref().unwrap();
+        assert_eq!(anti_affinity.required_during_scheduling_ignored_during_execution, None);
+        let preferred =
+            anti_affinity.preferred_during_scheduling_ignored_during_execution.as_ref().unwrap();
+        assert_eq!(preferred[0].weight, PREFERRED_WEIGHT);
+        assert_eq!(preferred[0].pod_affinity_term.topology_key, TOPOLOGY_KEY);
+        let match_labels =
+            preferred[0].pod_affinity_term.label_selector.as_ref().unwrap().match_labels.as_ref();
+        assert_eq!(match_labels.unwrap()[labels::MANAGED_BY], PROJECT_NAME);
+
+        Spread::Required.apply(&mut spec);
+        let anti_affinity = spec.affinity.unwrap().pod_anti_affinity.unwrap();
+        assert_eq!(anti_affinity.preferred_during_scheduling_ignored_during_execution, None);
+        assert_eq!(
+            anti_affinity.required_during_scheduling_ignored_during_execution.unwrap().len(),
+            1
+        );
+
+        assert_eq!(serde_yaml::from_str::<Spread>("required").unwrap(), Spread::Required);
+    }
+}
@@ -0,0 +1,170 @@
+//! Writes log messages to the local syslog daemon.
+//!
+//! Messages are sent as datagrams in the BSD syslog format, e.g.
+//! `<14>axon[42]: message`, to the socket of the daemon, `/dev/log` or
+//! `/var/run/syslog` on macOS. The priority of every message combines the
+//! configured `SyslogFacility` and the level of the event.
+
+use std::{io, os::unix::net::UnixDatagram, path::Path, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::fmt::MakeWriter;
+
+use crate::PROJECT_NAME;
+
+/// The sockets of the syslog daemon, tried in order.
+const SOCKET_PATHS: [&str; 2] = ["/dev/log", "/var/run/syslog"];
+
+/// Represents the syslog facility messages are logged with.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SyslogFacility {
+    /// Messages of user-level programs.
+    #[default]
+    User,
+    /// Messages of system daemons.
+    Daemon,
+    /// Messages reserved for local use.
+    Local0,
+    /// Messages reserved for local use.
+    Local1,
+    /// Messages reserved for local use.
+    Local2,
+    /// Messages reserved for local use.
+    Local3,
+    /// Messages reserved for local use.
+    Local4,
+    /// Messages reserved for local use.
+    Local5,
+    /// Messages reserved for local use.
+    Local6,
+    /// Messages reserved for local use.
+    Local7,
+}
+
+impl SyslogFacility {
+    /// Returns the numerical code of the facility, as defined by RFC 5424.
+    const fn code(self) -> u8 {
+        match self {
+            Self::User => 1,
+            Self::Daemon => 3,
+            Self::Local0 => 16,
+            Self::Local1 => 17,
+            Self::Local2 => 18,
+            Self::Local3 => 19,
+            Self::Local4 => 20,
+            Self::Local5 => 21,
+            Self::Local6 => 22,
+            Self::Local7 => 23,
+        }
+    }
+
+    /// Returns the priority of a message of `level`, combining the facility
+    /// and the severity of the level.
+    const fn priority(self, level: tracing::Level) -> u8 {
+        let severity = match level {
+            tracing::Level::ERROR => 3,
+            tracing::Level::WARN => 4,
+            tracing::Level::INFO => 6,
+            _ => 7,
+        };
+        self.code() * 8 + severity
+    }
+}
+
+/// Creates a `SyslogMessage` for every event, sending it to the syslog
+/// daemon.
+#[derive(Clone, Debug)]
+pub struct SyslogWriter {
+    /// The socket connected to the syslog daemon.
+    socket: Arc<UnixDatagram>,
+    /// The facility messages are logged with.
+    facility: SyslogFacility,
+}
+
+impl SyslogWriter {
+    /// Connects to the syslog daemon, returning `None` if no socket of the
+    /// daemon is available.
+    pub fn connect(facility: SyslogFacility) -> Option<Self> {
+        SOCKET_PATHS.into_iter().find_map(|path| Self::connect_to(path, facility).ok())
+    }
+
+    /// Connects to the syslog socket at `path`.
+    fn connect_to(path: impl AsRef<Path>, facility: SyslogFacility) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self { socket: Arc::new(socket), facility })
+    }
+
+    /// Returns a message of `level`.
+    fn message(&self, level: tracing::Level) -> SyslogMessage<'_> {
+        SyslogMessage { socket: &self.socket, priority: self.facility.priority(level) }
+    }
+}
+
+impl<'a> MakeWriter<'a> for SyslogWriter {
+    type Writer = SyslogMessage<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer { self.message(tracing::Level::INFO) }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        self.message(*meta.level())
+    }
+}
+
+/// A message to the syslog daemon, sent as one datagram per write.
+#[derive(Debug)]
+pub struct SyslogMessage<'a> {
+    /// The socket connected to the syslog daemon.
+    socket: &'a UnixDatagram,
+    /// The priority of the message.
+    priority: u8,
+}
+
+impl io::Write for SyslogMessage<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let header = format!("<{}>{PROJECT_NAME}[{}]: ", self.priority, std::process::id());
+        let body = buf.strip_suffix(b"\n").unwrap_or(buf);
+        let _sent = self.socket.send(&[header.as_bytes(), body].concat())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    #[test]
+    fn test_priority() {
+        assert_eq!(SyslogFacility::User.priority(tracing::Level::INFO), 14);
+        assert_eq!(SyslogFacility::Daemon.priority(tracing::Level::ERROR), 27);
+        assert_eq!(SyslogFacility::Local7.priority(tracing::Level::TRACE), 191);
+        assert_eq!(
+            serde_yaml::from_str::<SyslogFacility>("local0").unwrap(),
+            SyslogFacility::Local0
+        );
+    }
+
+    #[test]
+    fn test_syslog_writer() {
+        let path = std::env::temp_dir().join(format!("axon-syslog-test-{}", std::process::id()));
+        let _unused = std::fs::remove_file(&path);
+        let daemon = UnixDatagram::bind(&path).unwrap();
+
+        let writer = SyslogWriter::connect_to(&path, SyslogFacility::User).unwrap();
+        writer.make_writer().write_all(b"hello\n").unwrap();
+        let mut buf = [0; 128];
+        let len = daemon.recv(&mut buf).unwrap();
+        assert_eq!(
+            std::str::from_utf8(&buf[..len]).unwrap(),
+            format!("<14>{PROJECT_NAME}[{}]: hello", std::process::id())
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(SyslogWriter::connect_to(&path, SyslogFacility::User).is_err());
+    }
+}
@@ -7,6 +7,10 @@ pub mod labels {
     /// resource is managed by Axon.
     pub const MANAGED_BY: &str = "app.kubernetes.io/managed-by";
 
+    /// The `app.kubernetes.io/instance` label, holding the name of the pod a
+    /// resource belongs to. Services created by Axon select their pod with it.
+    pub const INSTANCE: &str = "app.kubernetes.io/instance";
+
     /// The `kubectl.kubernetes.io/default-container` annotation, specifying
     /// the default container to attach to in a multi-container pod.
     pub const DEFAULT_CONTAINER: &str = "kubectl.kubernetes.io/default-container";
@@ -39,4 +43,8 @@ pub mod annotations {
     /// The annotation key used to store the version of Axon that created or
     /// last modified a resource.
     pub static VERSION: LazyLock<String> = LazyLock::new(|| format!("{PROJECT_NAME}.version"));
+
+    /// The annotation key used to record when SSH access was last set up in
+    /// a pod, as an RFC 3339 timestamp.
+    pub static SSH_SETUP: LazyLock<String> = LazyLock::new(|| format!("{PROJECT_NAME}.ssh/setup"));
 }
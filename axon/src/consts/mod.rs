@@ -1,10 +1,22 @@
 pub mod k8s;
 
-use std::sync::LazyLock;
+use std::{sync::LazyLock, time::Duration};
 
 /// The default name for a pod created by Axon.
 pub const DEFAULT_POD_NAME: &str = "axon";
 
+/// The default time to wait for a pod to be running, used when neither the
+/// command's `--timeout-seconds` nor the global `--pod-ready-timeout` is given.
+pub const DEFAULT_POD_READY_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The default time to wait for a newly created pod to be running, which
+/// includes pulling its image.
+pub const DEFAULT_CREATE_POD_READY_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// The default time the `wait` command waits for a condition of a pod, used
+/// when neither its `--timeout` nor the global `--pod-ready-timeout` is given.
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// The default container image used when creating a new pod if no other image
 /// is specified.
 pub const DEFAULT_IMAGE: &str = "docker.io/alpine:latest";
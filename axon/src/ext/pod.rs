@@ -2,20 +2,18 @@
 //!
 //! This module provides [`PodExt`], an extension trait for Kubernetes `Pod`
 //! objects that extracts Axon-specific configurations such as interactive
-//! shell commands, port mappings, and service ports from pod annotations.
+//! shell commands, port mappings, and service ports from pod annotations, and
+//! summarizes the phase, readiness and restarts of its containers.
 
-//! Pod extension trait for Axon-specific configurations.
-//!
-//! This module provides [`PodExt`], an extension trait for Kubernetes `Pod`
-//! objects that extracts Axon-specific configurations such as interactive
-//! shell commands, port mappings, and service ports from pod annotations.
-
-use k8s_openapi::{Metadata, api::core::v1::Pod};
+use k8s_openapi::{
+    Metadata,
+    api::core::v1::{ContainerPort, ContainerStatus, Pod},
+};
 
 use crate::{
     config::{PortMapping, ServicePorts},
     consts,
-    consts::k8s::annotations,
+    consts::k8s::{annotations, labels},
 };
 
 /// Extension trait for `Pod` providing methods for extracting Axon-specific
@@ -66,6 +64,47 @@ pub trait PodExt {
     /// A `ServicePorts` object representing the pod's configured service ports.
     /// This object will reflect any service port annotations found on the pod.
     fn service_ports(&self) -> ServicePorts;
+
+    /// Determines the container to attach to by default.
+    ///
+    /// The `kubectl.kubernetes.io/default-container` annotation is honored as
+    /// kubectl does, as well as the label of the same name set by Axon on the
+    /// pods it creates. Otherwise, the first container of the pod is used, so
+    /// that pods not created by Axon can be targeted as well.
+    ///
+    /// # Returns
+    ///
+    /// The name of the default container, or `None` if the pod has no
+    /// containers.
+    fn default_container(&self) -> Option<String>;
+
+    /// Looks up the number of the container port declared with `name` by any
+    /// container of the pod, e.g. `8080` for a port named `http`.
+    fn container_port_named(&self, name: &str) -> Option<u16>;
+
+    /// Returns the names of the container ports declared by the containers of
+    /// the pod, in declaration order.
+    fn container_port_names(&self) -> Vec<String>;
+
+    /// Returns whether the pod reports the `Ready` condition.
+    fn is_ready(&self) -> bool;
+
+    /// Counts the ready containers of the pod.
+    ///
+    /// # Returns
+    ///
+    /// A tuple of the number of ready containers and the number of containers
+    /// in the pod spec, e.g. `(1, 2)`, rendered as `1/2` by kubectl.
+    fn ready_containers(&self) -> (usize, usize);
+
+    /// Returns the total number of restarts of the containers of the pod.
+    fn restart_count(&self) -> i32;
+
+    /// Summarizes the phase of the pod, along with the reason a container is
+    /// waiting for, if any, e.g. `Pending (ContainerCreating)`.
+    ///
+    /// The phase is `Unknown` until it has been reported.
+    fn phase_summary(&self) -> String;
 }
 
 /// Implements the `PodExt` trait for `k8s_openapi::api::core::v1::Pod`,
@@ -95,4 +134,165 @@ impl PodExt for Pod {
     fn service_ports(&self) -> ServicePorts {
         ServicePorts::from_kubernetes_annotations(self.metadata().annotations.iter().flatten())
     }
+
+    fn default_container(&self) -> Option<String> {
+        let metadata = self.metadata();
+        [&metadata.annotations, &metadata.labels]
+            .into_iter()
+            .flatten()
+            .find_map(|map| map.get(labels::DEFAULT_CONTAINER))
+            .cloned()
+            .or_else(|| {
+                self.spec.as_ref()?.containers.first().map(|container| container.name.clone())
+            })
+    }
+
+    fn container_port_named(&self, name: &str) -> Option<u16> {
+        container_ports(self)
+            .find(|container_port| container_port.name.as_deref() == Some(name))
+            .and_then(|container_port| u16::try_from(container_port.container_port).ok())
+    }
+
+    fn container_port_names(&self) -> Vec<String> {
+        container_ports(self).filter_map(|container_port| container_port.name.clone()).collect()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.status.as_ref().and_then(|status| status.conditions.as_ref()).is_some_and(
+            |conditions| {
+                conditions
+                    .iter()
+                    .any(|condition| condition.type_ == "Ready" && condition.status == "True")
+            },
+        )
+    }
+
+    fn ready_containers(&self) -> (usize, usize) {
+        let ready = container_statuses(self).iter().filter(|status| status.ready).count();
+        (ready, self.spec.as_ref().map_or(0, |spec| spec.containers.len()))
+    }
+
+    fn restart_count(&self) -> i32 {
+        container_statuses(self).iter().map(|status| status.restart_count).sum()
+    }
+
+    fn phase_summary(&self) -> String {
+        let phase =
+            self.status.as_ref().and_then(|status| status.phase.as_deref()).unwrap_or("Unknown");
+        let waiting_reason = container_statuses(self)
+            .iter()
+            .find_map(|status| status.state.as_ref()?.waiting.as_ref()?.reason.as_deref());
+        waiting_reason.map_or_else(|| phase.to_string(), |reason| format!("{phase} ({reason})"))
+    }
+}
+
+/// Returns the ports declared by the containers of `pod`.
+fn container_ports(pod: &Pod) -> impl Iterator<Item = &ContainerPort> {
+    pod.spec
+        .iter()
+        .flat_map(|spec| &spec.containers)
+        .flat_map(|container| container.ports.iter().flatten())
+}
+
+/// Returns the statuses of the containers of `pod`, empty until reported.
+fn container_statuses(pod: &Pod) -> &[ContainerStatus] {
+    pod.status.as_ref().and_then(|status| status.container_statuses.as_deref()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{
+        Container, ContainerState, ContainerStateWaiting, PodCondition, PodSpec, PodStatus,
+    };
+
+    use super::*;
+
+    fn pod(conditions: &[(&str, &str)], container_statuses: &[(bool, i32)]) -> Pod {
+        Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container::default(); container_statuses.len()],
+                ..PodSpec::default()
+            }),
+            status: Some(PodStatus {
+                conditions: Some(
+                    conditions
+                        .iter()
+                        .map(|(type_, status)| PodCondition {
+                            type_: (*type_).to_string(),
+                            status: (*status).to_string(),
+                            ..PodCondition::default()
+                        })
+                        .collect(),
+                ),
+                container_statuses: Some(
+                    container_statuses
+                        .iter()
+                        .map(|&(ready, restart_count)| ContainerStatus {
+                            ready,
+                            restart_count,
+                            ..ContainerStatus::default()
+                        })
+                        .collect(),
+                ),
+                ..PodStatus::default()
+            }),
+            ..Pod::default()
+        }
+    }
+
+    #[test]
+    fn test_interactive_shell() {
+        let argv = ["bash", "-lc", "exec zsh"].map(String::from).to_vec();
+        let mut pod = pod(&[], &[]);
+        pod.metadata.annotations = Some(
+            [(annotations::SHELL_INTERACTIVE.clone(), serde_json::to_string(&argv).unwrap())]
+                .into(),
+        );
+        assert_eq!(pod.interactive_shell(), argv);
+
+        pod.metadata.annotations = None;
+        assert_eq!(pod.interactive_shell(), *consts::DEFAULT_INTERACTIVE_SHELL);
+    }
+
+    #[test]
+    fn test_is_ready() {
+        assert!(pod(&[("PodScheduled", "True"), ("Ready", "True")], &[]).is_ready());
+        assert!(!pod(&[("Ready", "False")], &[]).is_ready());
+        assert!(!pod(&[("PodScheduled", "True")], &[]).is_ready());
+        assert!(!Pod::default().is_ready());
+    }
+
+    #[test]
+    fn test_container_summary() {
+        let pod = pod(&[], &[(true, 0), (false, 3), (true, 1)]);
+        assert_eq!(pod.ready_containers(), (2, 3));
+        assert_eq!(pod.restart_count(), 4);
+
+        let pending = Pod {
+            spec: Some(PodSpec { containers: vec![Container::default()], ..PodSpec::default() }),
+            ..Pod::default()
+        };
+        assert_eq!(pending.ready_containers(), (0, 1));
+        assert_eq!(pending.restart_count(), 0);
+    }
+
+    #[test]
+    fn test_phase_summary() {
+        assert_eq!(Pod::default().phase_summary(), "Unknown");
+
+        let mut pod = pod(&[], &[(false, 0)]);
+        let status = pod.status.as_mut().unwrap();
+        status.phase = Some("Pending".to_string());
+        assert_eq!(pod.phase_summary(), "Pending");
+
+        pod.status.as_mut().unwrap().container_statuses.as_mut().unwrap()[0].state =
+            Some(ContainerState {
+                waiting: Some(ContainerStateWaiting {
+                    reason: Some("ContainerCreating".to_string()),
+                    ..ContainerStateWaiting::default()
+                }),
+                ..ContainerState::default()
+            });
+        assert_eq!(pod.phase_summary(), "Pending (ContainerCreating)");
+    }
 }
@@ -6,6 +6,7 @@ mod cli;
 mod config;
 mod consts;
 mod ext;
+mod metrics;
 mod pod_console;
 mod port_forwarder;
 mod ssh;
@@ -110,19 +111,23 @@ pub fn fallback_project_config_directories() -> Vec<PathBuf> {
 ///
 /// This function parses command-line arguments, executes the requested command,
 /// and handles any errors that occur during execution. It exits the process
-/// with an appropriate status code (0 for success, 1 for error).
+/// with an appropriate status code (0 for success, non-zero for error).
 ///
 /// # Errors
 /// If the `Cli::run()` method returns an `Err`, an error message is printed
-/// to `stderr`, and the process exits with a status code of 1.
+/// to `stderr` in the format selected with `--error-format`, and the process
+/// exits with the status code of the error category, see
+/// `cli::Error::exit_code`.
 fn main() {
-    match Cli::default().run() {
+    let cli = Cli::default();
+    let error_format = cli.error_format();
+    match cli.run() {
         Ok(exit_code) => {
             std::process::exit(exit_code);
         }
         Err(err) => {
-            eprintln!("Error: {err}");
-            std::process::exit(1);
+            eprintln!("{}", err.render(error_format));
+            std::process::exit(err.exit_code());
         }
     }
 }
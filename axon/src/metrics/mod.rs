@@ -0,0 +1,122 @@
+//! Types for the Kubernetes resource metrics API (`metrics.k8s.io`).
+//!
+//! The metrics API is served by metrics-server rather than by the API server
+//! itself, so its types are not part of `k8s_openapi`. This module defines the
+//! subset of them needed to report the resource usage of pods, along with
+//! helpers to add up resource quantities.
+
+mod quantity;
+
+use std::collections::BTreeMap;
+
+use k8s_openapi::{
+    ListableResource, Metadata, NamespaceResourceScope, Resource,
+    api::core::v1::Pod,
+    apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::ObjectMeta},
+};
+use serde::Deserialize;
+
+pub use self::quantity::{cpu_millis, memory_bytes};
+
+/// The resource usage of a pod, as reported by metrics-server.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PodMetrics {
+    /// The metadata of the pod the metrics belong to.
+    pub metadata: ObjectMeta,
+
+    /// The resource usage of each container of the pod.
+    #[serde(default)]
+    pub containers: Vec<ContainerMetrics>,
+}
+
+/// The resource usage of a single container.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ContainerMetrics {
+    /// The usage of each resource, keyed by resource name (e.g. `cpu`).
+    #[serde(default)]
+    pub usage: BTreeMap<String, Quantity>,
+}
+
+impl Resource for PodMetrics {
+    type Scope = NamespaceResourceScope;
+
+    const API_VERSION: &'static str = "metrics.k8s.io/v1beta1";
+    const GROUP: &'static str = "metrics.k8s.io";
+    const KIND: &'static str = "PodMetrics";
+    const URL_PATH_SEGMENT: &'static str = "pods";
+    const VERSION: &'static str = "v1beta1";
+}
+
+impl ListableResource for PodMetrics {
+    const LIST_KIND: &'static str = "PodMetricsList";
+}
+
+impl Metadata for PodMetrics {
+    type Ty = ObjectMeta;
+
+    fn metadata(&self) -> &Self::Ty { &self.metadata }
+
+    fn metadata_mut(&mut self) -> &mut Self::Ty { &mut self.metadata }
+}
+
+/// The resource usage of a pod next to its resource requests and limits.
+///
+/// CPU amounts are in millicores and memory amounts in bytes. Requests and
+/// limits are `None` when no container of the pod sets them.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PodUsage {
+    /// The namespace of the pod.
+    pub namespace: String,
+    /// The name of the pod.
+    pub name: String,
+    /// The CPU usage of the pod, if reported.
+    pub cpu_usage: Option<u64>,
+    /// The sum of the CPU requests of the containers.
+    pub cpu_request: Option<u64>,
+    /// The sum of the CPU limits of the containers.
+    pub cpu_limit: Option<u64>,
+    /// The memory usage of the pod, if reported.
+    pub memory_usage: Option<u64>,
+    /// The sum of the memory requests of the containers.
+    pub memory_request: Option<u64>,
+    /// The sum of the memory limits of the containers.
+    pub memory_limit: Option<u64>,
+}
+
+impl PodUsage {
+    /// Combines the specification of a pod with its metrics.
+    ///
+    /// # Arguments
+    ///
+    /// * `pod` - The pod, providing the resource requests and limits.
+    /// * `metrics` - The metrics of the pod, if metrics-server reported any.
+    pub fn new(pod: &Pod, metrics: Option<&PodMetrics>) -> Self {
+        let resources =
+            pod.spec.iter().flat_map(|spec| &spec.containers).filter_map(|c| c.resources.as_ref());
+        let requests = resources.clone().filter_map(|r| r.requests.as_ref());
+        let limits = resources.filter_map(|r| r.limits.as_ref());
+        let usage = metrics.into_iter().flat_map(|metrics| &metrics.containers).map(|c| &c.usage);
+
+        Self {
+            namespace: pod.metadata.namespace.clone().unwrap_or_default(),
+            name: pod.metadata.name.clone().unwrap_or_default(),
+            cpu_usage: sum(usage.clone(), "cpu", cpu_millis),
+            cpu_request: sum(requests.clone(), "cpu", cpu_millis),
+            cpu_limit: sum(limits.clone(), "cpu", cpu_millis),
+            memory_usage: sum(usage, "memory", memory_bytes),
+            memory_request: sum(requests, "memory", memory_bytes),
+            memory_limit: sum(limits, "memory", memory_bytes),
+        }
+    }
+}
+
+/// Adds up the quantities of the resource `name` across `resource_lists`.
+///
+/// Returns `None` if no list contains a valid quantity for the resource.
+fn sum<'a>(
+    resource_lists: impl Iterator<Item = &'a BTreeMap<String, Quantity>>,
+    name: &str,
+    parse: fn(&Quantity) -> Option<u64>,
+) -> Option<u64> {
+    resource_lists.filter_map(|list| parse(list.get(name)?)).reduce(u64::saturating_add)
+}
@@ -0,0 +1,94 @@
+//! Parsing of Kubernetes resource quantities.
+
+use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+/// Parses a CPU quantity (e.g. `250m`, `0.5` or `1234567n`) into millicores.
+///
+/// Fractions of a millicore are rounded up. Returns `None` if the quantity is
+/// malformed.
+pub fn cpu_millis(quantity: &Quantity) -> Option<u64> { to_u64(parse(&quantity.0)? * 1_000.0) }
+
+/// Parses a memory quantity (e.g. `128Mi`, `1G` or `1048576`) into bytes.
+///
+/// Fractions of a byte are rounded up. Returns `None` if the quantity is
+/// malformed.
+pub fn memory_bytes(quantity: &Quantity) -> Option<u64> { to_u64(parse(&quantity.0)?) }
+
+/// Parses a quantity into its value in base units.
+fn parse(quantity: &str) -> Option<f64> {
+    let quantity = quantity.trim();
+    let split = quantity
+        .find(|c: char| !(c.is_ascii_digit() || matches!(c, '.' | '+' | '-')))
+        .unwrap_or(quantity.len());
+    let (number, suffix) = quantity.split_at(split);
+    let number = number.parse::<f64>().ok()?;
+
+    let multiplier = match suffix {
+        "" => 1.0,
+        "n" => 1e-9,
+        "u" => 1e-6,
+        "m" => 1e-3,
+        "k" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        "T" => 1e12,
+        "P" => 1e15,
+        "E" => 1e18,
+        "Ki" => 1024_f64,
+        "Mi" => 1024_f64.powi(2),
+        "Gi" => 1024_f64.powi(3),
+        "Ti" => 1024_f64.powi(4),
+        "Pi" => 1024_f64.powi(5),
+        "Ei" => 1024_f64.powi(6),
+        exponent => {
+            let exponent = exponent.strip_prefix(['e', 'E'])?.parse::<i32>().ok()?;
+            10_f64.powi(exponent)
+        }
+    };
+
+    Some(number * multiplier)
+}
+
+/// Rounds a non-negative value up to the nearest integer.
+///
+/// Values beyond `u64::MAX` saturate, which is far beyond any real resource
+/// amount.
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "The value is checked to be finite and non-negative, and `as` saturates at `u64::MAX`"
+)]
+fn to_u64(value: f64) -> Option<u64> {
+    (value.is_finite() && value >= 0.0).then(|| value.ceil() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::apimachinery::pkg::api::resource::Quantity;
+
+    use super::{cpu_millis, memory_bytes};
+
+    fn quantity(value: &str) -> Quantity { Quantity(value.to_string()) }
+
+    #[test]
+    fn test_cpu_millis() {
+        assert_eq!(cpu_millis(&quantity("250m")), Some(250));
+        assert_eq!(cpu_millis(&quantity("2")), Some(2_000));
+        assert_eq!(cpu_millis(&quantity("0.5")), Some(500));
+        assert_eq!(cpu_millis(&quantity("1234567n")), Some(2));
+        assert_eq!(cpu_millis(&quantity("1500u")), Some(2));
+        assert_eq!(cpu_millis(&quantity("cores")), None);
+    }
+
+    #[test]
+    fn test_memory_bytes() {
+        assert_eq!(memory_bytes(&quantity("1048576")), Some(1_048_576));
+        assert_eq!(memory_bytes(&quantity("128Mi")), Some(128 * 1024 * 1024));
+        assert_eq!(memory_bytes(&quantity("1Gi")), Some(1024 * 1024 * 1024));
+        assert_eq!(memory_bytes(&quantity("1G")), Some(1_000_000_000));
+        assert_eq!(memory_bytes(&quantity("12345Ki")), Some(12_345 * 1024));
+        assert_eq!(memory_bytes(&quantity("1e3")), Some(1_000));
+        assert_eq!(memory_bytes(&quantity("-1Mi")), None);
+        assert_eq!(memory_bytes(&quantity("Mi")), None);
+    }
+}
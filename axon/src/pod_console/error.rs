@@ -57,10 +57,6 @@ pub enum Error {
         stream: Cow<'static, str>,
     },
 
-    /// Failed to retrieve the current terminal size.
-    #[snafu(display("Failed to get terminal size, error: {source}"))]
-    GetTerminalSize { source: std::io::Error },
-
     /// Failed to change the terminal size (e.g., due to an OS error or an
     /// invalid size).
     #[snafu(display("Failed to change terminal size"))]
@@ -7,8 +7,10 @@
 
 mod error;
 
+use std::time::Duration;
+
 use futures::{FutureExt, SinkExt, channel::mpsc::Sender};
-use k8s_openapi::api::core::v1::Pod;
+use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::Status};
 use kube::{
     Api,
     api::{AttachParams, TerminalSize},
@@ -20,7 +22,19 @@ use tokio::{
 };
 
 pub use self::error::Error;
-use crate::ui::terminal::TerminalRawModeGuard;
+use crate::ui::terminal::{self, TerminalRawModeGuard};
+
+/// The time to wait before reattaching after the connection to a Pod is lost.
+const REATTACH_DELAY: Duration = Duration::from_secs(1);
+
+/// The time to wait for the exit status of the remote shell once its streams
+/// are closed.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The keys typed into the remote terminal to end the shell on detach:
+/// `Ctrl-C` interrupts the foreground job, then `Ctrl-D` makes the shell exit
+/// at its prompt, which hangs up the rest of its process group.
+const HANG_UP_SEQUENCE: &[u8] = b"\x03\x04";
 
 /// A controller for managing an interactive terminal session with a Kubernetes
 /// Pod.
@@ -38,6 +52,13 @@ pub struct PodConsole {
     namespace: String,
     /// The command to run within the container (e.g., `["/bin/sh"]`).
     shell: Vec<String>,
+    /// The container to attach to. If `None`, the Pod must have a single
+    /// container.
+    container: Option<String>,
+    /// How many times to reattach after losing the connection to the Pod.
+    max_reattach_attempts: u32,
+    /// Whether to end the remote shell when the session is ended locally.
+    cleanup_on_detach: bool,
 }
 
 impl PodConsole {
@@ -85,9 +106,58 @@ impl PodConsole {
             pod_name: pod_name.into(),
             namespace: namespace.into(),
             shell: shell.into_iter().map(Into::into).collect(),
+            container: None,
+            max_reattach_attempts: 0,
+            cleanup_on_detach: false,
         }
     }
 
+    /// Sets the container to attach to, which is required for Pods with more
+    /// than one container.
+    ///
+    /// # Arguments
+    ///
+    /// * `container` - The name of the container, or `None` to let the API
+    ///   server pick the only container of the Pod.
+    #[must_use]
+    pub fn container(mut self, container: Option<String>) -> Self {
+        self.container = container;
+        self
+    }
+
+    /// Sets how many times the session is resumed after the connection to the
+    /// Pod is lost, with the shell started again each time.
+    ///
+    /// The session is never resumed once the remote shell has exited, so that
+    /// typing `exit` ends it as usual.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_attempts` - The maximum number of attempts, `0` to never
+    ///   reattach.
+    #[must_use]
+    pub const fn reattach(mut self, max_attempts: u32) -> Self {
+        self.max_reattach_attempts = max_attempts;
+        self
+    }
+
+    /// Sets whether the remote shell is ended when the session is ended
+    /// locally, e.g. by closing stdin, instead of being left running in the
+    /// container.
+    ///
+    /// The shell is ended by typing `Ctrl-C` and `Ctrl-D` into its terminal
+    /// and closing its stdin, so that repeated sessions do not accumulate
+    /// shells in the container.
+    ///
+    /// # Arguments
+    ///
+    /// * `cleanup` - Whether to end the remote shell on detach.
+    #[must_use]
+    pub const fn cleanup_on_detach(mut self, cleanup: bool) -> Self {
+        self.cleanup_on_detach = cleanup;
+        self
+    }
+
     /// Establishes and manages an interactive terminal session with the
     /// Kubernetes Pod.
     ///
@@ -96,7 +166,8 @@ impl PodConsole {
     /// It also spawns a background task to handle terminal window resizing
     /// (`SIGWINCH`). The session continues until the Pod connection is
     /// closed, an I/O error occurs, or the terminal size handling task
-    /// finishes unexpectedly.
+    /// finishes unexpectedly. If the connection is lost while the shell is
+    /// still running, the session is resumed as set with [`Self::reattach`].
     ///
     /// # Errors
     ///
@@ -137,18 +208,57 @@ impl PodConsole {
     /// ```
     pub async fn run(self) -> Result<(), Error> {
         let _raw_mode_guard = TerminalRawModeGuard::setup()?;
-        let Self { api, pod_name, namespace, shell } = self;
+
+        let mut attempts = 0;
+        loop {
+            let session_end = match self.attach().await {
+                Ok(session_end) => session_end,
+                // The pod may still be restarting when reattaching
+                Err(err @ Error::AttachPod { .. }) if attempts > 0 => {
+                    tracing::debug!("Failed to reattach: {err}");
+                    SessionEnd::Dropped
+                }
+                Err(err) => return Err(err),
+            };
+            if session_end != SessionEnd::Dropped {
+                return Ok(());
+            }
+            if attempts == self.max_reattach_attempts {
+                if attempts > 0 {
+                    eprint!("\r\nConnection to pod {} lost, giving up\r\n", self.pod_name);
+                }
+                return Ok(());
+            }
+            attempts += 1;
+            eprint!(
+                "\r\nConnection to pod {} lost, reattaching ({attempts}/{})...\r\n",
+                self.pod_name, self.max_reattach_attempts
+            );
+            tokio::time::sleep(REATTACH_DELAY).await;
+        }
+    }
+
+    /// Runs the shell in the Pod once, piping I/O until the session ends, and
+    /// reports how it ended.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`Error`] if the session cannot be established or if an I/O
+    /// error occurs while writing to the Pod or the local terminal.
+    async fn attach(&self) -> Result<SessionEnd, Error> {
+        let Self { api, pod_name, namespace, shell, container, cleanup_on_detach, .. } = self;
 
         // Initiate Exec
         let mut attached = api
             .exec(
-                &pod_name,
-                shell,
+                pod_name,
+                shell.clone(),
                 &AttachParams {
                     stdin: true,
                     stdout: true,
                     stderr: false,
                     tty: true,
+                    container: container.clone(),
                     ..AttachParams::default()
                 },
             )
@@ -168,6 +278,7 @@ impl PodConsole {
             attached.stdout().context(error::GetPodStreamSnafu { stream: "stdout" })?;
         let mut pod_stdin =
             attached.stdin().context(error::GetPodStreamSnafu { stream: "stdin" })?;
+        let status = attached.take_status();
 
         let mut local_stdin = tokio_fd::AsyncFd::try_from(0)
             .context(error::InitializeStdioSnafu { stream: "stdin" })?;
@@ -179,15 +290,15 @@ impl PodConsole {
 
         let mut attached_join = attached.join().fuse().boxed();
 
-        loop {
+        let closed_by_remote = loop {
             tokio::select! {
-                _ = &mut attached_join => {
-                    tracing::debug!("Pod connection closed by remote");
-                    break;
+                res = &mut attached_join => {
+                    tracing::debug!("Pod connection closed by remote: {res:?}");
+                    break true;
                 },
                 res = local_stdin.read(&mut in_buffer) => {
                     match res {
-                        Ok(0) | Err(_) => break,
+                        Ok(0) | Err(_) => break false,
                         Ok(n) => {
                             pod_stdin.write_all(&in_buffer[..n]).await.context(error::CopyIoSnafu)?;
                             pod_stdin.flush().await.context(error::CopyIoSnafu)?;
@@ -196,7 +307,7 @@ impl PodConsole {
                 },
                 res = pod_stdout.read(&mut out_buffer) => {
                     match res {
-                        Ok(0) | Err(_) => break,
+                        Ok(0) | Err(_) => break true,
                         Ok(n) => {
                             local_stdout.write_all(&out_buffer[..n]).await.context(error::CopyIoSnafu)?;
                             local_stdout.flush().await.context(error::CopyIoSnafu)?;
@@ -205,15 +316,71 @@ impl PodConsole {
                 },
                 res = &mut terminal_size_handle => {
                     tracing::debug!("Terminal size task finished: {:?}", res);
-                    break;
+                    break false;
                 }
             }
-        }
+        };
 
         cancel_token.cancel();
         let _unused = terminal_size_handle.await;
 
-        Ok(())
+        if !closed_by_remote {
+            if *cleanup_on_detach {
+                hang_up(pod_stdin, status).await;
+            }
+            return Ok(SessionEnd::Local);
+        }
+        // A shell that exits is reported with a status, whatever its exit code,
+        // while a dropped connection closes the streams without one
+        let status = match status {
+            Some(status) => tokio::time::timeout(STATUS_TIMEOUT, status).await.ok().flatten(),
+            None => None,
+        };
+        Ok(status.map_or(SessionEnd::Dropped, |status| {
+            tracing::debug!("Remote shell exited with status {:?}", status.status);
+            SessionEnd::Exited
+        }))
+    }
+}
+
+/// How an attached session ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SessionEnd {
+    /// The remote shell exited, e.g. because the user typed `exit`.
+    Exited,
+    /// The connection to the Pod was lost while the shell was running.
+    Dropped,
+    /// The session was ended locally, e.g. by closing stdin.
+    Local,
+}
+
+/// Ends the remote shell of a session ended locally by typing
+/// `HANG_UP_SEQUENCE` into its terminal and closing its stdin, then waits
+/// briefly for its exit status.
+///
+/// Failures are only logged, since the session is over either way.
+async fn hang_up(
+    mut pod_stdin: impl AsyncWriteExt + Unpin,
+    status: Option<impl Future<Output = Option<Status>>>,
+) {
+    let result = async {
+        pod_stdin.write_all(HANG_UP_SEQUENCE).await?;
+        pod_stdin.shutdown().await
+    }
+    .await;
+    drop(pod_stdin);
+    if let Err(err) = result {
+        tracing::debug!("Failed to end the remote shell: {err}");
+        return;
+    }
+    let status = match status {
+        Some(status) => tokio::time::timeout(STATUS_TIMEOUT, status).await.ok().flatten(),
+        None => None,
+    };
+    if let Some(status) = status {
+        tracing::debug!("Remote shell ended with status {:?}", status.status);
+    } else {
+        tracing::debug!("Remote shell did not report an exit status after hanging up");
     }
 }
 
@@ -238,14 +405,10 @@ impl PodConsole {
 ///
 /// Returns an [`Error`] if:
 ///
-/// * The initial terminal size cannot be retrieved
-///   (`error::GetTerminalSizeSnafu`).
 /// * Sending the initial terminal size over the channel fails
 ///   (`Error::ChangeTerminalSize`).
 /// * The `SIGWINCH` signal stream cannot be created
 ///   (`error::CreateSignalStreamSnafu`).
-/// * Retrieving terminal size after a resize event fails
-///   (`error::GetTerminalSizeSnafu`).
 /// * Sending a subsequent terminal size update over the channel fails
 ///   (`Error::ChangeTerminalSize`).
 ///
@@ -285,7 +448,7 @@ async fn handle_terminal_size(
     mut channel: Sender<TerminalSize>,
     cancel_token: tokio_util::sync::CancellationToken,
 ) -> Result<(), Error> {
-    let (width, height) = crossterm::terminal::size().context(error::GetTerminalSizeSnafu)?;
+    let (width, height) = terminal::size();
     channel.send(TerminalSize { height, width }).await.map_err(|_| Error::ChangeTerminalSize)?;
 
     // create a stream to catch SIGWINCH signal
@@ -299,8 +462,7 @@ async fn handle_terminal_size(
         };
 
         if maybe_signal.is_some() {
-            let (width, height) =
-                crossterm::terminal::size().context(error::GetTerminalSizeSnafu)?;
+            let (width, height) = terminal::size();
             channel
                 .send(TerminalSize { height, width })
                 .await
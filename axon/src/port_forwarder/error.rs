@@ -1,6 +1,6 @@
 //! Defines the error types for the port forwarder module.
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 
 use snafu::Snafu;
 
@@ -21,6 +21,16 @@ pub enum Error {
         source: std::io::Error,
     },
 
+    /// Occurs when the requested local address is not assigned to any network
+    /// interface of this host, so nothing can be bound to it.
+    #[snafu(display(
+        "Failed to bind TCP socket {socket_address}, the address is not assigned to this host"
+    ))]
+    AddressNotAvailable {
+        /// The socket address that the system attempted to bind to.
+        socket_address: SocketAddr,
+    },
+
     /// Occurs when there is a failure to create a pod stream.
     ///
     /// This error typically arises when interacting with the Kubernetes API
@@ -34,4 +44,42 @@ pub enum Error {
         #[snafu(source(from(kube::Error, Box::new)))]
         source: Box<kube::Error>,
     },
+
+    /// Occurs when the remote port of the pod does not accept a test
+    /// connection through the tunnel within the allotted time.
+    #[snafu(display(
+        "Port {remote_port} of pod {pod_name} did not become reachable within {}, last error: \
+         {reason}",
+        humantime::format_duration(*timeout)
+    ))]
+    PodPortUnreachable {
+        /// The name of the pod.
+        pod_name: String,
+        /// The port on the pod that was probed.
+        remote_port: u16,
+        /// The time spent probing before giving up.
+        timeout: Duration,
+        /// The reason the last test connection failed.
+        reason: String,
+    },
+
+    /// Occurs when a SOCKS5 client does not complete the handshake, e.g.
+    /// because it requires authentication or sends an unsupported request.
+    #[snafu(display("SOCKS5 handshake with {peer} failed, error: {source}"))]
+    SocksHandshake {
+        /// The address of the local client.
+        peer: SocketAddr,
+        /// The underlying I/O or protocol error.
+        source: std::io::Error,
+    },
+
+    /// Occurs when the target requested by a SOCKS5 client cannot be
+    /// connected to.
+    #[snafu(display("Failed to connect to SOCKS5 target {target}, error: {source}"))]
+    SocksConnect {
+        /// The requested target, `HOST:PORT`.
+        target: String,
+        /// The underlying error of the connector.
+        source: std::io::Error,
+    },
 }
@@ -0,0 +1,351 @@
+//! Prometheus metrics of port forwarders.
+//!
+//! Metrics are opt-in: a forwarder only updates counters when it is given a
+//! [`ForwarderMetrics`], obtained by registering it in a [`MetricsRegistry`].
+//! The registry renders the metrics of all its forwarders in the Prometheus
+//! text format and can serve them over HTTP at `/metrics`.
+
+use std::{
+    fmt::Write as _,
+    io,
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use snafu::ResultExt;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::port_forwarder::{Error, bind_listener, error};
+
+/// The path the metrics are served at.
+const METRICS_PATH: &str = "/metrics";
+
+/// The maximum size of an HTTP request head read by the metrics server.
+const MAX_REQUEST_SIZE: usize = 8192;
+
+/// The time a client of the metrics server has to send its request head,
+/// after which the connection is closed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Selects the counter of a metric family from the counters of a forwarder.
+type MetricValue = fn(&ForwarderMetrics) -> &AtomicU64;
+
+/// The counters of a single forwarder.
+#[derive(Debug, Default)]
+pub struct ForwarderMetrics {
+    /// The number of connections currently bridged.
+    active_connections: AtomicU64,
+    /// The number of connections bridged since the forwarder started.
+    connections: AtomicU64,
+    /// The number of connections that failed.
+    connection_errors: AtomicU64,
+    /// The number of bytes sent from local clients to the pod.
+    bytes_sent: AtomicU64,
+    /// The number of bytes sent from the pod to local clients.
+    bytes_received: AtomicU64,
+    /// The number of times the forwarder moved to another pod.
+    reconnects: AtomicU64,
+}
+
+impl ForwarderMetrics {
+    /// Records that a connection has been bridged.
+    pub fn connection_opened(&self) {
+        let _ = self.active_connections.fetch_add(1, Ordering::Relaxed);
+        let _ = self.connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a bridged connection has been closed after transferring
+    /// `sent` bytes to the pod and `received` bytes from it.
+    pub fn connection_closed(&self, sent: u64, received: u64) {
+        let _ = self.active_connections.fetch_sub(1, Ordering::Relaxed);
+        let _ = self.bytes_sent.fetch_add(sent, Ordering::Relaxed);
+        let _ = self.bytes_received.fetch_add(received, Ordering::Relaxed);
+    }
+
+    /// Records that a connection failed.
+    pub fn connection_failed(&self) {
+        let _ = self.connection_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that the forwarder moved to another pod.
+    pub fn reconnected(&self) { let _ = self.reconnects.fetch_add(1, Ordering::Relaxed); }
+}
+
+/// A forwarder registered in a `MetricsRegistry`.
+struct Entry {
+    /// The local address of the forwarder, as requested.
+    local_address: String,
+    /// The target of the forwarder, e.g. `svc/web:80`.
+    target: String,
+    /// The counters of the forwarder.
+    metrics: Arc<ForwarderMetrics>,
+}
+
+/// The metrics of every forwarder of a process.
+#[derive(Default)]
+pub struct MetricsRegistry {
+    /// The registered forwarders, in registration order.
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl MetricsRegistry {
+    /// Registers a forwarder from `local_address` to `target`, returning the
+    /// counters it must update.
+    ///
+    /// Registering the same forwarder twice returns the same counters.
+    pub fn register(&self, local_address: SocketAddr, target: &str) -> Arc<ForwarderMetrics> {
+        let local_address = local_address.to_string();
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(entry) = entries
+            .iter()
+            .find(|entry| entry.local_address == local_address && entry.target == target)
+        {
+            return Arc::clone(&entry.metrics);
+        }
+        let metrics = Arc::new(ForwarderMetrics::default());
+        entries.push(Entry {
+            local_address,
+            target: target.to_string(),
+            metrics: Arc::clone(&metrics),
+        });
+        metrics
+    }
+
+    /// Renders the metrics of every registered forwarder in the Prometheus
+    /// text exposition format.
+    pub fn render(&self) -> String {
+        let families: [(&str, &str, &str, MetricValue); 6] = [
+            (
+                "axon_forward_active_connections",
+                "gauge",
+                "Number of connections currently bridged.",
+                |metrics| &metrics.active_connections,
+            ),
+            (
+                "axon_forward_connections_total",
+                "counter",
+                "Number of connections bridged.",
+                |metrics| &metrics.connections,
+            ),
+            (
+                "axon_forward_connection_errors_total",
+                "counter",
+                "Number of connections that failed.",
+                |metrics| &metrics.connection_errors,
+            ),
+            (
+                "axon_forward_sent_bytes_total",
+                "counter",
+                "Number of bytes sent from local clients to the pod.",
+                |metrics| &metrics.bytes_sent,
+            ),
+            (
+                "axon_forward_received_bytes_total",
+                "counter",
+                "Number of bytes sent from the pod to local clients.",
+                |metrics| &metrics.bytes_received,
+            ),
+            (
+                "axon_forward_reconnects_total",
+                "counter",
+                "Number of times a forwarder moved to another pod.",
+                |metrics| &metrics.reconnects,
+            ),
+        ];
+
+        let entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut output = String::new();
+        for (name, kind, help, value) in families {
+            let _ = writeln!(output, "# HELP {name} {help}");
+            let _ = writeln!(output, "# TYPE {name} {kind}");
+            for Entry { local_address, target, metrics } in entries.iter() {
+                let _ = writeln!(
+                    output,
+                    "{name}{{local_address=\"{}\",target=\"{}\"}} {}",
+                    escape_label_value(local_address),
+                    escape_label_value(target),
+                    value(metrics).load(Ordering::Relaxed)
+                );
+            }
+        }
+        output
+    }
+
+    /// Serves the metrics over HTTP at `/metrics` on `addr` until
+    /// `shutdown_signal` completes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if `addr` cannot be bound.
+    pub async fn serve(
+        self: Arc<Self>,
+        addr: SocketAddr,
+        shutdown_signal: impl Future<Output = ()>,
+    ) -> Result<(), Error> {
+        let listener = bind_listener(addr).await?;
+        let actual_addr = listener
+            .local_addr()
+            .with_context(|_| error::BindTcpSocketSnafu { socket_address: addr })?;
+        tracing::info!("Serving metrics on http://{actual_addr}{METRICS_PATH}");
+
+        tokio::pin!(shutdown_signal);
+        loop {
+            let stream = tokio::select! {
+                () = &mut shutdown_signal => break,
+                conn = listener.accept() => match conn {
+                    Ok((stream, _)) => stream,
+                    Err(err) => {
+                        tracing::debug!("Failed to accept a metrics connection: {err}");
+                        continue;
+                    }
+                },
+            };
+            let registry = Arc::clone(&self);
+            let _handle = tokio::spawn(async move {
+                if let Err(err) = registry.respond(stream, REQUEST_TIMEOUT).await {
+                    tracing::debug!("Failed to serve metrics: {err}");
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Answers a single HTTP request with the metrics, or with `404 Not
+    /// Found` for any other path than `/metrics`.
+    ///
+    /// Fails with `io::ErrorKind::TimedOut` if the request head is not read
+    /// within `request_timeout`, so that an idle client cannot hold the
+    /// connection open.
+    async fn respond(&self, mut stream: TcpStream, request_timeout: Duration) -> io::Result<()> {
+        let request =
+            tokio::time::timeout(request_timeout, read_request_head(&mut stream)).await.map_err(
+                |_| io::Error::new(io::ErrorKind::TimedOut, "timed out reading the request"),
+            )??;
+
+        let request = String::from_utf8_lossy(&request);
+        let mut request_line = request.lines().next().unwrap_or_default().split_whitespace();
+        let (method, path) = (request_line.next(), request_line.next());
+        let response = match (method, path.map(|path| path.split('?').next())) {
+            (Some("GET"), Some(Some(METRICS_PATH))) => {
+                let body = self.render();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; \
+                     version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            }
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+        };
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+/// Reads the head of an HTTP request from `stream`, up to
+/// `MAX_REQUEST_SIZE` bytes.
+async fn read_request_head(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut request = Vec::new();
+    let mut buf = [0; 1024];
+    while !request.windows(4).any(|window| window == b"\r\n\r\n")
+        && request.len() < MAX_REQUEST_SIZE
+    {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+    Ok(request)
+}
+
+/// Escapes a label value of the Prometheus text format.
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let registry = MetricsRegistry::default();
+        let local_address = SocketAddr::from(([127, 0, 0, 1], 8080));
+        let metrics = registry.register(local_address, "svc/web:80");
+        metrics.connection_opened();
+        metrics.connection_opened();
+        metrics.connection_closed(10, 20);
+        metrics.connection_failed();
+        assert!(Arc::ptr_eq(&metrics, &registry.register(local_address, "svc/web:80")));
+
+        let output = registry.render();
+        let labels = r#"{local_address="127.0.0.1:8080",target="svc/web:80"}"#;
+        for line in [
+            format!("axon_forward_active_connections{labels} 1"),
+            format!("axon_forward_connections_total{labels} 2"),
+            format!("axon_forward_connection_errors_total{labels} 1"),
+            format!("axon_forward_sent_bytes_total{labels} 10"),
+            format!("axon_forward_received_bytes_total{labels} 20"),
+            format!("axon_forward_reconnects_total{labels} 0"),
+        ] {
+            assert!(output.lines().any(|output_line| output_line == line), "missing {line}");
+        }
+        assert!(output.contains("# TYPE axon_forward_active_connections gauge"));
+    }
+
+    #[tokio::test]
+    async fn test_serve() {
+        let registry = Arc::new(MetricsRegistry::default());
+        let _metrics = registry.register(SocketAddr::from(([127, 0, 0, 1], 8080)), "pod:80");
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let (shutdown_sender, shutdown_receiver) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(Arc::clone(&registry).serve(addr, async {
+            let _ = shutdown_receiver.await;
+        }));
+
+        let get = |path: &'static str| async move {
+            let mut stream = loop {
+                if let Ok(stream) = TcpStream::connect(addr).await {
+                    break stream;
+                }
+                tokio::task::yield_now().await;
+            };
+            stream.write_all(format!("GET {path} HTTP/1.1\r\n\r\n").as_bytes()).await.unwrap();
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains(
+            r#"axon_forward_connections_total{local_address="127.0.0.1:8080",target="pod:80"} 0"#
+        ));
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+
+        drop(shutdown_sender);
+        server.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_respond_times_out() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let _idle_client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (stream, _) = listener.accept().await.unwrap();
+
+        let err = MetricsRegistry::default()
+            .respond(stream, Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -50,23 +50,73 @@
 //! }
 //! ```
 mod error;
+mod metrics;
+mod socks;
+
 use std::{
     future::Future,
+    io,
     net::{IpAddr, Ipv4Addr, SocketAddr},
+    num::NonZeroUsize,
+    sync::Arc,
     time::Duration,
 };
 
+use clap::ValueEnum;
 use k8s_openapi::api::core::v1::Pod;
 use kube::Api;
 use snafu::{IntoError, ResultExt};
 use tokio::{
+    io::AsyncReadExt,
     net::{TcpListener, TcpStream},
-    sync::mpsc,
+    sync::{OwnedSemaphorePermit, Semaphore, mpsc},
     task::JoinSet,
+    time::Instant,
 };
 use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
+
+pub use self::{
+    error::Error,
+    metrics::{ForwarderMetrics, MetricsRegistry},
+    socks::{Connector, SocksProxy, TargetAddr},
+};
+
+/// A notification about the lifetime of a single bridged connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionEvent {
+    /// A local client has been bridged to the remote Pod.
+    Opened {
+        /// The address of the local peer.
+        peer: SocketAddr,
+    },
+    /// A bridged connection has been closed, by either side or by shutdown.
+    Closed {
+        /// The address of the local peer.
+        peer: SocketAddr,
+    },
+}
 
-pub use self::error::Error;
+/// What the forwarder does with new connections while the maximum number of
+/// concurrent connections is reached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ConnectionLimitPolicy {
+    /// New connections wait until a connection closes.
+    #[default]
+    Queue,
+    /// New connections are closed right away.
+    Reject,
+}
+
+/// The delay between two test connections while waiting for the remote port.
+const PROBE_RETRY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long a test connection must stay open without error to be considered
+/// accepted by the remote port.
+const PROBE_SETTLE_TIME: Duration = Duration::from_millis(500);
+
+/// A callback invoked for every [`ConnectionEvent`].
+type ConnectionCallback = Arc<dyn Fn(ConnectionEvent) + Send + Sync>;
 
 /// Internal events that drive the `PortForwarder`'s main loop.
 enum Event {
@@ -78,6 +128,8 @@ enum Event {
         stream: TcpStream,
         /// The address of the peer that initiated the connection.
         peer: SocketAddr,
+        /// The slot of the connection among the concurrent connections.
+        permit: ConnectionPermit,
     },
     /// Signals the port forwarder to clean up any completed or failed
     /// connections.
@@ -101,6 +153,15 @@ where
     /// An optional callback function executed once the local listener is ready.
     /// It receives the actual local address the forwarder is listening on.
     on_ready: Option<F>,
+    /// An optional callback notified whenever a connection opens or closes.
+    on_connection: Option<ConnectionCallback>,
+    /// The counters updated by the forwarder, if metrics are enabled.
+    metrics: Option<Arc<ForwarderMetrics>>,
+    /// If set, the remote port must accept a test connection within this time
+    /// before the forwarder is reported as ready.
+    port_ready_timeout: Option<Duration>,
+    /// Caps the number of concurrent connections.
+    connection_limiter: ConnectionLimiter,
     /// A set of spawned Tokio tasks managing individual connections and
     /// internal operations.
     join_set: JoinSet<Result<(), Error>>,
@@ -123,6 +184,17 @@ pub struct PortForwarderBuilder<F> {
     /// An optional callback function to be executed once the local listener is
     /// ready.
     on_ready: Option<F>,
+    /// An optional callback notified whenever a connection opens or closes.
+    on_connection: Option<ConnectionCallback>,
+    /// The counters updated by the forwarder, if metrics are enabled.
+    metrics: Option<Arc<ForwarderMetrics>>,
+    /// If set, the remote port must accept a test connection within this time
+    /// before the forwarder is reported as ready.
+    port_ready_timeout: Option<Duration>,
+    /// The maximum number of concurrent connections, if any.
+    max_connections: Option<NonZeroUsize>,
+    /// What to do with new connections while `max_connections` is reached.
+    connection_limit_policy: ConnectionLimitPolicy,
 }
 
 impl<F> PortForwarderBuilder<F> {
@@ -153,7 +225,18 @@ impl<F> PortForwarderBuilder<F> {
     /// }
     /// ```
     pub fn new(api: Api<Pod>, pod_name: impl Into<String>, remote_port: u16) -> Self {
-        Self { api, pod_name: pod_name.into(), remote_port, local_addr: None, on_ready: None }
+        Self {
+            api,
+            pod_name: pod_name.into(),
+            remote_port,
+            local_addr: None,
+            on_ready: None,
+            on_connection: None,
+            metrics: None,
+            port_ready_timeout: None,
+            max_connections: None,
+            connection_limit_policy: ConnectionLimitPolicy::Queue,
+        }
     }
 
     /// Sets the local address for the port forwarder to bind to.
@@ -190,6 +273,96 @@ impl<F> PortForwarderBuilder<F> {
         self.local_addr = Some(addr);
         self
     }
+
+    /// Sets a callback function to be notified whenever a local connection is
+    /// bridged to the Pod or closed.
+    ///
+    /// The callback is invoked from the task serving the connection, so it
+    /// should return quickly.
+    ///
+    /// # Arguments
+    ///
+    /// * `callback` - A closure that takes a `ConnectionEvent`.
+    ///
+    /// # Returns
+    ///
+    /// The modified `PortForwarderBuilder` instance.
+    pub fn on_connection(
+        mut self,
+        callback: impl Fn(ConnectionEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_connection = Some(Arc::new(callback));
+        self
+    }
+
+    /// Makes the forwarder update `metrics` for every connection it bridges.
+    ///
+    /// # Arguments
+    ///
+    /// * `metrics` - The counters of the forwarder, usually obtained from a
+    ///   `MetricsRegistry`.
+    ///
+    /// # Returns
+    ///
+    /// The modified `PortForwarderBuilder` instance.
+    #[must_use]
+    pub fn metrics(mut self, metrics: Arc<ForwarderMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Makes the forwarder verify that the remote port accepts connections
+    /// before invoking the `on_ready` callback.
+    ///
+    /// Test connections are made through the tunnel until one succeeds or
+    /// `timeout` elapses, in which case `run` fails with
+    /// `Error::PodPortUnreachable`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum time to wait for the remote port.
+    ///
+    /// # Returns
+    ///
+    /// The modified `PortForwarderBuilder` instance.
+    pub const fn wait_for_port(mut self, timeout: Duration) -> Self {
+        self.port_ready_timeout = Some(timeout);
+        self
+    }
+
+    /// Caps the number of connections bridged to the Pod at the same time.
+    ///
+    /// What happens to new connections while the cap is reached is set with
+    /// [`PortForwarderBuilder::connection_limit_policy`]; by default they are
+    /// queued.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_connections` - The maximum number of concurrent connections,
+    ///   non-zero so that the forwarder can accept at least one.
+    ///
+    /// # Returns
+    ///
+    /// The modified `PortForwarderBuilder` instance.
+    pub const fn max_connections(mut self, max_connections: NonZeroUsize) -> Self {
+        self.max_connections = Some(max_connections);
+        self
+    }
+
+    /// Sets what happens to new connections while the maximum number of
+    /// concurrent connections is reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `policy` - Whether to queue or reject the connections.
+    ///
+    /// # Returns
+    ///
+    /// The modified `PortForwarderBuilder` instance.
+    pub const fn connection_limit_policy(mut self, policy: ConnectionLimitPolicy) -> Self {
+        self.connection_limit_policy = policy;
+        self
+    }
 }
 
 impl<F> PortForwarderBuilder<F>
@@ -234,6 +407,11 @@ where
             local_addr: self.local_addr,
             remote_port: self.remote_port,
             on_ready: Some(callback),
+            on_connection: self.on_connection,
+            metrics: self.metrics,
+            port_ready_timeout: self.port_ready_timeout,
+            max_connections: self.max_connections,
+            connection_limit_policy: self.connection_limit_policy,
         }
     }
 
@@ -261,10 +439,32 @@ where
     /// }
     /// ```
     pub fn build(self) -> PortForwarder<F> {
-        let Self { api, pod_name, local_addr, remote_port, on_ready } = self;
+        let Self {
+            api,
+            pod_name,
+            local_addr,
+            remote_port,
+            on_ready,
+            on_connection,
+            metrics,
+            port_ready_timeout,
+            max_connections,
+            connection_limit_policy,
+        } = self;
         let local_addr =
             local_addr.unwrap_or_else(|| SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0));
-        PortForwarder { api, pod_name, local_addr, remote_port, on_ready, join_set: JoinSet::new() }
+        PortForwarder {
+            api,
+            pod_name,
+            local_addr,
+            remote_port,
+            on_ready,
+            on_connection,
+            metrics,
+            port_ready_timeout,
+            connection_limiter: ConnectionLimiter::new(max_connections, connection_limit_policy),
+            join_set: JoinSet::new(),
+        }
     }
 }
 
@@ -297,6 +497,10 @@ where
     /// * `Error::BindTcpSocket { socket_address }`: If the local TCP listener
     ///   cannot bind to the specified `local_addr` or determine its
     ///   `local_addr`.
+    /// * `Error::AddressNotAvailable { socket_address }`: If `local_addr` is
+    ///   not assigned to any network interface of this host.
+    /// * `Error::PodPortUnreachable { .. }`: If waiting for the remote port was
+    ///   requested and it did not accept a test connection in time.
     /// * Any errors originating from the `kube` client during port-forwarding
     ///   setup or connection handling are propagated as `Error::KubeError`.
     /// * Any `io::Error` during bidirectional copying of data between streams
@@ -351,11 +555,20 @@ where
         self,
         shutdown_signal: impl Future<Output = ()> + Send + Unpin + 'static,
     ) -> Result<(), Error> {
-        let Self { api, pod_name, local_addr, remote_port, on_ready, mut join_set } = self;
+        let Self {
+            api,
+            pod_name,
+            local_addr,
+            remote_port,
+            on_ready,
+            on_connection,
+            metrics,
+            port_ready_timeout,
+            connection_limiter,
+            mut join_set,
+        } = self;
 
-        let listener = TcpListener::bind(&local_addr)
-            .await
-            .with_context(|_| error::BindTcpSocketSnafu { socket_address: local_addr })?;
+        let listener = bind_listener(local_addr).await?;
 
         let actual_addr = listener
             .local_addr()
@@ -363,6 +576,10 @@ where
 
         tracing::info!("Forwarding from: {actual_addr} -> {pod_name}:{remote_port}");
 
+        if let Some(timeout) = port_ready_timeout {
+            wait_for_remote_port(&api, &pod_name, remote_port, timeout).await?;
+        }
+
         if let Some(on_ready) = on_ready {
             on_ready(actual_addr);
         }
@@ -398,14 +615,23 @@ where
 
             async move {
                 loop {
+                    // Leaves new connections in the listen backlog while the
+                    // limit is reached, if they are to be queued
+                    let reserved = tokio::select! {
+                        () = token_accept.cancelled() => break,
+                        reserved = connection_limiter.reserve() => reserved,
+                    };
                     let conn = tokio::select! {
                         () = token_accept.cancelled() => break,
                         conn = listener.accept() => conn,
                     };
 
-                    if let Ok((stream, peer)) = conn
-                        && event_sender.send(Event::NewConnection { stream, peer }).is_err()
-                    {
+                    let Ok((stream, peer)) = conn else { continue };
+                    let Some(permit) = connection_limiter.admit(reserved) else {
+                        tracing::warn!("Rejecting connection from {peer}, too many connections");
+                        continue;
+                    };
+                    if event_sender.send(Event::NewConnection { stream, peer, permit }).is_err() {
                         break;
                     }
                 }
@@ -441,6 +667,8 @@ where
             remote_port,
             actual_addr,
             cancel_token: cancel_token.clone(),
+            on_connection,
+            metrics,
         };
 
         while let Some(event) = event_receiver.recv().await {
@@ -458,9 +686,9 @@ where
                         }
                     }
                 }
-                Event::NewConnection { stream, peer } => {
-                    let _unused =
-                        join_set.spawn(connection_handler_factory.create().handle(stream, peer));
+                Event::NewConnection { stream, peer, permit } => {
+                    let _unused = join_set
+                        .spawn(connection_handler_factory.create().handle(stream, peer, permit));
                 }
             }
         }
@@ -483,6 +711,127 @@ where
     }
 }
 
+/// Caps the number of connections a forwarder serves at the same time.
+#[derive(Clone)]
+struct ConnectionLimiter {
+    /// The free connection slots, or `None` if connections are not limited.
+    semaphore: Option<Arc<Semaphore>>,
+    /// What to do with new connections while no slot is free.
+    policy: ConnectionLimitPolicy,
+}
+
+/// The slot of a connection, released when the connection is dropped.
+struct ConnectionPermit {
+    /// The permit of the slot, `None` if connections are not limited.
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl ConnectionLimiter {
+    /// Creates a limiter allowing `max_connections` concurrent connections, or
+    /// any number of them if `None`.
+    fn new(max_connections: Option<NonZeroUsize>, policy: ConnectionLimitPolicy) -> Self {
+        Self { semaphore: max_connections.map(|max| Arc::new(Semaphore::new(max.get()))), policy }
+    }
+
+    /// Reserves a slot before a connection is accepted.
+    ///
+    /// With `ConnectionLimitPolicy::Queue`, this waits until a slot is free;
+    /// otherwise nothing is reserved.
+    async fn reserve(&self) -> Option<OwnedSemaphorePermit> {
+        match (&self.semaphore, self.policy) {
+            (Some(semaphore), ConnectionLimitPolicy::Queue) => {
+                Arc::clone(semaphore).acquire_owned().await.ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// Admits an accepted connection, using the slot `reserved` for it if any.
+    ///
+    /// Returns `None` if the connection must be rejected because no slot is
+    /// free.
+    fn admit(&self, reserved: Option<OwnedSemaphorePermit>) -> Option<ConnectionPermit> {
+        let permit = match (&self.semaphore, reserved) {
+            (None, _) => None,
+            (Some(_), Some(reserved)) => Some(reserved),
+            (Some(semaphore), None) => Some(Arc::clone(semaphore).try_acquire_owned().ok()?),
+        };
+        Some(ConnectionPermit { _permit: permit })
+    }
+}
+
+/// Binds a TCP listener on `local_addr`.
+///
+/// # Errors
+///
+/// Returns `Error::AddressNotAvailable` if the address is not assigned to this
+/// host, or `Error::BindTcpSocket` if it cannot be bound for another reason.
+async fn bind_listener(local_addr: SocketAddr) -> Result<TcpListener, Error> {
+    TcpListener::bind(&local_addr).await.map_err(|source| {
+        if source.kind() == io::ErrorKind::AddrNotAvailable {
+            error::AddressNotAvailableSnafu { socket_address: local_addr }.build()
+        } else {
+            error::BindTcpSocketSnafu { socket_address: local_addr }.into_error(source)
+        }
+    })
+}
+
+/// Waits until `remote_port` of the pod accepts a test connection through the
+/// tunnel, retrying until `timeout` elapses.
+async fn wait_for_remote_port(
+    api: &Api<Pod>,
+    pod_name: &str,
+    remote_port: u16,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let reason =
+            match tokio::time::timeout_at(deadline, probe_remote_port(api, pod_name, remote_port))
+                .await
+            {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(reason)) => reason,
+                Err(_elapsed) => "the test connection timed out".to_string(),
+            };
+        tracing::debug!("Port {remote_port} of pod {pod_name} is not reachable yet: {reason}");
+
+        if Instant::now() + PROBE_RETRY_INTERVAL >= deadline {
+            return error::PodPortUnreachableSnafu { pod_name, remote_port, timeout, reason }
+                .fail();
+        }
+        tokio::time::sleep(PROBE_RETRY_INTERVAL).await;
+    }
+}
+
+/// Makes a single test connection to `remote_port` of the pod.
+///
+/// The connection counts as accepted if it stays open without error for
+/// `PROBE_SETTLE_TIME`, or if the pod sends data first.
+async fn probe_remote_port(api: &Api<Pod>, pod_name: &str, remote_port: u16) -> Result<(), String> {
+    let mut forwarder =
+        api.portforward(pod_name, &[remote_port]).await.map_err(|err| err.to_string())?;
+    let (Some(mut stream), Some(error)) =
+        (forwarder.take_stream(remote_port), forwarder.take_error(remote_port))
+    else {
+        forwarder.abort();
+        return Err("no port-forward stream was created".to_string());
+    };
+
+    let mut buf = [0; 1];
+    let result = tokio::select! {
+        Some(message) = error => Err(message),
+        read = stream.read(&mut buf) => match read {
+            Ok(0) => Err("the connection was closed by the pod".to_string()),
+            Ok(_) => Ok(()),
+            Err(err) => Err(err.to_string()),
+        },
+        () = tokio::time::sleep(PROBE_SETTLE_TIME) => Ok(()),
+    };
+    forwarder.abort();
+    result
+}
+
 /// Encapsulates the configuration and logic needed to bridge a single local TCP
 /// connection to a Kubernetes Pod's port-forwarding stream.
 #[derive(Clone)]
@@ -497,6 +846,10 @@ struct ConnectionHandler {
     actual_addr: SocketAddr,
     /// A cancellation token to signal immediate shutdown to active connections.
     cancel_token: CancellationToken,
+    /// An optional callback notified whenever the connection opens or closes.
+    on_connection: Option<ConnectionCallback>,
+    /// The counters updated for the connection, if metrics are enabled.
+    metrics: Option<Arc<ForwarderMetrics>>,
 }
 
 impl ConnectionHandler {
@@ -526,7 +879,8 @@ impl ConnectionHandler {
     /// # let actual_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 8000);
     /// # let cancel_token = CancellationToken::new();
     /// let base_handler = ConnectionHandler {
-    ///     api, pod_name, remote_port, actual_addr, cancel_token
+    ///     api, pod_name, remote_port, actual_addr, cancel_token, on_connection: None,
+    ///     metrics: None,
     /// };
     /// let new_handler = base_handler.create();
     /// # Ok(())
@@ -543,10 +897,16 @@ impl ConnectionHandler {
     /// client stream and the Pod's stream. It respects the provided
     /// `cancel_token` for graceful shutdown.
     ///
+    /// Everything logged for the connection is recorded in a `connection`
+    /// span whose `stream_id` field identifies it among concurrent
+    /// connections.
+    ///
     /// # Arguments
     ///
     /// * `local_stream` - The incoming local `TcpStream` from the client.
     /// * `peer` - The `SocketAddr` of the connected local peer.
+    /// * `_permit` - The slot of the connection, released when the connection
+    ///   is closed.
     ///
     /// # Returns
     ///
@@ -584,16 +944,34 @@ impl ConnectionHandler {
     /// # let (mut local_stream, _) = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().accept().await.unwrap();
     /// # let peer = local_stream.peer_addr().unwrap();
     /// let handler = ConnectionHandler {
-    ///     api, pod_name, remote_port, actual_addr, cancel_token
+    ///     api, pod_name, remote_port, actual_addr, cancel_token, on_connection: None,
+    ///     metrics: None,
     /// };
-    /// handler.handle(local_stream, peer).await?;
+    /// # let permit = ConnectionLimiter::new(None, ConnectionLimitPolicy::Queue).admit(None).unwrap();
+    /// handler.handle(local_stream, peer, permit).await?;
     /// # Ok(())
     /// # }
     /// ```
-    async fn handle(self, mut local_stream: TcpStream, peer: SocketAddr) -> Result<(), Error> {
-        let Self { api, pod_name, remote_port, actual_addr, cancel_token } = self;
+    async fn handle(
+        self,
+        local_stream: TcpStream,
+        peer: SocketAddr,
+        permit: ConnectionPermit,
+    ) -> Result<(), Error> {
+        let stream_id = format!("stream-{}-{}", self.actual_addr, peer.port());
+        let span = tracing::info_span!("connection", %stream_id, %peer);
+        self.bridge(local_stream, peer, permit, stream_id).instrument(span).await
+    }
 
-        let stream_id = format!("stream-{actual_addr}-{}", peer.port());
+    /// Bridges the connection of `handle`, identified by `stream_id`.
+    async fn bridge(
+        self,
+        mut local_stream: TcpStream,
+        peer: SocketAddr,
+        _permit: ConnectionPermit,
+        stream_id: String,
+    ) -> Result<(), Error> {
+        let Self { api, pod_name, remote_port, cancel_token, on_connection, metrics, .. } = self;
 
         // Establish the Kubernetes Portforward stream
         let pf_res = api
@@ -607,21 +985,85 @@ impl ConnectionHandler {
                 // Port forward stream not found, connection ignored.
                 return Ok(());
             }
-            Err(source) => return Err(error::CreatePodStreamSnafu { stream_id }.into_error(source)),
+            Err(source) => {
+                if let Some(metrics) = &metrics {
+                    metrics.connection_failed();
+                }
+                return Err(error::CreatePodStreamSnafu { stream_id }.into_error(source));
+            }
         };
 
         tracing::info!("Bridging connection: {peer} <-> {pod_name}:{remote_port}");
+        if let Some(on_connection) = &on_connection {
+            on_connection(ConnectionEvent::Opened { peer });
+        }
+        if let Some(metrics) = &metrics {
+            metrics.connection_opened();
+        }
 
-        tokio::select! {
+        let (sent, received) = tokio::select! {
             () = cancel_token.cancelled() => {
                 tracing::debug!("Closing connection {peer} due to shutdown");
+                (0, 0)
             }
             res = tokio::io::copy_bidirectional(&mut local_stream, &mut pod_stream) => {
-                if let Err(err) = res {
+                res.unwrap_or_else(|err| {
                     tracing::debug!("Connection {peer} closed with error: {err}");
-                }
+                    if let Some(metrics) = &metrics {
+                        metrics.connection_failed();
+                    }
+                    (0, 0)
+                })
             }
+        };
+        if let Some(metrics) = &metrics {
+            metrics.connection_closed(sent, received);
+        }
+        if let Some(on_connection) = &on_connection {
+            on_connection(ConnectionEvent::Closed { peer });
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_limiter_rejects_over_limit() {
+        let limiter = ConnectionLimiter::new(NonZeroUsize::new(2), ConnectionLimitPolicy::Reject);
+        let first = limiter.admit(limiter.reserve().await).expect("a free slot");
+        let _second = limiter.admit(limiter.reserve().await).expect("a free slot");
+        assert!(limiter.admit(limiter.reserve().await).is_none());
+
+        drop(first);
+        assert!(limiter.admit(limiter.reserve().await).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_connection_limiter_queues_over_limit() {
+        let limiter = ConnectionLimiter::new(NonZeroUsize::new(1), ConnectionLimitPolicy::Queue);
+        let first = limiter.admit(limiter.reserve().await).expect("a free slot");
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), limiter.reserve()).await.is_err(),
+            "no slot must be reserved while the limit is reached"
+        );
+
+        drop(first);
+        let reserved = tokio::time::timeout(Duration::from_millis(50), limiter.reserve())
+            .await
+            .expect("the released slot");
+        assert!(limiter.admit(reserved).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_connection_limiter_unlimited() {
+        let limiter = ConnectionLimiter::new(None, ConnectionLimitPolicy::Reject);
+        let mut permits = Vec::new();
+        for _ in 0..100 {
+            permits.push(limiter.admit(None).expect("no limit"));
+        }
+        drop(permits);
+    }
+}
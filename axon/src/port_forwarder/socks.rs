@@ -0,0 +1,391 @@
+//! A local SOCKS5 proxy, the building block of dynamic port forwarding.
+//!
+//! The proxy accepts SOCKS5 `CONNECT` requests without authentication, as
+//! `ssh -D` does, and hands every requested target to a [`Connector`], which
+//! decides how the outbound connection is made. Bridging the connector
+//! through a pod lets arbitrary traffic leave from the pod's network
+//! namespace.
+
+use std::{
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+use snafu::{IntoError, ResultExt};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpStream,
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+
+use crate::port_forwarder::{ConnectionCallback, ConnectionEvent, Error, bind_listener, error};
+
+/// The version byte of the SOCKS5 protocol.
+const SOCKS_VERSION: u8 = 0x05;
+
+/// The authentication method requiring no authentication.
+const METHOD_NO_AUTHENTICATION: u8 = 0x00;
+
+/// The reply to a method negotiation when none of the offered methods is
+/// acceptable.
+const METHOD_NOT_ACCEPTABLE: u8 = 0xff;
+
+/// The `CONNECT` command, the only command supported by the proxy.
+const COMMAND_CONNECT: u8 = 0x01;
+
+/// The address type of an IPv4 address.
+const ADDRESS_TYPE_IPV4: u8 = 0x01;
+
+/// The address type of a domain name.
+const ADDRESS_TYPE_DOMAIN: u8 = 0x03;
+
+/// The address type of an IPv6 address.
+const ADDRESS_TYPE_IPV6: u8 = 0x04;
+
+/// The destination requested by a SOCKS5 client.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TargetAddr {
+    /// A literal IP address and port.
+    Ip(SocketAddr),
+    /// A domain name, resolved on the far side of the tunnel, and a port.
+    Domain(String, u16),
+}
+
+impl TargetAddr {
+    /// Returns the host part of the target, an IP address or a domain name.
+    pub fn host(&self) -> String {
+        match self {
+            Self::Ip(addr) => addr.ip().to_string(),
+            Self::Domain(domain, _) => domain.clone(),
+        }
+    }
+
+    /// Returns the port of the target.
+    pub const fn port(&self) -> u16 {
+        match self {
+            Self::Ip(addr) => addr.port(),
+            Self::Domain(_, port) => *port,
+        }
+    }
+}
+
+impl fmt::Display for TargetAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ip(addr) => write!(f, "{addr}"),
+            Self::Domain(domain, port) => write!(f, "{domain}:{port}"),
+        }
+    }
+}
+
+/// Opens the outbound connections requested through the proxy.
+pub trait Connector: Clone + Send + Sync + 'static {
+    /// The stream of an outbound connection.
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send;
+
+    /// Connects to `target` on behalf of the local client `peer`.
+    fn connect(
+        &self,
+        target: &TargetAddr,
+        peer: SocketAddr,
+    ) -> impl Future<Output = io::Result<Self::Stream>> + Send;
+}
+
+/// The reply codes sent to a client in answer to its request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+enum Reply {
+    /// The connection to the target has been established.
+    Succeeded = 0x00,
+    /// The target could not be reached.
+    HostUnreachable = 0x04,
+    /// The requested command is not supported.
+    CommandNotSupported = 0x07,
+    /// The requested address type is not supported.
+    AddressTypeNotSupported = 0x08,
+}
+
+/// A SOCKS5 proxy listening on a local address.
+pub struct SocksProxy<C> {
+    /// The local address that the proxy will bind to and listen on.
+    local_addr: SocketAddr,
+    /// The connector opening the outbound connections.
+    connector: C,
+    /// An optional callback notified whenever a connection opens or closes.
+    on_connection: Option<ConnectionCallback>,
+}
+
+impl<C> SocksProxy<C>
+where
+    C: Connector,
+{
+    /// Creates a new proxy listening on `local_addr` and opening its outbound
+    /// connections with `connector`.
+    pub const fn new(local_addr: SocketAddr, connector: C) -> Self {
+        Self { local_addr, connector, on_connection: None }
+    }
+
+    /// Sets a callback notified whenever a proxied connection is opened or
+    /// closed.
+    #[must_use]
+    pub fn on_connection(
+        mut self,
+        callback: impl Fn(ConnectionEvent) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_connection = Some(Arc::new(callback));
+        self
+    }
+
+    /// Runs the proxy until `shutdown_signal` completes, then closes all
+    /// proxied connections.
+    ///
+    /// `on_ready` is called with the address the proxy is listening on once
+    /// the listener is bound.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the local address cannot be bound. Failures of
+    /// individual connections are logged and do not stop the proxy.
+    pub async fn run(
+        self,
+        on_ready: impl FnOnce(SocketAddr),
+        shutdown_signal: impl Future<Output = ()>,
+    ) -> Result<(), Error> {
+        let Self { local_addr, connector, on_connection } = self;
+        let listener = bind_listener(local_addr).await?;
+        let actual_addr = listener
+            .local_addr()
+            .with_context(|_| error::BindTcpSocketSnafu { socket_address: local_addr })?;
+        tracing::info!("SOCKS5 proxy listening on {actual_addr}");
+        on_ready(actual_addr);
+
+        let cancel_token = CancellationToken::new();
+        let mut join_set = JoinSet::new();
+        tokio::pin!(shutdown_signal);
+        loop {
+            let (stream, peer) = tokio::select! {
+                () = &mut shutdown_signal => break,
+                Some(result) = join_set.join_next(), if !join_set.is_empty() => {
+                    if let Ok(Err(err)) = result {
+                        tracing::warn!("{err}");
+                    }
+                    continue;
+                }
+                conn = listener.accept() => match conn {
+                    Ok(conn) => conn,
+                    Err(err) => {
+                        tracing::debug!("Failed to accept a SOCKS5 connection: {err}");
+                        continue;
+                    }
+                },
+            };
+            let _unused = join_set.spawn(handle_connection(
+                stream,
+                peer,
+                connector.clone(),
+                cancel_token.clone(),
+                on_connection.clone(),
+            ));
+        }
+
+        tracing::info!("Closing all SOCKS5 connections...");
+        cancel_token.cancel();
+        while let Some(result) = join_set.join_next().await {
+            if let Ok(Err(err)) = result {
+                tracing::warn!("{err}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Serves a single SOCKS5 client: negotiates the request, connects to the
+/// target and bridges both streams until either side closes or the proxy
+/// shuts down.
+async fn handle_connection<C: Connector>(
+    mut local_stream: TcpStream,
+    peer: SocketAddr,
+    connector: C,
+    cancel_token: CancellationToken,
+    on_connection: Option<ConnectionCallback>,
+) -> Result<(), Error> {
+    let target = accept_request(&mut local_stream)
+        .await
+        .with_context(|_| error::SocksHandshakeSnafu { peer })?;
+
+    let mut remote_stream = match connector.connect(&target, peer).await {
+        Ok(stream) => stream,
+        Err(source) => {
+            drop(send_reply(&mut local_stream, Reply::HostUnreachable).await);
+            return Err(error::SocksConnectSnafu { target: target.to_string() }.into_error(source));
+        }
+    };
+    send_reply(&mut local_stream, Reply::Succeeded)
+        .await
+        .with_context(|_| error::SocksHandshakeSnafu { peer })?;
+
+    tracing::info!("Bridging SOCKS5 connection: {peer} <-> {target}");
+    if let Some(on_connection) = &on_connection {
+        on_connection(ConnectionEvent::Opened { peer });
+    }
+    tokio::select! {
+        () = cancel_token.cancelled() => {
+            tracing::debug!("Closing connection {peer} due to shutdown");
+        }
+        res = tokio::io::copy_bidirectional(&mut local_stream, &mut remote_stream) => {
+            if let Err(err) = res {
+                tracing::debug!("Connection {peer} closed with error: {err}");
+            }
+        }
+    }
+    if let Some(on_connection) = &on_connection {
+        on_connection(ConnectionEvent::Closed { peer });
+    }
+    Ok(())
+}
+
+/// Performs the SOCKS5 method negotiation and reads the client's request.
+///
+/// Only clients offering the "no authentication" method and sending a
+/// `CONNECT` request are accepted; any other client is sent the matching
+/// failure reply.
+///
+/// # Errors
+///
+/// Returns an `io::Error` if the stream fails or the client does not follow
+/// the protocol.
+async fn accept_request<S>(stream: &mut S) -> io::Result<TargetAddr>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let [version, method_count] = read_array(stream).await?;
+    ensure_version(version)?;
+    let mut methods = vec![0; usize::from(method_count)];
+    let _ = stream.read_exact(&mut methods).await?;
+    if !methods.contains(&METHOD_NO_AUTHENTICATION) {
+        stream.write_all(&[SOCKS_VERSION, METHOD_NOT_ACCEPTABLE]).await?;
+        return Err(protocol_error("the client requires authentication"));
+    }
+    stream.write_all(&[SOCKS_VERSION, METHOD_NO_AUTHENTICATION]).await?;
+
+    let [version, command, _reserved, address_type] = read_array(stream).await?;
+    ensure_version(version)?;
+    if command != COMMAND_CONNECT {
+        send_reply(stream, Reply::CommandNotSupported).await?;
+        return Err(protocol_error(format!("unsupported command {command:#04x}")));
+    }
+    let target = match address_type {
+        ADDRESS_TYPE_IPV4 => {
+            let ip = Ipv4Addr::from(read_array::<_, 4>(stream).await?);
+            TargetAddr::Ip(SocketAddr::new(IpAddr::V4(ip), read_port(stream).await?))
+        }
+        ADDRESS_TYPE_IPV6 => {
+            let ip = Ipv6Addr::from(read_array::<_, 16>(stream).await?);
+            TargetAddr::Ip(SocketAddr::new(IpAddr::V6(ip), read_port(stream).await?))
+        }
+        ADDRESS_TYPE_DOMAIN => {
+            let [length] = read_array(stream).await?;
+            let mut domain = vec![0; usize::from(length)];
+            let _ = stream.read_exact(&mut domain).await?;
+            let domain = String::from_utf8(domain)
+                .map_err(|_| protocol_error("the domain name is not valid UTF-8"))?;
+            TargetAddr::Domain(domain, read_port(stream).await?)
+        }
+        _ => {
+            send_reply(stream, Reply::AddressTypeNotSupported).await?;
+            return Err(protocol_error(format!("unsupported address type {address_type:#04x}")));
+        }
+    };
+    Ok(target)
+}
+
+/// Sends `reply` to the client.
+///
+/// The bound address of the reply is left unspecified, as the outbound
+/// connection is made on the far side of the tunnel.
+async fn send_reply<S>(stream: &mut S, reply: Reply) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    stream.write_all(&[SOCKS_VERSION, reply as u8, 0x00, ADDRESS_TYPE_IPV4, 0, 0, 0, 0, 0, 0]).await
+}
+
+/// Reads exactly `N` bytes from `stream`.
+async fn read_array<S, const N: usize>(stream: &mut S) -> io::Result<[u8; N]>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buf = [0; N];
+    let _ = stream.read_exact(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Reads a port in network byte order from `stream`.
+async fn read_port<S>(stream: &mut S) -> io::Result<u16>
+where
+    S: AsyncRead + Unpin,
+{
+    read_array(stream).await.map(u16::from_be_bytes)
+}
+
+/// Fails unless `version` is the SOCKS5 version byte.
+fn ensure_version(version: u8) -> io::Result<()> {
+    if version == SOCKS_VERSION {
+        Ok(())
+    } else {
+        Err(protocol_error(format!("unsupported SOCKS version {version}")))
+    }
+}
+
+/// Creates the error reported for a client that does not follow the protocol.
+fn protocol_error(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::duplex;
+
+    use super::*;
+
+    /// Runs `accept_request` against a client sending `request`, returning the
+    /// result and every byte the proxy answered with.
+    async fn negotiate(request: &[u8]) -> (io::Result<TargetAddr>, Vec<u8>) {
+        let (mut client, mut server) = duplex(1024);
+        client.write_all(request).await.unwrap();
+        let result = accept_request(&mut server).await;
+        drop(server);
+        let mut response = Vec::new();
+        let _ = client.read_to_end(&mut response).await.unwrap();
+        (result, response)
+    }
+
+    #[tokio::test]
+    async fn test_accept_request() {
+        let (result, response) = negotiate(&[5, 1, 0, 5, 1, 0, 1, 10, 0, 0, 1, 0x1f, 0x90]).await;
+        assert_eq!(result.unwrap(), TargetAddr::Ip(SocketAddr::from(([10, 0, 0, 1], 8080))));
+        assert_eq!(response, [5, 0]);
+
+        let mut request = vec![5, 2, 2, 0, 5, 1, 0, 3, 11];
+        request.extend_from_slice(b"example.com");
+        request.extend_from_slice(&443_u16.to_be_bytes());
+        let (result, _) = negotiate(&request).await;
+        assert_eq!(result.unwrap(), TargetAddr::Domain("example.com".to_string(), 443));
+    }
+
+    #[tokio::test]
+    async fn test_accept_request_rejects_unsupported_requests() {
+        let (result, response) = negotiate(&[5, 1, 2]).await;
+        assert!(result.is_err());
+        assert_eq!(response, [5, METHOD_NOT_ACCEPTABLE]);
+
+        let (result, response) = negotiate(&[5, 1, 0, 5, 2, 0, 1, 10, 0, 0, 1, 0, 80]).await;
+        assert!(result.is_err());
+        assert_eq!(response[2..4], [5, Reply::CommandNotSupported as u8]);
+
+        let (result, _) = negotiate(&[4, 1, 0]).await;
+        assert!(result.is_err());
+    }
+}
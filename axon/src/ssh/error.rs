@@ -1,10 +1,13 @@
 //! This module defines the error types that can occur during SSH operations
 //! within the application.
 
-use std::{borrow::Cow, path::PathBuf};
+use std::{borrow::Cow, fmt, path::PathBuf};
 
+use russh_sftp::protocol::StatusCode;
 use snafu::Snafu;
 
+use crate::ssh::ByteRange;
+
 /// Represents the various errors that can occur during SSH operations,
 /// including connection issues, authentication failures, channel management,
 /// command execution, and SFTP transfers.
@@ -45,6 +48,23 @@ pub enum Error {
     #[snafu(display("Failed to read the local SSH private key file {}, error: {source}", file_path.display()))]
     ReadSshPrivateKey { file_path: PathBuf, source: std::io::Error },
 
+    /// Failed to generate an SSH key pair.
+    #[snafu(display("Failed to generate SSH key pair, error: {source}"))]
+    GenerateSshKeyPair { source: russh::keys::ssh_key::Error },
+
+    /// Failed to serialize the SSH private key in the OpenSSH format.
+    #[snafu(display("Failed to serialize SSH private key, error: {source}"))]
+    SerializeSshPrivateKey { source: russh::keys::ssh_key::Error },
+
+    /// Failed to write the local SSH private key file.
+    ///
+    /// # Fields
+    /// - `file_path`: The path to the private key file that could not be
+    ///   written.
+    /// - `source`: The underlying `std::io::Error`.
+    #[snafu(display("Failed to write the local SSH private key file {}, error: {source}", file_path.display()))]
+    WriteSshPrivateKey { file_path: PathBuf, source: std::io::Error },
+
     /// Failed to parse the provided SSH private key.
     ///
     /// This typically indicates an invalid key format.
@@ -89,6 +109,35 @@ pub enum Error {
     #[snafu(display("Failed to open a new SSH session channel, error: {source}"))]
     OpenChannel { source: russh::Error },
 
+    /// Failed to open a `direct-tcpip` channel, a connection made by the remote
+    /// host on behalf of the client.
+    ///
+    /// # Fields
+    /// - `target`: The requested destination, `HOST:PORT`.
+    /// - `source`: The underlying `russh::Error`.
+    #[snafu(display("Failed to open a tunnel to {target} over SSH, error: {source}"))]
+    OpenDirectTcpip { target: String, source: russh::Error },
+
+    /// Failed to have the remote host listen for connections to forward back.
+    ///
+    /// # Fields
+    /// - `address`: The remote address to listen on.
+    /// - `port`: The remote port to listen on.
+    /// - `source`: The underlying `russh::Error`.
+    #[snafu(display("Failed to listen on {address}:{port} on the remote host, error: {source}"))]
+    RequestRemoteForward { address: String, port: u16, source: russh::Error },
+
+    /// Failed to stop a remote listener.
+    ///
+    /// # Fields
+    /// - `address`: The remote address listened on.
+    /// - `port`: The remote port listened on.
+    /// - `source`: The underlying `russh::Error`.
+    #[snafu(display(
+        "Failed to stop listening on {address}:{port} on the remote host, error: {source}"
+    ))]
+    CancelRemoteForward { address: String, port: u16, source: russh::Error },
+
     /// Failed to request a PTY (pseudo-terminal) for the SSH session.
     ///
     /// # Fields
@@ -117,16 +166,6 @@ pub enum Error {
     #[snafu(display("Failed to close the SSH channel (EOF), error: {source}"))]
     CloseChannel { source: russh::Error },
 
-    /// Failed to determine the terminal size.
-    ///
-    /// This error occurs when attempting to get the dimensions of the local
-    /// terminal.
-    ///
-    /// # Fields
-    /// - `source`: The underlying `std::io::Error`.
-    #[snafu(display("Failed to determine terminal size, error: {source}"))]
-    GetTerminalSize { source: std::io::Error },
-
     /// Failed to initialize a standard I/O stream (e.g., stdin, stdout,
     /// stderr).
     ///
@@ -151,6 +190,13 @@ pub enum Error {
     #[snafu(display("Failed to read from standard input, error: {source}"))]
     ReadStdin { source: std::io::Error },
 
+    /// Failed to pipe data into or out of a remote command.
+    ///
+    /// # Fields
+    /// - `source`: The underlying `std::io::Error`.
+    #[snafu(display("Failed to pipe data to or from the remote command, error: {source}"))]
+    PipeCommandData { source: std::io::Error },
+
     /// Failed to disconnect the SSH session.
     ///
     /// # Fields
@@ -185,17 +231,125 @@ pub enum Error {
     /// # Fields
     /// - `path`: The path to the remote file that could not be opened.
     /// - `source`: The underlying `russh_sftp::client::error::Error`.
-    #[snafu(display("Failed to open remote file '{path}', error: {source}"))]
+    #[snafu(display("Failed to open remote file '{path}': {}", describe_sftp_error(source)))]
     OpenRemoteFile { path: String, source: russh_sftp::client::error::Error },
 
     /// Failed to transfer data for a file during SFTP.
     ///
-    /// This could occur during reading from a local file or writing to a remote
-    /// file.
+    /// This could occur during reading from or writing to the local file, or
+    /// during reading from or writing to the remote file.
     ///
     /// # Fields
-    /// - `path`: The path to the file involved in the failed transfer.
+    /// - `end`: The end of the transfer where the error occurred.
     /// - `source`: The underlying `std::io::Error`.
-    #[snafu(display("Failed to transfer data for '{}', error: {source}", path.display()))]
-    TransferData { path: PathBuf, source: std::io::Error },
+    #[snafu(display("Failed to transfer data for {end}, error: {source}"))]
+    TransferData { end: TransferEnd, source: std::io::Error },
+
+    /// A byte range to download extends past the end of the remote file.
+    ///
+    /// # Fields
+    /// - `path`: The path to the remote file.
+    /// - `range`: The requested range of bytes.
+    /// - `size`: The size of the remote file in bytes.
+    #[snafu(display("Byte range {range} exceeds the size of remote file '{path}' ({size} bytes)"))]
+    InvalidByteRange { path: String, range: ByteRange, size: u64 },
+}
+
+/// The end of a file transfer where an I/O error occurred.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TransferEnd {
+    /// The local file at the given path, or a local stream such as the
+    /// standard input or output if there is no path.
+    Local(Option<PathBuf>),
+    /// The remote file at the given path.
+    Remote(PathBuf),
+}
+
+impl fmt::Display for TransferEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(Some(path)) => write!(f, "local file '{}'", path.display()),
+            Self::Local(None) => f.write_str("local stream"),
+            Self::Remote(path) => write!(f, "remote file '{}'", path.display()),
+        }
+    }
+}
+
+/// Describes an SFTP client error in human-readable terms.
+///
+/// Common status codes reported by the SFTP server are mapped to plain text,
+/// followed by the server message if it adds anything. Other errors are
+/// displayed as is.
+fn describe_sftp_error(err: &russh_sftp::client::error::Error) -> String {
+    let russh_sftp::client::error::Error::Status(status) = err else {
+        return err.to_string();
+    };
+
+    let description = match status.status_code {
+        StatusCode::NoSuchFile => "no such file or directory",
+        StatusCode::PermissionDenied => "permission denied",
+        StatusCode::Failure => "the SFTP server reported a failure",
+        _ => return err.to_string(),
+    };
+
+    let message = status.error_message.trim();
+    if message.is_empty() || message.eq_ignore_ascii_case(&status.status_code.to_string()) {
+        description.to_string()
+    } else {
+        format!("{description} ({message})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use russh_sftp::protocol::Status;
+
+    use super::*;
+
+    fn open_remote_file_error(status_code: StatusCode, error_message: &str) -> Error {
+        let status = Status {
+            id: 0,
+            status_code,
+            error_message: error_message.to_string(),
+            language_tag: "en-US".to_string(),
+        };
+        Error::OpenRemoteFile { path: "/tmp/file.txt".to_string(), source: status.into() }
+    }
+
+    #[test]
+    fn test_open_remote_file_no_such_file() {
+        let err = open_remote_file_error(StatusCode::NoSuchFile, "No such file");
+        assert_eq!(
+            err.to_string(),
+            "Failed to open remote file '/tmp/file.txt': no such file or directory"
+        );
+    }
+
+    #[test]
+    fn test_open_remote_file_permission_denied() {
+        let err = open_remote_file_error(StatusCode::PermissionDenied, "Permission denied");
+        assert_eq!(
+            err.to_string(),
+            "Failed to open remote file '/tmp/file.txt': permission denied"
+        );
+    }
+
+    #[test]
+    fn test_open_remote_file_failure_includes_server_message() {
+        let err = open_remote_file_error(StatusCode::Failure, "Is a directory");
+        assert_eq!(
+            err.to_string(),
+            "Failed to open remote file '/tmp/file.txt': the SFTP server reported a failure (Is a \
+             directory)"
+        );
+    }
+
+    #[test]
+    fn test_open_remote_file_other_error() {
+        let err = Error::OpenRemoteFile {
+            path: "/tmp/file.txt".to_string(),
+            source: russh_sftp::client::error::Error::Timeout,
+        };
+        assert_eq!(err.to_string(), "Failed to open remote file '/tmp/file.txt': Timeout");
+    }
 }
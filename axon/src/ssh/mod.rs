@@ -1,18 +1,48 @@
 //! This module provides utilities for handling SSH keys and sessions.
 //!
 //! It includes functionality to load private keys from files, optionally
-//! deciphering them with a password, and to derive public keys. It also
-//! re-exports error types and session management.
+//! deciphering them with a password, to generate and save new key pairs, and
+//! to derive public keys. It also re-exports error types and session
+//! management.
 
 mod error;
 mod session;
+mod sftp;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-use russh::keys::PrivateKey;
+use clap::ValueEnum;
+use russh::keys::{Algorithm, EcdsaCurve, PrivateKey, key::safe_rng, ssh_key::LineEnding};
 use snafu::{OptionExt, ResultExt};
+use tokio::io::AsyncWriteExt;
 
-pub use self::{error::Error, session::Session};
+pub use self::{
+    error::Error,
+    session::{ByteRange, CommandOutcome, CommandOutput, ForwardedConnection, Session},
+};
+
+/// The private key files tried when no key file is given, in the `.ssh`
+/// directory of the home directory of the user.
+const DEFAULT_SSH_PRIVATE_KEY_FILES: [&str; 3] = ["id_ed25519", "id_rsa", "id_ecdsa"];
+
+/// The algorithm of a key pair generated with [`generate_key_pair`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum KeyAlgorithm {
+    /// An Ed25519 key.
+    #[default]
+    Ed25519,
+    /// An ECDSA key on the NIST P-256 curve.
+    Ecdsa,
+}
+
+impl From<KeyAlgorithm> for Algorithm {
+    fn from(algorithm: KeyAlgorithm) -> Self {
+        match algorithm {
+            KeyAlgorithm::Ed25519 => Self::Ed25519,
+            KeyAlgorithm::Ecdsa => Self::Ecdsa { curve: EcdsaCurve::NistP256 },
+        }
+    }
+}
 
 /// Loads a secret key from a file, optionally deciphering it with a password.
 ///
@@ -21,6 +51,9 @@ pub use self::{error::Error, session::Session};
 /// password is provided, it will be used to decipher the key. The comment of
 /// the loaded private key is set to an empty string.
 ///
+/// Ed25519, RSA and ECDSA keys are supported in the OpenSSH format, as well as
+/// unencrypted keys in the PKCS#8 format, which have no comment.
+///
 /// # Arguments
 ///
 /// * `secret_key_file_path` - The path to the file containing the secret key.
@@ -49,34 +82,80 @@ pub async fn load_secret_key<P: AsRef<Path>>(
         .to_string();
     russh::keys::decode_secret_key(&secret, password)
         .map(|mut secret_key| {
-            // Remove the comment
-            secret_key.set_comment(String::new());
+            // Remove the comment, if the format of the key has one at all. The key
+            // itself and its fingerprint are left unchanged.
+            if !secret_key.comment().is_empty() {
+                secret_key.set_comment(String::new());
+            }
             secret_key
         })
         .map_err(|_| error::ParseSshPrivateKeySnafu.build())
 }
 
-/// Resolves an SSH key pair by trying multiple file paths in order.
-///
-/// This function iterates through the provided paths, attempting to load each
-/// as an SSH private key using [`load_secret_key`]. The first successfully
-/// loaded key is returned along with its corresponding public key in OpenSSH
-/// format.
+/// Generates a new SSH key pair.
 ///
 /// # Arguments
 ///
-/// * `paths` - An iterable of file paths to try, in priority order.
+/// * `algorithm` - The algorithm of the key pair.
 ///
 /// # Errors
 ///
 /// This function returns an `Err` if:
 ///
-/// * None of the provided paths contain a valid SSH private key. The error will
-///   be of type `Error::ResolveIdentities`, containing all attempted paths and
-///   the last error encountered.
-/// * A valid key is found but its public key cannot be serialized to OpenSSH
-///   format. The error will be of type `error::SerializeSshPublicKeySnafu`.
+/// * The key pair cannot be generated. The error will be of type
+///   `error::GenerateSshKeyPairSnafu`.
+/// * The public key cannot be serialized to OpenSSH format. The error will be
+///   of type `error::SerializeSshPublicKeySnafu`.
+///
+/// # Returns
+///
+/// The private key along with its corresponding public key in OpenSSH format.
+pub fn generate_key_pair(algorithm: KeyAlgorithm) -> Result<(PrivateKey, String), Error> {
+    let private_key = PrivateKey::random(&mut safe_rng(), algorithm.into())
+        .context(error::GenerateSshKeyPairSnafu)?;
+    let public_key =
+        private_key.public_key().to_openssh().ok().context(error::SerializeSshPublicKeySnafu)?;
+    Ok((private_key, public_key))
+}
+
+/// Saves a secret key to a new file in the OpenSSH format.
+///
+/// The file is created readable and writable by its owner only, as required
+/// by SSH clients. An existing file is never overwritten.
+///
+/// # Arguments
+///
+/// * `secret_key_file_path` - The path of the file to create.
+/// * `secret_key` - The secret key to save.
+///
+/// # Errors
+///
+/// This function returns an `Err` if:
 ///
+/// * The key cannot be serialized. The error will be of type
+///   `error::SerializeSshPrivateKeySnafu`.
+/// * The file already exists or cannot be written. The error will be of type
+///   `error::WriteSshPrivateKeySnafu`.
+pub async fn save_secret_key<P: AsRef<Path>>(
+    secret_key_file_path: P,
+    secret_key: &PrivateKey,
+) -> Result<(), Error> {
+    let file_path = secret_key_file_path.as_ref();
+    let secret =
+        secret_key.to_openssh(LineEnding::LF).context(error::SerializeSshPrivateKeySnafu)?;
+    let mut file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(file_path)
+        .await
+        .with_context(|_| error::WriteSshPrivateKeySnafu { file_path })?;
+    file.write_all(secret.as_bytes())
+        .await
+        .with_context(|_| error::WriteSshPrivateKeySnafu { file_path })?;
+    file.flush().await.with_context(|_| error::WriteSshPrivateKeySnafu { file_path })
+}
+
 /// Resolves an SSH key pair by trying multiple file paths in order.
 ///
 /// This function iterates through the provided paths, attempting to load each
@@ -84,6 +163,9 @@ pub async fn load_secret_key<P: AsRef<Path>>(
 /// loaded key is returned along with its corresponding public key in OpenSSH
 /// format.
 ///
+/// If no path is provided, the conventional key files of the user are tried
+/// instead: `~/.ssh/id_ed25519`, `~/.ssh/id_rsa` and `~/.ssh/id_ecdsa`.
+///
 /// # Arguments
 ///
 /// * `paths` - An iterable of file paths to try, in priority order.
@@ -92,9 +174,11 @@ pub async fn load_secret_key<P: AsRef<Path>>(
 ///
 /// This function returns an `Err` if:
 ///
-/// * None of the provided paths contain a valid SSH private key. The error will
-///   be of type `Error::ResolveIdentities`, containing all attempted paths and
-///   the last error encountered.
+/// * None of the attempted paths contain a valid SSH private key. The error
+///   will be of type `Error::ResolveIdentities`, containing all attempted paths
+///   and the last error encountered.
+/// * No path is provided and the home directory of the user cannot be
+///   determined. The error will be of type `Error::NoSshPrivateKeyProvided`.
 /// * A valid key is found but its public key cannot be serialized to OpenSSH
 ///   format. The error will be of type `error::SerializeSshPublicKeySnafu`.
 pub async fn resolve_ssh_key_pair<I, P>(paths: I) -> Result<(PrivateKey, String), Error>
@@ -102,11 +186,15 @@ where
     I: IntoIterator<Item = P>,
     P: AsRef<Path>,
 {
+    let paths = candidate_key_paths(
+        paths.into_iter().map(|path| path.as_ref().to_path_buf()).collect(),
+        directories::UserDirs::new().as_ref().map(directories::UserDirs::home_dir),
+    );
     let mut last_error = None;
     let mut attempted_paths = Vec::new();
 
     for path in paths {
-        attempted_paths.push(path.as_ref().to_path_buf());
+        attempted_paths.push(path.clone());
 
         match load_secret_key(path, None).await {
             Ok(private_key) => {
@@ -121,8 +209,110 @@ where
         }
     }
 
-    Err(Error::ResolveIdentities {
-        paths: attempted_paths,
-        source: last_error.map(Box::new).expect("`last_error` must be Some"),
-    })
+    let source = last_error.context(error::NoSshPrivateKeyProvidedSnafu)?;
+    Err(Error::ResolveIdentities { paths: attempted_paths, source: Box::new(source) })
+}
+
+/// Returns the key files to try, the `explicit` ones if there are any, or
+/// else the conventional key files in `home_dir`.
+fn candidate_key_paths(explicit: Vec<PathBuf>, home_dir: Option<&Path>) -> Vec<PathBuf> {
+    if !explicit.is_empty() {
+        return explicit;
+    }
+    home_dir
+        .map(|home_dir| {
+            DEFAULT_SSH_PRIVATE_KEY_FILES
+                .iter()
+                .map(|file_name| home_dir.join(".ssh").join(file_name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use russh::keys::{HashAlg, PublicKey};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_generate_and_save_key_pair() {
+        for algorithm in [KeyAlgorithm::Ed25519, KeyAlgorithm::Ecdsa] {
+            let (private_key, public_key) = generate_key_pair(algorithm).unwrap();
+            assert_eq!(private_key.algorithm(), Algorithm::from(algorithm));
+
+            let file_path = std::env::temp_dir()
+                .join(format!("axon-test-key-{}-{algorithm:?}", std::process::id()));
+            save_secret_key(&file_path, &private_key).await.unwrap();
+            assert!(matches!(
+                save_secret_key(&file_path, &private_key).await,
+                Err(Error::WriteSshPrivateKey { .. })
+            ));
+            let (_, loaded_public_key) = resolve_ssh_key_pair([&file_path]).await.unwrap();
+            std::fs::remove_file(&file_path).unwrap();
+            assert_eq!(loaded_public_key, public_key);
+        }
+    }
+
+    /// Returns the path of a sample key in the `testdata` directory.
+    fn sample_key_path(name: &str) -> PathBuf {
+        [env!("CARGO_MANIFEST_DIR"), "src", "ssh", "testdata", name].iter().collect()
+    }
+
+    #[tokio::test]
+    async fn test_load_secret_key() {
+        // The keys in the OpenSSH format have a comment, the one in the PKCS#8
+        // format has none
+        for name in ["ed25519", "rsa", "ecdsa", "ecdsa_pkcs8"] {
+            let secret_key = load_secret_key(sample_key_path(name), None)
+                .await
+                .unwrap_or_else(|err| panic!("failed to load {name}: {err}"));
+            assert!(secret_key.comment().is_empty(), "comment of {name}");
+
+            let public_key =
+                std::fs::read_to_string(sample_key_path(&format!("{name}.pub"))).unwrap();
+            let public_key = PublicKey::from_openssh(&public_key).unwrap();
+            assert_eq!(secret_key.algorithm(), public_key.algorithm(), "algorithm of {name}");
+            assert_eq!(
+                secret_key.fingerprint(HashAlg::Sha256),
+                public_key.fingerprint(HashAlg::Sha256),
+                "fingerprint of {name}"
+            );
+            let mut public_key = public_key;
+            public_key.set_comment(String::new());
+            assert_eq!(
+                secret_key.public_key().to_openssh().unwrap(),
+                public_key.to_openssh().unwrap(),
+                "public key of {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_candidate_key_paths() {
+        let explicit = vec![PathBuf::from("/keys/axon")];
+        assert_eq!(candidate_key_paths(explicit.clone(), Some(Path::new("/home/axon"))), explicit);
+
+        assert_eq!(
+            candidate_key_paths(Vec::new(), Some(Path::new("/home/axon"))),
+            [
+                PathBuf::from("/home/axon/.ssh/id_ed25519"),
+                PathBuf::from("/home/axon/.ssh/id_rsa"),
+                PathBuf::from("/home/axon/.ssh/id_ecdsa"),
+            ]
+        );
+        assert_eq!(candidate_key_paths(Vec::new(), None), Vec::<PathBuf>::new());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_ssh_key_pair_lists_attempted_paths() {
+        let paths =
+            [PathBuf::from("/nonexistent/id_ed25519"), PathBuf::from("/nonexistent/id_rsa")];
+        let Err(Error::ResolveIdentities { paths: attempted_paths, .. }) =
+            resolve_ssh_key_pair(&paths).await
+        else {
+            panic!("expected Error::ResolveIdentities");
+        };
+        assert_eq!(attempted_paths, paths);
+    }
 }
@@ -2,52 +2,207 @@
 //! executing commands, and performing file transfers (upload/download) over
 //! SFTP.
 
-use std::{path::Path, sync::Arc, time::Duration};
+use std::{
+    fmt,
+    io::{self, SeekFrom},
+    net::SocketAddr,
+    num::NonZeroUsize,
+    path::Path,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
 
 use futures::{FutureExt, future};
 use russh::{
-    ChannelMsg, Disconnect, client,
+    ChannelMsg, ChannelStream, Disconnect, Sig, client,
     keys::{PrivateKey, PublicKey, key::PrivateKeyWithHashAlg},
 };
 use russh_sftp::{client::SftpSession, protocol::OpenFlags};
-use snafu::{IntoError, ResultExt};
+use snafu::{IntoError, OptionExt, ResultExt};
 use tokio::{
     fs::File as LocalFile,
-    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, ReadBuf},
     net::ToSocketAddrs,
+    sync::{Mutex, mpsc},
 };
 use tokio_util::either::Either as AsyncEither;
 
-use crate::ssh::{error, error::Error};
+use crate::ssh::{
+    error,
+    error::{Error, TransferEnd},
+    sftp,
+};
+
+/// A reader over the content of a remote file being downloaded.
+///
+/// Depending on the SFTP concurrency of the session, this is either the remote
+/// file itself or a reader issuing several read requests at once.
+pub type RemoteFileReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Decides whether the public key presented by the server during the SSH
+/// handshake is trusted.
+pub type HostKeyVerifier = Box<dyn Fn(&PublicKey) -> bool + Send + Sync>;
+
+/// The interval between two keepalive messages sent on an idle session.
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A connection accepted by a remote listener requested with
+/// [`Session::request_remote_forward`].
+pub struct ForwardedConnection {
+    /// The data of the connection.
+    pub stream: ChannelStream<client::Msg>,
+    /// The remote port the connection was accepted on.
+    pub connected_port: u16,
+    /// The address of the remote peer, `HOST:PORT`.
+    pub originator: String,
+}
+
+/// How a remote command executed with [`Session::call`] terminated.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandOutcome {
+    /// The exit code of the command, if it exited on its own.
+    pub exit_code: Option<u32>,
+    /// The name of the signal that killed the command, e.g. `KILL`, if it was
+    /// killed.
+    pub signal: Option<String>,
+}
+
+impl fmt::Display for CommandOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.exit_code, &self.signal) {
+            (_, Some(signal)) => write!(f, "killed by signal SIG{signal}"),
+            (Some(exit_code), None) => write!(f, "exited with code {exit_code}"),
+            (None, None) => f.write_str("terminated without an exit status"),
+        }
+    }
+}
+
+/// A range of bytes of a remote file to download, see
+/// [`Session::with_byte_range`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ByteRange {
+    /// The position of the first byte of the range.
+    pub offset: u64,
+    /// The number of bytes in the range, or `None` for all bytes up to the end
+    /// of the file.
+    pub length: Option<u64>,
+}
+
+impl ByteRange {
+    /// Returns the number of bytes in the range of a file of `size` bytes, or
+    /// `None` if the range extends past the end of the file.
+    pub fn length_within(self, size: u64) -> Option<u64> {
+        let remaining = size.checked_sub(self.offset)?;
+        let length = self.length.unwrap_or(remaining);
+        (length <= remaining).then_some(length)
+    }
+}
+
+impl fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.length {
+            Some(length) => write!(f, "{}..{}", self.offset, self.offset.saturating_add(length)),
+            None => write!(f, "{}..", self.offset),
+        }
+    }
+}
+
+/// The output of a remote command executed with [`Session::exec_capture`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommandOutput {
+    /// How the command terminated.
+    pub outcome: CommandOutcome,
+    /// The data the command wrote to its standard output.
+    pub stdout: Vec<u8>,
+    /// The data the command wrote to its standard error.
+    pub stderr: Vec<u8>,
+}
+
+/// The extended data type of the standard error of a remote command, as
+/// defined in RFC 4254.
+const EXTENDED_DATA_STDERR: u32 = 1;
+
+/// Returns the name of `signal` as sent by the server, without the `SIG`
+/// prefix.
+fn signal_name(signal: &Sig) -> String {
+    match signal {
+        Sig::ABRT => "ABRT",
+        Sig::ALRM => "ALRM",
+        Sig::FPE => "FPE",
+        Sig::HUP => "HUP",
+        Sig::ILL => "ILL",
+        Sig::INT => "INT",
+        Sig::KILL => "KILL",
+        Sig::PIPE => "PIPE",
+        Sig::QUIT => "QUIT",
+        Sig::SEGV => "SEGV",
+        Sig::TERM => "TERM",
+        Sig::USR1 => "USR1",
+        Sig::Custom(name) => name,
+    }
+    .to_string()
+}
 
 /// A client handler for `russh` sessions.
 ///
 /// This struct implements the `client::Handler` trait, primarily to handle
-/// server key verification.
-#[derive(Default)]
-struct Client {}
+/// server key verification. Connections accepted by remote listeners are
+/// handed over to the `Session` through `forwarded_sender`.
+struct Client {
+    /// Verifies the public key of the server. Any key is accepted if it is
+    /// `None`.
+    host_key_verifier: Option<HostKeyVerifier>,
+
+    /// The sender of the connections accepted by remote listeners.
+    forwarded_sender: mpsc::UnboundedSender<ForwardedConnection>,
+}
 
 impl client::Handler for Client {
     type Error = russh::Error;
 
     /// Checks the server's public key during the SSH handshake.
     ///
-    /// This implementation currently accepts any server key, which is suitable
-    /// for scenarios where host key checking is managed externally or
-    /// during development.
+    /// The key is checked by the `host_key_verifier` of the client. Without
+    /// one, any server key is accepted, which is suitable for scenarios where
+    /// host key checking is managed externally or during development.
     ///
     /// # Arguments
     ///
-    /// * `_server_public_key` - The public key presented by the server.
+    /// * `server_public_key` - The public key presented by the server.
     ///
     /// # Returns
     ///
-    /// `Ok(true)` always, indicating the server key is accepted.
-    async fn check_server_key(
+    /// `Ok(true)` if the server key is accepted, `Ok(false)` otherwise.
+    fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKey,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send {
+        let accepted =
+            self.host_key_verifier.as_ref().is_none_or(|verify| verify(server_public_key));
+        std::future::ready(Ok(accepted))
+    }
+
+    /// Hands a connection accepted by a remote listener over to the
+    /// `Session`.
+    fn server_channel_open_forwarded_tcpip(
         &mut self,
-        _server_public_key: &PublicKey,
-    ) -> Result<bool, Self::Error> {
-        Ok(true)
+        channel: russh::Channel<client::Msg>,
+        _connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut client::Session,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        let connection = ForwardedConnection {
+            stream: channel.into_stream(),
+            connected_port: u16::try_from(connected_port).unwrap_or_default(),
+            originator: format!("{originator_address}:{originator_port}"),
+        };
+        // The connection is simply closed if the session is gone
+        drop(self.forwarded_sender.send(connection));
+        std::future::ready(Ok(()))
     }
 }
 
@@ -55,7 +210,22 @@ impl client::Handler for Client {
 ///
 /// This session can be used to execute commands and perform SFTP operations.
 pub struct Session {
-    session: client::Handle<Client>,
+    /// The handle of the underlying `russh` session.
+    handle: client::Handle<Client>,
+
+    /// The receiver of the connections accepted by remote listeners.
+    forwarded_receiver: Mutex<mpsc::UnboundedReceiver<ForwardedConnection>>,
+
+    /// The number of SFTP read or write requests kept in flight during file
+    /// transfers.
+    sftp_concurrency: NonZeroUsize,
+
+    /// Whether file transfers fail instead of overwriting an existing
+    /// destination.
+    no_clobber: bool,
+
+    /// The range of bytes of the remote file that downloads are limited to.
+    byte_range: Option<ByteRange>,
 }
 
 impl Session {
@@ -68,11 +238,14 @@ impl Session {
     /// * `user` - The username for authentication on the remote host.
     /// * `addrs` - The address of the remote host (e.g., "localhost:22",
     ///   "192.168.1.1:22").
+    /// * `host_key_verifier` - Decides whether the public key of the server is
+    ///   trusted. If `None`, any server key is accepted.
     ///
     /// # Errors
     ///
     /// This function returns an `Error` if:
-    /// - The connection to the server fails (`error::ConnectServerSnafu`).
+    /// - The connection to the server fails, including when the server key is
+    ///   rejected by `host_key_verifier` (`error::ConnectServerSnafu`).
     /// - The public key authentication fails (`error::AuthenticateUserSnafu`).
     /// - Access is denied after successful authentication
     ///   (`error::DenyAccessSnafu`).
@@ -85,8 +258,8 @@ impl Session {
     /// # Example
     /// ```no_run
     /// use std::path::Path;
-    /// use russh::keys::PrivateKey;
-    /// use crate::ssh::{session::Session, error};
+    /// use russh::keys::{PrivateKey, PublicKey};
+    /// use crate::ssh::{session::{HostKeyVerifier, Session}, error};
     /// use snafu::ResultExt;
     ///
     /// #[tokio::main]
@@ -99,8 +272,10 @@ impl Session {
     ///         .await
     ///         .context(error::ReadPrivateKeySnafu)?;
     ///
-    ///     let session = Session::connect(private_key, "user", "localhost:22")
-    ///         .await?;
+    ///     let known_host_key = PublicKey::from_openssh("ssh-ed25519 AAAA...")?;
+    ///     let verifier: HostKeyVerifier = Box::new(move |key| *key == known_host_key);
+    ///     let session =
+    ///         Session::connect(private_key, "user", "localhost:22", Some(verifier)).await?;
     ///
     ///     println!("SSH session established!");
     ///     session.close().await?;
@@ -111,21 +286,75 @@ impl Session {
         private_key: PrivateKey,
         user: impl Into<String>,
         addrs: A,
+        host_key_verifier: Option<HostKeyVerifier>,
     ) -> Result<Self, Error> {
-        let mut session = {
-            let client = Client::default();
-            let config = Arc::new(client::Config {
-                inactivity_timeout: Some(Duration::from_secs(5)),
-                ..<_>::default()
-            });
-            client::connect(config, addrs, client).await.context(error::ConnectServerSnafu)?
-        };
+        let (forwarded_sender, forwarded_receiver) = mpsc::unbounded_channel();
+        let client = Client { host_key_verifier, forwarded_sender };
+        let handle = client::connect(Self::client_config(), addrs, client)
+            .await
+            .context(error::ConnectServerSnafu)?;
+        Self::authenticate(handle, forwarded_receiver, private_key, user).await
+    }
 
+    /// Establishes a new SSH session over an already connected `stream`
+    /// using public key authentication.
+    ///
+    /// This is how a host only reachable through another one is connected to,
+    /// like `ssh -J` does: the `stream` is a connection to the host tunneled
+    /// through a session to the other one with [`Session::open_direct_tcpip`].
+    ///
+    /// # Arguments
+    ///
+    /// * `private_key` - The private key used for authentication.
+    /// * `user` - The username for authentication on the remote host.
+    /// * `stream` - The connection to the remote host.
+    /// * `host_key_verifier` - Decides whether the public key of the server is
+    ///   trusted. If `None`, any server key is accepted.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` in the same cases as
+    /// [`Session::connect`].
+    pub async fn connect_stream<S>(
+        private_key: PrivateKey,
+        user: impl Into<String>,
+        stream: S,
+        host_key_verifier: Option<HostKeyVerifier>,
+    ) -> Result<Self, Error>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let (forwarded_sender, forwarded_receiver) = mpsc::unbounded_channel();
+        let client = Client { host_key_verifier, forwarded_sender };
+        let handle = client::connect_stream(Self::client_config(), stream, client)
+            .await
+            .context(error::ConnectServerSnafu)?;
+        Self::authenticate(handle, forwarded_receiver, private_key, user).await
+    }
+
+    /// Returns the configuration of the `russh` client of a session.
+    fn client_config() -> Arc<client::Config> {
+        Arc::new(client::Config {
+            inactivity_timeout: Some(Duration::from_secs(5)),
+            // Keeps idle sessions, such as a quiet tunnel, from hitting the inactivity
+            // timeout
+            keepalive_interval: Some(KEEPALIVE_INTERVAL),
+            ..<_>::default()
+        })
+    }
+
+    /// Authenticates `user` with `private_key` on a freshly connected session.
+    async fn authenticate(
+        mut handle: client::Handle<Client>,
+        forwarded_receiver: mpsc::UnboundedReceiver<ForwardedConnection>,
+        private_key: PrivateKey,
+        user: impl Into<String>,
+    ) -> Result<Self, Error> {
         let best_hash =
-            session.best_supported_rsa_hash().await.context(error::ConnectServerSnafu)?.flatten();
+            handle.best_supported_rsa_hash().await.context(error::ConnectServerSnafu)?.flatten();
 
         let user_str = user.into();
-        let auth_res = session
+        let auth_res = handle
             .authenticate_publickey(
                 &user_str,
                 PrivateKeyWithHashAlg::new(Arc::new(private_key), best_hash),
@@ -135,7 +364,81 @@ impl Session {
 
         snafu::ensure!(auth_res.success(), error::DenyAccessSnafu { user: user_str.clone() });
 
-        Ok(Self { session })
+        Ok(Self {
+            handle,
+            forwarded_receiver: Mutex::new(forwarded_receiver),
+            sftp_concurrency: NonZeroUsize::MIN,
+            no_clobber: false,
+            byte_range: None,
+        })
+    }
+
+    /// Establishes a new SSH session like [`Session::connect`], accepting any
+    /// public key presented by the server.
+    ///
+    /// This suits pods reached through a port forwarding tunnel, whose host
+    /// keys are generated on the fly and cannot be known in advance.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` in the same cases as
+    /// [`Session::connect`].
+    pub async fn connect_accepting_any_host_key<A: ToSocketAddrs>(
+        private_key: PrivateKey,
+        user: impl Into<String>,
+        addrs: A,
+    ) -> Result<Self, Error> {
+        Self::connect(private_key, user, addrs, None).await
+    }
+
+    /// Sets the number of SFTP read or write requests kept in flight during
+    /// file transfers.
+    ///
+    /// A value of `1` (the default) transfers data serially, one request at a
+    /// time. Larger values pipeline the requests, which improves throughput on
+    /// high-latency links.
+    #[must_use]
+    pub const fn with_sftp_concurrency(mut self, sftp_concurrency: NonZeroUsize) -> Self {
+        self.sftp_concurrency = sftp_concurrency;
+        self
+    }
+
+    /// Sets whether file transfers fail instead of overwriting an existing
+    /// destination file.
+    ///
+    /// The destination is then created exclusively, so that a file appearing
+    /// between a check for its existence and the transfer is not overwritten
+    /// either.
+    #[must_use]
+    pub const fn with_no_clobber(mut self, no_clobber: bool) -> Self {
+        self.no_clobber = no_clobber;
+        self
+    }
+
+    /// Limits downloads to a range of bytes of the remote file, or lifts the
+    /// limit if `byte_range` is `None`.
+    ///
+    /// The range is validated against the size of the remote file once it has
+    /// been opened.
+    #[must_use]
+    pub const fn with_byte_range(mut self, byte_range: Option<ByteRange>) -> Self {
+        self.byte_range = byte_range;
+        self
+    }
+
+    /// Returns whether the file or directory `path` exists on the remote host.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    /// - The SFTP session cannot be prepared (errors from
+    ///   `prepare_sftp_session`).
+    /// - The existence of `path` cannot be determined, e.g. because a parent
+    ///   directory is not accessible (`Error::OpenRemoteFile`).
+    pub async fn exists(&self, path: impl AsRef<Path>) -> Result<bool, Error> {
+        let path = path.as_ref().to_string_lossy().to_string();
+        let sftp = self.prepare_sftp_session().await?;
+        sftp.try_exists(path.clone()).await.map_err(|source| Error::OpenRemoteFile { path, source })
     }
 
     /// Executes a command on the remote host and streams stdin/stdout.
@@ -152,7 +455,6 @@ impl Session {
     ///
     /// This function returns an `Error` if:
     /// - Opening a new channel fails (`error::OpenChannelSnafu`).
-    /// - Retrieving terminal size fails (`error::GetTerminalSizeSnafu`).
     /// - Requesting a pseudo-terminal (PTY) fails (`error::RequestPtySnafu`).
     /// - Executing the command fails (`error::ExecuteCommandSnafu`).
     /// - Initializing standard I/O for stdin/stdout fails
@@ -165,8 +467,9 @@ impl Session {
     ///
     /// # Returns
     ///
-    /// A `Result` containing the exit status code of the remote command on
-    /// success, or an `Error` on failure.
+    /// A `Result` containing the `CommandOutcome` of the remote command, its
+    /// exit code or the signal that killed it, on success, or an `Error` on
+    /// failure.
     ///
     /// # Example
     /// ```no_run
@@ -185,30 +488,31 @@ impl Session {
     ///         .await
     ///         .context(error::ReadPrivateKeySnafu)?;
     ///
-    ///     let session = Session::connect(private_key, "user", "localhost:22")
-    ///         .await?;
+    ///     let session =
+    ///         Session::connect_accepting_any_host_key(private_key, "user", "localhost:22").await?;
     ///
     ///     println!("Executing 'echo Hello, remote world!' on remote...");
-    ///     let exit_code = session.call("echo Hello, remote world!").await?;
-    ///     println!("Command finished with exit code: {}", exit_code);
+    ///     let outcome = session.call("echo Hello, remote world!").await?;
+    ///     println!("Command {outcome}");
+    ///     assert_eq!(outcome.exit_code, Some(0));
     ///
     ///     session.close().await?;
     ///     Ok(())
     /// }
     /// ```
-    pub async fn call(&self, command: &str) -> Result<u32, Error> {
+    pub async fn call(&self, command: &str) -> Result<CommandOutcome, Error> {
         let mut channel =
-            self.session.channel_open_session().await.context(error::OpenChannelSnafu)?;
+            self.handle.channel_open_session().await.context(error::OpenChannelSnafu)?;
 
         let term = std::env::var("TERM").unwrap_or_else(|_| "xterm".into());
-        let (width, height) = crossterm::terminal::size().context(error::GetTerminalSizeSnafu)?;
+        let (width, height) = crate::ui::terminal::size();
         channel
             .request_pty(false, &term, u32::from(width), u32::from(height), 0, 0, &[])
             .await
             .context(error::RequestPtySnafu)?;
         channel.exec(true, command).await.context(error::ExecuteCommandSnafu)?;
 
-        let code;
+        let mut outcome = CommandOutcome::default();
         let mut stdin = tokio_fd::AsyncFd::try_from(0)
             .context(error::InitializeStdioSnafu { stream: "stdin" })?;
         let mut stdout = tokio_fd::AsyncFd::try_from(1)
@@ -235,7 +539,14 @@ impl Session {
                             stdout.flush().await.context(error::WriteStdoutSnafu)?;
                         }
                         ChannelMsg::ExitStatus { exit_status } => {
-                            code = exit_status;
+                            outcome.exit_code = Some(exit_status);
+                            if !stdin_closed {
+                                channel.eof().await.context(error::CloseChannelSnafu)?;
+                            }
+                            break;
+                        }
+                        ChannelMsg::ExitSignal { ref signal_name, .. } => {
+                            outcome.signal = Some(self::signal_name(signal_name));
                             if !stdin_closed {
                                 channel.eof().await.context(error::CloseChannelSnafu)?;
                             }
@@ -246,7 +557,139 @@ impl Session {
                 },
             }
         }
-        Ok(code)
+        Ok(outcome)
+    }
+
+    /// Executes a non-interactive command on the remote host and captures its
+    /// output.
+    ///
+    /// Unlike [`Session::call`], no pseudo-terminal is requested, so the
+    /// standard output and standard error of the command are kept apart and
+    /// free of terminal control codes. The command gets an empty standard
+    /// input.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string to execute on the remote host.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    /// - Opening a new channel fails (`error::OpenChannelSnafu`).
+    /// - Executing the command fails (`error::ExecuteCommandSnafu`).
+    /// - Closing the standard input of the command fails
+    ///   (`error::CloseChannelSnafu`).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `CommandOutput` of the remote command on
+    /// success, or an `Error` on failure.
+    pub async fn exec_capture(&self, command: &str) -> Result<CommandOutput, Error> {
+        let mut channel =
+            self.handle.channel_open_session().await.context(error::OpenChannelSnafu)?;
+        channel.exec(true, command).await.context(error::ExecuteCommandSnafu)?;
+        channel.eof().await.context(error::CloseChannelSnafu)?;
+
+        let mut output = CommandOutput::default();
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Data { ref data } => output.stdout.extend_from_slice(data),
+                ChannelMsg::ExtendedData { ref data, ext: EXTENDED_DATA_STDERR } => {
+                    output.stderr.extend_from_slice(data);
+                }
+                ChannelMsg::ExitStatus { exit_status } => {
+                    output.outcome.exit_code = Some(exit_status);
+                }
+                ChannelMsg::ExitSignal { ref signal_name, .. } => {
+                    output.outcome.signal = Some(self::signal_name(signal_name));
+                }
+                _ => {}
+            }
+        }
+        Ok(output)
+    }
+
+    /// Executes a non-interactive command on the remote host, piping `input`
+    /// into its standard input and its standard output into `output`.
+    ///
+    /// Like [`Session::exec_capture`], no pseudo-terminal is requested. The
+    /// standard input of the command is closed once `input` is exhausted, and
+    /// its standard error is captured.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command string to execute on the remote host.
+    /// * `input` - The data written to the standard input of the command.
+    /// * `output` - The writer the standard output of the command is copied
+    ///   into.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    /// - Opening a new channel fails (`error::OpenChannelSnafu`).
+    /// - Executing the command fails (`error::ExecuteCommandSnafu`).
+    /// - Reading `input` or writing `output` fails
+    ///   (`error::PipeCommandDataSnafu`).
+    /// - Sending data to the remote channel fails
+    ///   (`error::SendChannelDataSnafu`).
+    /// - Closing the standard input of the command fails
+    ///   (`error::CloseChannelSnafu`).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `CommandOutput` of the remote command, whose
+    /// `stdout` is left empty as it has been written to `output`, on success,
+    /// or an `Error` on failure.
+    pub async fn exec_piped<I, O>(
+        &self,
+        command: &str,
+        mut input: I,
+        mut output: O,
+    ) -> Result<CommandOutput, Error>
+    where
+        I: AsyncRead + Unpin,
+        O: AsyncWrite + Unpin,
+    {
+        let mut channel =
+            self.handle.channel_open_session().await.context(error::OpenChannelSnafu)?;
+        channel.exec(true, command).await.context(error::ExecuteCommandSnafu)?;
+
+        let mut command_output = CommandOutput::default();
+        let mut buf = vec![0; 32 * 1024];
+        let mut input_closed = false;
+        loop {
+            tokio::select! {
+                r = input.read(&mut buf), if !input_closed => {
+                    match r.context(error::PipeCommandDataSnafu)? {
+                        0 => {
+                            input_closed = true;
+                            channel.eof().await.context(error::CloseChannelSnafu)?;
+                        }
+                        n => channel.data(&buf[..n]).await.context(error::SendChannelDataSnafu)?,
+                    }
+                },
+                msg = channel.wait() => {
+                    match msg {
+                        Some(ChannelMsg::Data { ref data }) => {
+                            output.write_all(data).await.context(error::PipeCommandDataSnafu)?;
+                        }
+                        Some(ChannelMsg::ExtendedData { ref data, ext: EXTENDED_DATA_STDERR }) => {
+                            command_output.stderr.extend_from_slice(data);
+                        }
+                        Some(ChannelMsg::ExitStatus { exit_status }) => {
+                            command_output.outcome.exit_code = Some(exit_status);
+                        }
+                        Some(ChannelMsg::ExitSignal { ref signal_name, .. }) => {
+                            command_output.outcome.signal = Some(self::signal_name(signal_name));
+                        }
+                        Some(_) => {}
+                        None => break,
+                    }
+                },
+            }
+        }
+        output.shutdown().await.context(error::PipeCommandDataSnafu)?;
+        Ok(command_output)
     }
 
     /// Uploads a local file to the remote host via SFTP.
@@ -297,8 +740,8 @@ impl Session {
     ///         .await
     ///         .context(error::ReadPrivateKeySnafu)?;
     ///
-    ///     let session = Session::connect(private_key, "user", "localhost:22")
-    ///         .await?;
+    ///     let session =
+    ///         Session::connect_accepting_any_host_key(private_key, "user", "localhost:22").await?;
     ///
     ///     let local_path = Path::new("local_file_to_upload.txt");
     ///     let remote_path = Path::new("/tmp/remote_file_uploaded.txt");
@@ -343,7 +786,6 @@ impl Session {
         Sig: Future<Output = ()> + Unpin,
     {
         let src = src.as_ref();
-        let dst = dst.as_ref();
 
         let local_file =
             LocalFile::open(src).await.context(error::OpenLocalFileSnafu { path: src })?;
@@ -358,34 +800,126 @@ impl Session {
                 .context(error::OpenLocalFileSnafu { path: src })?;
         }
 
+        self.upload_reader(local_file, Some(src), dst, reader_wrapper, cancel_signal).await
+    }
+
+    /// Uploads the content of an arbitrary reader to the remote host via SFTP.
+    ///
+    /// Unlike [`Session::upload`], the size of the data is not known in
+    /// advance, which makes this suitable for streaming sources such as the
+    /// local standard input.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - The reader providing the data to upload.
+    /// * `dst` - The destination path on the remote host.
+    /// * `reader_wrapper` - An optional function to wrap the reader, allowing
+    ///   for custom processing or progress tracking during the read.
+    /// * `cancel_signal` - An optional future that, if resolved, will cancel
+    ///   the upload operation.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    /// - The SFTP session cannot be prepared (errors from
+    ///   `prepare_sftp_session`).
+    /// - The remote destination file cannot be opened or created
+    ///   (`Error::OpenRemoteFile`).
+    /// - Data transfer between the reader and remote fails
+    ///   (`error::TransferDataSnafu`).
+    /// - The upload operation is cancelled by the `cancel_signal`
+    ///   (`Error::Cancelled`).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of bytes uploaded on success, or an
+    /// `Error` on failure.
+    pub async fn upload_from<S, D, R, F, Sig>(
+        &self,
+        src: S,
+        dst: D,
+        reader_wrapper: Option<F>,
+        cancel_signal: Option<Sig>,
+    ) -> Result<u64, Error>
+    where
+        S: AsyncRead + Send + Unpin,
+        D: AsRef<Path>,
+        R: AsyncRead + Send + Unpin,
+        F: FnOnce(S) -> R,
+        Sig: Future<Output = ()> + Unpin,
+    {
+        self.upload_reader(src, None, dst, reader_wrapper, cancel_signal).await
+    }
+
+    /// Uploads the content of a reader to the remote host via SFTP, attributing
+    /// a failure of the reader to `local_path`, or to a local stream if it is
+    /// `None`.
+    async fn upload_reader<S, D, R, F, Sig>(
+        &self,
+        src: S,
+        local_path: Option<&Path>,
+        dst: D,
+        reader_wrapper: Option<F>,
+        cancel_signal: Option<Sig>,
+    ) -> Result<u64, Error>
+    where
+        S: AsyncRead + Send + Unpin,
+        D: AsRef<Path>,
+        R: AsyncRead + Send + Unpin,
+        F: FnOnce(S) -> R,
+        Sig: Future<Output = ()> + Unpin,
+    {
+        let dst = dst.as_ref();
         let dst_str = dst.to_string_lossy().to_string();
         let sftp = self.prepare_sftp_session().await?;
 
+        let flags = if self.no_clobber {
+            OpenFlags::CREATE | OpenFlags::EXCLUDE | OpenFlags::WRITE
+        } else {
+            OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE
+        };
         let mut remote_file = sftp
-            .open_with_flags(&dst_str, OpenFlags::CREATE | OpenFlags::TRUNCATE | OpenFlags::WRITE)
+            .open_with_flags(&dst_str, flags)
             .await
-            .map_err(|source| Error::OpenRemoteFile { path: dst_str, source })?;
+            .map_err(|source| Error::OpenRemoteFile { path: dst_str.clone(), source })?;
 
         // Wrap reader if provided
-        let mut local_file = match reader_wrapper {
-            Some(wrapper) => AsyncEither::Left(wrapper(local_file)),
-            None => AsyncEither::Right(local_file),
-        };
+        let mut reader = LocalIo(match reader_wrapper {
+            Some(wrapper) => AsyncEither::Left(wrapper(src)),
+            None => AsyncEither::Right(src),
+        });
 
         // Create the copy future
-        let copy_task = tokio::io::copy(&mut local_file, &mut remote_file).boxed();
+        let copy_task = if self.sftp_concurrency.get() > 1 {
+            // Every extra request is issued on its own handle to the same file
+            let mut remote_files = vec![remote_file];
+            for _ in 1..self.sftp_concurrency.get() {
+                remote_files.push(
+                    sftp.open_with_flags(&dst_str, OpenFlags::WRITE).await.map_err(|source| {
+                        Error::OpenRemoteFile { path: dst_str.clone(), source }
+                    })?,
+                );
+            }
+            sftp::write_pipelined(reader, remote_files).boxed()
+        } else {
+            async move {
+                let n = tokio::io::copy(&mut reader, &mut remote_file).await?;
+                let _ = remote_file.shutdown().await.ok();
+                Ok(n)
+            }
+            .boxed()
+        };
 
         let n = match cancel_signal {
             Some(sig) => match future::select(copy_task, sig).await {
                 future::Either::Left((copy_res, _)) => {
-                    copy_res.context(error::TransferDataSnafu { path: src })?
+                    copy_res.map_err(|err| transfer_error(err, local_path, dst))?
                 }
                 future::Either::Right((..)) => return Err(Error::Cancelled),
             },
-            None => copy_task.await.context(error::TransferDataSnafu { path: src })?,
+            None => copy_task.await.map_err(|err| transfer_error(err, local_path, dst))?,
         };
 
-        let _ = remote_file.shutdown().await.ok();
         Ok(n)
     }
 
@@ -398,8 +932,9 @@ impl Session {
     /// * `on_length` - An optional closure that will be called with the total
     ///   length of the file once it's known. Useful for progress indicators.
     /// * `reader_wrapper` - An optional function to wrap the
-    ///   `russh_sftp::client::fs::File` reader, allowing for custom processing
-    ///   or progress tracking during the read.
+    ///   [`RemoteFileReader`], allowing for custom processing or progress
+    ///   tracking during the read. The data always arrives in file order, even
+    ///   when several SFTP requests are in flight.
     /// * `cancel_signal` - An optional future that, if resolved, will cancel
     ///   the download operation.
     ///
@@ -437,8 +972,8 @@ impl Session {
     ///         .await
     ///         .context(error::ReadPrivateKeySnafu)?;
     ///
-    ///     let session = Session::connect(private_key, "user", "localhost:22")
-    ///         .await?;
+    ///     let session =
+    ///         Session::connect_accepting_any_host_key(private_key, "user", "localhost:22").await?;
     ///
     ///     let remote_path = Path::new("/tmp/remote_file_to_download.txt");
     ///     let local_path = Path::new("downloaded_remote_file.txt");
@@ -453,7 +988,7 @@ impl Session {
     ///         &remote_path,
     ///         &local_path,
     ///         Some(|len| println!("File size: {} bytes", len)),
-    ///         None::<fn(russh_sftp::client::fs::File) -> russh_sftp::client::fs::File>, // No custom wrapper
+    ///         None::<fn(RemoteFileReader) -> RemoteFileReader>, // No custom wrapper
     ///         Some(cancel_rx.map(|_| ())), // Convert oneshot::Receiver into a Future<Output=()>
     ///     ).await?;
     ///
@@ -479,35 +1014,169 @@ impl Session {
         D: AsRef<Path>,
         R: AsyncRead + Send + Unpin,
         L: FnOnce(u64),
-        F: FnOnce(russh_sftp::client::fs::File) -> R,
+        F: FnOnce(RemoteFileReader) -> R,
         Sig: Future<Output = ()> + Unpin,
     {
-        let src = src.as_ref();
         let dst = dst.as_ref();
+
+        // The local file is only created once the remote file has been opened
+        let local_file = async {
+            let mut options = LocalFile::options();
+            let _options = if self.no_clobber {
+                options.write(true).create_new(true)
+            } else {
+                options.write(true).create(true).truncate(true)
+            };
+            options.open(dst).await.context(error::OpenLocalFileSnafu { path: dst })
+        };
+
+        self.download_into(src, local_file, Some(dst), on_length, reader_wrapper, cancel_signal)
+            .await
+    }
+
+    /// Downloads a remote file from the host via SFTP into an arbitrary writer.
+    ///
+    /// The writer is produced by `dst` only after the remote file has been
+    /// opened successfully, so nothing is created locally when the remote
+    /// source does not exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `src` - The path to the remote file to download.
+    /// * `dst` - A future resolving to the writer the data is copied into, such
+    ///   as the local standard output.
+    /// * `on_length` - An optional closure that will be called with the total
+    ///   length of the file once it's known.
+    /// * `reader_wrapper` - An optional function to wrap the
+    ///   [`RemoteFileReader`].
+    /// * `cancel_signal` - An optional future that, if resolved, will cancel
+    ///   the download operation.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if:
+    /// - The SFTP session cannot be prepared (errors from
+    ///   `prepare_sftp_session`).
+    /// - The remote source file cannot be opened or its metadata accessed
+    ///   (`error::OpenRemoteFileSnafu`).
+    /// - The byte range set with [`Session::with_byte_range`] extends past the
+    ///   end of the remote file (`error::InvalidByteRangeSnafu`).
+    /// - The writer cannot be created (the error produced by `dst`).
+    /// - Data transfer between remote and the writer fails
+    ///   (`error::TransferDataSnafu`).
+    /// - The download operation is cancelled by the `cancel_signal`
+    ///   (`Error::Cancelled`).
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of bytes downloaded on success, or an
+    /// `Error` on failure.
+    pub async fn download_to<S, W, Dst, L, R, F, Sig>(
+        &self,
+        src: S,
+        dst: Dst,
+        on_length: Option<L>,
+        reader_wrapper: Option<F>,
+        cancel_signal: Option<Sig>,
+    ) -> Result<u64, Error>
+    where
+        S: AsRef<Path>,
+        W: AsyncWrite + Send + Unpin,
+        Dst: Future<Output = Result<W, Error>>,
+        R: AsyncRead + Send + Unpin,
+        L: FnOnce(u64),
+        F: FnOnce(RemoteFileReader) -> R,
+        Sig: Future<Output = ()> + Unpin,
+    {
+        self.download_into(src, dst, None, on_length, reader_wrapper, cancel_signal).await
+    }
+
+    /// Downloads a remote file from the host via SFTP into the writer produced
+    /// by `dst`, attributing a failure of the writer to `local_path`, or to a
+    /// local stream if it is `None`.
+    async fn download_into<S, W, Dst, L, R, F, Sig>(
+        &self,
+        src: S,
+        dst: Dst,
+        local_path: Option<&Path>,
+        on_length: Option<L>,
+        reader_wrapper: Option<F>,
+        cancel_signal: Option<Sig>,
+    ) -> Result<u64, Error>
+    where
+        S: AsRef<Path>,
+        W: AsyncWrite + Send + Unpin,
+        Dst: Future<Output = Result<W, Error>>,
+        R: AsyncRead + Send + Unpin,
+        L: FnOnce(u64),
+        F: FnOnce(RemoteFileReader) -> R,
+        Sig: Future<Output = ()> + Unpin,
+    {
+        let src = src.as_ref();
         let src_str = src.to_string_lossy().to_string();
 
         let sftp = self.prepare_sftp_session().await?;
 
         // Open remote file for reading
-        let remote_file = sftp
+        let mut remote_file = sftp
             .open_with_flags(&src_str, OpenFlags::READ)
             .await
             .with_context(|_| error::OpenRemoteFileSnafu { path: src_str.clone() })?;
 
-        // Create local file
-        let mut local_file =
-            LocalFile::create(dst).await.context(error::OpenLocalFileSnafu { path: dst })?;
-
-        if let Some(on_length) = on_length {
-            let _unused = remote_file
+        let size = if self.byte_range.is_some()
+            || on_length.is_some()
+            || self.sftp_concurrency.get() > 1
+        {
+            let metadata = remote_file
                 .metadata()
                 .await
-                .inspect(|metadata| {
-                    on_length(metadata.len());
-                })
                 .context(error::OpenRemoteFileSnafu { path: src_str.clone() })?;
+            Some(metadata.len())
+        } else {
+            None
+        };
+        let (offset, length) = match (self.byte_range, size) {
+            (Some(range), Some(size)) => {
+                let length = range.length_within(size).context(error::InvalidByteRangeSnafu {
+                    path: src_str.clone(),
+                    range,
+                    size,
+                })?;
+                (range.offset, Some(length))
+            }
+            _ => (0, size),
+        };
+
+        // Create the destination writer
+        let mut writer = LocalIo(dst.await?);
+
+        if let (Some(on_length), Some(length)) = (on_length, length) {
+            on_length(length);
         }
 
+        let remote_file: RemoteFileReader = match length {
+            // Files reporting no size (e.g. under `/proc`) are read serially until the end
+            Some(length) if length > 0 && self.sftp_concurrency.get() > 1 => {
+                // Every extra request is issued on its own handle to the same file
+                let mut remote_files = vec![remote_file];
+                for _ in 1..self.sftp_concurrency.get() {
+                    remote_files.push(
+                        sftp.open_with_flags(&src_str, OpenFlags::READ).await.with_context(
+                            |_| error::OpenRemoteFileSnafu { path: src_str.clone() },
+                        )?,
+                    );
+                }
+                Box::pin(sftp::pipelined_reader(remote_files, offset, length))
+            }
+            Some(length) if self.byte_range.is_some() => {
+                let _pos = remote_file.seek(SeekFrom::Start(offset)).await.context(
+                    error::TransferDataSnafu { end: TransferEnd::Remote(src.to_path_buf()) },
+                )?;
+                Box::pin(remote_file.take(length))
+            }
+            _ => Box::pin(remote_file),
+        };
+
         // Wrap writer if provided (similar to reader_wrapper in upload)
         let mut remote_file = match reader_wrapper {
             Some(wrapper) => AsyncEither::Left(wrapper(remote_file)),
@@ -515,20 +1184,20 @@ impl Session {
         };
 
         // Create the copy future
-        let copy_task = tokio::io::copy(&mut remote_file, &mut local_file).boxed();
+        let copy_task = tokio::io::copy(&mut remote_file, &mut writer).boxed();
 
         let n = match cancel_signal {
             Some(sig) => match future::select(copy_task, sig).await {
                 future::Either::Left((copy_res, _)) => {
-                    copy_res.context(error::TransferDataSnafu { path: dst })?
+                    copy_res.map_err(|err| transfer_error(err, local_path, src))?
                 }
                 future::Either::Right((..)) => return Err(Error::Cancelled),
             },
-            None => copy_task.await.context(error::TransferDataSnafu { path: dst })?,
+            None => copy_task.await.map_err(|err| transfer_error(err, local_path, src))?,
         };
 
-        // Ensure data is flushed to disk
-        let _ = local_file.shutdown().await.ok();
+        // Ensure data is flushed
+        let _ = writer.shutdown().await.ok();
 
         Ok(n)
     }
@@ -561,8 +1230,8 @@ impl Session {
     ///         .await
     ///         .context(error::ReadPrivateKeySnafu)?;
     ///
-    ///     let session = Session::connect(private_key, "user", "localhost:22")
-    ///         .await?;
+    ///     let session =
+    ///         Session::connect_accepting_any_host_key(private_key, "user", "localhost:22").await?;
     ///
     ///     println!("Session established, now closing...");
     ///     session.close().await?;
@@ -571,13 +1240,103 @@ impl Session {
     /// }
     /// ```
     pub async fn close(self) -> Result<(), Error> {
-        self.session
+        self.handle
             .disconnect(Disconnect::ByApplication, "", "English")
             .await
             .context(error::DisconnectSessionSnafu)?;
         Ok(())
     }
 
+    /// Opens a TCP connection from the remote host to `host:port`, tunneled
+    /// through the session like `ssh -L` or `ssh -D` do.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - The host to connect to, resolved by the remote host.
+    /// * `port` - The port to connect to.
+    /// * `originator` - The address of the local client the connection is made
+    ///   for, reported to the remote host.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if the remote host refuses to open the
+    /// channel (`error::OpenDirectTcpipSnafu`), e.g. because the target cannot
+    /// be reached or TCP forwarding is disabled.
+    ///
+    /// # Returns
+    ///
+    /// A bidirectional stream carrying the data of the connection.
+    pub async fn open_direct_tcpip(
+        &self,
+        host: &str,
+        port: u16,
+        originator: SocketAddr,
+    ) -> Result<ChannelStream<client::Msg>, Error> {
+        let channel = self
+            .handle
+            .channel_open_direct_tcpip(
+                host,
+                u32::from(port),
+                originator.ip().to_string(),
+                u32::from(originator.port()),
+            )
+            .await
+            .with_context(|_| error::OpenDirectTcpipSnafu { target: format!("{host}:{port}") })?;
+        Ok(channel.into_stream())
+    }
+
+    /// Asks the remote host to listen on `address:port` and forward the
+    /// connections it accepts back through the session, like `ssh -R` does.
+    ///
+    /// The accepted connections are returned by
+    /// [`Session::accept_forwarded`].
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The remote address to listen on.
+    /// * `port` - The remote port to listen on, `0` to let the remote host
+    ///   choose one.
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if the remote host refuses to listen
+    /// (`error::RequestRemoteForwardSnafu`), e.g. because the port is in use
+    /// or TCP forwarding is disabled.
+    ///
+    /// # Returns
+    ///
+    /// The remote port actually listened on.
+    pub async fn request_remote_forward(&self, address: &str, port: u16) -> Result<u16, Error> {
+        let bound_port = self
+            .handle
+            .tcpip_forward(address, u32::from(port))
+            .await
+            .with_context(|_| error::RequestRemoteForwardSnafu { address, port })?;
+        // The server only reports the port if it chose it
+        Ok(u16::try_from(bound_port).ok().filter(|&bound_port| bound_port != 0).unwrap_or(port))
+    }
+
+    /// Stops the remote listener on `address:port` requested with
+    /// [`Session::request_remote_forward`].
+    ///
+    /// # Errors
+    ///
+    /// This function returns an `Error` if the remote host fails to stop the
+    /// listener (`error::CancelRemoteForwardSnafu`).
+    pub async fn cancel_remote_forward(&self, address: &str, port: u16) -> Result<(), Error> {
+        self.handle
+            .cancel_tcpip_forward(address, u32::from(port))
+            .await
+            .with_context(|_| error::CancelRemoteForwardSnafu { address, port })
+    }
+
+    /// Waits for the next connection accepted by a remote listener.
+    ///
+    /// Returns `None` once the session is closed.
+    pub async fn accept_forwarded(&self) -> Option<ForwardedConnection> {
+        self.forwarded_receiver.lock().await.recv().await
+    }
+
     /// Prepares and returns an SFTP session for file transfer operations.
     ///
     /// This internal helper function opens a new channel and requests the SFTP
@@ -596,9 +1355,162 @@ impl Session {
     /// A `Result` containing the `SftpSession` on success, or an `Error` on
     /// failure.
     async fn prepare_sftp_session(&self) -> Result<SftpSession, Error> {
-        let channel = self.session.channel_open_session().await.context(error::OpenSftpSnafu)?;
+        let channel = self.handle.channel_open_session().await.context(error::OpenSftpSnafu)?;
         channel.request_subsystem(true, "sftp").await.context(error::OpenSftpSnafu)?;
 
         SftpSession::new(channel.into_stream()).await.with_context(|_| error::OpenSftpSessionSnafu)
     }
 }
+
+/// Wraps the local reader or writer of a file transfer, marking its I/O errors
+/// so that a failed copy can be attributed to the local end.
+struct LocalIo<T>(T);
+
+/// The marker of an I/O error of the local end of a file transfer.
+#[derive(Debug)]
+struct LocalIoError(io::Error);
+
+impl fmt::Display for LocalIoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { self.0.fmt(f) }
+}
+
+impl std::error::Error for LocalIoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> { self.0.source() }
+}
+
+/// Marks `err` as an error of the local end of a file transfer.
+fn mark_local(err: io::Error) -> io::Error { io::Error::new(err.kind(), LocalIoError(err)) }
+
+impl<T> AsyncRead for LocalIo<T>
+where
+    T: AsyncRead + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf).map_err(mark_local)
+    }
+}
+
+impl<T> AsyncWrite for LocalIo<T>
+where
+    T: AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf).map_err(mark_local)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx).map_err(mark_local)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx).map_err(mark_local)
+    }
+}
+
+/// Builds the error of a failed copy between `local_path`, or a local stream
+/// if it is `None`, and the remote file at `remote_path`, naming the end that
+/// failed.
+fn transfer_error(source: io::Error, local_path: Option<&Path>, remote_path: &Path) -> Error {
+    let end = if matches!(source.get_ref(), Some(err) if err.is::<LocalIoError>()) {
+        TransferEnd::Local(local_path.map(Path::to_path_buf))
+    } else {
+        TransferEnd::Remote(remote_path.to_path_buf())
+    };
+    Error::TransferData { end, source }
+}
+
+#[cfg(test)]
+mod tests {
+    use russh::client::Handler as _;
+
+    use super::*;
+
+    const HOST_KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIFxlO7S8mo9TnDQPV0BSusMTn+yBkHFHwROg82RRGuoJ";
+
+    fn client(host_key_verifier: Option<HostKeyVerifier>) -> Client {
+        let (forwarded_sender, _) = mpsc::unbounded_channel();
+        Client { host_key_verifier, forwarded_sender }
+    }
+
+    #[tokio::test]
+    async fn test_check_server_key() {
+        let host_key = PublicKey::from_openssh(HOST_KEY).unwrap();
+
+        assert!(client(None).check_server_key(&host_key).await.unwrap());
+
+        let known_host_key = host_key.clone();
+        let verifier: HostKeyVerifier = Box::new(move |key| *key == known_host_key);
+        assert!(client(Some(verifier)).check_server_key(&host_key).await.unwrap());
+
+        let verifier: HostKeyVerifier = Box::new(|_| false);
+        assert!(!client(Some(verifier)).check_server_key(&host_key).await.unwrap());
+    }
+
+    #[test]
+    fn test_command_outcome() {
+        let exited = CommandOutcome { exit_code: Some(137), signal: None };
+        assert_eq!(exited.to_string(), "exited with code 137");
+
+        let killed = CommandOutcome { exit_code: None, signal: Some(signal_name(&Sig::KILL)) };
+        assert_eq!(killed.to_string(), "killed by signal SIGKILL");
+        assert_ne!(killed.exit_code, Some(0));
+
+        assert_eq!(signal_name(&Sig::Custom("WINCH".to_string())), "WINCH");
+        assert_eq!(CommandOutcome::default().to_string(), "terminated without an exit status");
+    }
+
+    #[test]
+    fn test_byte_range() {
+        let range = |offset, length| ByteRange { offset, length };
+        assert_eq!(range(0, None).length_within(100), Some(100));
+        assert_eq!(range(40, None).length_within(100), Some(60));
+        assert_eq!(range(40, Some(60)).length_within(100), Some(60));
+        assert_eq!(range(100, Some(0)).length_within(100), Some(0));
+        assert_eq!(range(40, Some(61)).length_within(100), None);
+        assert_eq!(range(101, None).length_within(100), None);
+        assert_eq!(range(u64::MAX, Some(u64::MAX)).length_within(100), None);
+
+        assert_eq!(range(40, Some(60)).to_string(), "40..100");
+        assert_eq!(range(40, None).to_string(), "40..");
+    }
+
+    #[tokio::test]
+    async fn test_transfer_error() {
+        let failing = || {
+            tokio_util::io::StreamReader::new(futures::stream::iter([
+                Err::<io::Cursor<Vec<u8>>, _>(io::Error::other("disk failure")),
+            ]))
+        };
+        let remote_path = Path::new("/srv/app.log");
+
+        let err =
+            tokio::io::copy(&mut LocalIo(failing()), &mut tokio::io::sink()).await.unwrap_err();
+        assert_eq!(
+            transfer_error(err, Some(Path::new("app.log")), remote_path).to_string(),
+            "Failed to transfer data for local file 'app.log', error: disk failure"
+        );
+
+        let err =
+            tokio::io::copy(&mut LocalIo(failing()), &mut tokio::io::sink()).await.unwrap_err();
+        assert_eq!(
+            transfer_error(err, None, remote_path).to_string(),
+            "Failed to transfer data for local stream, error: disk failure"
+        );
+
+        let err =
+            tokio::io::copy(&mut failing(), &mut LocalIo(tokio::io::sink())).await.unwrap_err();
+        assert_eq!(
+            transfer_error(err, Some(Path::new("app.log")), remote_path).to_string(),
+            "Failed to transfer data for remote file '/srv/app.log', error: disk failure"
+        );
+    }
+}
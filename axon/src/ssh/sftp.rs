@@ -0,0 +1,313 @@
+//! Pipelined SFTP transfers.
+//!
+//! A single SFTP read or write request has to wait for a full round trip
+//! before the next one is issued, which makes plain copies latency-bound over
+//! a forwarded tunnel. The helpers in this module keep several requests in
+//! flight at once, each on its own handle to the same remote file, to improve
+//! throughput on high-latency links.
+
+use std::{
+    io::{self, Cursor, SeekFrom},
+    sync::{Arc, Mutex},
+};
+
+use futures::{Stream, StreamExt, stream};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt},
+    task::JoinSet,
+};
+
+/// The number of bytes carried by a single SFTP read or write request.
+const CHUNK_SIZE: u64 = 32 * 1024;
+
+/// Creates a reader yielding `length` bytes of a remote file starting at
+/// `offset`, with one read request in flight per handle in `files`.
+///
+/// Requests may complete out of order, but the data is always yielded in file
+/// order, so the reader can be wrapped for progress tracking like a plain
+/// file.
+///
+/// # Arguments
+///
+/// * `files` - Handles opened for reading on the same remote file. Must not be
+///   empty.
+/// * `offset` - The position of the first byte to read.
+/// * `length` - The number of bytes to read.
+pub fn pipelined_reader<F>(files: Vec<F>, offset: u64, length: u64) -> impl AsyncRead + Send + Unpin
+where
+    F: AsyncRead + AsyncSeek + Send + Unpin + 'static,
+{
+    tokio_util::io::StreamReader::new(read_chunks(files, offset, length))
+}
+
+/// Creates a stream of the chunks of a remote file, in file order.
+fn read_chunks<F>(
+    files: Vec<F>,
+    offset: u64,
+    length: u64,
+) -> impl Stream<Item = io::Result<Cursor<Vec<u8>>>> + Send + Unpin
+where
+    F: AsyncRead + AsyncSeek + Send + Unpin + 'static,
+{
+    let concurrency = files.len().max(1);
+    let idle_files = Arc::new(Mutex::new(files));
+
+    let end = offset.saturating_add(length);
+    stream::iter((offset..end).step_by(usize::try_from(CHUNK_SIZE).unwrap_or(usize::MAX)))
+        .map(move |offset| {
+            let idle_files = Arc::clone(&idle_files);
+            async move {
+                // At most `concurrency` requests are in flight, so a handle is always idle
+                let mut file = take_idle_file(&idle_files)?;
+                let result = read_chunk(&mut file, offset, CHUNK_SIZE.min(end - offset)).await;
+                idle_files.lock().map_err(|_| poisoned_pool_error())?.push(file);
+                result.map(Cursor::new)
+            }
+        })
+        .buffered(concurrency)
+        .boxed()
+}
+
+/// Reads up to `len` bytes starting at `offset` from a remote file.
+///
+/// Fewer bytes are returned only if the end of the file is reached.
+async fn read_chunk<F>(file: &mut F, offset: u64, len: u64) -> io::Result<Vec<u8>>
+where
+    F: AsyncRead + AsyncSeek + Unpin,
+{
+    let _pos = file.seek(SeekFrom::Start(offset)).await?;
+
+    let mut buf = Vec::with_capacity(usize::try_from(len).unwrap_or(usize::MAX));
+    let _n = (&mut *file).take(len).read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
+/// Copies the whole content of `reader` into a remote file, with one write
+/// request in flight per handle in `files`.
+///
+/// The handles are shut down once all data has been written.
+///
+/// # Arguments
+///
+/// * `reader` - The source of the data.
+/// * `files` - Handles opened for writing on the same remote file. Must not be
+///   empty.
+///
+/// # Returns
+///
+/// The number of bytes written.
+pub async fn write_pipelined<R, F>(mut reader: R, files: Vec<F>) -> io::Result<u64>
+where
+    R: AsyncRead + Unpin,
+    F: AsyncWrite + AsyncSeek + Send + Unpin + 'static,
+{
+    let mut idle_files = files;
+    // Dropping the set aborts every request still in flight
+    let mut requests = JoinSet::new();
+    let mut offset = 0;
+
+    loop {
+        if idle_files.is_empty() {
+            match requests.join_next().await {
+                Some(result) => idle_files.push(result.map_err(io::Error::other)??),
+                None => return Err(io::Error::other("no SFTP handle is available")),
+            }
+        }
+
+        let mut chunk = Vec::new();
+        let n = (&mut reader).take(CHUNK_SIZE).read_to_end(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+
+        let Some(mut file) = idle_files.pop() else {
+            return Err(io::Error::other("no SFTP handle is available"));
+        };
+        let chunk_offset = offset;
+        let _handle = requests.spawn(async move {
+            let _pos = file.seek(SeekFrom::Start(chunk_offset)).await?;
+            file.write_all(&chunk).await?;
+            Ok::<_, io::Error>(file)
+        });
+        offset += n as u64;
+    }
+
+    while let Some(result) = requests.join_next().await {
+        idle_files.push(result.map_err(io::Error::other)??);
+    }
+    for mut file in idle_files {
+        let _ = file.shutdown().await.ok();
+    }
+
+    Ok(offset)
+}
+
+/// Takes an idle handle from the pool shared by the in-flight read requests.
+fn take_idle_file<F>(idle_files: &Mutex<Vec<F>>) -> io::Result<F> {
+    idle_files
+        .lock()
+        .map_err(|_| poisoned_pool_error())?
+        .pop()
+        .ok_or_else(|| io::Error::other("no SFTP handle is available"))
+}
+
+fn poisoned_pool_error() -> io::Error { io::Error::other("the SFTP handle pool is poisoned") }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll, ready},
+        time::Duration,
+    };
+
+    use tokio::{io::ReadBuf, time::Sleep};
+
+    use super::*;
+
+    /// A vector shared by the handles to a mock file.
+    type Shared<T> = Arc<Mutex<Vec<T>>>;
+
+    /// A handle to an in-memory file shared by several handles.
+    ///
+    /// A seek to an earlier chunk takes longer, so that requests in flight
+    /// complete out of file order. The offsets of the completed seeks are
+    /// recorded in `completed`.
+    struct MockFile {
+        data: Shared<u8>,
+        completed: Shared<u64>,
+        pos: u64,
+        delay: Option<Pin<Box<Sleep>>>,
+    }
+
+    impl AsyncSeek for MockFile {
+        fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+            let SeekFrom::Start(pos) = position else {
+                return Err(io::Error::other("only seeks from the start are supported"));
+            };
+            let this = self.get_mut();
+            this.pos = pos;
+            let delay = 10 * 8_u64.saturating_sub(pos / CHUNK_SIZE);
+            this.delay = Some(Box::pin(tokio::time::sleep(Duration::from_millis(delay))));
+            Ok(())
+        }
+
+        fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+            let this = self.get_mut();
+            if let Some(delay) = this.delay.as_mut() {
+                ready!(delay.as_mut().poll(cx));
+                this.delay = None;
+                this.completed.lock().unwrap().push(this.pos);
+            }
+            Poll::Ready(Ok(this.pos))
+        }
+    }
+
+    impl AsyncRead for MockFile {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            let data = this.data.lock().unwrap();
+            let start = usize::try_from(this.pos).unwrap().min(data.len());
+            let n = buf.remaining().min(data.len() - start);
+            buf.put_slice(&data[start..start + n]);
+            drop(data);
+            this.pos += n as u64;
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for MockFile {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            let this = self.get_mut();
+            let mut data = this.data.lock().unwrap();
+            let start = usize::try_from(this.pos).unwrap();
+            if data.len() < start + buf.len() {
+                data.resize(start + buf.len(), 0);
+            }
+            data[start..start + buf.len()].copy_from_slice(buf);
+            drop(data);
+            this.pos += buf.len() as u64;
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    /// Opens `count` handles to a file holding `data`, and returns them with
+    /// the shared content and the record of completed seeks.
+    fn open_files(data: Vec<u8>, count: usize) -> (Vec<MockFile>, Shared<u8>, Shared<u64>) {
+        let data = Arc::new(Mutex::new(data));
+        let completed = Arc::new(Mutex::new(Vec::new()));
+        let files = (0..count)
+            .map(|_| MockFile {
+                data: Arc::clone(&data),
+                completed: Arc::clone(&completed),
+                pos: 0,
+                delay: None,
+            })
+            .collect();
+        (files, data, completed)
+    }
+
+    /// Returns data of four full chunks and a final partial chunk.
+    fn sample_data() -> Vec<u8> {
+        let len = usize::try_from(4 * CHUNK_SIZE + 100).unwrap();
+        (0..len).map(|i| u8::try_from(i % 251).unwrap()).collect()
+    }
+
+    fn is_sorted(offsets: &[u64]) -> bool { offsets.windows(2).all(|pair| pair[0] <= pair[1]) }
+
+    #[tokio::test]
+    async fn test_pipelined_reader() {
+        let data = sample_data();
+        let length = data.len() as u64;
+
+        let (files, _, completed) = open_files(data.clone(), 4);
+        let mut output = Vec::new();
+        let _n = pipelined_reader(files, 0, length).read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, data);
+        assert!(!is_sorted(&completed.lock().unwrap()), "requests completed in file order");
+
+        // A range ending in a partial chunk
+        let (files, ..) = open_files(data.clone(), 3);
+        let mut output = Vec::new();
+        let _n =
+            pipelined_reader(files, 1000, length - 1050).read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, data[1000..data.len() - 50]);
+
+        // The file ends before the requested length, e.g. as it was truncated
+        let (files, ..) = open_files(data.clone(), 4);
+        let mut output = Vec::new();
+        let _n =
+            pipelined_reader(files, 0, length + CHUNK_SIZE).read_to_end(&mut output).await.unwrap();
+        assert_eq!(output, data);
+    }
+
+    #[tokio::test]
+    async fn test_write_pipelined() {
+        let data = sample_data();
+
+        let (files, written, completed) = open_files(Vec::new(), 4);
+        assert_eq!(write_pipelined(data.as_slice(), files).await.unwrap(), data.len() as u64);
+        assert_eq!(*written.lock().unwrap(), data);
+        assert!(!is_sorted(&completed.lock().unwrap()), "requests completed in file order");
+
+        let (files, written, _) = open_files(Vec::new(), 2);
+        assert_eq!(write_pipelined([].as_slice(), files).await.unwrap(), 0);
+        assert!(written.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+//! Renders the age of Kubernetes objects the way `kubectl` does.
+//!
+//! Ages are compact, e.g. `90s`, `5m`, `3h` or `4d6h`, and keep a second unit
+//! only while it is still significant, so that the same age reads the same in
+//! every view.
+
+use k8s_openapi::{apimachinery::pkg::apis::meta::v1::Time, jiff::Timestamp};
+
+/// Formats the time elapsed since `time`.
+pub fn format_age(time: &Time) -> String { format_age_at(time, Timestamp::now()) }
+
+/// Formats the time elapsed between `time` and `now`.
+///
+/// A `time` slightly in the future, as caused by clock skew between the client
+/// and the cluster, is rendered as `0s`, and one further ahead as `<invalid>`.
+pub fn format_age_at(time: &Time, now: Timestamp) -> String {
+    format_duration(now.as_second() - time.0.as_second())
+}
+
+/// Formats a duration in seconds with the rules of `kubectl`'s
+/// `HumanDuration`.
+fn format_duration(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+    const YEAR: i64 = 365 * DAY;
+
+    let with_remainder = |value: i64, unit: &str, remainder: i64, remainder_unit: &str| {
+        if remainder == 0 {
+            format!("{value}{unit}")
+        } else {
+            format!("{value}{unit}{remainder}{remainder_unit}")
+        }
+    };
+    match seconds {
+        ..-1 => "<invalid>".to_string(),
+        -1 => "0s".to_string(),
+        0..120 => format!("{seconds}s"),
+        120..600 => with_remainder(seconds / MINUTE, "m", seconds % MINUTE, "s"),
+        600..10_800 => format!("{}m", seconds / MINUTE),
+        10_800..28_800 => with_remainder(seconds / HOUR, "h", seconds % HOUR / MINUTE, "m"),
+        28_800..172_800 => format!("{}h", seconds / HOUR),
+        172_800..691_200 => with_remainder(seconds / DAY, "d", seconds % DAY / HOUR, "h"),
+        691_200..63_072_000 => format!("{}d", seconds / DAY),
+        63_072_000..252_288_000 => with_remainder(seconds / YEAR, "y", seconds % YEAR / DAY, "d"),
+        _ => format!("{}y", seconds / YEAR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        for (seconds, expected) in [
+            (-5, "<invalid>"),
+            (-1, "0s"),
+            (0, "0s"),
+            (90, "90s"),
+            (119, "119s"),
+            (120, "2m"),
+            (150, "2m30s"),
+            (599, "9m59s"),
+            (600, "10m"),
+            (10_799, "179m"),
+            (10_800, "3h"),
+            (11_100, "3h5m"),
+            (28_799, "7h59m"),
+            (28_800, "8h"),
+            (172_799, "47h"),
+            (172_800, "2d"),
+            (367_200, "4d6h"),
+            (691_199, "7d23h"),
+            (691_200, "8d"),
+            (63_071_999, "729d"),
+            (63_072_000, "2y"),
+            (63_158_400, "2y1d"),
+            (252_288_000, "8y"),
+        ] {
+            assert_eq!(format_duration(seconds), expected, "{seconds} seconds");
+        }
+    }
+
+    #[test]
+    fn test_format_age_at() {
+        let time = Time(Timestamp::from_second(1_000).unwrap());
+        let now = Timestamp::from_second(1_300).unwrap();
+        assert_eq!(format_age_at(&time, now), "5m");
+    }
+}
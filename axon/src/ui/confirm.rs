@@ -0,0 +1,40 @@
+//! Asks the user a yes/no question on the terminal.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+/// Asks `question` on stderr and reads the answer from stdin, returning
+/// whether it is yes.
+///
+/// Anything but `y` or `yes`, in any case, is a no, and so is a stdin that is
+/// not a terminal, where nobody could answer.
+pub async fn confirm(question: String) -> bool {
+    if !std::io::stdin().is_terminal() {
+        return false;
+    }
+    tokio::task::spawn_blocking(move || {
+        let mut stderr = std::io::stderr().lock();
+        let _unused = write!(stderr, "{question} [y/N] ").and_then(|()| stderr.flush());
+        let mut answer = String::new();
+        std::io::stdin().lock().read_line(&mut answer).is_ok() && is_yes(&answer)
+    })
+    .await
+    .unwrap_or_default()
+}
+
+/// Returns whether `answer` is a yes.
+fn is_yes(answer: &str) -> bool { matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_yes() {
+        for answer in ["y\n", "Y", " yes\n", "YES"] {
+            assert!(is_yes(answer), "{answer}");
+        }
+        for answer in ["", "\n", "n", "no", "yep", "y e s"] {
+            assert!(!is_yes(answer), "{answer}");
+        }
+    }
+}
@@ -1,7 +1,7 @@
 //! Provides a progress bar for file transfers, indicating upload or download
 //! progress.
 
-use tokio::io::AsyncRead;
+use tokio::io::{AsyncRead, AsyncWrite};
 
 /// A progress bar specifically designed for file transfer operations,
 /// indicating either an upload or a download.
@@ -26,6 +26,33 @@ impl FileTransferProgressBar {
     /// The progress bar will display "Downloading" as its message.
     pub fn new_download() -> Self { Self::new(Direction::Download) }
 
+    /// Creates a new `FileTransferProgressBar` configured for an upload whose
+    /// total size is unknown, such as data piped from standard input.
+    ///
+    /// A spinner with the number of bytes transferred so far is displayed
+    /// instead of a bar.
+    pub fn new_upload_spinner() -> Self { Self::new_spinner(Direction::Upload) }
+
+    /// Creates a new `FileTransferProgressBar` configured for a download whose
+    /// total size is unknown, such as an archive produced on the fly.
+    ///
+    /// A spinner with the number of bytes transferred so far is displayed
+    /// instead of a bar.
+    pub fn new_download_spinner() -> Self { Self::new_spinner(Direction::Download) }
+
+    /// Creates a new spinner `FileTransferProgressBar` with a specified
+    /// transfer direction.
+    fn new_spinner(direction: Direction) -> Self {
+        let pb = Self::new(direction);
+        pb.inner.set_style(
+            indicatif::ProgressStyle::default_spinner()
+                .template("{spinner:.green} [{elapsed_precise}] {bytes} ({bytes_per_sec}) {msg}")
+                .expect("the template is valid"),
+        );
+        pb.inner.enable_steady_tick(std::time::Duration::from_millis(100));
+        pb
+    }
+
     /// Creates a new `FileTransferProgressBar` with a specified transfer
     /// direction.
     ///
@@ -61,6 +88,19 @@ impl FileTransferProgressBar {
         Self { inner, direction }
     }
 
+    /// Hides the progress bar if `hidden` is set, e.g. when the command prints
+    /// machine-readable output instead. Progress is still tracked.
+    #[must_use]
+    pub fn hidden_if(self, hidden: bool) -> Self {
+        if hidden {
+            self.inner.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+        }
+        self
+    }
+
+    /// Returns the number of bytes transferred so far.
+    pub fn position(&self) -> u64 { self.inner.position() }
+
     /// Sets the total length of the progress bar, typically representing the
     /// total bytes to be transferred.
     ///
@@ -88,6 +128,16 @@ impl FileTransferProgressBar {
         self.inner.wrap_async_read(read)
     }
 
+    /// Wraps an `AsyncWrite` implementer with the progress bar, allowing it to
+    /// track the progress of the write operation.
+    ///
+    /// # Arguments
+    ///
+    /// * `write` - The asynchronous writer to wrap.
+    pub fn wrap_async_write<W: AsyncWrite + Unpin>(&self, write: W) -> impl AsyncWrite + Unpin {
+        self.inner.wrap_async_write(write)
+    }
+
     /// Finishes the progress bar, setting its message to indicate completion
     /// (e.g., "Upload completed" or "Download completed").
     ///
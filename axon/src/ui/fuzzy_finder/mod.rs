@@ -1,8 +1,11 @@
-//! This module provides utilities for fuzzy finding Kubernetes pods using the
-//! `skim` library, including a common column separator and re-exports for
-//! extended pod list functionality.
+//! This module provides utilities for fuzzy finding Kubernetes pods and the
+//! specs of the configuration using the `skim` library, including a common
+//! column separator and re-exports for extended list functionality.
 
 mod pod_list;
+mod spec_list;
+
+use skim::{SkimOptions, prelude::SkimOptionsBuilder};
 
 /// The default column separator used for formatting output in UI tables.
 ///
@@ -16,3 +19,27 @@ pub const COLUMN_SEPARATOR: &str = "\t";
 /// This trait provides extended functionality for collections of Kubernetes
 /// pods, particularly for fuzzy finding and selecting pods using `skim`.
 pub use self::pod_list::PodListExt;
+/// Re-exports the `SpecListExt` trait from the `spec_list` submodule.
+///
+/// This trait provides fuzzy finding and selection of the specs of the
+/// configuration using `skim`.
+pub use self::spec_list::SpecListExt;
+
+/// Generates the default `SkimOptions` used for the fuzzy finders.
+///
+/// Currently, it configures the fuzzy finder to take up 100% of the terminal
+/// height and allows only single item selection.
+///
+/// # Panics
+/// This function panics if the `SkimOptionsBuilder` fails to build the options,
+/// which indicates a configuration error in the `skim` library usage.
+///
+/// # Returns
+/// A `SkimOptions` struct configured for single selection.
+fn generate_skim_options() -> SkimOptions {
+    SkimOptionsBuilder::default()
+        .height("100%")
+        .multi(false)
+        .build()
+        .expect("Failed to build SkimOptions")
+}
@@ -7,12 +7,12 @@ use std::{borrow::Cow, sync::Arc};
 
 use k8s_openapi::api::core::v1::Pod;
 use kube::api::ObjectList;
-use skim::{
-    Skim, SkimItem, SkimItemReceiver, SkimItemSender, SkimOptions,
-    prelude::{SkimOptionsBuilder, unbounded},
-};
+use skim::{Skim, SkimItem, SkimItemReceiver, SkimItemSender, prelude::unbounded};
 
-use crate::ui::fuzzy_finder::COLUMN_SEPARATOR;
+use crate::ui::{
+    PodSortKey,
+    fuzzy_finder::{COLUMN_SEPARATOR, generate_skim_options},
+};
 
 /// Extension trait for `ObjectList<Pod>` to facilitate fuzzy finding and
 /// selection of pods.
@@ -21,7 +21,7 @@ pub trait PodListExt {
     /// SkimItem>` suitable for use with the `skim` fuzzy finder.
     ///
     /// This method is primarily used internally to prepare data for the fuzzy
-    /// finder.
+    /// finder. The items are ordered by the default [`PodSortKey`].
     ///
     /// # Returns
     /// A `Vec` of `Arc<dyn SkimItem>` where each item represents a Kubernetes
@@ -29,7 +29,7 @@ pub trait PodListExt {
     fn items(&self) -> Vec<Arc<dyn SkimItem>>;
 
     /// Displays a fuzzy finder interface to the user, allowing them to select
-    /// one or more `Pod` names from the list.
+    /// one or more pods from the list, which may span several namespaces.
     ///
     /// If no items are available, an empty vector is returned immediately.
     ///
@@ -38,9 +38,9 @@ pub trait PodListExt {
     /// join, which should ideally not happen under normal circumstances.
     ///
     /// # Returns
-    /// A `Vec<String>` containing the names of the selected pods. If the user
-    /// aborts the skim interface or no pods are selected, an empty vector
-    /// is returned.
+    /// A `Vec<(String, String)>` containing the namespace and the name of each
+    /// selected pod. If the user aborts the skim interface or no pods are
+    /// selected, an empty vector is returned.
     ///
     /// # Example
     /// ```no_run
@@ -66,12 +66,12 @@ pub trait PodListExt {
     ///         ..Default::default()
     ///     };
     ///
-    ///     let selected_pod_names = pod_list.find_pod_names().await;
-    ///     println!("Selected pods: {:?}", selected_pod_names);
+    ///     let selected_pods = pod_list.find_pods().await;
+    ///     println!("Selected pods: {:?}", selected_pods);
     ///     Ok(())
     /// }
     /// ```
-    async fn find_pod_names(&self) -> Vec<String> {
+    async fn find_pods(&self) -> Vec<(String, String)> {
         let items = self.items();
         if items.is_empty() {
             return Vec::new();
@@ -87,7 +87,10 @@ pub trait PodListExt {
                 if out.is_abort {
                     return Vec::new();
                 }
-                out.selected_items.iter().map(|item| item.output().to_string()).collect()
+                out.selected_items
+                    .iter()
+                    .filter_map(|item| PodSkimItem::parse_output(&item.output()))
+                    .collect()
             } else {
                 Vec::new()
             }
@@ -101,8 +104,10 @@ pub trait PodListExt {
 /// use of the fuzzy finding capabilities on lists of Kubernetes Pods.
 impl PodListExt for ObjectList<Pod> {
     fn items(&self) -> Vec<Arc<dyn SkimItem>> {
-        self.iter()
-            .map(|pod| -> Arc<dyn SkimItem> { Arc::new(PodSkimItem::from(pod.clone())) })
+        let mut pods = self.items.clone();
+        PodSortKey::default().sort(&mut pods);
+        pods.into_iter()
+            .map(|pod| -> Arc<dyn SkimItem> { Arc::new(PodSkimItem::from(pod)) })
             .collect()
     }
 }
@@ -113,7 +118,8 @@ impl PodListExt for ObjectList<Pod> {
 ///
 /// This struct adapts a Kubernetes `Pod` to display key information (name,
 /// image, phase, namespace, node name) in the fuzzy finder interface and
-/// returns the pod name when selected.
+/// returns `namespace/name` when selected, so that pods with the same name in
+/// different namespaces are never confused.
 pub struct PodSkimItem(Pod);
 
 /// Implements the `From` trait to convert a `k8s_openapi::api::core::v1::Pod`
@@ -122,12 +128,38 @@ impl From<Pod> for PodSkimItem {
     fn from(value: Pod) -> Self { Self(value) }
 }
 
+impl PodSkimItem {
+    /// The separator between the namespace and the name of a pod in the
+    /// output of the item.
+    const OUTPUT_SEPARATOR: char = '/';
+
+    /// Splits the output of an item back into the namespace and the name of
+    /// the pod.
+    ///
+    /// # Returns
+    /// `Some((namespace, name))`, or `None` if `output` is not in the
+    /// `namespace/name` form.
+    fn parse_output(output: &str) -> Option<(String, String)> {
+        output
+            .split_once(Self::OUTPUT_SEPARATOR)
+            .map(|(namespace, name)| (namespace.to_string(), name.to_string()))
+    }
+}
+
 /// Implements the `SkimItem` trait for `PodSkimItem`, defining how a `Pod` is
 /// displayed and interacted with within the `skim` fuzzy finder.
 impl SkimItem for PodSkimItem {
     fn text(&self) -> Cow<'_, str> { pod_column(&self.0).join(COLUMN_SEPARATOR).into() }
 
-    fn output(&self) -> Cow<'_, str> { self.0.metadata.name.clone().unwrap_or_default().into() }
+    fn output(&self) -> Cow<'_, str> {
+        format!(
+            "{}{}{}",
+            self.0.metadata.namespace.as_deref().unwrap_or_default(),
+            Self::OUTPUT_SEPARATOR,
+            self.0.metadata.name.as_deref().unwrap_or_default()
+        )
+        .into()
+    }
 }
 
 /// Extracts key information from a Kubernetes `Pod` object and formats it into
@@ -156,21 +188,41 @@ fn pod_column(pod: &Pod) -> [String; 5] {
     ]
 }
 
-/// Generates the default `SkimOptions` used for the pod fuzzy finder.
-///
-/// Currently, it configures the fuzzy finder to take up 100% of the terminal
-/// height and allows only single item selection.
-///
-/// # Panics
-/// This function panics if the `SkimOptionsBuilder` fails to build the options,
-/// which indicates a configuration error in the `skim` library usage.
-///
-/// # Returns
-/// A `SkimOptions` struct configured for pod selection.
-fn generate_skim_options() -> SkimOptions {
-    SkimOptionsBuilder::default()
-        .height("100%")
-        .multi(false)
-        .build()
-        .expect("Failed to build SkimOptions")
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+    use super::*;
+
+    fn pod(namespace: &str, name: &str) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..ObjectMeta::default()
+            },
+            ..Pod::default()
+        }
+    }
+
+    #[test]
+    fn test_output_distinguishes_namespaces() {
+        let first = PodSkimItem::from(pod("default", "axon"));
+        let second = PodSkimItem::from(pod("staging", "axon"));
+
+        assert_eq!(first.output(), "default/axon");
+        assert_eq!(second.output(), "staging/axon");
+        assert_ne!(first.output(), second.output());
+    }
+
+    #[test]
+    fn test_output_round_trip() {
+        let item = PodSkimItem::from(pod("staging", "axon"));
+
+        assert_eq!(
+            PodSkimItem::parse_output(&item.output()),
+            Some(("staging".to_string(), "axon".to_string()))
+        );
+        assert_eq!(PodSkimItem::parse_output("axon"), None);
+    }
 }
@@ -0,0 +1,101 @@
+//! This module provides extensions for selecting a `Spec` of the configuration
+//! with the `skim` fuzzy finder library, so that a preset can be picked
+//! without remembering its name.
+
+use std::{borrow::Cow, sync::Arc};
+
+use skim::{Skim, SkimItem, SkimItemReceiver, SkimItemSender, prelude::unbounded};
+
+use crate::{
+    config::Spec,
+    ui::fuzzy_finder::{COLUMN_SEPARATOR, generate_skim_options},
+};
+
+/// Extension trait for lists of `Spec` to facilitate fuzzy finding and
+/// selection of a spec.
+pub trait SpecListExt {
+    /// Converts the specs into a vector of `Arc<dyn SkimItem>` suitable for
+    /// use with the `skim` fuzzy finder, in the order of the configuration.
+    fn items(&self) -> Vec<Arc<dyn SkimItem>>;
+
+    /// Displays a fuzzy finder interface to the user, showing the name, the
+    /// image and the image pull policy of every spec, and allowing them to
+    /// select one.
+    ///
+    /// # Panics
+    /// This method panics if the `tokio::task::spawn_blocking` task fails to
+    /// join, which should ideally not happen under normal circumstances.
+    ///
+    /// # Returns
+    /// The name of the selected spec, or `None` if there is no spec or the
+    /// user aborts the skim interface.
+    async fn find_spec(&self) -> Option<String> {
+        let items = self.items();
+        if items.is_empty() {
+            return None;
+        }
+
+        tokio::task::spawn_blocking(move || {
+            let (tx_item, rx_item): (SkimItemSender, SkimItemReceiver) = unbounded();
+            drop(tx_item.send(items));
+            drop(tx_item);
+
+            let out = Skim::run_with(generate_skim_options(), Some(rx_item)).ok()?;
+            if out.is_abort {
+                return None;
+            }
+            out.selected_items.first().map(|item| item.output().to_string())
+        })
+        .await
+        .expect("Failed to join spawn_blocking task")
+    }
+}
+
+/// Implements `SpecListExt` for slices of `Spec`, such as the `specs` of the
+/// configuration.
+impl SpecListExt for [Spec] {
+    fn items(&self) -> Vec<Arc<dyn SkimItem>> {
+        self.iter()
+            .map(|spec| -> Arc<dyn SkimItem> { Arc::new(SpecSkimItem(spec.clone())) })
+            .collect()
+    }
+}
+
+/// A wrapper struct for `Spec` that implements the `SkimItem` trait, showing
+/// the name, image and image pull policy of the spec and returning its name
+/// when selected.
+struct SpecSkimItem(Spec);
+
+impl SkimItem for SpecSkimItem {
+    fn text(&self) -> Cow<'_, str> {
+        [self.0.name.as_str(), self.0.image.as_str(), &self.0.image_pull_policy.to_string()]
+            .join(COLUMN_SEPARATOR)
+            .into()
+    }
+
+    fn output(&self) -> Cow<'_, str> { self.0.name.as_str().into() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ImagePullPolicy;
+
+    #[test]
+    fn test_items() {
+        let specs = [
+            Spec {
+                name: "tools".to_string(),
+                image: "nicolaka/netshoot".to_string(),
+                image_pull_policy: ImagePullPolicy::Always,
+                ..Spec::default()
+            },
+            Spec::default(),
+        ];
+        let items = specs.items();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].text(), "tools\tnicolaka/netshoot\tAlways");
+        assert_eq!(items[0].output(), "tools");
+        assert_eq!(items[1].output(), Spec::default().name);
+    }
+}
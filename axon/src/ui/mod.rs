@@ -1,17 +1,31 @@
 //! Contains various UI components used throughout the application.
 //!
 //! This module re-exports several UI-related components, including:
+//! - [`format_age`]: For rendering the age of Kubernetes objects.
+//! - [`confirm`]: For asking the user a yes/no question.
 //! - [`file_transfer_progress_bar`]: For displaying progress during file
 //!   transfers.
 //! - [`fuzzy_finder`]: For interactive, fuzzy searching of items.
+//! - [`PodSortKey`]: For ordering pods consistently across views.
 //! - [`table`]: For displaying data in a tabular format.
 //! - [`terminal`]: For terminal-specific UI functionalities.
+//! - [`WaitSpinner`]: For showing the progress of waiting for a pod.
 
+mod age;
+mod confirm;
 mod file_transfer_progress_bar;
 pub mod fuzzy_finder;
+mod pod_sort;
 pub mod table;
 pub mod terminal;
+mod wait_spinner;
 
+/// Re-exports the helpers rendering the age of Kubernetes objects, e.g. `5m`
+/// or `4d6h`, as `kubectl` does.
+pub use self::age::{format_age, format_age_at};
+/// Re-exports the [`confirm`] function for asking the user a yes/no question
+/// on the terminal.
+pub use self::confirm::confirm;
 /// Re-exports the [`FileTransferProgressBar`] struct for displaying file
 /// transfer progress.
 ///
@@ -19,3 +33,11 @@ pub mod terminal;
 /// typically used in a terminal UI, to visualize the progress of file upload or
 /// download operations.
 pub use self::file_transfer_progress_bar::FileTransferProgressBar;
+/// Re-exports the [`PodSortKey`] enum for ordering pods.
+///
+/// The same ordering is applied to pods rendered in tables and listed in the
+/// fuzzy finder.
+pub use self::pod_sort::PodSortKey;
+/// Re-exports the [`WaitSpinner`] struct for showing the elapsed time and the
+/// phase of a pod while waiting for it to be running.
+pub use self::wait_spinner::WaitSpinner;
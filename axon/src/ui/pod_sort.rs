@@ -0,0 +1,125 @@
+//! Provides the ordering of Kubernetes pods shared by the table and fuzzy
+//! finder views.
+
+use std::cmp::Ordering;
+
+use clap::ValueEnum;
+use k8s_openapi::api::core::v1::Pod;
+
+/// The key by which pods are sorted before being displayed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum PodSortKey {
+    /// Sorts pods by name, in ascending order.
+    #[default]
+    Name,
+    /// Sorts pods by age, youngest first, based on their creation timestamp.
+    Age,
+    /// Sorts pods by status phase, in ascending order.
+    Status,
+    /// Sorts pods by namespace, in ascending order.
+    Namespace,
+}
+
+impl PodSortKey {
+    /// Compares two pods according to this key.
+    ///
+    /// Pods that are equal according to the key are ordered by namespace and
+    /// then by name, so that the resulting order is always stable.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The first pod to compare.
+    /// * `b` - The second pod to compare.
+    ///
+    /// # Returns
+    ///
+    /// The `Ordering` of `a` relative to `b`.
+    pub fn compare(self, a: &Pod, b: &Pod) -> Ordering {
+        let ordering = match self {
+            Self::Name => a.metadata.name.cmp(&b.metadata.name),
+            Self::Age => b.metadata.creation_timestamp.cmp(&a.metadata.creation_timestamp),
+            Self::Status => phase(a).cmp(&phase(b)),
+            Self::Namespace => a.metadata.namespace.cmp(&b.metadata.namespace),
+        };
+
+        ordering
+            .then_with(|| a.metadata.namespace.cmp(&b.metadata.namespace))
+            .then_with(|| a.metadata.name.cmp(&b.metadata.name))
+    }
+
+    /// Sorts a slice of pods in place according to this key.
+    ///
+    /// # Arguments
+    ///
+    /// * `pods` - The pods to sort.
+    pub fn sort(self, pods: &mut [Pod]) { pods.sort_by(|a, b| self.compare(a, b)); }
+}
+
+/// Returns the status phase of a pod, if known.
+fn phase(pod: &Pod) -> Option<&str> { pod.status.as_ref()?.phase.as_deref() }
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::{
+        api::core::v1::PodStatus,
+        apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time},
+        jiff::Timestamp,
+    };
+
+    use super::*;
+
+    fn pod(namespace: &str, name: &str, phase: &str, created_at: i64) -> Pod {
+        Pod {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(namespace.to_string()),
+                creation_timestamp: Some(Time(
+                    Timestamp::from_second(created_at).expect("the timestamp is valid"),
+                )),
+                ..ObjectMeta::default()
+            },
+            status: Some(PodStatus { phase: Some(phase.to_string()), ..PodStatus::default() }),
+            ..Pod::default()
+        }
+    }
+
+    fn names(pods: &[Pod]) -> Vec<&str> {
+        pods.iter().filter_map(|pod| pod.metadata.name.as_deref()).collect()
+    }
+
+    fn pods() -> Vec<Pod> {
+        vec![
+            pod("staging", "bravo", "Running", 100),
+            pod("default", "charlie", "Pending", 300),
+            pod("default", "alpha", "Running", 200),
+        ]
+    }
+
+    #[test]
+    fn test_sort_by_name() {
+        let mut pods = pods();
+        PodSortKey::Name.sort(&mut pods);
+        assert_eq!(names(&pods), ["alpha", "bravo", "charlie"]);
+    }
+
+    #[test]
+    fn test_sort_by_age() {
+        let mut pods = pods();
+        PodSortKey::Age.sort(&mut pods);
+        assert_eq!(names(&pods), ["charlie", "alpha", "bravo"]);
+    }
+
+    #[test]
+    fn test_sort_by_status() {
+        let mut pods = pods();
+        PodSortKey::Status.sort(&mut pods);
+        assert_eq!(names(&pods), ["charlie", "alpha", "bravo"]);
+    }
+
+    #[test]
+    fn test_sort_by_namespace() {
+        let mut pods = pods();
+        PodSortKey::Namespace.sort(&mut pods);
+        assert_eq!(names(&pods), ["alpha", "charlie", "bravo"]);
+    }
+}
@@ -0,0 +1,192 @@
+//! Tables of user-defined columns, like `kubectl -o custom-columns`.
+//!
+//! Every column pairs a header with a path into the JSON representation of
+//! an object, e.g. `IMAGE:.spec.containers[0].image`. Paths support the
+//! subset of `JSONPath` needed to reach a single field: dotted field names and
+//! `[n]` array indices.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// The value rendered for a field that is missing or null.
+const NONE: &str = "<none>";
+
+/// A step of a `FieldPath`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Segment {
+    /// A field of an object.
+    Field(String),
+    /// An element of an array.
+    Index(usize),
+}
+
+/// A path to a field of a JSON value, e.g. `.spec.containers[0].image`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldPath {
+    /// The steps leading to the field.
+    segments: Vec<Segment>,
+}
+
+impl FieldPath {
+    /// Returns the field of `value` the path leads to, if it exists.
+    fn get<'a>(&self, value: &'a Value) -> Option<&'a Value> {
+        self.segments.iter().try_fold(value, |value, segment| match segment {
+            Segment::Field(field) => value.get(field),
+            Segment::Index(index) => value.get(index),
+        })
+    }
+}
+
+impl FromStr for FieldPath {
+    type Err = String;
+
+    /// Parses a path such as `.metadata.name` or `.spec.containers[0].image`.
+    /// The leading dot is optional.
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid path '{expression}'");
+        let mut segments = Vec::new();
+        let mut rest = expression.strip_prefix('.').unwrap_or(expression);
+        while !rest.is_empty() {
+            if let Some(index) = rest.strip_prefix('[') {
+                let (index, tail) = index.split_once(']').ok_or_else(invalid)?;
+                segments.push(Segment::Index(index.parse().map_err(|_| invalid())?));
+                rest = tail.strip_prefix('.').unwrap_or(tail);
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let (field, tail) = rest.split_at(end);
+                if field.is_empty() || field.contains(']') {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Field(field.to_string()));
+                rest = tail.strip_prefix('.').unwrap_or(tail);
+                if tail.starts_with('.') && rest.is_empty() {
+                    return Err(invalid());
+                }
+            }
+        }
+        if segments.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self { segments })
+    }
+}
+
+/// The columns of a custom table, given as `HEADER:PATH,HEADER:PATH,...`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CustomColumns {
+    /// The header and the path of every column, in order.
+    columns: Vec<(String, FieldPath)>,
+}
+
+impl CustomColumns {
+    /// Renders `objects` as a table with one row per object.
+    ///
+    /// Fields that are missing or null are rendered as `<none>`, strings as
+    /// is, and any other value as compact JSON.
+    pub fn render_table<T: Serialize>(&self, objects: &[T]) -> String {
+        let rows = objects.iter().map(|object| {
+            let value = serde_json::to_value(object).unwrap_or_default();
+            self.columns
+                .iter()
+                .map(|(_, path)| match path.get(&value) {
+                    None | Some(Value::Null) => NONE.to_string(),
+                    Some(Value::String(value)) => value.clone(),
+                    Some(value) => value.to_string(),
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut table = comfy_table::Table::new();
+        let _table = table
+            .load_preset(comfy_table::presets::NOTHING)
+            .set_content_arrangement(comfy_table::ContentArrangement::Disabled)
+            .set_header(self.columns.iter().map(|(header, _)| header.as_str()))
+            .add_rows(rows);
+        table.to_string()
+    }
+}
+
+impl FromStr for CustomColumns {
+    type Err = String;
+
+    /// Parses columns in the form `HEADER:PATH,HEADER:PATH,...`, e.g.
+    /// `NAME:.metadata.name,IMAGE:.spec.containers[0].image`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let columns = input
+            .split(',')
+            .map(|column| {
+                let (header, path) = column
+                    .split_once(':')
+                    .filter(|(header, _)| !header.is_empty())
+                    .ok_or_else(|| format!("invalid column '{column}', expected HEADER:PATH"))?;
+                Ok((header.to_string(), path.parse()?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { columns })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_field_path() {
+        let path: FieldPath = ".spec.containers[0].image".parse().unwrap();
+        assert_eq!(
+            path.segments,
+            [
+                Segment::Field("spec".to_string()),
+                Segment::Field("containers".to_string()),
+                Segment::Index(0),
+                Segment::Field("image".to_string()),
+            ]
+        );
+        assert_eq!("metadata.name".parse::<FieldPath>().unwrap().segments.len(), 2);
+        assert_eq!(".items[1][2]".parse::<FieldPath>().unwrap().segments.len(), 3);
+
+        for expression in [".", "", ".spec..name", ".spec.", ".a[x]", ".a[0", ".a]"] {
+            assert_eq!(
+                expression.parse::<FieldPath>(),
+                Err(format!("invalid path '{expression}'")),
+                "{expression}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_field_path_get() {
+        let value = json!({
+            "metadata": { "name": "axon" },
+            "spec": { "containers": [{ "image": "alpine" }, { "image": "busybox" }] },
+        });
+        let get = |expression: &str| expression.parse::<FieldPath>().unwrap().get(&value).cloned();
+        assert_eq!(get(".metadata.name"), Some(json!("axon")));
+        assert_eq!(get(".spec.containers[1].image"), Some(json!("busybox")));
+        assert_eq!(get(".spec.containers[2].image"), None);
+        assert_eq!(get(".metadata.labels"), None);
+    }
+
+    #[test]
+    fn test_custom_columns() {
+        let columns: CustomColumns = "NAME:.name,PORT:.ports[0],TAG:.tag".parse().unwrap();
+        let table = columns.render_table(&[
+            json!({ "name": "axon", "ports": [22], "tag": null }),
+            json!({ "name": "web", "ports": [] }),
+        ]);
+        let lines =
+            table.lines().map(str::split_whitespace).map(Vec::from_iter).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [vec!["NAME", "PORT", "TAG"], vec!["axon", "22", NONE], vec!["web", NONE, NONE]]
+        );
+
+        assert!("NAME".parse::<CustomColumns>().is_err());
+        assert!(":.name".parse::<CustomColumns>().is_err());
+        assert_eq!("NAME:.a[".parse::<CustomColumns>(), Err("invalid path '.a['".to_string()));
+    }
+}
@@ -0,0 +1,96 @@
+//! This module provides extensions for `ObjectList<Event>` to render a
+//! formatted table.
+
+use k8s_openapi::{api::core::v1::Event, apimachinery::pkg::apis::meta::v1::Time, jiff::Timestamp};
+use kube::api::ObjectList;
+
+use crate::ui::format_age_at;
+
+/// Extension trait for `ObjectList<Event>` to provide table rendering
+/// capabilities.
+pub trait EventListExt {
+    /// Renders the list of events into a human-readable table string.
+    ///
+    /// The table includes columns for "TYPE", "REASON", "AGE" and "MESSAGE",
+    /// with the oldest event first.
+    ///
+    /// # Returns
+    /// A `String` containing the formatted table.
+    fn render_table(&self) -> String;
+}
+
+/// Extension trait for a single Kubernetes `Event`, used when events are
+/// streamed one at a time rather than listed.
+pub trait EventExt {
+    /// Returns the header line matching the rows produced by
+    /// [`EventExt::render_row`].
+    fn render_row_header() -> String;
+
+    /// Renders the event as a single line with the same columns as the table
+    /// rendered by [`EventListExt::render_table`].
+    fn render_row(&self) -> String;
+
+    /// Returns the time at which the event was last observed.
+    ///
+    /// Events reported through the `events.k8s.io` API only carry an event
+    /// time, while older ones only carry a last timestamp, so both are
+    /// considered before falling back to the creation timestamp.
+    fn last_seen(&self) -> Option<Time>;
+}
+
+impl EventListExt for ObjectList<Event> {
+    fn render_table(&self) -> String {
+        let mut events = self.items.iter().collect::<Vec<_>>();
+        events.sort_by_key(|event| event.last_seen().map(|time| time.0));
+
+        let now = Timestamp::now();
+        let rows = events.into_iter().map(|event| event_column(event, now));
+
+        let mut table = comfy_table::Table::new();
+        let _table = table
+            .load_preset(comfy_table::presets::NOTHING)
+            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
+            .set_header(vec!["TYPE", "REASON", "AGE", "MESSAGE"])
+            .add_rows(rows);
+        table.to_string()
+    }
+}
+
+impl EventExt for Event {
+    fn render_row_header() -> String { format_row(["TYPE", "REASON", "AGE", "MESSAGE"]) }
+
+    fn render_row(&self) -> String {
+        let [type_, reason, age, message] = event_column(self, Timestamp::now());
+        format_row([&type_, &reason, &age, &message])
+    }
+
+    fn last_seen(&self) -> Option<Time> {
+        self.last_timestamp
+            .clone()
+            .or_else(|| self.event_time.as_ref().map(|time| Time(time.0)))
+            .or_else(|| self.metadata.creation_timestamp.clone())
+    }
+}
+
+/// Lays out the columns of a streamed event row with fixed widths.
+fn format_row([type_, reason, age, message]: [&str; 4]) -> String {
+    format!("{type_:<8} {reason:<24} {age:<6} {message}")
+}
+
+/// Extracts the column data for a single Kubernetes `Event` object.
+///
+/// # Arguments
+/// * `event` - A reference to the `Event` object from which to extract data.
+/// * `now` - The current time, which the age of the event is relative to.
+///
+/// # Returns
+/// An array of four `String`s, representing the column values in the order:
+/// `[TYPE, REASON, AGE, MESSAGE]`.
+fn event_column(event: &Event, now: Timestamp) -> [String; 4] {
+    [
+        event.type_.clone().unwrap_or_default(),
+        event.reason.clone().unwrap_or_default(),
+        event.last_seen().map_or_else(|| "<unknown>".to_string(), |time| format_age_at(&time, now)),
+        event.message.clone().unwrap_or_default().trim().to_string(),
+    ]
+}
@@ -1,10 +1,14 @@
 //! This module provides extensions for Kubernetes Pod lists and specifications.
 //!
-//! It re-exports `PodListExt` and `SpecExt` traits, which offer additional
-//! functionality and helper methods for working with Kubernetes Pod data
-//! structures.
+//! It re-exports `EventListExt`, `PodListExt`, `PodUsageExt`, `ProxyRuleExt`
+//! and `SpecExt` traits, which offer additional functionality and helper
+//! methods for working with Kubernetes Pod data structures.
 
+mod custom_columns;
+mod event_list_ext;
 mod pod_list_ext;
+mod pod_usage_ext;
+mod proxy_rule_ext;
 mod spec_ext;
 
 /// Re-exports the [`PodListExt`] trait, which provides extension methods for
@@ -13,4 +17,11 @@ mod spec_ext;
 /// This trait is intended to add convenience methods to `Vec<Pod>` or similar
 /// collections for common operations like filtering, sorting, or extracting
 /// information.
-pub use self::{pod_list_ext::PodListExt, spec_ext::SpecExt};
+pub use self::{
+    custom_columns::CustomColumns,
+    event_list_ext::{EventExt, EventListExt},
+    pod_list_ext::PodListExt,
+    pod_usage_ext::PodUsageExt,
+    proxy_rule_ext::ProxyRuleExt,
+    spec_ext::SpecExt,
+};
@@ -1,27 +1,65 @@
 //! This module provides extensions for `ObjectList<Pod>` to render a formatted
 //! table.
 
+use comfy_table::{Cell, Color};
 use k8s_openapi::api::core::v1::Pod;
 use kube::api::ObjectList;
 
+use crate::ui::{PodSortKey, format_age};
+
+/// The index of the IMAGE column in the rows produced by [`pod_column`].
+const IMAGE_COLUMN: usize = 1;
+
+/// The index of the STATUS column in the rows produced by [`pod_column`].
+const STATUS_COLUMN: usize = 2;
+
+/// The headers of the columns produced by [`pod_column`].
+const HEADERS: [&str; 6] = ["NAME", "IMAGE", "STATUS", "NAMESPACE", "NODE", "AGE"];
+
+/// The padding around the content of every cell, on both sides.
+const CELL_PADDING: usize = 2;
+
+/// The width below which the IMAGE column is never truncated, so that the
+/// repository of the image stays recognizable.
+const MIN_IMAGE_WIDTH: usize = 16;
+
+/// The character marking a truncated value.
+const ELLIPSIS: char = '…';
+
 /// Extension trait for `ObjectList<Pod>` to provide table rendering
 /// capabilities.
 pub trait PodListExt {
     /// Renders the list of pods into a human-readable table string.
     ///
     /// The table includes columns for "NAME", "IMAGE", "STATUS", "NAMESPACE",
-    /// and "NODE".
+    /// "NODE" and "AGE".
+    ///
+    /// # Arguments
+    /// * `colorize` - Whether the STATUS column is colored according to the
+    ///   phase of each pod.
+    /// * `max_width` - The width the table should fit in, usually that of the
+    ///   terminal. Long images are truncated with an ellipsis to fit it, while
+    ///   the other columns are kept intact. `None` renders every value in full.
     ///
     /// # Returns
     /// A `String` containing the formatted table.
-    fn render_table(&self) -> String;
+    fn render_table(&self, colorize: bool, max_width: Option<usize>) -> String;
+
+    /// Sorts the list of pods in place according to `sort_by`.
+    ///
+    /// # Arguments
+    /// * `sort_by` - The key by which pods are sorted.
+    fn sort_by_key(&mut self, sort_by: PodSortKey);
 }
 
 impl PodListExt for ObjectList<Pod> {
     /// Renders the list of pods into a human-readable table string.
     ///
     /// Each row in the table represents a pod, with columns for name, image,
-    /// status, namespace, and node.
+    /// status, namespace, node and age. When `colorize` is set, the status is
+    /// green for running pods, yellow for pending pods and red for failed or
+    /// unknown pods. Rows are never wrapped: with `max_width`, the IMAGE column
+    /// is truncated instead.
     ///
     /// # Returns
     /// A `String` containing the formatted table representation of the
@@ -86,33 +124,52 @@ impl PodListExt for ObjectList<Pod> {
     ///     ..Default::default()
     /// };
     ///
-    /// let table_string = pod_list.render_table();
+    /// let table_string = pod_list.render_table(false, Some(80));
     /// println!("{}", table_string);
     /// ```
-    fn render_table(&self) -> String {
-        let rows = self.items.iter().map(pod_column).collect::<Vec<_>>();
-        comfy_table::Table::new()
+    fn render_table(&self, colorize: bool, max_width: Option<usize>) -> String {
+        let mut rows = self.items.iter().map(pod_column).collect::<Vec<_>>();
+        if let Some(max_width) = max_width {
+            truncate_images(&mut rows, max_width);
+        }
+        let rows = rows.into_iter().map(|row| {
+            let mut cells = row.map(Cell::new);
+            if colorize {
+                let status = &mut cells[STATUS_COLUMN];
+                *status = status.clone().fg(status_color(&status.content()));
+            }
+            cells
+        });
+
+        let mut table = comfy_table::Table::new();
+        let _table = table
             .load_preset(comfy_table::presets::NOTHING)
-            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
-            .set_header(vec!["NAME", "IMAGE", "STATUS", "NAMESPACE", "NODE"])
-            .add_rows(rows)
-            .to_string()
+            .set_content_arrangement(comfy_table::ContentArrangement::Disabled)
+            .set_header(HEADERS.to_vec())
+            .add_rows(rows);
+        if colorize {
+            // Whether stdout is a terminal has already been checked by the caller
+            let _table = table.enforce_styling();
+        }
+        table.to_string()
     }
+
+    fn sort_by_key(&mut self, sort_by: PodSortKey) { sort_by.sort(&mut self.items); }
 }
 
 /// Extracts specific column data for a single Kubernetes `Pod` object.
 ///
 /// This function retrieves the pod's name, the image of its first container,
-/// its status phase, namespace, and the node it's scheduled on.
+/// its status phase, namespace, the node it's scheduled on and its age.
 /// Defaults are used if any information is missing.
 ///
 /// # Arguments
 /// * `pod` - A reference to the `Pod` object from which to extract data.
 ///
 /// # Returns
-/// An array of five `String`s, representing the column values in the order:
-/// `[NAME, IMAGE, STATUS, NAMESPACE, NODE]`.
-fn pod_column(pod: &Pod) -> [String; 5] {
+/// An array of six `String`s, representing the column values in the order:
+/// `[NAME, IMAGE, STATUS, NAMESPACE, NODE, AGE]`.
+fn pod_column(pod: &Pod) -> [String; 6] {
     [
         pod.metadata.name.clone().unwrap_or_default(),
         pod.spec
@@ -123,5 +180,84 @@ fn pod_column(pod: &Pod) -> [String; 5] {
         pod.status.as_ref().and_then(|s| s.phase.clone()).unwrap_or_else(|| "Unknown".to_string()),
         pod.metadata.namespace.clone().unwrap_or_default(),
         pod.spec.as_ref().and_then(|s| s.node_name.clone()).unwrap_or_default(),
+        pod.metadata.creation_timestamp.as_ref().map(format_age).unwrap_or_default(),
     ]
 }
+
+/// Truncates the IMAGE column of `rows` so that the table fits in `max_width`,
+/// leaving at least [`MIN_IMAGE_WIDTH`] characters of every image.
+fn truncate_images(rows: &mut [[String; 6]], max_width: usize) {
+    let column_width = |column: usize| {
+        rows.iter()
+            .map(|row| row[column].chars().count())
+            .chain(std::iter::once(HEADERS[column].len()))
+            .max()
+            .unwrap_or_default()
+            + CELL_PADDING
+    };
+    let other_columns_width = (0..HEADERS.len())
+        .filter(|&column| column != IMAGE_COLUMN)
+        .map(column_width)
+        .sum::<usize>();
+    let image_width =
+        max_width.saturating_sub(other_columns_width + CELL_PADDING).max(MIN_IMAGE_WIDTH);
+
+    for row in rows {
+        let image = &mut row[IMAGE_COLUMN];
+        if image.chars().count() > image_width {
+            *image = image.chars().take(image_width - 1).chain(std::iter::once(ELLIPSIS)).collect();
+        }
+    }
+}
+
+/// Returns the color of the STATUS cell for a pod phase.
+///
+/// # Arguments
+/// * `phase` - The status phase of the pod, as rendered in the table.
+///
+/// # Returns
+/// Green for `Running` and `Succeeded`, yellow for `Pending`, and red for
+/// `Failed`, `Unknown` and any other phase.
+fn status_color(phase: &str) -> Color {
+    match phase {
+        "Running" | "Succeeded" => Color::Green,
+        "Pending" => Color::Yellow,
+        _ => Color::Red,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(name: &str, image: &str) -> [String; 6] {
+        [name, image, "Running", "default", "worker-1", "5m"].map(String::from)
+    }
+
+    #[test]
+    fn test_truncate_images() {
+        let image = "registry.example.com/team/debugging-tools:2024.10.01";
+        let mut rows = [row("axon-debug-pod", image), row("axon", "alpine:3")];
+        truncate_images(&mut rows, 120);
+        assert_eq!(rows[0][IMAGE_COLUMN], image, "fits without truncation");
+
+        truncate_images(&mut rows, 70);
+        // NAME 14, STATUS 7, NAMESPACE 9, NODE 8, AGE 3, each padded by 2
+        let expected_width = 70 - (14 + 7 + 9 + 8 + 3 + 5 * CELL_PADDING) - CELL_PADDING;
+        assert_eq!(rows[0][IMAGE_COLUMN].chars().count(), expected_width);
+        assert!(rows[0][IMAGE_COLUMN].ends_with(ELLIPSIS));
+        assert_eq!(rows[0][0], "axon-debug-pod");
+        assert_eq!(rows[1][IMAGE_COLUMN], "alpine:3");
+
+        let mut table = comfy_table::Table::new();
+        let _table = table
+            .load_preset(comfy_table::presets::NOTHING)
+            .set_content_arrangement(comfy_table::ContentArrangement::Disabled)
+            .set_header(HEADERS.to_vec())
+            .add_rows(rows.clone());
+        assert!(table.to_string().lines().all(|line| line.chars().count() <= 70));
+
+        truncate_images(&mut rows, 20);
+        assert_eq!(rows[0][IMAGE_COLUMN].chars().count(), MIN_IMAGE_WIDTH);
+    }
+}
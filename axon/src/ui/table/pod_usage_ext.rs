@@ -0,0 +1,62 @@
+//! This module provides extensions for `Vec<PodUsage>` to render a formatted
+//! table.
+
+use crate::metrics::PodUsage;
+
+/// Extension trait for `Vec<PodUsage>` to provide table rendering
+/// capabilities.
+pub trait PodUsageExt {
+    /// Renders the resource usage of pods into a human-readable table string.
+    ///
+    /// The table includes columns for "NAME", "NAMESPACE", and the usage,
+    /// request and limit of both CPU (in millicores) and memory (in MiB).
+    /// Values that are not known or not set are rendered as `-`.
+    ///
+    /// # Returns
+    /// A `String` containing the formatted table.
+    fn render_table(&self) -> String;
+}
+
+impl PodUsageExt for Vec<PodUsage> {
+    fn render_table(&self) -> String {
+        let rows = self.iter().map(|usage| {
+            [
+                usage.name.clone(),
+                usage.namespace.clone(),
+                format_cpu(usage.cpu_usage),
+                format_cpu(usage.cpu_request),
+                format_cpu(usage.cpu_limit),
+                format_memory(usage.memory_usage),
+                format_memory(usage.memory_request),
+                format_memory(usage.memory_limit),
+            ]
+        });
+
+        let mut table = comfy_table::Table::new();
+        let _table = table
+            .load_preset(comfy_table::presets::NOTHING)
+            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
+            .set_header(vec![
+                "NAME",
+                "NAMESPACE",
+                "CPU",
+                "CPU REQUEST",
+                "CPU LIMIT",
+                "MEMORY",
+                "MEMORY REQUEST",
+                "MEMORY LIMIT",
+            ])
+            .add_rows(rows);
+        table.to_string()
+    }
+}
+
+/// Formats an amount of CPU in millicores, e.g. `250m`.
+fn format_cpu(millis: Option<u64>) -> String {
+    millis.map_or_else(|| "-".to_string(), |millis| format!("{millis}m"))
+}
+
+/// Formats an amount of memory in MiB, e.g. `128Mi`.
+fn format_memory(bytes: Option<u64>) -> String {
+    bytes.map_or_else(|| "-".to_string(), |bytes| format!("{}Mi", bytes.div_ceil(1024 * 1024)))
+}
@@ -0,0 +1,38 @@
+//! This module provides extensions for `Vec<ProxyRule>` to render a formatted
+//! table.
+
+use crate::config::ProxyRule;
+
+/// Extension trait for `Vec<ProxyRule>` to provide table rendering
+/// capabilities.
+pub trait ProxyRuleExt {
+    /// Renders the bindings of proxy rules into a human-readable table string.
+    ///
+    /// The table includes columns for "LOCAL", "TARGET", "REMOTE PORT" and
+    /// "NAMESPACE". A rule without a namespace is rendered with `-`.
+    ///
+    /// # Returns
+    /// A `String` containing the formatted table.
+    fn render_table(&self) -> String;
+}
+
+impl ProxyRuleExt for Vec<ProxyRule> {
+    fn render_table(&self) -> String {
+        let rows = self.iter().map(|rule| {
+            [
+                format!("{}:{}", rule.address, rule.local_port),
+                rule.target.clone(),
+                rule.remote_port.to_string(),
+                rule.namespace.clone().unwrap_or_else(|| "-".to_string()),
+            ]
+        });
+
+        let mut table = comfy_table::Table::new();
+        let _table = table
+            .load_preset(comfy_table::presets::NOTHING)
+            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
+            .set_header(vec!["LOCAL", "TARGET", "REMOTE PORT", "NAMESPACE"])
+            .add_rows(rows);
+        table.to_string()
+    }
+}
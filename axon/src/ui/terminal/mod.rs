@@ -2,16 +2,50 @@
 //!
 //! This module provides utilities for enabling and disabling terminal raw
 //! mode, using a RAII guard ([`TerminalRawModeGuard`]) to ensure the
-//! terminal is restored to its previous state even on unexpected exits.
+//! terminal is restored to its previous state even on unexpected exits. It
+//! also decides whether colored output should be used ([`use_color`]) and
+//! which size to report for the terminal ([`size`]).
 
 mod error;
 
-use std::io::Write;
+use std::io::{IsTerminal, Write};
 
 use snafu::ResultExt;
 
 pub use self::error::Error;
 
+/// Determines whether colored output should be written to standard output.
+///
+/// Colors are used only if they are not disabled, either with the `no_color`
+/// flag or with a non-empty `NO_COLOR` environment variable (see
+/// <https://no-color.org>), and if standard output is a terminal.
+///
+/// # Arguments
+///
+/// * `no_color` - Whether colors are disabled on the command line.
+pub fn use_color(no_color: bool) -> bool {
+    !no_color
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && std::io::stdout().is_terminal()
+}
+
+/// The terminal size, as `(columns, rows)`, assumed when the actual size
+/// cannot be detected.
+pub const DEFAULT_TERMINAL_SIZE: (u16, u16) = (80, 24);
+
+/// Returns the size of the terminal as `(columns, rows)`.
+///
+/// Falls back to [`DEFAULT_TERMINAL_SIZE`] when the size cannot be detected,
+/// or is reported as zero, as happens without a controlling terminal (e.g.
+/// when piped or in CI), so that a remote pseudo-terminal is never given a
+/// degenerate size.
+pub fn size() -> (u16, u16) { size_or_default(crossterm::terminal::size().ok()) }
+
+/// Returns `size` if it is detected and non-zero, or the default size.
+fn size_or_default(size: Option<(u16, u16)>) -> (u16, u16) {
+    size.filter(|&(columns, rows)| columns > 0 && rows > 0).unwrap_or(DEFAULT_TERMINAL_SIZE)
+}
+
 /// A guard that ensures the terminal raw mode is properly enabled and disabled.
 ///
 /// When an instance of `TerminalRawModeGuard` is created using `setup()`,
@@ -63,3 +97,16 @@ impl Drop for TerminalRawModeGuard {
         let _unused = stdout.flush();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_or_default() {
+        assert_eq!(size_or_default(Some((120, 40))), (120, 40));
+        assert_eq!(size_or_default(Some((0, 0))), DEFAULT_TERMINAL_SIZE);
+        assert_eq!(size_or_default(Some((120, 0))), DEFAULT_TERMINAL_SIZE);
+        assert_eq!(size_or_default(None), DEFAULT_TERMINAL_SIZE);
+    }
+}
@@ -0,0 +1,49 @@
+//! Provides a spinner for the time spent waiting for a pod to be running.
+
+use std::time::Duration;
+
+/// How often the spinner and the elapsed time are redrawn.
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A status line on stderr showing the elapsed time and the current phase of
+/// a pod being waited for.
+///
+/// Nothing is drawn when stderr is not a terminal.
+pub struct WaitSpinner {
+    /// The inner `indicatif::ProgressBar` instance that draws the spinner.
+    inner: indicatif::ProgressBar,
+    /// The pod being waited for, e.g. `pod/axon in namespace dev`.
+    target: String,
+}
+
+impl WaitSpinner {
+    /// Creates a spinner for the pod `pod_name` in `namespace`, drawn on
+    /// stderr until it is finished.
+    pub fn new(pod_name: &str, namespace: &str) -> Self {
+        let inner = indicatif::ProgressBar::new_spinner();
+        inner.set_style(
+            indicatif::ProgressStyle::default_spinner()
+                .template("{spinner:.green} [{elapsed}] {msg}")
+                .expect("the template is valid"),
+        );
+        inner.enable_steady_tick(TICK_INTERVAL);
+        let spinner = Self { inner, target: format!("pod/{pod_name} in namespace {namespace}") };
+        spinner.set_phase("Unknown");
+        spinner
+    }
+
+    /// Creates a spinner that draws nothing, e.g. under `--quiet`.
+    pub fn hidden() -> Self {
+        Self { inner: indicatif::ProgressBar::hidden(), target: String::new() }
+    }
+
+    /// Shows `phase` as the current phase of the pod.
+    pub fn set_phase(&self, phase: &str) {
+        self.inner.set_message(format!("Waiting for {}: {phase}", self.target));
+    }
+
+    /// Clears the status line.
+    ///
+    /// This consumes the `WaitSpinner` instance.
+    pub fn finish(self) { self.inner.finish_and_clear(); }
+}